@@ -0,0 +1,46 @@
+use pcan_basic::format::blf::BlfWriter;
+use pcan_basic::format::trc::TrcReader;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let (input, output) = match (args.next(), args.next()) {
+        (Some(input), Some(output)) => (input, output),
+        _ => {
+            println!("usage: blf_1 <input.trc> <output.blf>");
+            return;
+        }
+    };
+
+    let reader = match TrcReader::open(input) {
+        Ok(reader) => reader,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+    let mut writer = match BlfWriter::create(output) {
+        Ok(writer) => writer,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    for record in reader {
+        let result = match record {
+            Ok(record) => writer.write_record(&record),
+            Err(err) => {
+                println!("{:?}", err);
+                return;
+            }
+        };
+        if let Err(err) = result {
+            println!("{:?}", err);
+            return;
+        }
+    }
+
+    if let Err(err) = writer.finish() {
+        println!("{:?}", err);
+    }
+}
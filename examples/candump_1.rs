@@ -0,0 +1,53 @@
+use pcan_basic::format::candump::CandumpReader;
+use pcan_basic::format::trc::TraceWriter;
+use pcan_basic::socket::CanFrame;
+use pcan_basic::trace::TraceFile;
+
+fn main() {
+    let frame: CanFrame = match "123#DEADBEEF".parse() {
+        Ok(frame) => frame,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+    println!("parsed {}", frame);
+
+    let mut args = std::env::args().skip(1);
+    let (input, output) = match (args.next(), args.next()) {
+        (Some(input), Some(output)) => (input, output),
+        _ => {
+            println!("usage: candump_1 <candump.log> <output.trc>");
+            return;
+        }
+    };
+
+    let reader = match CandumpReader::open(input) {
+        Ok(reader) => reader,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+    let mut writer = match TraceWriter::create(output, TraceFile::Overwrite, 100) {
+        Ok(writer) => writer,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    for record in reader {
+        let result = match record {
+            Ok(record) => writer.write_record(&record),
+            Err(err) => {
+                println!("{:?}", err);
+                return;
+            }
+        };
+        if let Err(err) = result {
+            println!("{:?}", err);
+            return;
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::canopen::{Node, ObjectDictionary};
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::Baudrate;
+
+fn main() {
+    let dictionary = match ObjectDictionary::from_file("drive.eds") {
+        Ok(dictionary) => dictionary,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let usb_socket = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let node = Node::new(&usb_socket, 5, &dictionary);
+
+    match node.read::<u32>("Identity object.Vendor-ID") {
+        Ok(vendor_id) => println!("vendor_id={:#010X}", vendor_id),
+        Err(err) => println!("{:?}", err),
+    }
+
+    match node.write("Controlword", 0x000Fu16) {
+        Ok(_) => println!("Is OK!"),
+        Err(err) => println!("{:?}", err),
+    }
+}
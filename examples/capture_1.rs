@@ -0,0 +1,50 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::capture::MultiChannelReader;
+use pcan_basic::format::trc::TraceWriter;
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::Baudrate;
+use pcan_basic::trace::TraceFile;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+fn main() {
+    let output = match std::env::args().nth(1) {
+        Some(output) => output,
+        None => {
+            println!("usage: capture_1 <output.trc>");
+            return;
+        }
+    };
+
+    let mut reader = MultiChannelReader::new(Duration::from_millis(20));
+    for bus in [UsbBus::USB1, UsbBus::USB2] {
+        match UsbCanSocket::open(bus, Baudrate::Baud500K) {
+            Ok(socket) => {
+                reader.add(bus, socket);
+            }
+            Err(err) => {
+                println!("{:?}", err);
+                return;
+            }
+        }
+    }
+
+    let mut writer = match TraceWriter::create(output, TraceFile::Segmented, 0) {
+        Ok(writer) => writer,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    // Runs until both channels failed.
+    let running = AtomicBool::new(true);
+    match reader.write_into(&running, |record| writer.write_record(record)) {
+        Ok(errors) => {
+            for err in errors {
+                println!("channel {:#x}: {:?}", err.channel, err.error);
+            }
+        }
+        Err(err) => println!("{:?}", err),
+    }
+}
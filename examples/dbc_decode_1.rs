@@ -0,0 +1,33 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::dbc::Database;
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::{Baudrate, RecvCan};
+
+fn main() {
+    let database = match Database::from_file("vehicle.dbc") {
+        Ok(database) => database,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let usb_socket = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    loop {
+        if let Ok(frame) = usb_socket.recv_frame() {
+            if let Some(decoded) = database.decode(&frame) {
+                println!("{}", decoded.message.name);
+                for signal in decoded.signals {
+                    println!("  {}={} {}", signal.name(), signal.value, signal.unit());
+                }
+            }
+        }
+    }
+}
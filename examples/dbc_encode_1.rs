@@ -0,0 +1,35 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::dbc::Database;
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::{Baudrate, SendCan};
+
+fn main() {
+    let database = match Database::from_file("vehicle.dbc") {
+        Ok(database) => database,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let usb_socket = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let message = match database.message_by_name("EngineData") {
+        Some(message) => message,
+        None => return,
+    };
+
+    match message.encode(&[("EngineSpeed", 2000.0), ("CoolantTemp", 85.0)]) {
+        Ok(frame) => match usb_socket.send(frame) {
+            Ok(_) => println!("Is OK!"),
+            Err(err) => println!("{:?}", err),
+        },
+        Err(err) => println!("{:?}", err),
+    }
+}
@@ -0,0 +1,39 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::gateway::{Gateway, Route, Rule};
+use pcan_basic::replay::CanSink;
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::Baudrate;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+fn main() {
+    let a = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+    let b = match UsbCanSocket::open(UsbBus::USB2, Baudrate::Baud500K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let mut gateway = Gateway::new()
+        .with_rule(Rule::block("diagnostics", 0x7E0, 0x7F0))
+        .with_rule(
+            Rule::forward("speed", 0x0C0, 0x7FF)
+                .with_route(Route::AToB)
+                .with_translation(0x1C0)
+                .with_min_interval(Duration::from_millis(100)),
+        );
+
+    let running = AtomicBool::new(true);
+    if let Err(err) = gateway.run(&mut CanSink::new(&a), &mut CanSink::new(&b), &running) {
+        println!("{:?}", err);
+    }
+    println!("{:?}", gateway.report());
+}
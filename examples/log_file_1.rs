@@ -0,0 +1,44 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::log::file::LogReader;
+use pcan_basic::log::{LogFunction, LogGuard};
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::Baudrate;
+
+fn main() {
+    let log_file = {
+        let guard = match LogGuard::new(std::env::temp_dir(), LogFunction::Entry) {
+            Ok(guard) => guard,
+            Err(err) => {
+                println!("{:?}", err);
+                return;
+            }
+        };
+        if let Err(err) = UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+            println!("{:?}", err);
+        }
+        match guard.log_file() {
+            Ok(log_file) => log_file,
+            Err(err) => {
+                println!("{:?}", err);
+                return;
+            }
+        }
+    };
+
+    let reader = match LogReader::open(log_file) {
+        Ok(reader) => reader,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+    for entry in reader {
+        match entry {
+            Ok(entry) => println!("{:?}", entry),
+            Err(err) => {
+                println!("{:?}", err);
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::nmea2000::{Nmea2000Reader, PgnValue};
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::Baudrate;
+use std::thread::sleep;
+use std::time::Duration;
+
+fn main() {
+    let usb_socket = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud250K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let mut reader = Nmea2000Reader::new(&usb_socket);
+
+    loop {
+        let message = match reader.recv() {
+            Ok(Some(message)) => message,
+            Ok(None) => {
+                sleep(Duration::from_millis(1));
+                continue;
+            }
+            Err(err) => {
+                println!("{:?}", err);
+                return;
+            }
+        };
+
+        match message.decode() {
+            Some(PgnValue::PositionRapidUpdate(position)) => println!(
+                "src={} lat={:?} lon={:?}",
+                message.source(),
+                position.latitude,
+                position.longitude
+            ),
+            Some(value) => println!("src={} {:?}", message.source(), value),
+            None => println!(
+                "src={} pgn={} data={:02X?}",
+                message.source(),
+                message.pgn(),
+                message.data
+            ),
+        }
+    }
+}
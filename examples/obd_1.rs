@@ -0,0 +1,46 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::obd::{Addressing, ObdClient};
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::Baudrate;
+
+fn main() {
+    let usb_socket = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let client = ObdClient::new(&usb_socket, Addressing::Standard);
+
+    match client.vin() {
+        Ok(vin) => println!("vin={}", vin),
+        Err(err) => println!("{:?}", err),
+    }
+
+    match client.current_data(0x0C) {
+        Ok(values) => {
+            for value in values {
+                println!(
+                    "ecu={:#X} value={:?} {:?}",
+                    value.ecu,
+                    value.value(),
+                    value.unit()
+                );
+            }
+        }
+        Err(err) => println!("{:?}", err),
+    }
+
+    match client.stored_dtcs() {
+        Ok(dtcs) => {
+            for (ecu, codes) in dtcs {
+                for code in codes {
+                    println!("ecu={:#X} dtc={}", ecu, code);
+                }
+            }
+        }
+        Err(err) => println!("{:?}", err),
+    }
+}
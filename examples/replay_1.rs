@@ -0,0 +1,49 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::format::trc::TrcReader;
+use pcan_basic::replay::{CanSink, Replay};
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::Baudrate;
+
+fn main() {
+    let input = match std::env::args().nth(1) {
+        Some(input) => input,
+        None => {
+            println!("usage: replay_1 <input.trc>");
+            return;
+        }
+    };
+
+    let reader = match TrcReader::open(input) {
+        Ok(reader) => reader,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+    let records = match reader.collect::<Result<Vec<_>, _>>() {
+        Ok(records) => records,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let usb_socket = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let replay = Replay::new(records).with_recording();
+    match replay.run(&mut [&mut CanSink::new(&usb_socket)]) {
+        Ok(report) => {
+            println!("sent {} frames, skipped {}", report.sent, report.skipped);
+            for response in report.responses {
+                println!("{:?}", response);
+            }
+        }
+        Err(err) => println!("{:?}", err),
+    }
+}
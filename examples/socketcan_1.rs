@@ -0,0 +1,30 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::replay::CanSink;
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::Baudrate;
+use pcan_basic::socketcan::{Bridge, SocketCan};
+use std::sync::atomic::AtomicBool;
+
+fn main() {
+    let pcan = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+    let socketcan = match SocketCan::open("vcan0") {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let mut bridge = Bridge::new();
+    let running = AtomicBool::new(true);
+    if let Err(err) = bridge.run(&mut CanSink::new(&pcan), &socketcan, &running) {
+        println!("{:?}", err);
+    }
+    println!("{:?}", bridge.report());
+}
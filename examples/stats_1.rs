@@ -0,0 +1,40 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::format::Frame;
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::{Baudrate, RecvCan};
+use pcan_basic::stats::{BusStatistics, BusTiming};
+use std::time::{Duration, Instant};
+
+fn main() {
+    let socket = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let timing = BusTiming::new(Baudrate::Baud500K.bits_per_second());
+    let mut statistics = BusStatistics::new(timing).with_window(Duration::from_secs(5));
+    let mut report = Instant::now();
+    loop {
+        match socket.recv() {
+            Ok((frame, timestamp)) => statistics.push(timestamp, &Frame::Can(frame)),
+            Err(_) => std::thread::sleep(Duration::from_millis(1)),
+        }
+
+        if report.elapsed() >= Duration::from_secs(1) {
+            let snapshot = statistics.snapshot();
+            println!(
+                "load {:.1}%  {:.0} frames/s  {:.1} error frames/s",
+                snapshot.load * 100.0,
+                snapshot.frame_rate,
+                snapshot.error_rate
+            );
+            for id in snapshot.ids {
+                println!("{:X} {:?}", id.can_id, id.cycle);
+            }
+            report = Instant::now();
+        }
+    }
+}
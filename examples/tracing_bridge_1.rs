@@ -0,0 +1,37 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::log::bridge::{DriverLogBridge, DriverLogLayer};
+use pcan_basic::log::{LogFunction, LogGuard};
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::Baudrate;
+use std::time::Duration;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::Registry;
+
+fn main() {
+    let subscriber = Registry::default().with(DriverLogLayer::new());
+    if let Err(err) = tracing::subscriber::set_global_default(subscriber) {
+        println!("{:?}", err);
+        return;
+    }
+
+    let _guard = match LogGuard::new(std::env::temp_dir(), LogFunction::Entry) {
+        Ok(guard) => guard,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+    let _bridge = match DriverLogBridge::from_log_location() {
+        Ok(bridge) => bridge.spawn(Duration::from_millis(100)),
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    tracing::info!("opening USB1");
+    if let Err(err) = UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+        println!("{:?}", err);
+    }
+    std::thread::sleep(Duration::from_millis(500));
+}
@@ -0,0 +1,50 @@
+use pcan_basic::format::trc::TrcReader;
+use pcan_basic::format::Frame;
+
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            println!("usage: trc_read_1 <file.trc>");
+            return;
+        }
+    };
+
+    let reader = match TrcReader::open(path) {
+        Ok(reader) => reader,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    for record in reader {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                println!("{:?}", err);
+                return;
+            }
+        };
+
+        match &record.frame {
+            Frame::Can(frame) => println!(
+                "{} us bus={} {:?} id={:#X} data={:02X?}",
+                record.timestamp.as_micros(),
+                record.bus,
+                record.direction,
+                frame.can_id(),
+                frame.data()
+            ),
+            Frame::CanFd(frame) => println!(
+                "{} us bus={} {:?} id={:#X} fd data={:02X?}",
+                record.timestamp.as_micros(),
+                record.bus,
+                record.direction,
+                frame.can_id(),
+                frame.data()
+            ),
+            frame => println!("{} us {:?}", record.timestamp.as_micros(), frame),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::format::trc::TraceWriter;
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::Baudrate;
+use pcan_basic::trace::TraceFile;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+fn main() {
+    let usb_socket = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let mut writer = match TraceWriter::create("recording.trc", TraceFile::Segmented, 5) {
+        Ok(writer) => writer,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+    let _ = writer.write_comment("Recorded with pcan-basic");
+
+    let start = Instant::now();
+    let mut frames = 0;
+    while start.elapsed() < Duration::from_secs(10) {
+        match writer.record(&usb_socket) {
+            Ok(count) => frames += count,
+            Err(err) => {
+                println!("{:?}", err);
+                break;
+            }
+        }
+        sleep(Duration::from_millis(1));
+    }
+
+    println!(
+        "recorded {} frames into {}",
+        frames,
+        writer.path().display()
+    );
+}
@@ -0,0 +1,26 @@
+use pcan_basic::socket::{CanFrame, MessageType, RecvCan, SendCan};
+use pcan_basic::tunnel::client::RemoteCanSocket;
+use pcan_basic::tunnel::Transport;
+
+fn main() {
+    let socket = match RemoteCanSocket::connect(Transport::Tcp, "192.168.1.10:20000") {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let frame = CanFrame::new(0x7DF, MessageType::Standard, &[0x02, 0x01, 0x0C]).unwrap();
+    if let Err(err) = socket.send(frame) {
+        println!("{:?}", err);
+        return;
+    }
+
+    loop {
+        if let Ok((frame, timestamp)) = socket.recv() {
+            println!("{:?}", frame);
+            println!("{:?}", timestamp);
+        }
+    }
+}
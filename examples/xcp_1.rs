@@ -0,0 +1,65 @@
+use pcan_basic::bus::UsbBus;
+use pcan_basic::socket::usb::UsbCanSocket;
+use pcan_basic::socket::{Baudrate, MessageType};
+use pcan_basic::xcp::{CanTransport, DaqList, OdtEntry, XcpMaster};
+
+fn main() {
+    let usb_socket = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+        Ok(socket) => socket,
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    };
+
+    let transport = CanTransport::new(&usb_socket, 0x7F0, 0x7F1, MessageType::Standard);
+    let mut master = XcpMaster::new(transport);
+
+    match master.connect() {
+        Ok(info) => println!("{:?}", info),
+        Err(err) => {
+            println!("{:?}", err);
+            return;
+        }
+    }
+
+    match master.short_upload(0x2000_0000, 0, 4) {
+        Ok(data) => println!("data={:02X?}", data),
+        Err(err) => println!("{:?}", err),
+    }
+
+    let list = DaqList::new(0)
+        .with_timestamp()
+        .with_odt(vec![OdtEntry::new(0x2000_0000, 0, 4)]);
+    if let Err(err) = master
+        .configure_daq(&[list])
+        .and_then(|_| master.start_daq())
+    {
+        println!("{:?}", err);
+        return;
+    }
+
+    let mut received = 0;
+    while received < 100 {
+        match master.recv_daq() {
+            Ok(Some(sample)) => {
+                received += 1;
+                println!(
+                    "daq={} odt={} t={}us data={:02X?}",
+                    sample.daq,
+                    sample.odt,
+                    sample.timestamp.as_micros(),
+                    sample.data
+                );
+            }
+            Ok(None) => std::thread::sleep(std::time::Duration::from_millis(1)),
+            Err(err) => {
+                println!("{:?}", err);
+                break;
+            }
+        }
+    }
+
+    let _ = master.stop_daq();
+    let _ = master.disconnect();
+}
@@ -0,0 +1,441 @@
+//! Prints the traffic of a channel and optionally records it into a log file, e.g.
+//!
+//! ```text
+//! pcan-dump usb1 --bitrate 250k --filter 100:7F0 --output drive.trc
+//! pcan-dump pcibus2 --fd --style view --timestamp delta
+//! ```
+//!
+//! Stops on Ctrl-C, the log file is completed and the channel uninitialized.
+
+use clap::{Parser, ValueEnum};
+use pcan_basic::bus::AnyBus;
+use pcan_basic::df::{SetAcceptanceCode11Bit, SetAcceptanceCode29Bit};
+use pcan_basic::error::PcanError;
+use pcan_basic::format::asc::AscWriter;
+use pcan_basic::format::blf::BlfWriter;
+use pcan_basic::format::candump::CandumpWriter;
+use pcan_basic::format::mdf::MdfWriter;
+use pcan_basic::format::trc::TraceWriter;
+use pcan_basic::format::{Frame, Record};
+use pcan_basic::socket::{
+    Baudrate, CanSocket, RecvCan, RecvCanFd, Timestamp, EXTENDED_MASK, FD_BITRATE_500K_2M,
+    STANDARD_MASK,
+};
+use pcan_basic::trace::TraceFile;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Prints and records the traffic of a PCAN channel")]
+struct Args {
+    /// Channel, e.g. usb1, pci2 or PCAN_LANBUS1
+    channel: AnyBus,
+    /// Bitrate of a classic channel, e.g. 500k or 1M
+    #[arg(short, long, default_value = "500k")]
+    bitrate: Baudrate,
+    /// Opens the channel in FD mode, optionally with --fd=BITRATE in the notation of the driver
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = FD_BITRATE_500K_2M)]
+    fd: Option<String>,
+    /// Passes IDs with ID & MASK equal to the filter, ID~MASK passes all others. Eight ID digits
+    /// filter extended frames. May be repeated
+    #[arg(short, long = "filter", value_parser = parse_filter)]
+    filters: Vec<Filter>,
+    #[arg(short, long, value_enum, default_value_t = TimeMode::Absolute)]
+    timestamp: TimeMode,
+    #[arg(short, long, value_enum, default_value_t = Style::Candump)]
+    style: Style,
+    /// Records into a log file, chosen by extension: trc, asc, blf, mf4 or log (candump)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Stops after the given number of frames
+    #[arg(short = 'n', long)]
+    count: Option<u64>,
+    /// Does not print frames
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+enum TimeMode {
+    /// Seconds since 1970
+    Absolute,
+    /// Seconds since the previous frame
+    Delta,
+    /// Seconds since the first frame
+    Zero,
+    None,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+enum Style {
+    /// Interface, ID, length and data like candump
+    Candump,
+    /// Numbered rows with type column like PCAN-View
+    View,
+}
+
+#[derive(Debug, Clone)]
+struct Filter {
+    id: u32,
+    mask: u32,
+    extended: bool,
+    inverted: bool,
+}
+
+impl Filter {
+    fn matches(&self, id: u32, extended: bool) -> bool {
+        let matching = extended == self.extended && id & self.mask == self.id & self.mask;
+        matching != self.inverted
+    }
+}
+
+fn parse_filter(text: &str) -> Result<Filter, String> {
+    let (id, mask, inverted) = match text.split_once(':') {
+        Some((id, mask)) => (id, mask, false),
+        None => match text.split_once('~') {
+            Some((id, mask)) => (id, mask, true),
+            None => return Err(format!("filter `{}` is not ID:MASK or ID~MASK", text)),
+        },
+    };
+    let parse = |hex: &str| {
+        u32::from_str_radix(hex, 16).map_err(|_| format!("invalid hex number `{}`", hex))
+    };
+    let extended = id.len() == 8;
+    let limit = if extended {
+        EXTENDED_MASK
+    } else {
+        STANDARD_MASK
+    };
+    Ok(Filter {
+        id: parse(id)? & limit,
+        mask: parse(mask)? & limit,
+        extended,
+        inverted,
+    })
+}
+
+/// Narrows the acceptance filters of the channel if a single filter per frame type allows it,
+/// all filters are checked on every received frame in addition.
+fn set_acceptance_filters(socket: &CanSocket, filters: &[Filter]) -> Result<(), PcanError> {
+    if filters.iter().any(|filter| filter.inverted) {
+        return Ok(());
+    }
+    let standard = filters.iter().filter(|f| !f.extended).collect::<Vec<_>>();
+    if let [filter] = standard[..] {
+        // The acceptance mask of the driver marks the ignored bits.
+        socket.set_acceptance_code_11bit(filter.id, !filter.mask & STANDARD_MASK)?;
+    }
+    let extended = filters.iter().filter(|f| f.extended).collect::<Vec<_>>();
+    if let [filter] = extended[..] {
+        socket.set_acceptance_code_29bit(filter.id, !filter.mask & EXTENDED_MASK)?;
+    }
+    Ok(())
+}
+
+/* Output */
+
+fn debug<E: std::fmt::Debug>(err: E) -> String {
+    format!("{:?}", err)
+}
+
+enum Output {
+    Trc(TraceWriter),
+    Asc(AscWriter<BufWriter<File>>),
+    Blf(BlfWriter<BufWriter<File>>),
+    Mdf(MdfWriter<BufWriter<File>>),
+    Candump(CandumpWriter<BufWriter<File>>),
+}
+
+impl Output {
+    fn create(path: &Path, interface: &str) -> Result<Output, String> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let output = match extension.as_str() {
+            "trc" => TraceWriter::create(path, TraceFile::Segmented, 0)
+                .map(Output::Trc)
+                .map_err(debug),
+            "asc" => AscWriter::create(path).map(Output::Asc).map_err(debug),
+            "blf" => BlfWriter::create(path).map(Output::Blf).map_err(debug),
+            "mf4" | "mdf" => MdfWriter::create(path).map(Output::Mdf).map_err(debug),
+            "log" => CandumpWriter::create(path)
+                .map(|mut writer| {
+                    writer.set_interface(1, interface);
+                    Output::Candump(writer)
+                })
+                .map_err(debug),
+            _ => return Err(format!("unknown log file type `{}`", path.display())),
+        };
+        output.map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    fn write(&mut self, record: &Record) -> Result<(), String> {
+        match self {
+            Output::Trc(writer) => writer.write_record(record).map_err(debug),
+            Output::Asc(writer) => writer.write_record(record).map_err(debug),
+            Output::Blf(writer) => writer.write_record(record).map_err(debug),
+            Output::Mdf(writer) => writer.write_record(record).map_err(debug),
+            Output::Candump(writer) => writer.write_record(record).map_err(debug),
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self {
+            Output::Trc(mut writer) => writer.flush().map_err(debug),
+            Output::Asc(writer) => writer.finish().map(drop).map_err(debug),
+            Output::Blf(writer) => writer.finish().map(drop).map_err(debug),
+            Output::Mdf(writer) => writer.finish().map(drop).map_err(debug),
+            Output::Candump(mut writer) => writer.flush().map_err(debug),
+        }
+    }
+}
+
+/* Printing */
+
+fn seconds(micros: u64) -> String {
+    format!("{}.{:06}", micros / 1_000_000, micros % 1_000_000)
+}
+
+fn data(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn id(id: u32, extended: bool) -> String {
+    match extended {
+        true => format!("{:08X}", id),
+        false => format!("{:03X}", id),
+    }
+}
+
+fn print_candump(time: Option<String>, interface: &str, frame: &Frame) {
+    let time = time.map(|time| format!("({}) ", time)).unwrap_or_default();
+    let row = match frame {
+        Frame::Can(frame) if frame.is_remote_frame() => format!(
+            "{:>8}  [{}]  remote request",
+            id(frame.can_id(), frame.is_extended_frame()),
+            frame.dlc()
+        ),
+        Frame::Can(frame) => format!(
+            "{:>8}   [{}]  {}",
+            id(frame.can_id(), frame.is_extended_frame()),
+            frame.dlc(),
+            data(frame.data())
+        ),
+        Frame::CanFd(frame) => format!(
+            "{:>8}  [{:02}]  {}",
+            id(frame.can_id(), frame.is_extended_frame()),
+            frame.data().len(),
+            data(frame.data())
+        ),
+        Frame::Error { data: bytes, .. } => {
+            format!("{:>8}   [{}]  {}", "ERROR", bytes.len(), data(bytes))
+        }
+        Frame::Status(text) => format!("{:>8}  {}", "STATUS", text),
+    };
+    println!("{}{}  {}", time, interface, row);
+}
+
+fn print_view(number: u64, time: Option<String>, frame: &Frame) {
+    let (kind, id, length, bytes) = match frame {
+        Frame::Can(frame) if frame.is_remote_frame() => (
+            "RR",
+            id(frame.can_id(), frame.is_extended_frame()),
+            frame.dlc() as usize,
+            String::new(),
+        ),
+        Frame::Can(frame) => (
+            "DT",
+            id(frame.can_id(), frame.is_extended_frame()),
+            frame.dlc() as usize,
+            data(frame.data()),
+        ),
+        Frame::CanFd(frame) => (
+            match (frame.is_bitrate_switch(), frame.is_error_state_indicator()) {
+                (true, true) => "FBE",
+                (true, false) => "FB",
+                (false, true) => "FE",
+                (false, false) => "FD",
+            },
+            id(frame.can_id(), frame.is_extended_frame()),
+            frame.data().len(),
+            data(frame.data()),
+        ),
+        Frame::Error { data: bytes, .. } => ("ER", String::new(), bytes.len(), data(bytes)),
+        Frame::Status(text) => ("ST", String::new(), 0, text.clone()),
+    };
+    println!(
+        "{:>7}) {:>17}  {:<3}  {:>8}  Rx  {:>2}  {}",
+        number,
+        time.unwrap_or_default(),
+        kind,
+        id,
+        length,
+        bytes
+    );
+}
+
+/* Capture */
+
+fn receive(socket: &CanSocket, fd: bool) -> Result<(Frame, u64), PcanError> {
+    match fd {
+        true => socket
+            .recv_fd()
+            .map(|(frame, micros)| (Frame::CanFd(frame), micros)),
+        false => socket
+            .recv()
+            .map(|(frame, timestamp)| (Frame::Can(frame), timestamp.as_micros())),
+    }
+}
+
+fn passes(filters: &[Filter], frame: &Frame) -> bool {
+    let (id, extended) = match frame {
+        Frame::Can(frame) => (frame.can_id(), frame.is_extended_frame()),
+        Frame::CanFd(frame) => (frame.can_id(), frame.is_extended_frame()),
+        _ => return true,
+    };
+    let (inverted, positive): (Vec<_>, Vec<_>) = filters.iter().partition(|f| f.inverted);
+    (positive.is_empty() || positive.iter().any(|f| f.matches(id, extended)))
+        && inverted.iter().all(|f| f.matches(id, extended))
+}
+
+fn unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_micros() as u64)
+        .unwrap_or_default()
+}
+
+fn run(args: Args, running: &AtomicBool) -> Result<u64, String> {
+    let interface = args.channel.to_string();
+    let socket = match &args.fd {
+        Some(bitrate) => CanSocket::open_fd(args.channel, bitrate),
+        None => CanSocket::open(args.channel, args.bitrate),
+    }
+    .map_err(|err| format!("opening {} failed: {:?}", interface, err))?;
+    set_acceptance_filters(&socket, &args.filters)
+        .map_err(|err| format!("setting the filters failed: {:?}", err))?;
+
+    let mut output = match &args.output {
+        Some(path) => Some(Output::create(path, &interface)?),
+        None => None,
+    };
+
+    let mut count = 0;
+    // Host time and device time of the first frame, later frames keep the device's spacing.
+    let mut start: Option<(u64, u64)> = None;
+    let mut previous = 0;
+    let mut status = None;
+    while running.load(Ordering::Relaxed) && args.count.is_none_or(|limit| count < limit) {
+        let (frame, micros) = match receive(&socket, args.fd.is_some()) {
+            Ok(received) => received,
+            Err(PcanError::QrcvEmpty) => {
+                std::thread::sleep(Duration::from_millis(1));
+                continue;
+            }
+            Err(
+                err @ (PcanError::BusLight
+                | PcanError::BusHeavy
+                | PcanError::BusPassive
+                | PcanError::BusOff
+                | PcanError::Overrun
+                | PcanError::QOverrun),
+            ) => {
+                if status.as_ref() != Some(&err) {
+                    eprintln!("{}: {:?}", interface, err);
+                    status = Some(err);
+                }
+                continue;
+            }
+            Err(err) => return Err(format!("receiving failed: {:?}", err)),
+        };
+        if !passes(&args.filters, &frame) {
+            continue;
+        }
+        count += 1;
+
+        let (host, device) = *start.get_or_insert((unix_micros(), micros));
+        let elapsed = micros.wrapping_sub(device);
+        let time = match args.timestamp {
+            TimeMode::Absolute => Some(seconds(host + elapsed)),
+            TimeMode::Delta => Some(seconds(elapsed - previous.min(elapsed))),
+            TimeMode::Zero => Some(seconds(elapsed)),
+            TimeMode::None => None,
+        };
+        previous = elapsed;
+
+        if !args.quiet {
+            match args.style {
+                Style::Candump => print_candump(time, &interface, &frame),
+                Style::View => print_view(count, time, &frame),
+            }
+        }
+        if let Some(output) = output.as_mut() {
+            output.write(&Record::new(Timestamp::from_micros(host + elapsed), frame))?;
+        }
+    }
+
+    if let Some(output) = output {
+        output.finish()?;
+    }
+    Ok(count)
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler = running.clone();
+    if let Err(err) = ctrlc::set_handler(move || handler.store(false, Ordering::Relaxed)) {
+        eprintln!("installing the Ctrl-C handler failed: {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    match run(args, &running) {
+        Ok(count) => {
+            eprintln!("{} frames received", count);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcan_basic::socket::{CanFrame, MessageType};
+
+    fn frame(id: u32, msg_type: MessageType) -> Frame {
+        Frame::Can(CanFrame::new(id, msg_type, &[]).unwrap())
+    }
+
+    #[test]
+    fn filter_001() {
+        let filters = [
+            parse_filter("100:7F0").unwrap(),
+            parse_filter("105~7FF").unwrap(),
+        ];
+        assert!(passes(&filters, &frame(0x10A, MessageType::Standard)));
+        assert!(!passes(&filters, &frame(0x105, MessageType::Standard)));
+        assert!(!passes(&filters, &frame(0x110, MessageType::Standard)));
+        assert!(!passes(&filters, &frame(0x10A, MessageType::Extended)));
+
+        let filters = [parse_filter("18FF0000:1FFF0000").unwrap()];
+        assert!(passes(&filters, &frame(0x18FF_1234, MessageType::Extended)));
+        assert!(!passes(&filters, &frame(0x123, MessageType::Standard)));
+        assert!(parse_filter("100").is_err());
+        assert!(parse_filter("10G:7FF").is_err());
+    }
+}
@@ -0,0 +1,415 @@
+//! Forwards traffic between two channels with the rules of a configuration file, e.g.
+//!
+//! ```text
+//! # Sides and their bitrates, `fd` opens a side in FD mode.
+//! a = usb1
+//! b = usb2
+//! bitrate = 500k
+//! b.fd = default
+//! # Frames matching no rule are forwarded, `block` drops them.
+//! unmatched = forward
+//!
+//! [rule diagnostics]
+//! id = 7E0
+//! mask = 7F0
+//! action = block
+//!
+//! [rule speed]
+//! id = 0C0
+//! route = a-b
+//! translate = 1C0
+//! rewrite = 0:F0:A0
+//! min_interval_ms = 100
+//! ```
+//!
+//! Identifiers, masks and rewrites are hexadecimal. Rules are matched in file order and the hit
+//! counters are printed periodically and on exit.
+
+use clap::Parser;
+use pcan_basic::bus::AnyBus;
+use pcan_basic::gateway::{Gateway, GatewayReport, Route, Rule};
+use pcan_basic::replay::{CanFdSink, CanSink, ReplaySink};
+use pcan_basic::socket::{Baudrate, CanSocket, EXTENDED_MASK, FD_BITRATE_500K_2M};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Forwards traffic between two PCAN channels")]
+struct Args {
+    /// Configuration file
+    config: PathBuf,
+    /// Seconds between two reports of the counters, 0 reports on exit only
+    #[arg(short, long, default_value_t = 10)]
+    stats: u64,
+    /// Checks the configuration and prints the rules without opening the channels
+    #[arg(long)]
+    check: bool,
+}
+
+/* Configuration */
+
+#[derive(Debug, PartialEq)]
+struct Side {
+    bus: Option<AnyBus>,
+    bitrate: Option<Baudrate>,
+    fd: Option<String>,
+}
+
+#[derive(Debug)]
+struct Config {
+    a: Side,
+    b: Side,
+    bitrate: Baudrate,
+    fd: Option<String>,
+    block_unmatched: bool,
+    rules: Vec<Rule>,
+}
+
+fn hex(value: &str) -> Result<u32, String> {
+    u32::from_str_radix(value, 16).map_err(|_| format!("invalid hex value `{}`", value))
+}
+
+fn fd_bitrate(value: &str) -> String {
+    match value {
+        "default" => String::from(FD_BITRATE_500K_2M),
+        value => String::from(value),
+    }
+}
+
+/// Keys of a rule section, turned into a rule at the end of the section.
+#[derive(Debug, Default)]
+struct RuleKeys {
+    name: String,
+    id: Option<u32>,
+    mask: Option<u32>,
+    block: bool,
+    route: Option<Route>,
+    extended: Option<bool>,
+    translation: Option<u32>,
+    rewrites: Vec<(usize, u8, u8)>,
+    min_interval: Option<Duration>,
+}
+
+impl RuleKeys {
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "id" => self.id = Some(hex(value)?),
+            "mask" => self.mask = Some(hex(value)?),
+            "action" => {
+                self.block = match value {
+                    "forward" => false,
+                    "block" => true,
+                    _ => return Err(format!("unknown action `{}`", value)),
+                }
+            }
+            "route" => {
+                self.route = match value {
+                    "a-b" => Some(Route::AToB),
+                    "b-a" => Some(Route::BToA),
+                    "both" => None,
+                    _ => return Err(format!("unknown route `{}`", value)),
+                }
+            }
+            "extended" => {
+                self.extended = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid boolean `{}`", value))?,
+                )
+            }
+            "translate" => self.translation = Some(hex(value)?),
+            "rewrite" => {
+                let parts = value.split(':').map(hex).collect::<Result<Vec<_>, _>>()?;
+                match parts[..] {
+                    [index, mask, byte] if mask <= 0xFF && byte <= 0xFF => {
+                        self.rewrites.push((index as usize, mask as u8, byte as u8))
+                    }
+                    _ => {
+                        return Err(format!(
+                            "invalid rewrite `{}`, expected INDEX:MASK:VALUE",
+                            value
+                        ))
+                    }
+                }
+            }
+            "min_interval_ms" => {
+                let millis = value
+                    .parse()
+                    .map_err(|_| format!("invalid interval `{}`", value))?;
+                self.min_interval = Some(Duration::from_millis(millis));
+            }
+            _ => return Err(format!("unknown key `{}`", key)),
+        }
+        Ok(())
+    }
+
+    fn build(self) -> Result<Rule, String> {
+        let id = self
+            .id
+            .ok_or_else(|| format!("rule `{}` has no `id`", self.name))?;
+        let mask = self.mask.unwrap_or(EXTENDED_MASK);
+        let mut rule = match self.block {
+            true => Rule::block(&self.name, id, mask),
+            false => Rule::forward(&self.name, id, mask),
+        };
+        if let Some(route) = self.route {
+            rule = rule.with_route(route);
+        }
+        if let Some(extended) = self.extended {
+            rule = rule.with_extended(extended);
+        }
+        if let Some(translation) = self.translation {
+            rule = rule.with_translation(translation);
+        }
+        if let Some(interval) = self.min_interval {
+            rule = rule.with_min_interval(interval);
+        }
+        Ok(self
+            .rewrites
+            .into_iter()
+            .fold(rule, |rule, (index, mask, value)| {
+                rule.with_rewrite(index, mask, value)
+            }))
+    }
+}
+
+fn parse_config(text: &str) -> Result<Config, String> {
+    let mut config = Config {
+        a: Side {
+            bus: None,
+            bitrate: None,
+            fd: None,
+        },
+        b: Side {
+            bus: None,
+            bitrate: None,
+            fd: None,
+        },
+        bitrate: Baudrate::Baud500K,
+        fd: None,
+        block_unmatched: false,
+        rules: Vec::new(),
+    };
+    let mut section: Option<RuleKeys> = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let error = |err: String| format!("line {}: {}", number + 1, err);
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            let name = header
+                .strip_prefix("rule")
+                .map(str::trim)
+                .ok_or_else(|| error(format!("unknown section `{}`", header)))?;
+            if let Some(keys) = section.take() {
+                config.rules.push(keys.build()?);
+            }
+            section = Some(RuleKeys {
+                name: String::from(name),
+                ..RuleKeys::default()
+            });
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or_else(|| error(String::from("expected `key = value`")))?;
+        if let Some(keys) = &mut section {
+            keys.set(key, value).map_err(error)?;
+            continue;
+        }
+
+        let bus = |value: &str| value.parse::<AnyBus>().map_err(|err| err.to_string());
+        let bitrate = |value: &str| value.parse::<Baudrate>().map_err(|err| err.to_string());
+        match key {
+            "a" => config.a.bus = Some(bus(value).map_err(error)?),
+            "b" => config.b.bus = Some(bus(value).map_err(error)?),
+            "bitrate" => config.bitrate = bitrate(value).map_err(error)?,
+            "a.bitrate" => config.a.bitrate = Some(bitrate(value).map_err(error)?),
+            "b.bitrate" => config.b.bitrate = Some(bitrate(value).map_err(error)?),
+            "fd" => config.fd = Some(fd_bitrate(value)),
+            "a.fd" => config.a.fd = Some(fd_bitrate(value)),
+            "b.fd" => config.b.fd = Some(fd_bitrate(value)),
+            "unmatched" => {
+                config.block_unmatched = match value {
+                    "forward" => false,
+                    "block" => true,
+                    _ => return Err(error(format!("unknown action `{}`", value))),
+                }
+            }
+            _ => return Err(error(format!("unknown key `{}`", key))),
+        }
+    }
+
+    if let Some(keys) = section {
+        config.rules.push(keys.build()?);
+    }
+    if config.a.bus.is_none() || config.b.bus.is_none() {
+        return Err(String::from("both sides `a` and `b` are required"));
+    }
+    Ok(config)
+}
+
+/* Forwarding */
+
+fn open(side: &Side, config: &Config) -> Result<(CanSocket, bool), String> {
+    let bus = side.bus.expect("checked by parse_config");
+    let fd = side.fd.as_ref().or(config.fd.as_ref());
+    match fd {
+        Some(bitrate) => CanSocket::open_fd(bus, bitrate).map(|socket| (socket, true)),
+        None => CanSocket::open(bus, side.bitrate.unwrap_or(config.bitrate))
+            .map(|socket| (socket, false)),
+    }
+    .map_err(|err| format!("opening {} failed: {:?}", bus, err))
+}
+
+fn sink(socket: &CanSocket, fd: bool) -> Box<dyn ReplaySink + '_> {
+    match fd {
+        true => Box::new(CanFdSink::new(socket)),
+        false => Box::new(CanSink::new(socket)),
+    }
+}
+
+fn print_report(report: &GatewayReport) {
+    for rule in &report.rules {
+        eprintln!(
+            "rule {}: {} hits, {} forwarded, {} dropped",
+            rule.name, rule.hits, rule.forwarded, rule.dropped
+        );
+    }
+    eprintln!(
+        "{} unmatched, {} send errors, {} receive errors",
+        report.unmatched, report.send_errors, report.receive_errors
+    );
+}
+
+fn run(config: Config, stats: u64, running: &AtomicBool) -> Result<GatewayReport, String> {
+    let (a, a_fd) = open(&config.a, &config)?;
+    let (b, b_fd) = open(&config.b, &config)?;
+    let (mut a, mut b) = (sink(&a, a_fd), sink(&b, b_fd));
+
+    let mut gateway = config
+        .rules
+        .into_iter()
+        .fold(Gateway::new(), |gateway, rule| gateway.with_rule(rule));
+    if config.block_unmatched {
+        gateway = gateway.with_block_unmatched();
+    }
+
+    let mut reported = Instant::now();
+    while running.load(Ordering::Relaxed) {
+        let pending = gateway
+            .poll(a.as_mut(), b.as_mut())
+            .map_err(|err| format!("receiving failed: {:?}", err))?;
+        if !pending {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        if stats > 0 && reported.elapsed() >= Duration::from_secs(stats) {
+            print_report(gateway.report());
+            reported = Instant::now();
+        }
+    }
+    Ok(gateway.report().clone())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let config = match std::fs::read_to_string(&args.config)
+        .map_err(|err| format!("reading {} failed: {}", args.config.display(), err))
+        .and_then(|text| parse_config(&text))
+    {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    if args.check {
+        for rule in &config.rules {
+            println!("{}", rule);
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler = running.clone();
+    if let Err(err) = ctrlc::set_handler(move || handler.store(false, Ordering::Relaxed)) {
+        eprintln!("installing the Ctrl-C handler failed: {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    match run(config, args.stats, &running) {
+        Ok(report) => {
+            print_report(&report);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcan_basic::bus::UsbBus;
+
+    #[test]
+    fn parse_config_001() {
+        let config = parse_config(
+            "a = usb1\n\
+             b = usb2 # second\n\
+             b.fd = default\n\
+             unmatched = block\n\
+             [rule diagnostics]\n\
+             action = block\n\
+             id = 7E0\n\
+             mask = 7F0\n\
+             [rule speed]\n\
+             id = 0C0\n\
+             route = a-b\n\
+             rewrite = 0:F0:A0\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.a.bus, Some(AnyBus::Usb(UsbBus::USB1)));
+        assert_eq!(config.b.fd.as_deref(), Some(FD_BITRATE_500K_2M));
+        assert!(config.block_unmatched);
+        assert_eq!(
+            config.rules,
+            vec![
+                Rule::block("diagnostics", 0x7E0, 0x7F0),
+                Rule::forward("speed", 0x0C0, EXTENDED_MASK)
+                    .with_route(Route::AToB)
+                    .with_rewrite(0, 0xF0, 0xA0),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_config_002() {
+        let error = |text| parse_config(text).unwrap_err();
+        assert_eq!(error("a = usb1"), "both sides `a` and `b` are required");
+        assert_eq!(
+            error("a = can0"),
+            format!("line 1: {}", pcan_basic::bus::ParseBusError)
+        );
+        assert_eq!(error("[rule x]\nmask = 7FF"), "rule `x` has no `id`");
+        assert_eq!(
+            error("[rule x]\nid = 1\nspeed = 2"),
+            "line 3: unknown key `speed`"
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Generates traffic with random, incrementing or fixed IDs and data, e.g.
+//!
+//! ```text
+//! pcan-gen usb1 --rate 100
+//! pcan-gen usb1 --id 7E0 --data i --length 8 --load 40
+//! pcan-gen usb1 --fd --brs --extended --length r --load 60
+//! ```
+//!
+//! Send errors like a full transmit queue are counted and reported every second.
+
+use clap::Parser;
+use pcan_basic::bus::AnyBus;
+use pcan_basic::error::PcanError;
+use pcan_basic::format::Frame;
+use pcan_basic::socket::{
+    Baudrate, CanFdFrame, CanFrame, CanSocket, MessageType, SendCan, SendCanFd, EXTENDED_MASK,
+    FD_BITRATE_500K_2M, STANDARD_MASK,
+};
+use pcan_basic::stats::BusTiming;
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Data lengths of CAN FD frames.
+const FD_LENGTHS: [usize; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Generates traffic on a PCAN channel")]
+struct Args {
+    /// Channel, e.g. usb1, pci2 or PCAN_LANBUS1
+    channel: AnyBus,
+    /// Bitrate of a classic channel, e.g. 500k or 1M
+    #[arg(short, long, default_value = "500k")]
+    bitrate: Baudrate,
+    /// Opens the channel in FD mode, optionally with --fd=BITRATE in the notation of the driver
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = FD_BITRATE_500K_2M)]
+    fd: Option<String>,
+    /// ID: r for random, i for incrementing or a hex ID
+    #[arg(short = 'I', long, default_value = "r", value_parser = parse_id)]
+    id: IdMode,
+    /// Data length: r for random or a number of bytes
+    #[arg(short = 'L', long, value_parser = parse_length)]
+    length: Option<Length>,
+    /// Data: r for random, i for incrementing bytes or hex bytes
+    #[arg(short = 'D', long, default_value = "r", value_parser = parse_data)]
+    data: DataMode,
+    /// Sends extended frames
+    #[arg(short, long)]
+    extended: bool,
+    /// Sets the bitrate switch of CAN FD frames
+    #[arg(long)]
+    brs: bool,
+    /// Frames per second
+    #[arg(short, long, conflicts_with = "load")]
+    rate: Option<f64>,
+    /// Bus load in percent, estimated from the bit lengths of the frames
+    #[arg(short, long)]
+    load: Option<f64>,
+    /// Stops after the given number of frames
+    #[arg(short = 'n', long)]
+    count: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+enum IdMode {
+    Random,
+    Increment,
+    Fixed(u32),
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Length {
+    Random,
+    Fixed(usize),
+}
+
+#[derive(Debug, Clone)]
+enum DataMode {
+    Random,
+    Increment,
+    Fixed(Vec<u8>),
+}
+
+fn parse_id(text: &str) -> Result<IdMode, String> {
+    match text {
+        "r" => Ok(IdMode::Random),
+        "i" => Ok(IdMode::Increment),
+        id => u32::from_str_radix(id, 16)
+            .map(IdMode::Fixed)
+            .map_err(|_| format!("invalid hex ID `{}`", id)),
+    }
+}
+
+fn parse_length(text: &str) -> Result<Length, String> {
+    match text {
+        "r" => Ok(Length::Random),
+        length => match length.parse() {
+            Ok(length) if length <= 64 => Ok(Length::Fixed(length)),
+            _ => Err(format!("invalid length `{}`", length)),
+        },
+    }
+}
+
+fn parse_data(text: &str) -> Result<DataMode, String> {
+    match text {
+        "r" => Ok(DataMode::Random),
+        "i" => Ok(DataMode::Increment),
+        data if data.len() & 1 == 0 && data.len() <= 128 => data
+            .as_bytes()
+            .chunks(2)
+            .map(|digits| match digits.iter().all(u8::is_ascii_hexdigit) {
+                true => u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok(),
+                false => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(DataMode::Fixed)
+            .ok_or_else(|| format!("invalid hex data `{}`", data)),
+        data => Err(format!("invalid hex data `{}`", data)),
+    }
+}
+
+/// Xorshift generator, good enough for test traffic.
+struct Random(u64);
+
+impl Random {
+    fn new() -> Random {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+        Random(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/* Generator */
+
+struct Generator {
+    args: Args,
+    random: Random,
+    counter: u64,
+}
+
+impl Generator {
+    fn frame(&mut self) -> CanFdFrame {
+        let fd = self.args.fd.is_some();
+        let (msg_type, id_mask) = match self.args.extended {
+            true => (MessageType::Extended, EXTENDED_MASK),
+            false => (MessageType::Standard, STANDARD_MASK),
+        };
+        let id = match self.args.id {
+            IdMode::Random => self.random.next() as u32 & id_mask,
+            IdMode::Increment => self.counter as u32 & id_mask,
+            IdMode::Fixed(id) => id & id_mask,
+        };
+
+        let max = if fd { 64 } else { 8 };
+        let length = match (self.args.length, &self.args.data) {
+            (Some(Length::Fixed(length)), _) => length.min(max),
+            (None, DataMode::Fixed(data)) => data.len().min(max),
+            (_, _) if fd => FD_LENGTHS[self.random.next() as usize % FD_LENGTHS.len()],
+            (_, _) => self.random.next() as usize % 9,
+        };
+        let data = match &self.args.data {
+            DataMode::Random => (0..length).map(|_| self.random.next() as u8).collect(),
+            DataMode::Increment => vec![self.counter as u8; length],
+            DataMode::Fixed(data) => {
+                let mut data = data.clone();
+                data.resize(length, 0);
+                data
+            }
+        };
+        self.counter += 1;
+
+        match fd {
+            true => {
+                let mut frame = CanFdFrame::new(id, msg_type, &data).unwrap();
+                frame.set_bitrate_switch(self.args.brs);
+                frame
+            }
+            false => CanFdFrame::from(CanFrame::new(id, msg_type, &data).unwrap()),
+        }
+    }
+}
+
+fn send(socket: &CanSocket, frame: CanFdFrame, fd: bool) -> Result<(), PcanError> {
+    match fd {
+        true => socket.send_fd(frame),
+        // Classic frames were converted without loss.
+        false => socket.send(
+            CanFrame::new(
+                frame.can_id(),
+                match frame.is_extended_frame() {
+                    true => MessageType::Extended,
+                    false => MessageType::Standard,
+                },
+                frame.data(),
+            )
+            .unwrap(),
+        ),
+    }
+}
+
+#[derive(Debug, Default)]
+struct Statistics {
+    sent: u64,
+    errors: BTreeMap<String, u64>,
+}
+
+impl Statistics {
+    fn report(&self, rate: f64) -> String {
+        let mut text = format!("{} sent, {:.0}/s", self.sent, rate);
+        for (index, (error, count)) in self.errors.iter().enumerate() {
+            let separator = if index == 0 { ", errors: " } else { ", " };
+            text.push_str(&format!("{}{} {}", separator, error, count));
+        }
+        text
+    }
+}
+
+fn run(args: Args, running: &AtomicBool) -> Result<Statistics, String> {
+    let fd = args.fd.is_some();
+    let socket = match &args.fd {
+        Some(bitrate) => CanSocket::open_fd(args.channel, bitrate),
+        None => CanSocket::open(args.channel, args.bitrate),
+    }
+    .map_err(|err| format!("opening {} failed: {:?}", args.channel, err))?;
+
+    let timing = match fd {
+        true if args.load.is_some() => BusTiming::read(&socket)
+            .map_err(|err| format!("reading the bus speeds failed: {:?}", err))?,
+        _ => BusTiming::new(args.bitrate.bits_per_second()),
+    };
+
+    let rate = args.rate;
+    let load = args.load.map(|load| load.clamp(0.1, 100.0) / 100.0);
+    let count = args.count;
+    let mut generator = Generator {
+        args,
+        random: Random::new(),
+        counter: 0,
+    };
+
+    let mut statistics = Statistics::default();
+    let mut deadline = Instant::now();
+    let mut report = (Instant::now(), 0);
+    while running.load(Ordering::Relaxed) && count.is_none_or(|count| statistics.sent < count) {
+        let frame = generator.frame();
+        let now = Instant::now();
+        if deadline > now {
+            std::thread::sleep(deadline - now);
+        } else if now - deadline > Duration::from_millis(100) {
+            // Too far behind, e.g. after a full transmit queue, restart instead of bursting.
+            deadline = now;
+        }
+
+        match send(&socket, frame, fd) {
+            Ok(()) => statistics.sent += 1,
+            Err(err) => *statistics.errors.entry(format!("{:?}", err)).or_default() += 1,
+        }
+        if let Some(rate) = rate {
+            deadline += Duration::from_secs_f64(1.0 / rate.max(0.001));
+        } else if let Some(load) = load {
+            let time = timing.frame_time(&Frame::CanFd(frame));
+            deadline += Duration::from_secs_f64(time / load);
+        }
+
+        let elapsed = report.0.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            let rate = (statistics.sent - report.1) as f64 / elapsed.as_secs_f64();
+            eprintln!("{}", statistics.report(rate));
+            report = (Instant::now(), statistics.sent);
+        }
+    }
+    Ok(statistics)
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler = running.clone();
+    if let Err(err) = ctrlc::set_handler(move || handler.store(false, Ordering::Relaxed)) {
+        eprintln!("installing the Ctrl-C handler failed: {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    let start = Instant::now();
+    match run(args, &running) {
+        Ok(statistics) => {
+            let rate = statistics.sent as f64 / start.elapsed().as_secs_f64();
+            eprintln!("{}", statistics.report(rate));
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_001() {
+        assert!(matches!(parse_id("7E0"), Ok(IdMode::Fixed(0x7E0))));
+        assert!(matches!(parse_length("r"), Ok(Length::Random)));
+        assert!(parse_length("65").is_err());
+        assert!(matches!(parse_data("DEAD"), Ok(DataMode::Fixed(data)) if data == [0xDE, 0xAD]));
+        assert!(parse_data("DEA").is_err());
+    }
+
+    #[test]
+    fn parse_002() {
+        assert!(parse_data("aéb").is_err());
+        assert!(parse_data("+1").is_err());
+    }
+}
@@ -0,0 +1,304 @@
+//! Exposes channels to SavvyCAN as a network GVRET device, e.g.
+//!
+//! ```text
+//! pcan-gvret usb1 usb2 --listen 0.0.0.0:23
+//! ```
+//!
+//! The channels are the buses 0, 1, ... in the order given, SavvyCAN configures the first two.
+
+use clap::Parser;
+use pcan_basic::bus::AnyBus;
+use pcan_basic::gvret::{
+    encode_bus_params, encode_device_info, encode_digital_inputs, encode_ext_buses, encode_frame,
+    encode_keep_alive, encode_num_buses, encode_time_sync, BusConfig, Decoder, Request,
+};
+use pcan_basic::socket::{Baudrate, CanSocket, RecvCan, SendCan};
+use pcan_basic::special::SetListenOnly;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Exposes PCAN channels to SavvyCAN as GVRET device")]
+struct Args {
+    /// Channels, e.g. usb1 usb2
+    #[arg(required = true)]
+    channels: Vec<AnyBus>,
+    /// Address the server listens on, SavvyCAN connects to port 23
+    #[arg(short, long, default_value = "0.0.0.0:23")]
+    listen: SocketAddr,
+    /// Bitrate the channels are opened with until SavvyCAN configures them
+    #[arg(short, long, default_value = "500k")]
+    bitrate: Baudrate,
+}
+
+/* Ports */
+
+/// Channel exposed as a bus, the socket is `None` while the bus is disabled.
+struct Port {
+    bus: AnyBus,
+    config: BusConfig,
+    socket: Option<CanSocket>,
+}
+
+impl Port {
+    fn new(bus: AnyBus) -> Port {
+        Port {
+            bus,
+            config: BusConfig {
+                enabled: false,
+                listen_only: false,
+                speed: 0,
+            },
+            socket: None,
+        }
+    }
+
+    /// Reopens the channel with `config` if it differs from the current configuration.
+    fn configure(&mut self, config: BusConfig) -> Result<(), String> {
+        if config == self.config || (!config.enabled && !self.config.enabled) {
+            return Ok(());
+        }
+        self.socket = None;
+        self.config.enabled = false;
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let bitrate = Baudrate::from_bits_per_second(config.speed)
+            .ok_or_else(|| format!("{} bit/s is not supported by {}", config.speed, self.bus))?;
+        let socket = CanSocket::open(self.bus, bitrate)
+            .map_err(|err| format!("opening {} failed: {:?}", self.bus, err))?;
+        socket
+            .set_listen_only(config.listen_only)
+            .map_err(|err| format!("setting listen-only on {} failed: {:?}", self.bus, err))?;
+        self.socket = Some(socket);
+        self.config = BusConfig {
+            speed: bitrate.bits_per_second(),
+            ..config
+        };
+        Ok(())
+    }
+}
+
+/// Time base of the frame timestamps, extrapolated from the last received frame.
+struct Clock {
+    reference: Instant,
+    micros: u64,
+}
+
+impl Clock {
+    fn now(&self) -> u32 {
+        (self.micros + self.reference.elapsed().as_micros() as u64) as u32
+    }
+}
+
+/// Answer to `request`, an empty vector for requests without answer.
+fn handle(request: Request, ports: &mut [Port], clock: &Clock) -> Vec<u8> {
+    match request {
+        Request::Frame { bus, frame } => {
+            let sent = ports
+                .get(bus as usize)
+                .and_then(|port| port.socket.as_ref())
+                .map(|socket| socket.send(frame));
+            if let Some(Err(err)) = sent {
+                eprintln!("sending on bus {} failed: {:?}", bus, err);
+            }
+            Vec::new()
+        }
+        Request::EchoFrame { bus, frame } => encode_frame(bus, &frame, clock.now()),
+        Request::TimeSync => encode_time_sync(clock.now()),
+        Request::DigitalInputs => encode_digital_inputs(),
+        Request::SetupBuses(configs) => {
+            for (port, config) in ports.iter_mut().zip(configs) {
+                if let Err(err) = port.configure(config) {
+                    eprintln!("{}", err);
+                }
+            }
+            Vec::new()
+        }
+        Request::BusParams => {
+            let configs = ports.iter().map(|port| port.config).collect::<Vec<_>>();
+            encode_bus_params(&configs)
+        }
+        Request::DeviceInfo => encode_device_info(),
+        Request::KeepAlive => encode_keep_alive(),
+        Request::NumBuses => encode_num_buses(ports.len() as u8),
+        Request::ExtBuses => encode_ext_buses(),
+        Request::Ignored(_) => Vec::new(),
+    }
+}
+
+/* Server */
+
+/// Writes `data` to a non-blocking stream, waiting while the send buffer is full.
+fn write_all(stream: &mut TcpStream, mut data: &[u8]) -> io::Result<()> {
+    while !data.is_empty() {
+        match stream.write(data) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(size) => data = &data[size..],
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(1))
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Reads the pending bytes, `None` if the client disconnected.
+fn read(stream: &mut TcpStream) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut buffer = [0u8; 1024];
+    loop {
+        match stream.read(&mut buffer) {
+            Ok(0) => return None,
+            Ok(size) => data.extend_from_slice(&buffer[..size]),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Some(data),
+            Err(_) => return None,
+        }
+    }
+}
+
+fn run(args: Args, running: &AtomicBool) -> Result<(), String> {
+    let mut ports = args.channels.into_iter().map(Port::new).collect::<Vec<_>>();
+    let config = BusConfig {
+        enabled: true,
+        listen_only: false,
+        speed: args.bitrate.bits_per_second(),
+    };
+    for port in &mut ports {
+        port.configure(config)?;
+    }
+
+    let listener = TcpListener::bind(args.listen)
+        .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+        .map_err(|err| format!("listening on {} failed: {}", args.listen, err))?;
+    eprintln!("GVRET server listening on {}", args.listen);
+
+    let mut clock = Clock {
+        reference: Instant::now(),
+        micros: 0,
+    };
+    let mut client: Option<(TcpStream, Decoder)> = None;
+    while running.load(Ordering::Relaxed) {
+        if client.is_none() {
+            match listener.accept() {
+                Ok((stream, address)) => {
+                    stream
+                        .set_nonblocking(true)
+                        .and_then(|_| stream.set_nodelay(true))
+                        .map_err(|err| format!("configuring the connection failed: {}", err))?;
+                    eprintln!("{} connected", address);
+                    client = Some((stream, Decoder::new()));
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                Err(err) => return Err(format!("accepting a connection failed: {}", err)),
+            }
+        }
+
+        let mut output = Vec::new();
+        if let Some((stream, decoder)) = &mut client {
+            match read(stream) {
+                Some(data) => decoder.push(&data),
+                None => {
+                    eprintln!("client disconnected");
+                    client = None;
+                }
+            }
+        }
+        if let Some((_, decoder)) = &mut client {
+            while let Some(request) = decoder.next_request() {
+                output.extend(handle(request, &mut ports, &clock));
+            }
+        }
+
+        // Frames are read while no client is connected as well, so the queues do not overrun.
+        for (bus, port) in ports.iter().enumerate() {
+            let Some(socket) = &port.socket else {
+                continue;
+            };
+            // Stops at an empty queue, bus state changes and overruns alike.
+            while let Ok((frame, timestamp)) = socket.recv() {
+                clock = Clock {
+                    reference: Instant::now(),
+                    micros: timestamp.as_micros(),
+                };
+                if client.is_some() {
+                    let micros = timestamp.as_micros() as u32;
+                    output.extend(encode_frame(bus as u8, &frame, micros));
+                }
+            }
+        }
+
+        if output.is_empty() {
+            std::thread::sleep(Duration::from_millis(1));
+        } else if let Some((stream, _)) = &mut client {
+            if let Err(err) = write_all(stream, &output) {
+                eprintln!("client disconnected: {}", err);
+                client = None;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler = running.clone();
+    if let Err(err) = ctrlc::set_handler(move || handler.store(false, Ordering::Relaxed)) {
+        eprintln!("installing the Ctrl-C handler failed: {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    match run(args, &running) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcan_basic::bus::UsbBus;
+    use pcan_basic::socket::{CanFrame, MessageType};
+
+    #[test]
+    fn handle_001() {
+        let mut ports = vec![
+            Port::new(AnyBus::Usb(UsbBus::USB1)),
+            Port::new(AnyBus::Usb(UsbBus::USB2)),
+        ];
+        let clock = Clock {
+            reference: Instant::now(),
+            micros: 1_000,
+        };
+        assert_eq!(
+            handle(Request::NumBuses, &mut ports, &clock),
+            encode_num_buses(2)
+        );
+        assert_eq!(handle(Request::BusParams, &mut ports, &clock)[2..], [0; 10]);
+
+        let frame = CanFrame::new(0x123, MessageType::Standard, &[1]).unwrap();
+        let echo = handle(Request::EchoFrame { bus: 1, frame }, &mut ports, &clock);
+        assert_eq!(echo[6..], encode_frame(1, &frame, 0)[6..]);
+
+        // Disabling disabled buses does not open them.
+        let disabled = BusConfig {
+            enabled: false,
+            listen_only: false,
+            speed: 0,
+        };
+        let setup = Request::SetupBuses([disabled; 2]);
+        assert!(handle(setup, &mut ports, &clock).is_empty());
+        assert!(ports.iter().all(|port| port.socket.is_none()));
+    }
+}
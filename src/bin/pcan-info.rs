@@ -0,0 +1,307 @@
+//! Lists the attached channels with their hardware and driver information, e.g.
+//!
+//! ```text
+//! pcan-info
+//! pcan-info --json --firmware
+//! ```
+
+use clap::Parser;
+use pcan_basic::bus::AnyBus;
+use pcan_basic::error::PcanError;
+use pcan_basic::hw::{
+    attached_channels, ChannelConditionStatus, ChannelInformation, ControllerNumber,
+    DevicePartNumber, HardwareName, IpAddress,
+};
+use pcan_basic::info::{
+    api_version, lan_service_is_running, BitrateInfo, BitrateInfoFd, ChannelFeatures,
+    ChannelVersion, FirmwareVersion,
+};
+use pcan_basic::socket::{Baudrate, CanSocket};
+use std::fmt::Write;
+use std::process::ExitCode;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Lists the attached PCAN channels")]
+struct Args {
+    /// Prints JSON instead of a table
+    #[arg(short, long)]
+    json: bool,
+    /// Reads the firmware versions, which briefly initializes available channels
+    #[arg(short, long)]
+    firmware: bool,
+    /// Bitrate the channels are initialized with for --firmware
+    #[arg(short, long, default_value = "500k")]
+    bitrate: Baudrate,
+}
+
+/// Information of one channel, fields the channel does not provide are `None`.
+#[derive(Debug, Default)]
+struct Report {
+    channel: String,
+    condition: Option<&'static str>,
+    device_name: String,
+    device_type: u8,
+    device_id: u32,
+    hardware_name: Option<String>,
+    part_number: Option<String>,
+    controller_number: Option<u32>,
+    features: Option<Vec<&'static str>>,
+    ip_address: Option<String>,
+    driver_version: Option<String>,
+    bitrate_info: Option<String>,
+    bitrate_info_fd: Option<String>,
+    firmware_version: Option<String>,
+}
+
+fn condition(status: ChannelConditionStatus) -> &'static str {
+    match status {
+        ChannelConditionStatus::Unavailable => "unavailable",
+        ChannelConditionStatus::Available => "available",
+        ChannelConditionStatus::Occupied => "occupied",
+        ChannelConditionStatus::PcanView => "pcanview",
+    }
+}
+
+fn features(bus: &AnyBus) -> Result<Vec<&'static str>, PcanError> {
+    let mut features = Vec::new();
+    for (name, capable) in [
+        ("fd", bus.is_fd_capable()?),
+        ("delay", bus.is_delay_capable()?),
+        ("io", bus.is_io_capable()?),
+    ] {
+        if capable {
+            features.push(name);
+        }
+    }
+    Ok(features)
+}
+
+fn report(information: &ChannelInformation, args: &Args) -> Report {
+    let mut report = Report {
+        channel: format!("0x{:02X}", information.channel_handle()),
+        condition: information.channel_condition().map(condition),
+        device_name: information.device_name(),
+        device_type: information.device_type(),
+        device_id: information.device_id(),
+        ..Report::default()
+    };
+    let Ok(bus) = AnyBus::try_from(information.channel_handle()) else {
+        return report;
+    };
+
+    report.channel = bus.to_string();
+    report.hardware_name = bus.hardware_name().ok();
+    report.part_number = bus.device_part_number().ok();
+    report.controller_number = bus.controller_number().ok();
+    report.features = features(&bus).ok();
+    report.driver_version = bus
+        .channel_version()
+        .ok()
+        .map(|version| version.device_driver_name_and_version);
+    report.bitrate_info = bus
+        .bitrate_info()
+        .ok()
+        .map(|(btr0, btr1)| format!("{:02X}{:02X}", btr0, btr1));
+    report.bitrate_info_fd = bus.bitrate_info_fd().ok();
+    if let AnyBus::Lan(lan) = bus {
+        report.ip_address = lan.ip_address().ok().map(|ip| ip.to_string());
+    }
+
+    let available = information.channel_condition() == Some(ChannelConditionStatus::Available);
+    if args.firmware && available {
+        report.firmware_version = CanSocket::open(bus, args.bitrate)
+            .and_then(|socket| socket.firmware_version())
+            .ok();
+    }
+    report
+}
+
+/* Table */
+
+fn print_table(reports: &[Report], firmware: bool) {
+    let mut rows = vec![vec![
+        "CHANNEL",
+        "CONDITION",
+        "HARDWARE",
+        "PART NUMBER",
+        "CTRL",
+        "DEVICE ID",
+        "FEATURES",
+        "IP ADDRESS",
+        "FIRMWARE",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect::<Vec<_>>()];
+
+    let or_dash = |value: Option<String>| value.unwrap_or_else(|| String::from("-"));
+    for report in reports {
+        rows.push(vec![
+            report.channel.clone(),
+            or_dash(report.condition.map(String::from)),
+            or_dash(report.hardware_name.clone()),
+            or_dash(report.part_number.clone()),
+            or_dash(report.controller_number.map(|number| number.to_string())),
+            format!("0x{:08X}", report.device_id),
+            or_dash(report.features.as_ref().map(|features| features.join(","))),
+            or_dash(report.ip_address.clone()),
+            or_dash(report.firmware_version.clone()),
+        ]);
+    }
+    if !firmware {
+        rows.iter_mut().for_each(|row| {
+            row.pop();
+        });
+    }
+
+    let mut widths = vec![0; rows[0].len()];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in &rows {
+        let mut line = String::new();
+        for (cell, width) in row.iter().zip(&widths) {
+            let _ = write!(line, "{:width$}  ", cell, width = width);
+        }
+        println!("{}", line.trim_end());
+    }
+}
+
+/* JSON */
+
+fn json_string(value: &str) -> String {
+    let mut text = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => text.push_str("\\\""),
+            '\\' => text.push_str("\\\\"),
+            '\n' => text.push_str("\\n"),
+            '\r' => text.push_str("\\r"),
+            '\t' => text.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(text, "\\u{:04x}", c as u32);
+            }
+            c => text.push(c),
+        }
+    }
+    text.push('"');
+    text
+}
+
+fn json_option(value: Option<&str>) -> String {
+    value
+        .map(json_string)
+        .unwrap_or_else(|| String::from("null"))
+}
+
+fn json_report(report: &Report) -> String {
+    let features = report.features.as_ref().map(|features| {
+        let features = features.iter().map(|feature| json_string(feature));
+        format!("[{}]", features.collect::<Vec<_>>().join(", "))
+    });
+    let fields = [
+        ("channel", json_string(&report.channel)),
+        ("condition", json_option(report.condition)),
+        ("device_name", json_string(&report.device_name)),
+        ("device_type", report.device_type.to_string()),
+        ("device_id", report.device_id.to_string()),
+        (
+            "hardware_name",
+            json_option(report.hardware_name.as_deref()),
+        ),
+        ("part_number", json_option(report.part_number.as_deref())),
+        (
+            "controller_number",
+            report
+                .controller_number
+                .map_or(String::from("null"), |number| number.to_string()),
+        ),
+        ("features", features.unwrap_or_else(|| String::from("null"))),
+        ("ip_address", json_option(report.ip_address.as_deref())),
+        (
+            "driver_version",
+            json_option(report.driver_version.as_deref()),
+        ),
+        ("bitrate_info", json_option(report.bitrate_info.as_deref())),
+        (
+            "bitrate_info_fd",
+            json_option(report.bitrate_info_fd.as_deref()),
+        ),
+        (
+            "firmware_version",
+            json_option(report.firmware_version.as_deref()),
+        ),
+    ];
+    let fields = fields
+        .iter()
+        .map(|(name, value)| format!("      \"{}\": {}", name, value))
+        .collect::<Vec<_>>();
+    format!("    {{\n{}\n    }}", fields.join(",\n"))
+}
+
+fn print_json(reports: &[Report]) {
+    let api_version = api_version().ok();
+    let lan_service = lan_service_is_running().ok();
+    let channels = reports.iter().map(json_report).collect::<Vec<_>>();
+    println!("{{");
+    println!(
+        "  \"api_version\": {},",
+        json_option(api_version.as_deref())
+    );
+    println!(
+        "  \"lan_service_running\": {},",
+        lan_service.map_or(String::from("null"), |running| running.to_string())
+    );
+    match channels.is_empty() {
+        true => println!("  \"channels\": []"),
+        false => println!("  \"channels\": [\n{}\n  ]", channels.join(",\n")),
+    }
+    println!("}}");
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let channels = match attached_channels() {
+        Ok(channels) => channels,
+        Err(err) => {
+            eprintln!("listing the attached channels failed: {:?}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let reports = channels
+        .iter()
+        .map(|information| report(information, &args))
+        .collect::<Vec<_>>();
+
+    if args.json {
+        print_json(&reports);
+    } else {
+        match api_version() {
+            Ok(version) => println!("PCAN-Basic API {}", version),
+            Err(err) => println!("PCAN-Basic API unknown: {:?}", err),
+        }
+        match lan_service_is_running() {
+            Ok(true) => println!("PCAN-LAN service running"),
+            Ok(false) => println!("PCAN-LAN service stopped"),
+            Err(_) => {}
+        }
+        println!();
+        print_table(&reports, args.firmware);
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_string_001() {
+        assert_eq!(json_string("PCAN-USB"), "\"PCAN-USB\"");
+        assert_eq!(json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\n\"");
+        assert_eq!(json_string("\u{1}"), "\"\\u0001\"");
+    }
+}
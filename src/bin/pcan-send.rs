@@ -0,0 +1,131 @@
+//! Sends frames given in `ID#DATA` notation, e.g.
+//!
+//! ```text
+//! pcan-send usb1 123#DEADBEEF 18FF0001#R
+//! pcan-send usb1 --fd 123##1000102030405060708 --repeat 10 --interval 100
+//! ```
+
+use clap::Parser;
+use pcan_basic::bus::AnyBus;
+use pcan_basic::error::PcanError;
+use pcan_basic::socket::{
+    Baudrate, CanFdFrame, CanFrame, CanSocket, ParseFrameError, SendCan, SendCanFd,
+    FD_BITRATE_500K_2M,
+};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Time a full transmit queue is retried for.
+const RETRY_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Sends frames on a PCAN channel")]
+struct Args {
+    /// Channel, e.g. usb1, pci2 or PCAN_LANBUS1
+    channel: AnyBus,
+    /// Frames like 123#DEADBEEF, 0000ABCD#R4 or 123##1DEADBEEF (CAN FD with flags)
+    #[arg(required = true, value_parser = parse_frame)]
+    frames: Vec<Message>,
+    /// Bitrate of a classic channel, e.g. 500k or 1M
+    #[arg(short, long, default_value = "500k")]
+    bitrate: Baudrate,
+    /// Opens the channel in FD mode, optionally with --fd=BITRATE in the notation of the driver
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = FD_BITRATE_500K_2M)]
+    fd: Option<String>,
+    /// Sends the frames the given number of times, 0 until Ctrl-C
+    #[arg(short, long, default_value_t = 1)]
+    repeat: u64,
+    /// Milliseconds between two frames
+    #[arg(short, long, default_value_t = 0)]
+    interval: u64,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Message {
+    Can(CanFrame),
+    CanFd(CanFdFrame),
+}
+
+fn parse_frame(text: &str) -> Result<Message, ParseFrameError> {
+    match text.contains("##") {
+        true => text.parse().map(Message::CanFd),
+        false => text.parse().map(Message::Can),
+    }
+}
+
+/// Sends a frame, retrying while the transmit queue is full.
+fn send(socket: &CanSocket, message: Message, fd: bool) -> Result<(), PcanError> {
+    let start = Instant::now();
+    loop {
+        let result = match (message, fd) {
+            (Message::Can(frame), false) => socket.send(frame),
+            (Message::Can(frame), true) => socket.send_fd(CanFdFrame::from(frame)),
+            (Message::CanFd(frame), _) => socket.send_fd(frame),
+        };
+        match result {
+            Err(PcanError::XmtFull | PcanError::QxmtFull) if start.elapsed() < RETRY_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            result => return result,
+        }
+    }
+}
+
+fn run(args: Args, running: &AtomicBool) -> Result<u64, String> {
+    let fd = args.fd.is_some();
+    if !fd
+        && args
+            .frames
+            .iter()
+            .any(|frame| matches!(frame, Message::CanFd(_)))
+    {
+        return Err(String::from("CAN FD frames require --fd"));
+    }
+
+    let socket = match &args.fd {
+        Some(bitrate) => CanSocket::open_fd(args.channel, bitrate),
+        None => CanSocket::open(args.channel, args.bitrate),
+    }
+    .map_err(|err| format!("opening {} failed: {:?}", args.channel, err))?;
+
+    let mut sent = 0;
+    let mut round = 0;
+    while args.repeat == 0 || round < args.repeat {
+        for message in &args.frames {
+            if !running.load(Ordering::Relaxed) {
+                return Ok(sent);
+            }
+            if sent > 0 && args.interval > 0 {
+                std::thread::sleep(Duration::from_millis(args.interval));
+            }
+            send(&socket, *message, fd).map_err(|err| format!("sending failed: {:?}", err))?;
+            sent += 1;
+        }
+        round += 1;
+    }
+    Ok(sent)
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler = running.clone();
+    if let Err(err) = ctrlc::set_handler(move || handler.store(false, Ordering::Relaxed)) {
+        eprintln!("installing the Ctrl-C handler failed: {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    match run(args, &running) {
+        Ok(count) => {
+            eprintln!("{} frames sent", count);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
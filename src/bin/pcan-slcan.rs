@@ -0,0 +1,339 @@
+//! Emulates an SLCAN adapter on a pseudo-terminal for tools speaking the Lawicel protocol, e.g.
+//!
+//! ```text
+//! pcan-slcan usb1 --link /tmp/ttySLCAN0
+//! slcand -o -s6 /tmp/ttySLCAN0 slcan0
+//! ```
+
+use clap::Parser;
+use pcan_basic::bus::AnyBus;
+use pcan_basic::error::PcanError;
+use pcan_basic::slcan::{format_frame, format_status, transmit_answer, Command, ERROR, OK};
+use pcan_basic::socket::{Baudrate, BusStatus, CanSocket, RecvCan, SendCan};
+use std::ffi::CStr;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const VERSION: &str = "V1013\r";
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Emulates an SLCAN adapter for a PCAN channel on a pseudo-terminal"
+)]
+struct Args {
+    /// Channel, e.g. usb1, pci2 or PCAN_LANBUS1
+    channel: AnyBus,
+    /// Creates a symbolic link to the pseudo-terminal, e.g. /tmp/ttySLCAN0
+    #[arg(short, long)]
+    link: Option<PathBuf>,
+    /// Bitrate used until an S command selects another one
+    #[arg(short, long, default_value = "500k")]
+    bitrate: Baudrate,
+    /// Serial number answered to the N command, four letters or digits
+    #[arg(long, default_value = "PCAN", value_parser = parse_serial)]
+    serial: String,
+}
+
+fn parse_serial(text: &str) -> Result<String, String> {
+    match text.len() == 4 && text.bytes().all(|byte| byte.is_ascii_alphanumeric()) {
+        true => Ok(text.to_string()),
+        false => Err(format!("expected four letters or digits, got `{}`", text)),
+    }
+}
+
+/* Pseudo-terminal */
+
+struct Pty {
+    master: OwnedFd,
+    /// Kept open, so reading the master does not fail while no tool has the terminal open.
+    _slave: OwnedFd,
+    path: PathBuf,
+}
+
+fn last_error<T>() -> io::Result<T> {
+    Err(io::Error::last_os_error())
+}
+
+/// Opens a pseudo-terminal in raw mode with a non-blocking master.
+fn open_pty() -> io::Result<Pty> {
+    unsafe {
+        let master = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        if master < 0 {
+            return last_error();
+        }
+        let master = OwnedFd::from_raw_fd(master);
+        if libc::grantpt(master.as_raw_fd()) != 0 || libc::unlockpt(master.as_raw_fd()) != 0 {
+            return last_error();
+        }
+        let mut name = [0 as libc::c_char; 128];
+        if libc::ptsname_r(master.as_raw_fd(), name.as_mut_ptr(), name.len()) != 0 {
+            return last_error();
+        }
+        let path = CStr::from_ptr(name.as_ptr());
+
+        let slave = libc::open(path.as_ptr(), libc::O_RDWR | libc::O_NOCTTY);
+        if slave < 0 {
+            return last_error();
+        }
+        let slave = OwnedFd::from_raw_fd(slave);
+        let mut termios = std::mem::zeroed::<libc::termios>();
+        if libc::tcgetattr(slave.as_raw_fd(), &mut termios) != 0 {
+            return last_error();
+        }
+        libc::cfmakeraw(&mut termios);
+        if libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios) != 0 {
+            return last_error();
+        }
+
+        let flags = libc::fcntl(master.as_raw_fd(), libc::F_GETFL);
+        if flags < 0 || libc::fcntl(master.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) < 0
+        {
+            return last_error();
+        }
+        Ok(Pty {
+            master,
+            _slave: slave,
+            path: PathBuf::from(path.to_string_lossy().into_owned()),
+        })
+    }
+}
+
+impl Pty {
+    /// Reads the pending bytes, an empty vector if none are pending.
+    fn read(&self) -> io::Result<Vec<u8>> {
+        let mut buffer = [0u8; 1024];
+        let size = unsafe {
+            libc::read(
+                self.master.as_raw_fd(),
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+            )
+        };
+        if size < 0 {
+            let err = io::Error::last_os_error();
+            return match err.kind() {
+                io::ErrorKind::WouldBlock => Ok(Vec::new()),
+                _ => Err(err),
+            };
+        }
+        Ok(buffer[..size as usize].to_vec())
+    }
+
+    fn write_all(&self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let size = unsafe {
+                libc::write(
+                    self.master.as_raw_fd(),
+                    data.as_ptr() as *const libc::c_void,
+                    data.len(),
+                )
+            };
+            if size < 0 {
+                let err = io::Error::last_os_error();
+                match err.kind() {
+                    io::ErrorKind::WouldBlock => std::thread::sleep(Duration::from_millis(1)),
+                    _ => return Err(err),
+                }
+            } else {
+                data = &data[size as usize..];
+            }
+        }
+        Ok(())
+    }
+}
+
+/* Adapter */
+
+/// State of the emulated adapter, the channel is initialized by the O command.
+struct Adapter {
+    channel: AnyBus,
+    bitrate: Baudrate,
+    serial: String,
+    socket: Option<CanSocket>,
+    timestamps: bool,
+    overrun: bool,
+}
+
+impl Adapter {
+    fn new(channel: AnyBus, bitrate: Baudrate, serial: String) -> Adapter {
+        Adapter {
+            channel,
+            bitrate,
+            serial,
+            socket: None,
+            timestamps: false,
+            overrun: false,
+        }
+    }
+
+    /// Answer to a command line without the carriage return.
+    fn handle(&mut self, line: &str) -> Vec<u8> {
+        let Ok(command) = line.parse::<Command>() else {
+            return ERROR.to_vec();
+        };
+        let answer = match (command, &self.socket) {
+            (Command::Setup(bitrate), None) => {
+                self.bitrate = bitrate;
+                OK
+            }
+            (Command::Timestamps(timestamps), None) => {
+                self.timestamps = timestamps;
+                OK
+            }
+            (Command::Open, None) => match CanSocket::open(self.channel, self.bitrate) {
+                Ok(socket) => {
+                    self.socket = Some(socket);
+                    self.overrun = false;
+                    OK
+                }
+                Err(err) => {
+                    eprintln!("opening {} failed: {:?}", self.channel, err);
+                    ERROR
+                }
+            },
+            (Command::Close, Some(_)) => {
+                self.socket = None;
+                OK
+            }
+            (Command::Transmit(frame), Some(socket)) => match socket.send(frame) {
+                Ok(()) => transmit_answer(&frame),
+                Err(_) => ERROR,
+            },
+            (Command::Status, Some(socket)) => match socket.bus_status() {
+                Ok(state) => {
+                    let answer = format_status(state, self.overrun);
+                    self.overrun = false;
+                    return answer.into_bytes();
+                }
+                Err(_) => ERROR,
+            },
+            (Command::Version, _) => VERSION.as_bytes(),
+            (Command::SerialNumber, _) => return format!("N{}\r", self.serial).into_bytes(),
+            _ => ERROR,
+        };
+        answer.to_vec()
+    }
+
+    /// Appends the frames received on the open channel to `output`.
+    fn receive(&mut self, output: &mut Vec<u8>) {
+        let Some(socket) = &self.socket else {
+            return;
+        };
+        loop {
+            match socket.recv() {
+                Ok((frame, timestamp)) => {
+                    let millis = self.timestamps.then(|| timestamp.as_micros() / 1000);
+                    output.extend_from_slice(format_frame(&frame, millis).as_bytes());
+                }
+                Err(PcanError::Overrun | PcanError::QOverrun) => self.overrun = true,
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+fn run(args: Args, running: &AtomicBool) -> Result<(), String> {
+    let pty = open_pty().map_err(|err| format!("opening a pseudo-terminal failed: {}", err))?;
+    if let Some(link) = &args.link {
+        if link.is_symlink() {
+            let _ = std::fs::remove_file(link);
+        }
+        std::os::unix::fs::symlink(&pty.path, link)
+            .map_err(|err| format!("linking {} failed: {}", link.display(), err))?;
+    }
+    let path = args.link.as_ref().unwrap_or(&pty.path);
+    eprintln!("{} emulated on {}", args.channel, path.display());
+
+    let mut adapter = Adapter::new(args.channel, args.bitrate, args.serial);
+    let mut input = Vec::new();
+    let result = loop {
+        if !running.load(Ordering::Relaxed) {
+            break Ok(());
+        }
+        let received = match pty.read() {
+            Ok(received) => received,
+            Err(err) => break Err(format!("reading the pseudo-terminal failed: {}", err)),
+        };
+        input.extend(received.iter().filter(|byte| **byte != b'\n'));
+
+        let mut output = Vec::new();
+        while let Some(end) = input.iter().position(|byte| *byte == b'\r') {
+            let line = String::from_utf8_lossy(&input[..end]).into_owned();
+            input.drain(..=end);
+            output.extend(adapter.handle(&line));
+        }
+        adapter.receive(&mut output);
+
+        if output.is_empty() {
+            std::thread::sleep(Duration::from_millis(1));
+        } else if let Err(err) = pty.write_all(&output) {
+            break Err(format!("writing the pseudo-terminal failed: {}", err));
+        }
+    };
+
+    if let Some(link) = &args.link {
+        let _ = std::fs::remove_file(link);
+    }
+    result
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler = running.clone();
+    if let Err(err) = ctrlc::set_handler(move || handler.store(false, Ordering::Relaxed)) {
+        eprintln!("installing the Ctrl-C handler failed: {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    match run(args, &running) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcan_basic::bus::UsbBus;
+
+    #[test]
+    fn adapter_001() {
+        let mut adapter = Adapter::new(
+            AnyBus::Usb(UsbBus::USB1),
+            Baudrate::Baud500K,
+            String::from("A123"),
+        );
+        assert_eq!(adapter.handle("V"), b"V1013\r");
+        assert_eq!(adapter.handle("N"), b"NA123\r");
+        assert_eq!(adapter.handle("S4"), OK);
+        assert_eq!(adapter.bitrate, Baudrate::Baud125K);
+        assert_eq!(adapter.handle("Z1"), OK);
+        assert!(adapter.timestamps);
+
+        // Frames, status and close require an open channel.
+        assert_eq!(adapter.handle("t1230"), ERROR);
+        assert_eq!(adapter.handle("F"), ERROR);
+        assert_eq!(adapter.handle("C"), ERROR);
+        assert_eq!(adapter.handle("Q"), ERROR);
+    }
+
+    #[test]
+    fn parse_serial_001() {
+        assert_eq!(parse_serial("A123"), Ok(String::from("A123")));
+        assert!(parse_serial("A12").is_err());
+        assert!(parse_serial("A1234").is_err());
+        assert!(parse_serial("A1\r3").is_err());
+        assert!(parse_serial("A1\u{e9}").is_err());
+    }
+}
@@ -0,0 +1,813 @@
+//! Interactive monitor with one row per CAN-ID and a scrolling trace, e.g.
+//!
+//! ```text
+//! pcan-top usb1
+//! pcan-top usb1 --fd
+//! pcan-top --file recording.trc --speed 2
+//! ```
+//!
+//! Keys: `q` quits, space pauses, `f` edits the ID filter, `l` toggles listen-only, `c` clears
+//! and the arrow keys scroll the table.
+
+use clap::Parser;
+use pcan_basic::bus::AnyBus;
+use pcan_basic::error::PcanError;
+use pcan_basic::format::asc::AscReader;
+use pcan_basic::format::blf::BlfReader;
+use pcan_basic::format::candump::CandumpReader;
+use pcan_basic::format::mdf::MdfReader;
+use pcan_basic::format::trc::TrcReader;
+use pcan_basic::format::{Frame, Record};
+use pcan_basic::socket::{
+    Baudrate, BusState, BusStatus, CanSocket, RecvCan, RecvCanFd, Timestamp, FD_BITRATE_500K_2M,
+};
+use pcan_basic::special::{ListenOnly, SetListenOnly};
+use pcan_basic::stats::{BusStatistics, BusTiming, IdStatistics, Snapshot};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use ratatui::DefaultTerminal;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Debug;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Interval in which an empty receive queue is polled.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+/// Interval in which the screen is redrawn and keys are read.
+const FRAME_INTERVAL: Duration = Duration::from_millis(50);
+/// Interval in which the bus state is queried.
+const STATUS_INTERVAL: Duration = Duration::from_millis(250);
+/// Time changed bytes stay highlighted.
+const HIGHLIGHT: Duration = Duration::from_secs(1);
+/// Time without records after which the load and rates are shown as zero.
+const IDLE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Interactive monitor of a PCAN channel or a recording"
+)]
+struct Args {
+    /// Channel, e.g. usb1, pci2 or PCAN_LANBUS1
+    #[arg(required_unless_present = "file", conflicts_with = "file")]
+    channel: Option<AnyBus>,
+    /// Plays a recording (.trc, .asc, .blf, .mf4 or candump .log) instead of reading a channel
+    #[arg(long)]
+    file: Option<PathBuf>,
+    /// Playback speed of --file, 0 reads the file at once
+    #[arg(long, default_value_t = 1.0)]
+    speed: f64,
+    /// Bitrate of a classic channel or of the recording, e.g. 500k or 1M
+    #[arg(short, long, default_value = "500k")]
+    bitrate: Baudrate,
+    /// Opens the channel in FD mode, optionally with --fd=BITRATE in the notation of the driver
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = FD_BITRATE_500K_2M)]
+    fd: Option<String>,
+    /// Number of lines kept in the trace
+    #[arg(long, default_value_t = 1000)]
+    trace: usize,
+}
+
+/* Sources */
+
+/// Record or error of the capture or playback thread.
+enum Update {
+    Record(Record),
+    Error(String),
+}
+
+fn debug<E: Debug>(err: E) -> String {
+    format!("{:?}", err)
+}
+
+type Records = Box<dyn Iterator<Item = Result<Record, String>> + Send>;
+
+/// Opens a recording by its file extension.
+fn open_recording(path: &Path) -> Result<Records, String> {
+    fn boxed<I, E>(records: I) -> Records
+    where
+        I: Iterator<Item = Result<Record, E>> + Send + 'static,
+        E: Debug,
+    {
+        Box::new(records.map(|record| record.map_err(debug)))
+    }
+
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "trc" => TrcReader::open(path).map(boxed).map_err(debug),
+        "asc" => AscReader::open(path).map(boxed).map_err(debug),
+        "blf" => BlfReader::open(path).map(boxed).map_err(debug),
+        "mf4" | "mdf" => MdfReader::open(path).map(boxed).map_err(debug),
+        "log" => CandumpReader::open(path).map(boxed).map_err(debug),
+        _ => Err(format!("unknown recording format of {}", path.display())),
+    }
+}
+
+/// Sends the records of a recording with their original timing divided by `speed`.
+fn play(records: Records, speed: f64, sender: Sender<Update>) {
+    let start = Instant::now();
+    let mut origin = None;
+    for record in records {
+        let update = match record {
+            Ok(record) => {
+                let micros = record.timestamp.as_micros();
+                let origin = *origin.get_or_insert(micros);
+                if speed > 0.0 {
+                    let due = micros.saturating_sub(origin) as f64 / 1_000_000.0 / speed;
+                    if let Some(wait) = Duration::from_secs_f64(due).checked_sub(start.elapsed()) {
+                        thread::sleep(wait);
+                    }
+                }
+                Update::Record(record)
+            }
+            Err(err) => Update::Error(err),
+        };
+        if sender.send(update).is_err() {
+            return;
+        }
+    }
+}
+
+/// Sends the received frames, errors are sent once until a frame is received again.
+fn capture(socket: &CanSocket, fd: bool, sender: Sender<Update>) {
+    let mut last_error = None;
+    loop {
+        let result = match fd {
+            true => socket
+                .recv_fd()
+                .map(|(frame, micros)| (Frame::CanFd(frame), micros)),
+            false => socket
+                .recv()
+                .map(|(frame, timestamp)| (Frame::Can(frame), timestamp.as_micros())),
+        };
+        let update = match result {
+            Ok((frame, micros)) => {
+                last_error = None;
+                Update::Record(Record::new(Timestamp::from_micros(micros), frame))
+            }
+            Err(PcanError::QrcvEmpty) => {
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+            Err(err) => {
+                thread::sleep(POLL_INTERVAL);
+                if last_error.as_ref() == Some(&err) {
+                    continue;
+                }
+                let update = Update::Error(debug(&err));
+                last_error = Some(err);
+                update
+            }
+        };
+        if sender.send(update).is_err() {
+            return;
+        }
+    }
+}
+
+/* Monitor */
+
+/// Latest frame of one CAN-ID.
+#[derive(Debug)]
+struct Entry {
+    data: Vec<u8>,
+    /// Time each data byte last changed.
+    changed: Vec<Instant>,
+    fd: bool,
+}
+
+/// Line of the trace.
+#[derive(Debug)]
+struct TraceLine {
+    micros: u64,
+    id: Option<(u32, bool)>,
+    text: String,
+}
+
+#[derive(Debug)]
+struct Monitor {
+    entries: BTreeMap<(u32, bool), Entry>,
+    trace: VecDeque<TraceLine>,
+    trace_capacity: usize,
+    origin: Option<u64>,
+    statistics: BusStatistics,
+    /// Statistics shown, refreshed with the bus state.
+    snapshot: Snapshot,
+    last_record: Option<Instant>,
+    status: Option<String>,
+}
+
+impl Monitor {
+    fn new(timing: BusTiming, trace_capacity: usize) -> Monitor {
+        Monitor {
+            entries: BTreeMap::new(),
+            trace: VecDeque::new(),
+            trace_capacity,
+            origin: None,
+            statistics: BusStatistics::new(timing),
+            snapshot: Snapshot::default(),
+            last_record: None,
+            status: None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.trace.clear();
+        self.origin = None;
+        self.statistics.clear();
+        self.snapshot = Snapshot::default();
+    }
+
+    /// Measures the bus load and timing, also while the view is paused.
+    fn measure(&mut self, record: &Record, now: Instant) {
+        self.statistics.push_record(record);
+        self.last_record = Some(now);
+    }
+
+    /// Summarizes the statistics, a bus idle for [IDLE] has no load.
+    fn refresh(&mut self, now: Instant) {
+        self.snapshot = self.statistics.snapshot();
+        if self
+            .last_record
+            .is_none_or(|last| now.duration_since(last) > IDLE)
+        {
+            self.snapshot.load = 0.0;
+            self.snapshot.frame_rate = 0.0;
+            self.snapshot.error_rate = 0.0;
+        }
+    }
+
+    /// Statistics of a CAN-ID as of the last refresh.
+    fn id_statistics(&self, id: u32, extended: bool) -> Option<&IdStatistics> {
+        let ids = &self.snapshot.ids;
+        ids.binary_search_by_key(&(id, extended), |ids| (ids.can_id, ids.extended))
+            .ok()
+            .map(|index| &ids[index])
+    }
+
+    fn record(&mut self, record: &Record, now: Instant) {
+        let micros = record.timestamp.as_micros();
+        let origin = *self.origin.get_or_insert(micros);
+        let offset = micros.saturating_sub(origin);
+        let (id, extended, fd, data) = match &record.frame {
+            Frame::Can(frame) => (
+                frame.can_id(),
+                frame.is_extended_frame(),
+                false,
+                frame.data(),
+            ),
+            Frame::CanFd(frame) => (
+                frame.can_id(),
+                frame.is_extended_frame(),
+                frame.is_fd_frame(),
+                frame.data(),
+            ),
+            Frame::Error { data, .. } => {
+                self.push_trace(offset, None, format!("error frame {}", hex(data)));
+                return;
+            }
+            Frame::Status(status) => {
+                self.status = Some(status.clone());
+                self.push_trace(offset, None, status.clone());
+                return;
+            }
+        };
+
+        let entry = self.entries.entry((id, extended)).or_insert_with(|| Entry {
+            data: Vec::new(),
+            changed: Vec::new(),
+            fd,
+        });
+        entry.changed.resize(data.len(), now);
+        for (index, byte) in data.iter().enumerate() {
+            if entry.data.get(index) != Some(byte) {
+                entry.changed[index] = now;
+            }
+        }
+        entry.data = data.to_vec();
+        entry.fd = fd;
+
+        let text = format!(
+            "{:>10} [{:>2}]  {}",
+            id_text(id, extended),
+            data.len(),
+            hex(data)
+        );
+        self.push_trace(offset, Some((id, extended)), text);
+    }
+
+    fn push_trace(&mut self, micros: u64, id: Option<(u32, bool)>, text: String) {
+        if self.trace.len() >= self.trace_capacity {
+            self.trace.pop_front();
+        }
+        self.trace.push_back(TraceLine { micros, id, text });
+    }
+}
+
+fn hex(data: &[u8]) -> String {
+    let bytes = data.iter().map(|byte| format!("{:02X}", byte));
+    bytes.collect::<Vec<_>>().join(" ")
+}
+
+fn id_text(id: u32, extended: bool) -> String {
+    match extended {
+        true => format!("{:08X}", id),
+        false => format!("{:03X}", id),
+    }
+}
+
+/* Filter */
+
+/// CAN-IDs and ranges like `7E0,100-1FF`, an empty filter passes all IDs.
+#[derive(Debug, Default, PartialEq)]
+struct IdFilter(Vec<(u32, u32)>);
+
+impl IdFilter {
+    fn parse(text: &str) -> Result<IdFilter, String> {
+        let mut ranges = Vec::new();
+        for part in text
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            let (low, high) = part.split_once('-').unwrap_or((part, part));
+            let parse = |id: &str| {
+                u32::from_str_radix(id.trim(), 16).map_err(|_| format!("invalid ID `{}`", id))
+            };
+            ranges.push((parse(low)?, parse(high)?));
+        }
+        Ok(IdFilter(ranges))
+    }
+
+    fn matches(&self, id: u32) -> bool {
+        self.0.is_empty()
+            || self
+                .0
+                .iter()
+                .any(|(low, high)| (*low..=*high).contains(&id))
+    }
+}
+
+/* Application */
+
+enum Mode {
+    Normal,
+    Filter(String),
+}
+
+struct App {
+    source: String,
+    socket: Option<Arc<CanSocket>>,
+    monitor: Monitor,
+    state: Option<BusState>,
+    listen_only: Option<bool>,
+    paused: bool,
+    mode: Mode,
+    filter: IdFilter,
+    filter_text: String,
+    message: Option<String>,
+    table: TableState,
+}
+
+impl App {
+    fn new(source: String, socket: Option<Arc<CanSocket>>, monitor: Monitor) -> App {
+        let listen_only = socket.as_ref().and_then(|socket| socket.listen_only().ok());
+        App {
+            source,
+            socket,
+            monitor,
+            state: None,
+            listen_only,
+            paused: false,
+            mode: Mode::Normal,
+            filter: IdFilter::default(),
+            filter_text: String::new(),
+            message: None,
+            table: TableState::default(),
+        }
+    }
+
+    fn update(&mut self, update: Update, now: Instant) {
+        match update {
+            Update::Record(record) => {
+                self.monitor.measure(&record, now);
+                if !self.paused {
+                    self.monitor.record(&record, now);
+                }
+            }
+            Update::Error(err) => self.message = Some(err),
+        }
+    }
+
+    /// Refreshes the bus state and statistics unless the view is paused.
+    fn poll_status(&mut self) {
+        if self.paused {
+            return;
+        }
+        if let Some(socket) = &self.socket {
+            match socket.bus_status() {
+                Ok(state) => self.state = Some(state),
+                Err(err) => self.message = Some(format!("reading the bus state failed: {:?}", err)),
+            }
+        }
+        self.monitor.refresh(Instant::now());
+    }
+
+    fn toggle_listen_only(&mut self) {
+        let Some(socket) = &self.socket else {
+            self.message = Some(String::from("listen-only needs a channel"));
+            return;
+        };
+        let value = !self.listen_only.unwrap_or(false);
+        match socket.set_listen_only(value) {
+            Ok(()) => self.listen_only = Some(value),
+            Err(err) => self.message = Some(format!("setting listen-only failed: {:?}", err)),
+        }
+    }
+
+    /// Handles a key, returns `false` to quit.
+    fn key(&mut self, code: KeyCode) -> bool {
+        if let Mode::Filter(text) = &mut self.mode {
+            match code {
+                KeyCode::Enter => match IdFilter::parse(text) {
+                    Ok(filter) => {
+                        self.filter = filter;
+                        self.filter_text = text.trim().to_string();
+                        self.mode = Mode::Normal;
+                    }
+                    Err(err) => self.message = Some(err),
+                },
+                KeyCode::Esc => self.mode = Mode::Normal,
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => {}
+            }
+            return true;
+        }
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char(' ') | KeyCode::Char('p') => self.paused = !self.paused,
+            KeyCode::Char('f') => self.mode = Mode::Filter(self.filter_text.clone()),
+            KeyCode::Char('l') => self.toggle_listen_only(),
+            KeyCode::Char('c') => {
+                self.monitor.clear();
+                self.message = None;
+            }
+            KeyCode::Down => self.table.select_next(),
+            KeyCode::Up => self.table.select_previous(),
+            KeyCode::PageDown => self.table.scroll_down_by(10),
+            KeyCode::PageUp => self.table.scroll_up_by(10),
+            _ => {}
+        }
+        true
+    }
+}
+
+/* Drawing */
+
+fn header(app: &App) -> Line<'static> {
+    let state = match app.state {
+        Some(BusState::Ok) => ("OK", Color::Green),
+        Some(BusState::Light) => ("LIGHT", Color::Yellow),
+        Some(BusState::Heavy) => ("HEAVY", Color::Yellow),
+        Some(BusState::Passive) => ("PASSIVE", Color::Red),
+        Some(BusState::Off) => ("BUS OFF", Color::Red),
+        None => (app.monitor.status.as_deref().unwrap_or("-"), Color::Reset),
+    };
+    let mut spans = vec![
+        Span::styled(
+            app.source.clone(),
+            Style::new().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("  state "),
+        Span::styled(state.0.to_string(), Style::new().fg(state.1)),
+        Span::raw(format!(
+            "  load {:.1}%  {:.0} frames/s  {} IDs  {} error frames",
+            app.monitor.snapshot.load * 100.0,
+            app.monitor.snapshot.frame_rate,
+            app.monitor.entries.len(),
+            app.monitor.snapshot.error_frames
+        )),
+    ];
+    if app.listen_only == Some(true) {
+        spans.push(Span::styled("  LISTEN-ONLY", Style::new().fg(Color::Cyan)));
+    }
+    if app.paused {
+        spans.push(Span::styled("  PAUSED", Style::new().fg(Color::Magenta)));
+    }
+    if !app.filter_text.is_empty() {
+        spans.push(Span::raw(format!("  filter {}", app.filter_text)));
+    }
+    Line::from(spans)
+}
+
+fn data_line(entry: &Entry) -> Line<'static> {
+    let spans = entry
+        .data
+        .iter()
+        .zip(&entry.changed)
+        .map(|(byte, changed)| {
+            let style = match changed.elapsed() < HIGHLIGHT {
+                true => Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                false => Style::new(),
+            };
+            Span::styled(format!("{:02X} ", byte), style)
+        });
+    Line::from(spans.collect::<Vec<_>>())
+}
+
+fn draw(frame: &mut ratatui::Frame, app: &mut App) {
+    let [header_area, table_area, trace_area, footer_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(5),
+        Constraint::Percentage(35),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    frame.render_widget(Paragraph::new(header(app)), header_area);
+
+    let rows = app
+        .monitor
+        .entries
+        .iter()
+        .filter(|((id, _), _)| app.filter.matches(*id))
+        .map(|((id, extended), entry)| {
+            let fd = if entry.fd { "FD" } else { "" };
+            let statistics = app.monitor.id_statistics(*id, *extended);
+            let cycle = statistics.and_then(|statistics| statistics.cycle);
+            let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+            Row::new(vec![
+                Cell::from(id_text(*id, *extended)),
+                Cell::from(fd),
+                Cell::from(entry.data.len().to_string()),
+                Cell::from(data_line(entry)),
+                Cell::from(
+                    statistics
+                        .map_or(0, |statistics| statistics.total)
+                        .to_string(),
+                ),
+                Cell::from(cycle.map_or(String::from("-"), |cycle| {
+                    format!("{:.1}", millis(cycle.mean))
+                })),
+                Cell::from(cycle.map_or(String::from("-"), |cycle| {
+                    format!("{:.2}", millis(cycle.jitter))
+                })),
+            ])
+        });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(2),
+            Constraint::Length(3),
+            Constraint::Min(24),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+        ],
+    )
+    .header(
+        Row::new(["ID", "", "LEN", "DATA", "COUNT", "CYCLE ms", "JITTER ms"])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+    .block(Block::new().borders(Borders::TOP | Borders::BOTTOM));
+    frame.render_stateful_widget(table, table_area, &mut app.table);
+
+    let height = trace_area.height as usize;
+    let mut lines = app
+        .monitor
+        .trace
+        .iter()
+        .rev()
+        .filter(|line| line.id.is_none_or(|(id, _)| app.filter.matches(id)))
+        .take(height)
+        .map(|line| {
+            let seconds = line.micros as f64 / 1_000_000.0;
+            Line::from(format!("{:>12.6}  {}", seconds, line.text))
+        })
+        .collect::<Vec<_>>();
+    lines.reverse();
+    frame.render_widget(Paragraph::new(lines), trace_area);
+
+    let footer = match &app.mode {
+        Mode::Filter(text) => Line::from(format!(
+            "filter: {}_   IDs or ranges like 7E0,100-1FF, Enter applies, Esc cancels",
+            text
+        )),
+        Mode::Normal => match &app.message {
+            Some(message) => Line::styled(message.clone(), Style::new().fg(Color::Red)),
+            None => Line::from(
+                "q quit  space pause  f filter  l listen-only  c clear  \u{2191}\u{2193} scroll",
+            ),
+        },
+    };
+    frame.render_widget(Paragraph::new(footer), footer_area);
+}
+
+/* Main */
+
+fn start(args: &Args, sender: Sender<Update>) -> Result<App, String> {
+    let bitrate = BusTiming::new(args.bitrate.bits_per_second());
+    if let Some(path) = &args.file {
+        let records = open_recording(path)?;
+        let speed = args.speed;
+        thread::spawn(move || play(records, speed, sender));
+        let monitor = Monitor::new(bitrate, args.trace);
+        return Ok(App::new(path.display().to_string(), None, monitor));
+    }
+
+    let channel = args.channel.ok_or("a channel or --file is required")?;
+    let socket = match &args.fd {
+        Some(bitrate) => CanSocket::open_fd(channel, bitrate),
+        None => CanSocket::open(channel, args.bitrate),
+    }
+    .map_err(|err| format!("opening {} failed: {:?}", channel, err))?;
+    let timing = match args.fd.is_some() {
+        true => BusTiming::read(&socket).map_err(debug)?,
+        false => bitrate,
+    };
+
+    let socket = Arc::new(socket);
+    let fd = args.fd.is_some();
+    let capture_socket = socket.clone();
+    thread::spawn(move || capture(&capture_socket, fd, sender));
+    let monitor = Monitor::new(timing, args.trace);
+    Ok(App::new(channel.to_string(), Some(socket), monitor))
+}
+
+fn run(terminal: &mut DefaultTerminal, app: &mut App, updates: Receiver<Update>) -> io::Result<()> {
+    let mut status = Instant::now() - STATUS_INTERVAL;
+    loop {
+        let now = Instant::now();
+        // Bounded, so that keys are still handled when the records come in faster than drawn.
+        for _ in 0..10_000 {
+            match updates.try_recv() {
+                Ok(update) => app.update(update, now),
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        if status.elapsed() >= STATUS_INTERVAL {
+            app.poll_status();
+            status = Instant::now();
+        }
+
+        terminal.draw(|frame| draw(frame, app))?;
+        if event::poll(FRAME_INTERVAL)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !app.key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let (sender, receiver) = mpsc::channel();
+    let mut app = match start(&args, sender) {
+        Ok(app) => app,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app, receiver);
+    ratatui::restore();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("terminal error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcan_basic::socket::{CanFrame, MessageType};
+
+    fn record(micros: u64, id: u32, data: &[u8]) -> Record {
+        let frame = CanFrame::new(id, MessageType::Standard, data).unwrap();
+        Record::new(Timestamp::from_micros(micros), Frame::Can(frame))
+    }
+
+    fn push(monitor: &mut Monitor, record: Record, now: Instant) {
+        monitor.measure(&record, now);
+        monitor.record(&record, now);
+    }
+
+    #[test]
+    fn monitor_001() {
+        let mut monitor = Monitor::new(BusTiming::new(500_000), 2);
+        let start = Instant::now();
+        push(&mut monitor, record(1_000, 0x100, &[1, 2]), start);
+        push(
+            &mut monitor,
+            record(11_000, 0x100, &[1, 3]),
+            start + HIGHLIGHT,
+        );
+        push(
+            &mut monitor,
+            record(23_000, 0x100, &[1, 3]),
+            start + HIGHLIGHT,
+        );
+        push(&mut monitor, record(26_000, 0x200, &[]), start + HIGHLIGHT);
+        monitor.refresh(start + HIGHLIGHT);
+
+        let entry = &monitor.entries[&(0x100, false)];
+        assert_eq!(entry.changed, [start, start + HIGHLIGHT]);
+        let statistics = monitor.id_statistics(0x100, false).unwrap();
+        let cycle = statistics.cycle.unwrap();
+        assert_eq!(statistics.total, 3);
+        assert_eq!(cycle.mean, Duration::from_millis(11));
+        assert_eq!(cycle.jitter, Duration::from_millis(1));
+        assert!(monitor.id_statistics(0x200, true).is_none());
+        assert!(monitor.snapshot.load > 0.0);
+        assert_eq!(monitor.trace.len(), 2);
+        assert_eq!(monitor.trace[1].micros, 25_000);
+
+        // An idle bus has no load, the counts stay.
+        monitor.refresh(start + HIGHLIGHT + IDLE * 2);
+        assert_eq!(monitor.snapshot.load, 0.0);
+        assert_eq!(monitor.snapshot.frames, 4);
+    }
+
+    #[test]
+    fn monitor_002() {
+        let mut monitor = Monitor::new(BusTiming::new(500_000), 3);
+        let start = Instant::now();
+        let error = Frame::Error {
+            class: 0,
+            data: vec![0, 4],
+        };
+        push(&mut monitor, record(1_000, 0x100, &[1]), start);
+        let error = Record::new(Timestamp::from_micros(3_000), error);
+        push(&mut monitor, error, start);
+        let status = Frame::Status("bus heavy".to_string());
+        push(
+            &mut monitor,
+            Record::new(Timestamp::from_micros(7_000), status),
+            start,
+        );
+        monitor.refresh(start);
+
+        let micros = monitor.trace.iter().map(|line| line.micros);
+        assert_eq!(micros.collect::<Vec<_>>(), [0, 2_000, 6_000]);
+        assert_eq!(monitor.snapshot.error_frames, 1);
+
+        // Cleared traces start at zero again.
+        monitor.clear();
+        push(&mut monitor, record(50_000, 0x100, &[2]), start);
+        assert_eq!(monitor.trace[0].micros, 0);
+    }
+
+    #[test]
+    fn app_001() {
+        let monitor = Monitor::new(BusTiming::new(500_000), 3);
+        let mut app = App::new(String::from("recording"), None, monitor);
+        let now = Instant::now();
+        app.key(KeyCode::Char(' '));
+        app.update(Update::Record(record(1_000, 0x100, &[1])), now);
+        app.poll_status();
+
+        // The paused view stays as it was, the frame is still measured.
+        assert!(app.monitor.entries.is_empty());
+        assert_eq!(app.monitor.snapshot.frames, 0);
+        app.key(KeyCode::Char(' '));
+        app.poll_status();
+        assert_eq!(app.monitor.snapshot.frames, 1);
+    }
+
+    #[test]
+    fn id_filter_001() {
+        let filter = IdFilter::parse("7E0, 100-1FF").unwrap();
+        assert!(filter.matches(0x7E0));
+        assert!(filter.matches(0x180));
+        assert!(!filter.matches(0x200));
+        assert!(IdFilter::parse("").unwrap().matches(0x123));
+        assert!(IdFilter::parse("7G0").is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! Exposes a channel to a remote machine over UDP or TCP, compatible with cannelloni, e.g.
+//!
+//! ```text
+//! pcan-tunnel usb1 --listen 0.0.0.0:20000
+//! pcan-tunnel usb1 --fd --tcp --listen 0.0.0.0:20000 --stats 10
+//! ```
+
+use clap::Parser;
+use pcan_basic::bus::AnyBus;
+use pcan_basic::replay::{CanFdSink, CanSink, ReplaySink};
+use pcan_basic::socket::{Baudrate, CanSocket, FD_BITRATE_500K_2M};
+use pcan_basic::tunnel::server::TunnelServer;
+use pcan_basic::tunnel::{Transport, TunnelReport};
+use std::net::SocketAddr;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Exposes a PCAN channel over UDP or TCP")]
+struct Args {
+    /// Channel, e.g. usb1, pci2 or PCAN_LANBUS1
+    channel: AnyBus,
+    /// Address the server listens on
+    #[arg(short, long, default_value = "0.0.0.0:20000")]
+    listen: SocketAddr,
+    /// Uses TCP instead of UDP
+    #[arg(long)]
+    tcp: bool,
+    /// Bitrate of a classic channel, e.g. 500k or 1M
+    #[arg(short, long, default_value = "500k")]
+    bitrate: Baudrate,
+    /// Opens the channel in FD mode, optionally with --fd=BITRATE in the notation of the driver
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = FD_BITRATE_500K_2M)]
+    fd: Option<String>,
+    /// Prints the counters every given number of seconds, 0 only on exit
+    #[arg(short, long, default_value_t = 0)]
+    stats: u64,
+}
+
+fn print_report(report: &TunnelReport) {
+    eprintln!(
+        "{} sent, {} received, {} lost datagrams, {} dropped, {} connections",
+        report.sent, report.received, report.lost, report.dropped, report.connections
+    );
+}
+
+fn run(args: &Args, running: &AtomicBool) -> Result<TunnelReport, String> {
+    let socket = match &args.fd {
+        Some(bitrate) => CanSocket::open_fd(args.channel, bitrate),
+        None => CanSocket::open(args.channel, args.bitrate),
+    }
+    .map_err(|err| format!("opening {} failed: {:?}", args.channel, err))?;
+    let mut sink: Box<dyn ReplaySink> = match args.fd {
+        Some(_) => Box::new(CanFdSink::new(&socket)),
+        None => Box::new(CanSink::new(&socket)),
+    };
+
+    let transport = match args.tcp {
+        true => Transport::Tcp,
+        false => Transport::Udp,
+    };
+    let mut server = TunnelServer::bind(transport, args.listen)
+        .map_err(|err| format!("listening on {} failed: {:?}", args.listen, err))?;
+    eprintln!("{} exposed on {}", args.channel, server.local_addr());
+
+    let mut reported = Instant::now();
+    while running.load(Ordering::Relaxed) {
+        let pending = server
+            .poll(sink.as_mut())
+            .map_err(|err| format!("forwarding failed: {:?}", err))?;
+        if !pending {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        if args.stats > 0 && reported.elapsed() >= Duration::from_secs(args.stats) {
+            print_report(server.report());
+            reported = Instant::now();
+        }
+    }
+    Ok(*server.report())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler = running.clone();
+    if let Err(err) = ctrlc::set_handler(move || handler.store(false, Ordering::Relaxed)) {
+        eprintln!("installing the Ctrl-C handler failed: {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    match run(&args, &running) {
+        Ok(report) => {
+            print_report(&report);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
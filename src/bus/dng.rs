@@ -1,9 +1,11 @@
-use crate::bus::Bus;
+use crate::bus::{parse_name, Bus, ParseBusError};
 use crate::channel::Channel;
 use crate::df::{HasReceiveStatus, HasSetReceiveStatus};
 use crate::hw::{HasChannelCondition, HasControllerNumber, HasDevicePartNumber, HasHardwareName};
 use crate::info::{HasBitrateInfo, HasBitrateInfoFd, HasChannelFeatures, HasChannelVersion};
 use crate::pcan;
+use std::fmt;
+use std::str::FromStr;
 
 ///
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -32,6 +34,26 @@ impl TryFrom<u16> for DngBus {
     }
 }
 
+/* Bus names */
+
+impl FromStr for DngBus {
+    type Err = ParseBusError;
+
+    /// Parses names like `dng1`, `DNG1` or `PCAN_DNGBUS1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_name(s, "DNG")? {
+            1 => Ok(DngBus::DNG1),
+            _ => Err(ParseBusError),
+        }
+    }
+}
+
+impl fmt::Display for DngBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_ascii_lowercase())
+    }
+}
+
 /* Bus trait implementation */
 
 impl Bus for DngBus {
@@ -1,9 +1,11 @@
-use crate::bus::Bus;
+use crate::bus::{parse_name, Bus, ParseBusError};
 use crate::channel::Channel;
 use crate::df::{HasReceiveStatus, HasSetReceiveStatus};
 use crate::hw::{HasChannelCondition, HasControllerNumber, HasDevicePartNumber, HasHardwareName};
 use crate::info::{HasBitrateInfo, HasBitrateInfoFd, HasChannelFeatures, HasChannelVersion};
 use crate::pcan;
+use std::fmt;
+use std::str::FromStr;
 
 ///
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -60,6 +62,33 @@ impl TryFrom<u16> for IsaBus {
     }
 }
 
+/* Bus names */
+
+impl FromStr for IsaBus {
+    type Err = ParseBusError;
+
+    /// Parses names like `isa1`, `ISA1` or `PCAN_ISABUS1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_name(s, "ISA")? {
+            1 => Ok(IsaBus::ISA1),
+            2 => Ok(IsaBus::ISA2),
+            3 => Ok(IsaBus::ISA3),
+            4 => Ok(IsaBus::ISA4),
+            5 => Ok(IsaBus::ISA5),
+            6 => Ok(IsaBus::ISA6),
+            7 => Ok(IsaBus::ISA7),
+            8 => Ok(IsaBus::ISA8),
+            _ => Err(ParseBusError),
+        }
+    }
+}
+
+impl fmt::Display for IsaBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_ascii_lowercase())
+    }
+}
+
 /* Bus trait implementation */
 
 impl Bus for IsaBus {
@@ -1,4 +1,4 @@
-use crate::bus::Bus;
+use crate::bus::{parse_name, Bus, ParseBusError};
 use crate::channel::Channel;
 use crate::df::{HasReceiveStatus, HasSetReceiveStatus};
 use crate::hw::{
@@ -7,6 +7,8 @@ use crate::hw::{
 };
 use crate::info::{HasBitrateInfo, HasBitrateInfoFd, HasChannelFeatures, HasChannelVersion};
 use crate::pcan;
+use std::fmt;
+use std::str::FromStr;
 
 ///
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -95,6 +97,41 @@ impl TryFrom<u16> for LanBus {
     }
 }
 
+/* Bus names */
+
+impl FromStr for LanBus {
+    type Err = ParseBusError;
+
+    /// Parses names like `lan1`, `LAN1` or `PCAN_LANBUS1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_name(s, "LAN")? {
+            1 => Ok(LanBus::LAN1),
+            2 => Ok(LanBus::LAN2),
+            3 => Ok(LanBus::LAN3),
+            4 => Ok(LanBus::LAN4),
+            5 => Ok(LanBus::LAN5),
+            6 => Ok(LanBus::LAN6),
+            7 => Ok(LanBus::LAN7),
+            8 => Ok(LanBus::LAN8),
+            9 => Ok(LanBus::LAN9),
+            10 => Ok(LanBus::LAN10),
+            11 => Ok(LanBus::LAN11),
+            12 => Ok(LanBus::LAN12),
+            13 => Ok(LanBus::LAN13),
+            14 => Ok(LanBus::LAN14),
+            15 => Ok(LanBus::LAN15),
+            16 => Ok(LanBus::LAN16),
+            _ => Err(ParseBusError),
+        }
+    }
+}
+
+impl fmt::Display for LanBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_ascii_lowercase())
+    }
+}
+
 /* Bus trait implementation */
 
 impl Bus for LanBus {
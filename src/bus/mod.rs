@@ -9,6 +9,13 @@ pub mod pcc;
 pub mod pci;
 pub mod usb;
 
+use crate::channel::Channel;
+use crate::df::{HasReceiveStatus, HasSetReceiveStatus};
+use crate::hw::{HasChannelCondition, HasControllerNumber, HasDevicePartNumber, HasHardwareName};
+use crate::info::{HasBitrateInfo, HasBitrateInfoFd, HasChannelFeatures, HasChannelVersion};
+use std::fmt;
+use std::str::FromStr;
+
 ///
 pub trait Bus {
     ///
@@ -21,3 +28,190 @@ pub use lan::LanBus;
 pub use pcc::PccBus;
 pub use pci::PciBus;
 pub use usb::UsbBus;
+
+/* Bus names */
+
+/// The name does not denote a bus.
+#[derive(Debug, PartialEq)]
+pub struct ParseBusError;
+
+impl fmt::Display for ParseBusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unknown bus, expected a name like usb1, pci2 or PCAN_LANBUS1")
+    }
+}
+
+impl std::error::Error for ParseBusError {}
+
+/// Number of names like `usb1`, `USB1` or `PCAN_USBBUS1` of the bus type `kind`.
+pub(crate) fn parse_name(name: &str, kind: &str) -> Result<u8, ParseBusError> {
+    let name = name.trim().to_ascii_uppercase();
+    let name = name.strip_prefix("PCAN_").unwrap_or(&name);
+    let number = name.strip_prefix(kind).ok_or(ParseBusError)?;
+    let number = number.strip_prefix("BUS").unwrap_or(number);
+    match number.starts_with(|c: char| c.is_ascii_digit()) {
+        true => number.parse().map_err(|_| ParseBusError),
+        false => Err(ParseBusError),
+    }
+}
+
+/* AnyBus */
+
+/// Bus of any type, e.g. chosen by name at runtime.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum AnyBus {
+    Dng(DngBus),
+    Isa(IsaBus),
+    Lan(LanBus),
+    Pcc(PccBus),
+    Pci(PciBus),
+    Usb(UsbBus),
+}
+
+impl From<DngBus> for AnyBus {
+    fn from(value: DngBus) -> Self {
+        AnyBus::Dng(value)
+    }
+}
+
+impl From<IsaBus> for AnyBus {
+    fn from(value: IsaBus) -> Self {
+        AnyBus::Isa(value)
+    }
+}
+
+impl From<LanBus> for AnyBus {
+    fn from(value: LanBus) -> Self {
+        AnyBus::Lan(value)
+    }
+}
+
+impl From<PccBus> for AnyBus {
+    fn from(value: PccBus) -> Self {
+        AnyBus::Pcc(value)
+    }
+}
+
+impl From<PciBus> for AnyBus {
+    fn from(value: PciBus) -> Self {
+        AnyBus::Pci(value)
+    }
+}
+
+impl From<UsbBus> for AnyBus {
+    fn from(value: UsbBus) -> Self {
+        AnyBus::Usb(value)
+    }
+}
+
+impl From<AnyBus> for u16 {
+    fn from(value: AnyBus) -> Self {
+        Bus::channel(&value)
+    }
+}
+
+impl TryFrom<u16> for AnyBus {
+    type Error = ();
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        DngBus::try_from(value)
+            .map(AnyBus::from)
+            .or_else(|_| IsaBus::try_from(value).map(AnyBus::from))
+            .or_else(|_| LanBus::try_from(value).map(AnyBus::from))
+            .or_else(|_| PccBus::try_from(value).map(AnyBus::from))
+            .or_else(|_| PciBus::try_from(value).map(AnyBus::from))
+            .or_else(|_| UsbBus::try_from(value).map(AnyBus::from))
+    }
+}
+
+impl FromStr for AnyBus {
+    type Err = ParseBusError;
+
+    /// Parses names of any bus type like `usb1`, `PCI2` or `PCAN_LANBUS3`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DngBus::from_str(s)
+            .map(AnyBus::from)
+            .or_else(|_| IsaBus::from_str(s).map(AnyBus::from))
+            .or_else(|_| LanBus::from_str(s).map(AnyBus::from))
+            .or_else(|_| PccBus::from_str(s).map(AnyBus::from))
+            .or_else(|_| PciBus::from_str(s).map(AnyBus::from))
+            .or_else(|_| UsbBus::from_str(s).map(AnyBus::from))
+    }
+}
+
+impl fmt::Display for AnyBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnyBus::Dng(bus) => bus.fmt(f),
+            AnyBus::Isa(bus) => bus.fmt(f),
+            AnyBus::Lan(bus) => bus.fmt(f),
+            AnyBus::Pcc(bus) => bus.fmt(f),
+            AnyBus::Pci(bus) => bus.fmt(f),
+            AnyBus::Usb(bus) => bus.fmt(f),
+        }
+    }
+}
+
+impl Bus for AnyBus {
+    fn channel(&self) -> u16 {
+        match self {
+            AnyBus::Dng(bus) => Bus::channel(bus),
+            AnyBus::Isa(bus) => Bus::channel(bus),
+            AnyBus::Lan(bus) => Bus::channel(bus),
+            AnyBus::Pcc(bus) => Bus::channel(bus),
+            AnyBus::Pci(bus) => Bus::channel(bus),
+            AnyBus::Usb(bus) => Bus::channel(bus),
+        }
+    }
+}
+
+impl Channel for AnyBus {
+    fn channel(&self) -> u16 {
+        Bus::channel(self)
+    }
+}
+
+/* HARDWARE IDENTIFICATION */
+
+impl HasChannelCondition for AnyBus {}
+
+impl HasHardwareName for AnyBus {}
+
+impl HasControllerNumber for AnyBus {}
+
+impl HasDevicePartNumber for AnyBus {}
+
+/* INFORMATIONAL PARAMETERS */
+
+impl HasChannelVersion for AnyBus {}
+
+impl HasChannelFeatures for AnyBus {}
+
+impl HasBitrateInfo for AnyBus {}
+
+impl HasBitrateInfoFd for AnyBus {}
+
+/* CONTROLLING DATA FLOW */
+
+impl HasReceiveStatus for AnyBus {}
+impl HasSetReceiveStatus for AnyBus {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn any_bus_001() {
+        assert_eq!("usb1".parse(), Ok(AnyBus::Usb(UsbBus::USB1)));
+        assert_eq!("PCAN_USBBUS12".parse(), Ok(AnyBus::Usb(UsbBus::USB12)));
+        assert_eq!(" Pci2 ".parse(), Ok(AnyBus::Pci(PciBus::PCI2)));
+        assert_eq!("lanbus16".parse(), Ok(AnyBus::Lan(LanBus::LAN16)));
+        assert_eq!("usb17".parse::<AnyBus>(), Err(ParseBusError));
+        assert_eq!("usb".parse::<AnyBus>(), Err(ParseBusError));
+        assert_eq!("can0".parse::<AnyBus>(), Err(ParseBusError));
+
+        let bus = AnyBus::Usb(UsbBus::USB3);
+        assert_eq!(bus.to_string(), "usb3");
+        assert_eq!(AnyBus::try_from(u16::from(bus)), Ok(bus));
+    }
+}
@@ -1,10 +1,12 @@
-use crate::bus::Bus;
+use crate::bus::{parse_name, Bus, ParseBusError};
 use crate::channel::Channel;
 use crate::df::{HasReceiveStatus, HasSetReceiveStatus};
 use crate::hw::{HasChannelCondition, HasControllerNumber, HasDevicePartNumber, HasHardwareName};
 use crate::info::{HasBitrateInfo, HasBitrateInfoFd, HasChannelFeatures, HasChannelVersion};
 use crate::pcan;
 use crate::special::HasFiveVoltsPower;
+use std::fmt;
+use std::str::FromStr;
 
 ///
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -37,6 +39,27 @@ impl TryFrom<u16> for PccBus {
     }
 }
 
+/* Bus names */
+
+impl FromStr for PccBus {
+    type Err = ParseBusError;
+
+    /// Parses names like `pcc1`, `PCC1` or `PCAN_PCCBUS1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_name(s, "PCC")? {
+            1 => Ok(PccBus::PCC1),
+            2 => Ok(PccBus::PCC2),
+            _ => Err(ParseBusError),
+        }
+    }
+}
+
+impl fmt::Display for PccBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_ascii_lowercase())
+    }
+}
+
 /* Bus trait implementation */
 
 impl Bus for PccBus {
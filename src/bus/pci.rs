@@ -1,4 +1,4 @@
-use crate::bus::Bus;
+use crate::bus::{parse_name, Bus, ParseBusError};
 use crate::channel::Channel;
 use crate::df::{HasReceiveStatus, HasSetReceiveStatus};
 use crate::hw::{
@@ -6,6 +6,8 @@ use crate::hw::{
 };
 use crate::info::{HasBitrateInfo, HasBitrateInfoFd, HasChannelFeatures, HasChannelVersion};
 use crate::pcan;
+use std::fmt;
+use std::str::FromStr;
 
 ///
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -94,6 +96,41 @@ impl TryFrom<u16> for PciBus {
     }
 }
 
+/* Bus names */
+
+impl FromStr for PciBus {
+    type Err = ParseBusError;
+
+    /// Parses names like `pci1`, `PCI1` or `PCAN_PCIBUS1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_name(s, "PCI")? {
+            1 => Ok(PciBus::PCI1),
+            2 => Ok(PciBus::PCI2),
+            3 => Ok(PciBus::PCI3),
+            4 => Ok(PciBus::PCI4),
+            5 => Ok(PciBus::PCI5),
+            6 => Ok(PciBus::PCI6),
+            7 => Ok(PciBus::PCI7),
+            8 => Ok(PciBus::PCI8),
+            9 => Ok(PciBus::PCI9),
+            10 => Ok(PciBus::PCI10),
+            11 => Ok(PciBus::PCI11),
+            12 => Ok(PciBus::PCI12),
+            13 => Ok(PciBus::PCI13),
+            14 => Ok(PciBus::PCI14),
+            15 => Ok(PciBus::PCI15),
+            16 => Ok(PciBus::PCI16),
+            _ => Err(ParseBusError),
+        }
+    }
+}
+
+impl fmt::Display for PciBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_ascii_lowercase())
+    }
+}
+
 /* Bus trait implementation */
 
 impl Bus for PciBus {
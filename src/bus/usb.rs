@@ -1,4 +1,4 @@
-use crate::bus::Bus;
+use crate::bus::{parse_name, Bus, ParseBusError};
 use crate::channel::Channel;
 use crate::df::{HasReceiveStatus, HasSetReceiveStatus};
 use crate::hw::{
@@ -8,6 +8,8 @@ use crate::hw::{
 use crate::info::{HasBitrateInfo, HasBitrateInfoFd, HasChannelFeatures, HasChannelVersion};
 use crate::pcan;
 use crate::special::HasFiveVoltsPower;
+use std::fmt;
+use std::str::FromStr;
 
 ///
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -96,6 +98,41 @@ impl TryFrom<u16> for UsbBus {
     }
 }
 
+/* Bus names */
+
+impl FromStr for UsbBus {
+    type Err = ParseBusError;
+
+    /// Parses names like `usb1`, `USB1` or `PCAN_USBBUS1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_name(s, "USB")? {
+            1 => Ok(UsbBus::USB1),
+            2 => Ok(UsbBus::USB2),
+            3 => Ok(UsbBus::USB3),
+            4 => Ok(UsbBus::USB4),
+            5 => Ok(UsbBus::USB5),
+            6 => Ok(UsbBus::USB6),
+            7 => Ok(UsbBus::USB7),
+            8 => Ok(UsbBus::USB8),
+            9 => Ok(UsbBus::USB9),
+            10 => Ok(UsbBus::USB10),
+            11 => Ok(UsbBus::USB11),
+            12 => Ok(UsbBus::USB12),
+            13 => Ok(UsbBus::USB13),
+            14 => Ok(UsbBus::USB14),
+            15 => Ok(UsbBus::USB15),
+            16 => Ok(UsbBus::USB16),
+            _ => Err(ParseBusError),
+        }
+    }
+}
+
+impl fmt::Display for UsbBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_ascii_lowercase())
+    }
+}
+
 /* Bus trait implementation */
 
 impl Bus for UsbBus {
@@ -0,0 +1,641 @@
+//! Parser for CANopen electronic data sheets (EDS) and device configuration files (DCF).
+//!
+//! Both formats are INI files describing the object dictionary of a node. Every object is stored
+//! in a section named after its index, e.g. `[1018]`, and every sub-object in a section named
+//! `[1018sub1]`.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/* DataType */
+
+/// Data types of the CANopen object dictionary (CiA 301).
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum DataType {
+    Boolean,
+    Integer8,
+    Integer16,
+    Integer24,
+    Integer32,
+    Integer40,
+    Integer48,
+    Integer56,
+    Integer64,
+    Unsigned8,
+    Unsigned16,
+    Unsigned24,
+    Unsigned32,
+    Unsigned40,
+    Unsigned48,
+    Unsigned56,
+    Unsigned64,
+    Real32,
+    Real64,
+    VisibleString,
+    OctetString,
+    UnicodeString,
+    TimeOfDay,
+    TimeDifference,
+    Domain,
+}
+
+impl DataType {
+    /// Returns the encoded size in bytes or `None` for variable sized types.
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            DataType::Boolean => Some(1),
+            DataType::Integer8 | DataType::Unsigned8 => Some(1),
+            DataType::Integer16 | DataType::Unsigned16 => Some(2),
+            DataType::Integer24 | DataType::Unsigned24 => Some(3),
+            DataType::Integer32 | DataType::Unsigned32 | DataType::Real32 => Some(4),
+            DataType::Integer40 | DataType::Unsigned40 => Some(5),
+            DataType::Integer48 | DataType::Unsigned48 => Some(6),
+            DataType::TimeOfDay | DataType::TimeDifference => Some(6),
+            DataType::Integer56 | DataType::Unsigned56 => Some(7),
+            DataType::Integer64 | DataType::Unsigned64 | DataType::Real64 => Some(8),
+            DataType::VisibleString
+            | DataType::OctetString
+            | DataType::UnicodeString
+            | DataType::Domain => None,
+        }
+    }
+}
+
+impl TryFrom<u16> for DataType {
+    type Error = ();
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0x0001 => Ok(DataType::Boolean),
+            0x0002 => Ok(DataType::Integer8),
+            0x0003 => Ok(DataType::Integer16),
+            0x0004 => Ok(DataType::Integer32),
+            0x0005 => Ok(DataType::Unsigned8),
+            0x0006 => Ok(DataType::Unsigned16),
+            0x0007 => Ok(DataType::Unsigned32),
+            0x0008 => Ok(DataType::Real32),
+            0x0009 => Ok(DataType::VisibleString),
+            0x000A => Ok(DataType::OctetString),
+            0x000B => Ok(DataType::UnicodeString),
+            0x000C => Ok(DataType::TimeOfDay),
+            0x000D => Ok(DataType::TimeDifference),
+            0x000F => Ok(DataType::Domain),
+            0x0010 => Ok(DataType::Integer24),
+            0x0011 => Ok(DataType::Real64),
+            0x0012 => Ok(DataType::Integer40),
+            0x0013 => Ok(DataType::Integer48),
+            0x0014 => Ok(DataType::Integer56),
+            0x0015 => Ok(DataType::Integer64),
+            0x0016 => Ok(DataType::Unsigned24),
+            0x0018 => Ok(DataType::Unsigned40),
+            0x0019 => Ok(DataType::Unsigned48),
+            0x001A => Ok(DataType::Unsigned56),
+            0x001B => Ok(DataType::Unsigned64),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<DataType> for u16 {
+    fn from(value: DataType) -> Self {
+        match value {
+            DataType::Boolean => 0x0001,
+            DataType::Integer8 => 0x0002,
+            DataType::Integer16 => 0x0003,
+            DataType::Integer32 => 0x0004,
+            DataType::Unsigned8 => 0x0005,
+            DataType::Unsigned16 => 0x0006,
+            DataType::Unsigned32 => 0x0007,
+            DataType::Real32 => 0x0008,
+            DataType::VisibleString => 0x0009,
+            DataType::OctetString => 0x000A,
+            DataType::UnicodeString => 0x000B,
+            DataType::TimeOfDay => 0x000C,
+            DataType::TimeDifference => 0x000D,
+            DataType::Domain => 0x000F,
+            DataType::Integer24 => 0x0010,
+            DataType::Real64 => 0x0011,
+            DataType::Integer40 => 0x0012,
+            DataType::Integer48 => 0x0013,
+            DataType::Integer56 => 0x0014,
+            DataType::Integer64 => 0x0015,
+            DataType::Unsigned24 => 0x0016,
+            DataType::Unsigned40 => 0x0018,
+            DataType::Unsigned48 => 0x0019,
+            DataType::Unsigned56 => 0x001A,
+            DataType::Unsigned64 => 0x001B,
+        }
+    }
+}
+
+/* AccessType */
+
+/// Access rights of an object dictionary entry.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum AccessType {
+    /// Read only, the value may change at runtime.
+    ReadOnly,
+    /// Write only.
+    WriteOnly,
+    /// Read and write.
+    ReadWrite,
+    /// Read and write, the entry is an input of the process mappable into a transmit PDO.
+    ReadWriteRead,
+    /// Read and write, the entry is an output of the process mappable into a receive PDO.
+    ReadWriteWrite,
+    /// Read only, the value never changes.
+    Const,
+}
+
+impl AccessType {
+    pub fn is_readable(&self) -> bool {
+        !matches!(self, AccessType::WriteOnly)
+    }
+
+    pub fn is_writable(&self) -> bool {
+        !matches!(self, AccessType::ReadOnly | AccessType::Const)
+    }
+}
+
+impl TryFrom<&str> for AccessType {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "ro" => Ok(AccessType::ReadOnly),
+            "wo" => Ok(AccessType::WriteOnly),
+            "rw" => Ok(AccessType::ReadWrite),
+            "rwr" => Ok(AccessType::ReadWriteRead),
+            "rww" => Ok(AccessType::ReadWriteWrite),
+            "const" => Ok(AccessType::Const),
+            _ => Err(()),
+        }
+    }
+}
+
+/* ObjectType */
+
+/// Object codes used by the `ObjectType` key.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ObjectType {
+    Null,
+    Domain,
+    DefType,
+    DefStruct,
+    Var,
+    Array,
+    Record,
+}
+
+impl TryFrom<u8> for ObjectType {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(ObjectType::Null),
+            0x2 => Ok(ObjectType::Domain),
+            0x5 => Ok(ObjectType::DefType),
+            0x6 => Ok(ObjectType::DefStruct),
+            0x7 => Ok(ObjectType::Var),
+            0x8 => Ok(ObjectType::Array),
+            0x9 => Ok(ObjectType::Record),
+            _ => Err(()),
+        }
+    }
+}
+
+/* Entry */
+
+/// A single readable or writable value of the object dictionary.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Entry {
+    pub index: u16,
+    pub subindex: u8,
+    /// Fully qualified name, see [ObjectDictionary::entry].
+    pub name: String,
+    pub data_type: DataType,
+    pub access: AccessType,
+    /// `DefaultValue` as written in the file, `$NODEID` is not yet resolved.
+    pub default_value: Option<String>,
+    /// `ParameterValue` of a DCF file.
+    pub parameter_value: Option<String>,
+    pub pdo_mapping: bool,
+}
+
+impl Entry {
+    /// Resolves the default value of the entry as integer, substituting `$NODEID`.
+    pub fn default_integer(&self, node_id: u8) -> Option<i128> {
+        let value = self.default_value.as_ref()?;
+        evaluate_integer(value, node_id)
+    }
+}
+
+/* EdsError */
+
+#[derive(Debug)]
+pub enum EdsError {
+    Io(std::io::Error),
+    /// The line could not be parsed as section header or key-value pair.
+    Syntax {
+        line: usize,
+    },
+    /// A key required for the section is missing or has an invalid value.
+    InvalidKey {
+        section: String,
+        key: String,
+    },
+}
+
+impl From<std::io::Error> for EdsError {
+    fn from(value: std::io::Error) -> Self {
+        EdsError::Io(value)
+    }
+}
+
+/* ObjectDictionary */
+
+/// Object dictionary of a CANopen node as described by an EDS or DCF file.
+#[derive(Debug, Default, Clone)]
+pub struct ObjectDictionary {
+    entries: Vec<Entry>,
+    by_name: HashMap<String, usize>,
+    by_address: HashMap<(u16, u8), usize>,
+    node_id: Option<u8>,
+}
+
+impl ObjectDictionary {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ObjectDictionary, EdsError> {
+        let bytes = std::fs::read(path)?;
+        // EDS files are frequently stored as Latin-1.
+        let text = bytes.iter().map(|b| char::from(*b)).collect::<String>();
+        ObjectDictionary::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<ObjectDictionary, EdsError> {
+        let sections = parse_ini(text)?;
+        let mut dictionary = ObjectDictionary::default();
+
+        if let Some(comissioning) = sections.get("devicecomissioning") {
+            dictionary.node_id = comissioning
+                .get("nodeid")
+                .and_then(|value| evaluate_integer(value, 0))
+                .and_then(|value| u8::try_from(value).ok());
+        }
+
+        let mut indices = sections
+            .keys()
+            .filter_map(|name| parse_hex_u16(name).map(|index| (index, name)))
+            .collect::<Vec<_>>();
+        indices.sort();
+
+        for (index, section_name) in indices {
+            let section = &sections[section_name];
+            let object_name = required(section, section_name, "parametername")?;
+            let object_type = match section.get("objecttype") {
+                None => ObjectType::Var,
+                Some(value) => evaluate_integer(value, 0)
+                    .and_then(|value| u8::try_from(value).ok())
+                    .and_then(|value| ObjectType::try_from(value).ok())
+                    .ok_or_else(|| invalid_key(section_name, "objecttype"))?,
+            };
+
+            match object_type {
+                ObjectType::Var | ObjectType::Domain => {
+                    let entry = parse_entry(section, section_name, index, 0, object_name)?;
+                    dictionary.insert(entry);
+                }
+                ObjectType::Array | ObjectType::Record => {
+                    let mut subs = sections
+                        .keys()
+                        .filter_map(|name| {
+                            let (head, tail) = name.split_once("sub")?;
+                            if parse_hex_u16(head)? != index {
+                                return None;
+                            }
+                            let subindex = u8::from_str_radix(tail, 16).ok()?;
+                            Some((subindex, name))
+                        })
+                        .collect::<Vec<_>>();
+                    subs.sort();
+
+                    for (subindex, sub_name) in subs {
+                        let sub_section = &sections[sub_name];
+                        let name = required(sub_section, sub_name, "parametername")?;
+                        let name = format!("{}.{}", object_name, name);
+                        let entry = parse_entry(sub_section, sub_name, index, subindex, &name)?;
+                        dictionary.insert(entry);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(dictionary)
+    }
+
+    fn insert(&mut self, entry: Entry) {
+        let position = self.entries.len();
+        self.by_name.entry(entry.name.clone()).or_insert(position);
+        self.by_address
+            .insert((entry.index, entry.subindex), position);
+        self.entries.push(entry);
+    }
+
+    /// Looks up an entry by name.
+    ///
+    /// Simple variables are named by their `ParameterName`. Sub-objects of arrays and records are
+    /// named `<object>.<sub-object>`, e.g. `Identity object.Vendor-ID`. Of entries with the same
+    /// name the one with the lowest index is returned, the others are found by [Self::entry_at].
+    pub fn entry(&self, name: &str) -> Option<&Entry> {
+        self.by_name
+            .get(name)
+            .map(|position| &self.entries[*position])
+    }
+
+    pub fn entry_at(&self, index: u16, subindex: u8) -> Option<&Entry> {
+        self.by_address
+            .get(&(index, subindex))
+            .map(|position| &self.entries[*position])
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Entries which can be mapped into a PDO.
+    pub fn pdo_mappable(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(|entry| entry.pdo_mapping)
+    }
+
+    /// Node-ID configured in the `[DeviceComissioning]` section of a DCF file.
+    pub fn node_id(&self) -> Option<u8> {
+        self.node_id
+    }
+}
+
+/* Helper functions */
+
+type Section = HashMap<String, String>;
+
+fn parse_ini(text: &str) -> Result<HashMap<String, Section>, EdsError> {
+    let mut sections: HashMap<String, Section> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = match name.strip_suffix(']') {
+                Some(name) => name.trim().to_ascii_lowercase(),
+                None => return Err(EdsError::Syntax { line: number + 1 }),
+            };
+            sections.entry(name.clone()).or_default();
+            current = Some(name);
+            continue;
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some(pair) => pair,
+            None => return Err(EdsError::Syntax { line: number + 1 }),
+        };
+        let section = match &current {
+            Some(name) => sections.get_mut(name).unwrap(),
+            None => return Err(EdsError::Syntax { line: number + 1 }),
+        };
+        section.insert(key.trim().to_ascii_lowercase(), value.trim().to_string());
+    }
+
+    Ok(sections)
+}
+
+fn parse_entry(
+    section: &Section,
+    section_name: &str,
+    index: u16,
+    subindex: u8,
+    name: &str,
+) -> Result<Entry, EdsError> {
+    let data_type = evaluate_integer(required(section, section_name, "datatype")?, 0)
+        .and_then(|value| u16::try_from(value).ok())
+        .and_then(|value| DataType::try_from(value).ok())
+        .ok_or_else(|| invalid_key(section_name, "datatype"))?;
+    let access = AccessType::try_from(required(section, section_name, "accesstype")?)
+        .map_err(|_| invalid_key(section_name, "accesstype"))?;
+    let pdo_mapping = match section.get("pdomapping") {
+        None => false,
+        Some(value) => {
+            evaluate_integer(value, 0).ok_or_else(|| invalid_key(section_name, "pdomapping"))? != 0
+        }
+    };
+
+    Ok(Entry {
+        index,
+        subindex,
+        name: String::from(name),
+        data_type,
+        access,
+        default_value: non_empty(section.get("defaultvalue")),
+        parameter_value: non_empty(section.get("parametervalue")),
+        pdo_mapping,
+    })
+}
+
+fn required<'a>(section: &'a Section, section_name: &str, key: &str) -> Result<&'a str, EdsError> {
+    match section.get(key) {
+        Some(value) => Ok(value.as_str()),
+        None => Err(invalid_key(section_name, key)),
+    }
+}
+
+fn invalid_key(section: &str, key: &str) -> EdsError {
+    EdsError::InvalidKey {
+        section: String::from(section),
+        key: String::from(key),
+    }
+}
+
+fn non_empty(value: Option<&String>) -> Option<String> {
+    match value {
+        Some(value) if !value.is_empty() => Some(value.clone()),
+        _ => None,
+    }
+}
+
+fn parse_hex_u16(value: &str) -> Option<u16> {
+    if value.is_empty() || value.len() > 4 {
+        return None;
+    }
+    u16::from_str_radix(value, 16).ok()
+}
+
+/// Evaluates integer values such as `0x1A`, `017` (octal), `-5` and `$NODEID+0x180`.
+pub(crate) fn evaluate_integer(value: &str, node_id: u8) -> Option<i128> {
+    let mut total = 0i128;
+    let upper = value.trim().to_ascii_uppercase();
+    if upper.is_empty() {
+        return None;
+    }
+    let mut rest = upper.as_str();
+    let mut sign = 1i128;
+
+    loop {
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| c == '+' || c == '-')
+            .map_or(rest.len(), |(p, _)| p);
+        let term = rest[..end].trim();
+        let (term_sign, term) = match term.chars().next() {
+            Some('+') => (1, term[1..].trim()),
+            Some('-') => (-1, term[1..].trim()),
+            _ => (1, term),
+        };
+        sign *= term_sign;
+
+        let number = if term == "$NODEID" {
+            node_id as i128
+        } else if let Some(hex) = term.strip_prefix("0X") {
+            i128::from_str_radix(hex, 16).ok()?
+        } else if term.len() > 1 && term.starts_with('0') {
+            i128::from_str_radix(&term[1..], 8).ok()?
+        } else {
+            term.parse::<i128>().ok()?
+        };
+        total += sign * number;
+        sign = 1;
+
+        if end == rest.len() {
+            return Some(total);
+        }
+        rest = &rest[end..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EDS: &str = "
+[FileInfo]
+FileName=drive.eds
+
+[DeviceComissioning]
+NodeID=0x05
+
+[1000]
+ParameterName=Device type
+ObjectType=0x7
+DataType=0x0007
+AccessType=ro
+DefaultValue=0x00020192
+PDOMapping=0
+
+[1018]
+ParameterName=Identity object
+ObjectType=0x9
+SubNumber=2
+
+[1018sub0]
+ParameterName=Number of entries
+ObjectType=0x7
+DataType=0x0005
+AccessType=ro
+DefaultValue=1
+
+[1018sub1]
+ParameterName=Vendor-ID
+ObjectType=0x7
+DataType=0x0007
+AccessType=ro
+DefaultValue=0x00000175
+
+[1800sub1]
+ParameterName=COB-ID
+DataType=0x0007
+AccessType=rw
+DefaultValue=$NODEID+0x180
+
+[1800]
+ParameterName=TPDO1 communication parameter
+ObjectType=0x9
+
+[6040]
+ParameterName=Controlword
+ObjectType=0x7
+DataType=0x0006
+AccessType=rww
+PDOMapping=1
+ParameterValue=0x000F
+";
+
+    #[test]
+    fn object_dictionary_parse_001() {
+        let dictionary = ObjectDictionary::parse(EDS).unwrap();
+
+        assert_eq!(dictionary.entries().len(), 5);
+        assert_eq!(dictionary.node_id(), Some(5));
+
+        let entry = dictionary.entry("Identity object.Vendor-ID").unwrap();
+        assert_eq!(entry.index, 0x1018);
+        assert_eq!(entry.subindex, 1);
+        assert_eq!(entry.data_type, DataType::Unsigned32);
+        assert_eq!(entry.access, AccessType::ReadOnly);
+        assert_eq!(entry.default_integer(0), Some(0x175));
+
+        let entry = dictionary.entry("Controlword").unwrap();
+        assert!(entry.pdo_mapping);
+        assert!(entry.access.is_writable());
+        assert_eq!(entry.parameter_value.as_deref(), Some("0x000F"));
+        assert_eq!(dictionary.pdo_mappable().count(), 1);
+    }
+
+    #[test]
+    fn object_dictionary_parse_002() {
+        let dictionary = ObjectDictionary::parse(EDS).unwrap();
+        let entry = dictionary.entry_at(0x1800, 1).unwrap();
+
+        assert_eq!(entry.name, "TPDO1 communication parameter.COB-ID");
+        assert_eq!(entry.default_integer(0x05), Some(0x185));
+    }
+
+    #[test]
+    fn object_dictionary_parse_003() {
+        let result =
+            ObjectDictionary::parse("[1000]\nParameterName=Device type\nDataType=0x0007\n");
+        assert!(matches!(result, Err(EdsError::InvalidKey { .. })));
+
+        let result = ObjectDictionary::parse("[1000]\nParameterName\n");
+        assert!(matches!(result, Err(EdsError::Syntax { line: 2 })));
+    }
+
+    #[test]
+    fn object_dictionary_parse_004() {
+        let text = "
+[2000]
+ParameterName=Speed
+DataType=0x0006
+AccessType=rw
+
+[2001]
+ParameterName=Speed
+DataType=0x0007
+AccessType=ro
+";
+        let dictionary = ObjectDictionary::parse(text).unwrap();
+        assert_eq!(dictionary.entries().len(), 2);
+        assert_eq!(dictionary.entry("Speed").unwrap().index, 0x2000);
+        assert_eq!(dictionary.entry_at(0x2001, 0).unwrap().name, "Speed");
+    }
+
+    #[test]
+    fn evaluate_integer_001() {
+        assert_eq!(evaluate_integer("$NODEID+0x180", 5), Some(0x185));
+        assert_eq!(evaluate_integer("0x10 - 010 + 1", 0), Some(9));
+        assert_eq!(evaluate_integer("\u{e9}", 0), None);
+        assert_eq!(evaluate_integer("\u{e9}+1", 0), None);
+        assert_eq!(evaluate_integer("1+\u{e9}", 0), None);
+    }
+}
@@ -0,0 +1,248 @@
+//! CANopen object dictionary access driven by EDS/DCF files.
+//!
+//! A [Node] combines an [SdoClient] with an [ObjectDictionary] so that values can be read and
+//! written by name. Every access is validated against the dictionary before an SDO request is
+//! issued on the socket.
+
+pub mod eds;
+pub mod sdo;
+
+pub use eds::{AccessType, DataType, EdsError, Entry, ObjectDictionary};
+pub use sdo::{SdoClient, SdoError};
+
+use crate::socket::{RecvCan, SendCan};
+
+/* ObjectValue trait */
+
+/// Rust types which can be converted from and to the SDO encoding of a [DataType].
+pub trait ObjectValue: Sized {
+    fn is_compatible(data_type: DataType) -> bool;
+    fn from_sdo(data: &[u8], data_type: DataType) -> Option<Self>;
+    fn to_sdo(&self, data_type: DataType) -> Option<Vec<u8>>;
+}
+
+macro_rules! impl_object_value_unsigned {
+    ($t:ty, [$($dt:ident),*]) => {
+        impl ObjectValue for $t {
+            fn is_compatible(data_type: DataType) -> bool {
+                matches!(data_type, $(DataType::$dt)|*)
+            }
+
+            fn from_sdo(data: &[u8], data_type: DataType) -> Option<Self> {
+                let value = from_le_unsigned(data, data_type)?;
+                <$t>::try_from(value).ok()
+            }
+
+            fn to_sdo(&self, data_type: DataType) -> Option<Vec<u8>> {
+                to_le_unsigned(*self as u64, data_type)
+            }
+        }
+    };
+}
+
+macro_rules! impl_object_value_signed {
+    ($t:ty, [$($dt:ident),*]) => {
+        impl ObjectValue for $t {
+            fn is_compatible(data_type: DataType) -> bool {
+                matches!(data_type, $(DataType::$dt)|*)
+            }
+
+            fn from_sdo(data: &[u8], data_type: DataType) -> Option<Self> {
+                let value = from_le_signed(data, data_type)?;
+                <$t>::try_from(value).ok()
+            }
+
+            fn to_sdo(&self, data_type: DataType) -> Option<Vec<u8>> {
+                to_le_signed(*self as i64, data_type)
+            }
+        }
+    };
+}
+
+impl_object_value_unsigned!(u8, [Unsigned8]);
+impl_object_value_unsigned!(u16, [Unsigned8, Unsigned16]);
+impl_object_value_unsigned!(u32, [Unsigned8, Unsigned16, Unsigned24, Unsigned32]);
+impl_object_value_unsigned!(
+    u64,
+    [
+        Unsigned8, Unsigned16, Unsigned24, Unsigned32, Unsigned40, Unsigned48, Unsigned56,
+        Unsigned64
+    ]
+);
+
+impl_object_value_signed!(i8, [Integer8]);
+impl_object_value_signed!(i16, [Integer8, Integer16]);
+impl_object_value_signed!(i32, [Integer8, Integer16, Integer24, Integer32]);
+impl_object_value_signed!(
+    i64,
+    [Integer8, Integer16, Integer24, Integer32, Integer40, Integer48, Integer56, Integer64]
+);
+
+impl ObjectValue for bool {
+    fn is_compatible(data_type: DataType) -> bool {
+        data_type == DataType::Boolean
+    }
+
+    fn from_sdo(data: &[u8], _data_type: DataType) -> Option<Self> {
+        match data {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+
+    fn to_sdo(&self, _data_type: DataType) -> Option<Vec<u8>> {
+        Some(vec![*self as u8])
+    }
+}
+
+impl ObjectValue for f32 {
+    fn is_compatible(data_type: DataType) -> bool {
+        data_type == DataType::Real32
+    }
+
+    fn from_sdo(data: &[u8], _data_type: DataType) -> Option<Self> {
+        Some(f32::from_le_bytes(data.try_into().ok()?))
+    }
+
+    fn to_sdo(&self, _data_type: DataType) -> Option<Vec<u8>> {
+        Some(self.to_le_bytes().to_vec())
+    }
+}
+
+impl ObjectValue for f64 {
+    fn is_compatible(data_type: DataType) -> bool {
+        data_type == DataType::Real64
+    }
+
+    fn from_sdo(data: &[u8], _data_type: DataType) -> Option<Self> {
+        Some(f64::from_le_bytes(data.try_into().ok()?))
+    }
+
+    fn to_sdo(&self, _data_type: DataType) -> Option<Vec<u8>> {
+        Some(self.to_le_bytes().to_vec())
+    }
+}
+
+impl ObjectValue for String {
+    fn is_compatible(data_type: DataType) -> bool {
+        data_type == DataType::VisibleString
+    }
+
+    fn from_sdo(data: &[u8], _data_type: DataType) -> Option<Self> {
+        let s = std::str::from_utf8(data).ok()?;
+        Some(String::from(s.trim_end_matches(char::from(0))))
+    }
+
+    fn to_sdo(&self, _data_type: DataType) -> Option<Vec<u8>> {
+        Some(self.as_bytes().to_vec())
+    }
+}
+
+impl ObjectValue for Vec<u8> {
+    fn is_compatible(data_type: DataType) -> bool {
+        matches!(data_type, DataType::OctetString | DataType::Domain)
+    }
+
+    fn from_sdo(data: &[u8], _data_type: DataType) -> Option<Self> {
+        Some(data.to_vec())
+    }
+
+    fn to_sdo(&self, _data_type: DataType) -> Option<Vec<u8>> {
+        Some(self.clone())
+    }
+}
+
+fn from_le_unsigned(data: &[u8], data_type: DataType) -> Option<u64> {
+    if Some(data.len()) != data_type.size() {
+        return None;
+    }
+
+    let mut bytes = [0u8; 8];
+    bytes[..data.len()].copy_from_slice(data);
+    Some(u64::from_le_bytes(bytes))
+}
+
+fn from_le_signed(data: &[u8], data_type: DataType) -> Option<i64> {
+    let value = from_le_unsigned(data, data_type)?;
+    let shift = 64 - 8 * data.len() as u32;
+    Some(((value << shift) as i64) >> shift)
+}
+
+fn to_le_unsigned(value: u64, data_type: DataType) -> Option<Vec<u8>> {
+    let size = data_type.size()?;
+    if size < 8 && value >> (8 * size) != 0 {
+        return None;
+    }
+    Some(value.to_le_bytes()[..size].to_vec())
+}
+
+fn to_le_signed(value: i64, data_type: DataType) -> Option<Vec<u8>> {
+    let size = data_type.size()?;
+    let shift = 64 - 8 * size as u32;
+    if ((value << shift) >> shift) != value {
+        return None;
+    }
+    Some(value.to_le_bytes()[..size].to_vec())
+}
+
+/* Node */
+
+/// CANopen node whose object dictionary is known from an EDS or DCF file.
+#[derive(Debug)]
+pub struct Node<'a, S> {
+    sdo: SdoClient<'a, S>,
+    dictionary: &'a ObjectDictionary,
+}
+
+impl<'a, S: SendCan + RecvCan> Node<'a, S> {
+    pub fn new(socket: &'a S, node_id: u8, dictionary: &'a ObjectDictionary) -> Node<'a, S> {
+        Node {
+            sdo: SdoClient::new(socket, node_id),
+            dictionary,
+        }
+    }
+
+    pub fn sdo(&self) -> &SdoClient<'a, S> {
+        &self.sdo
+    }
+
+    pub fn sdo_mut(&mut self) -> &mut SdoClient<'a, S> {
+        &mut self.sdo
+    }
+
+    pub fn dictionary(&self) -> &ObjectDictionary {
+        self.dictionary
+    }
+
+    /// Reads the entry called `name`, see [ObjectDictionary::entry] for the naming scheme.
+    pub fn read<T: ObjectValue>(&self, name: &str) -> Result<T, SdoError> {
+        let entry = self.dictionary.entry(name).ok_or(SdoError::UnknownEntry)?;
+        if !entry.access.is_readable() {
+            return Err(SdoError::AccessDenied);
+        }
+        if !T::is_compatible(entry.data_type) {
+            return Err(SdoError::TypeMismatch);
+        }
+
+        let data = self.sdo.upload(entry.index, entry.subindex)?;
+        T::from_sdo(&data, entry.data_type).ok_or(SdoError::Protocol)
+    }
+
+    /// Writes `value` to the entry called `name`, see [ObjectDictionary::entry] for the naming
+    /// scheme.
+    pub fn write<T: ObjectValue>(&self, name: &str, value: T) -> Result<(), SdoError> {
+        let entry = self.dictionary.entry(name).ok_or(SdoError::UnknownEntry)?;
+        if !entry.access.is_writable() {
+            return Err(SdoError::AccessDenied);
+        }
+        if !T::is_compatible(entry.data_type) {
+            return Err(SdoError::TypeMismatch);
+        }
+
+        let data = value
+            .to_sdo(entry.data_type)
+            .ok_or(SdoError::TypeMismatch)?;
+        self.sdo.download(entry.index, entry.subindex, &data)
+    }
+}
@@ -0,0 +1,336 @@
+//! Client side of the CANopen service data object (SDO) protocol.
+//!
+//! Expedited and segmented transfers are supported for both upload (read) and download (write).
+
+use crate::error::PcanError;
+use crate::socket::{CanFrame, MessageType, RecvCan, SendCan};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const SDO_TX_BASE: u32 = 0x600;
+const SDO_RX_BASE: u32 = 0x580;
+
+const CCS_DOWNLOAD_SEGMENT: u8 = 0;
+const CCS_INITIATE_DOWNLOAD: u8 = 1;
+const CCS_INITIATE_UPLOAD: u8 = 2;
+const CCS_UPLOAD_SEGMENT: u8 = 3;
+const CS_ABORT: u8 = 4;
+
+const SCS_UPLOAD_SEGMENT: u8 = 0;
+const SCS_DOWNLOAD_SEGMENT: u8 = 1;
+const SCS_INITIATE_UPLOAD: u8 = 2;
+const SCS_INITIATE_DOWNLOAD: u8 = 3;
+
+/// Abort code sent to the server when a response violates the protocol.
+const ABORT_INVALID_COMMAND: u32 = 0x0504_0001;
+
+#[derive(Debug, PartialEq)]
+pub enum SdoError {
+    /// Sending or receiving failed.
+    Pcan(PcanError),
+    /// The server did not respond within the configured timeout.
+    Timeout,
+    /// The transfer was aborted by the server with the given abort code.
+    Abort(u32),
+    /// The server responded with an unexpected command.
+    Protocol,
+    /// The object dictionary has no entry with the requested name.
+    UnknownEntry,
+    /// The entry can not be read or written according to the object dictionary.
+    AccessDenied,
+    /// The requested type does not match the data type of the entry.
+    TypeMismatch,
+    /// Downloads need at least one data byte.
+    NoData,
+}
+
+impl From<PcanError> for SdoError {
+    fn from(value: PcanError) -> Self {
+        SdoError::Pcan(value)
+    }
+}
+
+/* SdoClient */
+
+/// SDO client talking to the default SDO server of a single node.
+#[derive(Debug)]
+pub struct SdoClient<'a, S> {
+    socket: &'a S,
+    node_id: u8,
+    timeout: Duration,
+}
+
+impl<'a, S: SendCan + RecvCan> SdoClient<'a, S> {
+    const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+
+    pub fn new(socket: &'a S, node_id: u8) -> SdoClient<'a, S> {
+        SdoClient {
+            socket,
+            node_id: node_id & 0x7F,
+            timeout: Self::DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn node_id(&self) -> u8 {
+        self.node_id
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Reads the value of the object at `index` and `subindex` from the node.
+    pub fn upload(&self, index: u16, subindex: u8) -> Result<Vec<u8>, SdoError> {
+        let request = initiate(CCS_INITIATE_UPLOAD << 5, index, subindex, &[]);
+        let response = self.request(&request)?;
+
+        if response[0] >> 5 != SCS_INITIATE_UPLOAD || !same_object(&response, index, subindex) {
+            return Err(self.protocol_error(index, subindex));
+        }
+
+        let expedited = response[0] & 0x02 != 0;
+        let size_indicated = response[0] & 0x01 != 0;
+
+        if expedited {
+            let len = match size_indicated {
+                true => 4 - ((response[0] >> 2) & 0x03) as usize,
+                false => 4,
+            };
+            return Ok(response[4..4 + len].to_vec());
+        }
+
+        let size = match size_indicated {
+            true => Some(u32::from_le_bytes([
+                response[4],
+                response[5],
+                response[6],
+                response[7],
+            ])),
+            false => None,
+        };
+
+        let mut data = Vec::new();
+        let mut toggle = 0u8;
+        loop {
+            let request = [
+                (CCS_UPLOAD_SEGMENT << 5) | (toggle << 4),
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+            ];
+            let response = self.request(&request)?;
+
+            if response[0] >> 5 != SCS_UPLOAD_SEGMENT || (response[0] >> 4) & 0x01 != toggle {
+                return Err(self.protocol_error(index, subindex));
+            }
+
+            let unused = ((response[0] >> 1) & 0x07) as usize;
+            data.extend_from_slice(&response[1..8 - unused]);
+
+            if response[0] & 0x01 != 0 {
+                break;
+            }
+            toggle ^= 1;
+        }
+
+        match size {
+            Some(size) if size as usize != data.len() => Err(self.protocol_error(index, subindex)),
+            _ => Ok(data),
+        }
+    }
+
+    /// Writes `data` to the object at `index` and `subindex` of the node.
+    pub fn download(&self, index: u16, subindex: u8, data: &[u8]) -> Result<(), SdoError> {
+        if data.is_empty() {
+            return Err(SdoError::NoData);
+        }
+        if data.len() <= 4 {
+            let command = (CCS_INITIATE_DOWNLOAD << 5) | ((4 - data.len() as u8) << 2) | 0x03;
+            let request = initiate(command, index, subindex, data);
+            let response = self.request(&request)?;
+
+            if response[0] >> 5 != SCS_INITIATE_DOWNLOAD || !same_object(&response, index, subindex)
+            {
+                return Err(self.protocol_error(index, subindex));
+            }
+            return Ok(());
+        }
+
+        let size = (data.len() as u32).to_le_bytes();
+        let request = initiate((CCS_INITIATE_DOWNLOAD << 5) | 0x01, index, subindex, &size);
+        let response = self.request(&request)?;
+
+        if response[0] >> 5 != SCS_INITIATE_DOWNLOAD || !same_object(&response, index, subindex) {
+            return Err(self.protocol_error(index, subindex));
+        }
+
+        let mut toggle = 0u8;
+        let mut chunks = data.chunks(7).peekable();
+        while let Some(chunk) = chunks.next() {
+            let last = chunks.peek().is_none();
+            let mut request = [0u8; 8];
+            request[0] = (CCS_DOWNLOAD_SEGMENT << 5)
+                | (toggle << 4)
+                | ((7 - chunk.len() as u8) << 1)
+                | last as u8;
+            request[1..1 + chunk.len()].copy_from_slice(chunk);
+
+            let response = self.request(&request)?;
+            if response[0] >> 5 != SCS_DOWNLOAD_SEGMENT || (response[0] >> 4) & 0x01 != toggle {
+                return Err(self.protocol_error(index, subindex));
+            }
+            toggle ^= 1;
+        }
+
+        Ok(())
+    }
+
+    /// Aborts the current transfer of the object at `index` and `subindex`.
+    pub fn abort(&self, index: u16, subindex: u8, code: u32) -> Result<(), SdoError> {
+        let request = initiate(CS_ABORT << 5, index, subindex, &code.to_le_bytes());
+        self.send(&request)
+    }
+
+    fn protocol_error(&self, index: u16, subindex: u8) -> SdoError {
+        // The server is left in an undefined state otherwise.
+        let _ = self.abort(index, subindex, ABORT_INVALID_COMMAND);
+        SdoError::Protocol
+    }
+
+    fn send(&self, data: &[u8; 8]) -> Result<(), SdoError> {
+        let can_id = SDO_TX_BASE + self.node_id as u32;
+        let frame = CanFrame::new(can_id, MessageType::Standard, data).unwrap();
+        self.socket.send(frame)?;
+        Ok(())
+    }
+
+    fn request(&self, data: &[u8; 8]) -> Result<[u8; 8], SdoError> {
+        self.send(data)?;
+
+        let can_id = SDO_RX_BASE + self.node_id as u32;
+        let start = Instant::now();
+        loop {
+            if start.elapsed() >= self.timeout {
+                return Err(SdoError::Timeout);
+            }
+
+            match self.socket.recv_frame() {
+                Ok(frame) => {
+                    if frame.is_extended_frame() || frame.can_id() != can_id || frame.dlc() != 8 {
+                        continue;
+                    }
+
+                    let mut response = [0u8; 8];
+                    response.copy_from_slice(frame.data());
+
+                    if response[0] >> 5 == CS_ABORT {
+                        let code = u32::from_le_bytes([
+                            response[4],
+                            response[5],
+                            response[6],
+                            response[7],
+                        ]);
+                        return Err(SdoError::Abort(code));
+                    }
+                    return Ok(response);
+                }
+                Err(PcanError::QrcvEmpty) => sleep(Duration::from_millis(1)),
+                Err(err) => return Err(SdoError::Pcan(err)),
+            }
+        }
+    }
+}
+
+/* Helper functions */
+
+fn initiate(command: u8, index: u16, subindex: u8, data: &[u8]) -> [u8; 8] {
+    let index = index.to_le_bytes();
+    let mut request = [command, index[0], index[1], subindex, 0, 0, 0, 0];
+    request[4..4 + data.len()].copy_from_slice(data);
+    request
+}
+
+fn same_object(response: &[u8; 8], index: u16, subindex: u8) -> bool {
+    u16::from_le_bytes([response[1], response[2]]) == index && response[3] == subindex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::socket::mock::MockSocket;
+
+    fn socket(responses: &[[u8; 8]]) -> MockSocket {
+        let responses = responses
+            .iter()
+            .map(|data| vec![CanFrame::new(0x585, MessageType::Standard, data).unwrap()])
+            .collect();
+        MockSocket::with_responses(responses)
+    }
+
+    #[test]
+    fn sdo_upload_001() {
+        let socket = socket(&[[0x4B, 0x00, 0x10, 0x00, 0x92, 0x01, 0, 0]]);
+        let client = SdoClient::new(&socket, 5);
+
+        assert_eq!(client.upload(0x1000, 0), Ok(vec![0x92, 0x01]));
+        assert_eq!(socket.sent.borrow()[0].can_id(), 0x605);
+        assert_eq!(
+            socket.sent.borrow()[0].data(),
+            &[0x40, 0x00, 0x10, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn sdo_upload_002() {
+        let socket = socket(&[
+            [0x41, 0x08, 0x10, 0x00, 10, 0, 0, 0],
+            [0x00, b'P', b'C', b'A', b'N', b'-', b'U', b'S'],
+            [0x19, b'B', b'F', b'D', 0, 0, 0, 0],
+        ]);
+        let client = SdoClient::new(&socket, 5);
+
+        assert_eq!(client.upload(0x1008, 0), Ok(b"PCAN-USBFD".to_vec()));
+        assert_eq!(socket.sent.borrow()[2].data()[0], 0x70);
+    }
+
+    #[test]
+    fn sdo_download_001() {
+        let socket = socket(&[
+            [0x60, 0x00, 0x20, 0x01, 0, 0, 0, 0],
+            [0x20, 0, 0, 0, 0, 0, 0, 0],
+            [0x30, 0, 0, 0, 0, 0, 0, 0],
+        ]);
+        let client = SdoClient::new(&socket, 5);
+
+        assert_eq!(
+            client.download(0x2000, 1, &[1, 2, 3, 4, 5, 6, 7, 8, 9]),
+            Ok(())
+        );
+
+        let sent = socket.sent.borrow();
+        assert_eq!(sent[0].data(), &[0x21, 0x00, 0x20, 0x01, 9, 0, 0, 0]);
+        assert_eq!(sent[1].data(), &[0x00, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(sent[2].data(), &[0x1B, 8, 9, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn sdo_download_002() {
+        let socket = socket(&[[0x80, 0x00, 0x10, 0x00, 0x02, 0x00, 0x01, 0x06]]);
+        let client = SdoClient::new(&socket, 5);
+
+        assert_eq!(
+            client.download(0x1000, 0, &[0, 0, 0, 0]),
+            Err(SdoError::Abort(0x0601_0002))
+        );
+        assert_eq!(client.download(0x1000, 0, &[]), Err(SdoError::NoData));
+        assert_eq!(socket.sent.borrow().len(), 1);
+    }
+}
@@ -0,0 +1,422 @@
+//! Simultaneous capture of several channels merged into a single chronological stream.
+//!
+//! Every socket is read on its own thread. The timestamps of each device are moved onto a common
+//! time base, the time since the reader was created, by the smallest observed difference between
+//! the time a frame was received by the host and its device timestamp. Frames are held back for
+//! a reorder window, so frames of slower channels can still be sorted in. Sources without device
+//! timestamps, e.g. a [ReplaySink], are stamped with the time they were received.
+
+use crate::bus::Bus;
+use crate::error::PcanError;
+use crate::format::{Frame, Record};
+use crate::replay::ReplaySink;
+use crate::socket::{RecvCan, RecvCanFd, Timestamp};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Interval in which empty receive queues are polled.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Frame received on one of the channels of a [MultiChannelReader].
+#[derive(Debug, PartialEq, Clone)]
+pub struct CapturedFrame {
+    /// Channel handle of the bus the frame was received on.
+    pub channel: u16,
+    /// Frame with its timestamp on the common time base. The bus counts the channels in the
+    /// order they were added, starting with 1.
+    pub record: Record,
+}
+
+/// Error of a channel, which is not read any further.
+#[derive(Debug, PartialEq)]
+pub struct ChannelError {
+    pub channel: u16,
+    pub error: PcanError,
+}
+
+enum Message {
+    Frame {
+        source: usize,
+        frame: Frame,
+        /// Device and host timestamps in microseconds.
+        device: u64,
+        host: u64,
+    },
+    Error {
+        source: usize,
+        error: PcanError,
+    },
+}
+
+/// Frame waiting in the reorder window.
+struct Pending {
+    timestamp: u64,
+    /// Order of arrival, keeps frames with equal timestamps in order.
+    sequence: u64,
+    source: usize,
+    frame: Frame,
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.timestamp, self.sequence).cmp(&(other.timestamp, other.sequence))
+    }
+}
+
+/* MultiChannelReader */
+
+/// Iterator over the frames of several channels in the order of their timestamps. Blocks until
+/// the next frame left the reorder window and ends when all channels failed.
+pub struct MultiChannelReader {
+    start: Instant,
+    window: Duration,
+    capacity: usize,
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+    channels: Vec<u16>,
+    /// Difference between host and device time of every channel.
+    offsets: Vec<Option<i64>>,
+    running: usize,
+    pending: BinaryHeap<Reverse<Pending>>,
+    sequence: u64,
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl MultiChannelReader {
+    /// Reader holding frames back for `window` to sort them.
+    pub fn new(window: Duration) -> MultiChannelReader {
+        let (sender, receiver) = channel();
+        MultiChannelReader {
+            start: Instant::now(),
+            window,
+            capacity: 10_000,
+            sender,
+            receiver,
+            channels: Vec::new(),
+            offsets: Vec::new(),
+            running: 0,
+            pending: BinaryHeap::new(),
+            sequence: 0,
+            stop: Arc::new(AtomicBool::new(false)),
+            threads: Vec::new(),
+        }
+    }
+
+    /// Limits the number of frames held back, further frames push out the oldest one before its
+    /// window has passed.
+    pub fn with_capacity(mut self, capacity: usize) -> MultiChannelReader {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Starts reading `socket`, opened on `bus`. Returns the bus number of its records.
+    pub fn add<B: Bus, S: RecvCan + Send + 'static>(&mut self, bus: B, socket: S) -> u8 {
+        self.spawn(bus, move || match socket.recv() {
+            Ok((frame, timestamp)) => Ok(Some((Frame::Can(frame), Some(timestamp.as_micros())))),
+            Err(PcanError::QrcvEmpty) => Ok(None),
+            Err(err) => Err(err),
+        })
+    }
+
+    /// Starts reading `socket`, opened on `bus` in FD mode. Returns the bus number of its
+    /// records.
+    pub fn add_fd<B: Bus, S: RecvCanFd + Send + 'static>(&mut self, bus: B, socket: S) -> u8 {
+        self.spawn(bus, move || match socket.recv_fd() {
+            Ok((frame, micros)) => Ok(Some((Frame::CanFd(frame), Some(micros)))),
+            Err(PcanError::QrcvEmpty) => Ok(None),
+            Err(err) => Err(err),
+        })
+    }
+
+    /// Starts reading the frames received by `sink`, connected to `bus`. The frames have no
+    /// device timestamps and are stamped when received. Returns the bus number of its records.
+    pub fn add_sink<B: Bus, S: ReplaySink + Send + 'static>(&mut self, bus: B, mut sink: S) -> u8 {
+        self.spawn(bus, move || {
+            sink.recv_frame()
+                .map(|frame| frame.map(|frame| (frame, None)))
+        })
+    }
+
+    /// Reads with `recv` on a thread of its own until it fails. `recv` returns `None` while
+    /// no frame is pending and the frame with its device timestamp, if any, otherwise.
+    fn spawn<B, F>(&mut self, bus: B, mut recv: F) -> u8
+    where
+        B: Bus,
+        F: FnMut() -> Result<Option<(Frame, Option<u64>)>, PcanError> + Send + 'static,
+    {
+        let source = self.channels.len();
+        self.channels.push(bus.channel());
+        self.offsets.push(None);
+        self.running += 1;
+
+        let sender = self.sender.clone();
+        let stop = self.stop.clone();
+        let start = self.start;
+        self.threads.push(std::thread::spawn(move || {
+            while !stop.load(AtomicOrdering::Relaxed) {
+                let message = match recv() {
+                    Ok(Some((frame, device))) => {
+                        let host = start.elapsed().as_micros() as u64;
+                        Message::Frame {
+                            source,
+                            frame,
+                            device: device.unwrap_or(host),
+                            host,
+                        }
+                    }
+                    Ok(None) => {
+                        std::thread::sleep(POLL_INTERVAL);
+                        continue;
+                    }
+                    Err(error) => {
+                        let _ = sender.send(Message::Error { source, error });
+                        return;
+                    }
+                };
+                if sender.send(message).is_err() {
+                    return;
+                }
+            }
+        }));
+
+        source as u8 + 1
+    }
+
+    /// Channel handle of the bus number `bus`.
+    pub fn channel(&self, bus: u8) -> Option<u16> {
+        self.channels.get((bus as usize).checked_sub(1)?).copied()
+    }
+
+    /// Writes the merged frames with `write`, e.g. the `write_record` function of a log file
+    /// writer, until all channels failed or `running` is cleared. The frames held back are
+    /// written before returning. Returns the errors of the failed channels.
+    pub fn write_into<E, F>(
+        &mut self,
+        running: &AtomicBool,
+        mut write: F,
+    ) -> Result<Vec<ChannelError>, E>
+    where
+        F: FnMut(&Record) -> Result<(), E>,
+    {
+        let mut errors = Vec::new();
+        while let Some(item) = self.next_while(Some(running)) {
+            match item {
+                Ok(captured) => write(&captured.record)?,
+                Err(err) => errors.push(err),
+            }
+        }
+        Ok(errors)
+    }
+
+    fn now(&self) -> u64 {
+        self.start.elapsed().as_micros() as u64
+    }
+
+    fn push(&mut self, source: usize, frame: Frame, device: u64, host: u64) {
+        // The smallest difference belongs to the frame received with the least delay.
+        let difference = host as i64 - device as i64;
+        let offset = match self.offsets[source] {
+            Some(offset) => offset.min(difference),
+            None => difference,
+        };
+        self.offsets[source] = Some(offset);
+
+        self.pending.push(Reverse(Pending {
+            timestamp: (device as i64 + offset).max(0) as u64,
+            sequence: self.sequence,
+            source,
+            frame,
+        }));
+        self.sequence += 1;
+    }
+
+    fn pop(&mut self) -> Option<CapturedFrame> {
+        let Reverse(pending) = self.pending.pop()?;
+        let mut record = Record::new(Timestamp::from_micros(pending.timestamp), pending.frame);
+        record.bus = pending.source as u8 + 1;
+        Some(CapturedFrame {
+            channel: self.channels[pending.source],
+            record,
+        })
+    }
+
+    /// Next frame or channel error, ends when all channels failed or `running` is cleared and
+    /// no frame is held back anymore.
+    fn next_while(
+        &mut self,
+        running: Option<&AtomicBool>,
+    ) -> Option<Result<CapturedFrame, ChannelError>> {
+        let window = self.window.as_micros() as u64;
+        loop {
+            let stopped = running.is_some_and(|running| !running.load(AtomicOrdering::Relaxed));
+            let timeout = match self.pending.peek() {
+                Some(Reverse(pending)) => {
+                    let due = pending.timestamp + window;
+                    let now = self.now();
+                    if due <= now
+                        || self.pending.len() > self.capacity
+                        || self.running == 0
+                        || stopped
+                    {
+                        return self.pop().map(Ok);
+                    }
+                    Duration::from_micros(due - now)
+                }
+                None if self.running == 0 || stopped => return None,
+                None => Duration::from_millis(100),
+            };
+
+            match self.receiver.recv_timeout(timeout) {
+                Ok(Message::Frame {
+                    source,
+                    frame,
+                    device,
+                    host,
+                }) => self.push(source, frame, device, host),
+                Ok(Message::Error { source, error }) => {
+                    self.running -= 1;
+                    return Some(Err(ChannelError {
+                        channel: self.channels[source],
+                        error,
+                    }));
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+}
+
+impl Iterator for MultiChannelReader {
+    type Item = Result<CapturedFrame, ChannelError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_while(None)
+    }
+}
+
+impl Drop for MultiChannelReader {
+    fn drop(&mut self) {
+        self.stop.store(true, AtomicOrdering::Relaxed);
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::UsbBus;
+    use crate::replay::MemorySink;
+    use crate::socket::{CanFdFrame, CanFrame, MessageType};
+
+    fn frame(id: u32) -> Frame {
+        Frame::Can(CanFrame::new(id, MessageType::Standard, &[]).unwrap())
+    }
+
+    #[test]
+    fn multi_channel_reader_001() {
+        let fd = CanFdFrame::new(0x200, MessageType::Standard, &[0xAA; 12]).unwrap();
+        let mut first = MemorySink::new();
+        first
+            .pending
+            .extend([frame(0x100), frame(0x101), frame(0x102)]);
+        let mut second = MemorySink::new();
+        second
+            .pending
+            .extend([Frame::CanFd(fd), frame(0x201), frame(0x202)]);
+
+        let mut reader = MultiChannelReader::new(Duration::from_millis(5));
+        assert_eq!(reader.add_sink(UsbBus::USB1, first), 1);
+        assert_eq!(reader.add_sink(UsbBus::USB2, second), 2);
+        assert_eq!(reader.channel(2), Some(UsbBus::USB2.channel()));
+
+        let running = AtomicBool::new(true);
+        let mut records = Vec::new();
+        let errors = reader
+            .write_into(&running, |record| {
+                records.push(record.clone());
+                if records.len() == 6 {
+                    running.store(false, AtomicOrdering::Relaxed);
+                }
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+
+        assert_eq!(errors, vec![]);
+        assert_eq!(records.len(), 6);
+        let ids = |bus: u8| {
+            records
+                .iter()
+                .filter(|record| record.bus == bus)
+                .map(|record| record.frame.can_id().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(1), vec![0x100, 0x101, 0x102]);
+        assert_eq!(ids(2), vec![0x200, 0x201, 0x202]);
+        assert!(records
+            .iter()
+            .any(|record| record.bus == 2 && record.frame == Frame::CanFd(fd)));
+    }
+
+    #[test]
+    fn multi_channel_reader_002() {
+        let mut reader = MultiChannelReader::new(Duration::from_millis(5));
+        for bus in [UsbBus::USB1, UsbBus::USB2] {
+            reader.channels.push(bus.channel());
+            reader.offsets.push(None);
+        }
+
+        // Device clocks far apart, both map onto the host time of their fastest frame.
+        reader.push(0, frame(0x100), 7_000_000, 1_000);
+        reader.push(1, frame(0x200), 5_000, 1_200);
+        reader.push(0, frame(0x101), 7_010_000, 11_500);
+        reader.push(0, frame(0x102), 7_030_000, 31_000);
+        // Received late, sorted in by its device time.
+        reader.push(1, frame(0x201), 25_000, 33_000);
+
+        let frames = std::iter::from_fn(|| reader.pop()).collect::<Vec<_>>();
+        let order = frames
+            .iter()
+            .map(|frame| {
+                (
+                    frame.record.frame.can_id().unwrap(),
+                    frame.record.bus,
+                    frame.record.timestamp.as_micros(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec![
+                (0x100, 1, 1_000),
+                (0x200, 2, 1_200),
+                (0x101, 1, 11_000),
+                (0x201, 2, 21_200),
+                (0x102, 1, 31_000)
+            ]
+        );
+        assert_eq!(frames[1].channel, UsbBus::USB2.channel());
+    }
+}
@@ -0,0 +1,558 @@
+//! Signal decoding and encoding based on DBC databases.
+//!
+//! A [Database] is loaded from a DBC file and turns received [CanFrame]s and [CanFdFrame]s into
+//! named physical values. In the other direction, [Message::encode] builds frames from signal
+//! values which can be sent with [SendCan](crate::socket::SendCan).
+
+mod parser;
+
+use crate::socket::{CanFdFrame, CanFrame, FrameConstructionError, MessageType};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const EXTENDED_FLAG: u32 = 0x8000_0000;
+
+/// Mapping of raw signal values to their textual description.
+pub type ValueTable = BTreeMap<i64, String>;
+
+#[derive(Debug)]
+pub enum DbcError {
+    Io(std::io::Error),
+    /// The statement starting at the given line could not be parsed.
+    Syntax {
+        line: usize,
+    },
+    /// The message has no signal with the given name.
+    UnknownSignal(String),
+    /// The value of the given signal exceeds its range.
+    OutOfRange(String),
+    /// The encoded data does not fit into the frame.
+    Frame(FrameConstructionError),
+}
+
+impl From<std::io::Error> for DbcError {
+    fn from(value: std::io::Error) -> Self {
+        DbcError::Io(value)
+    }
+}
+
+impl From<FrameConstructionError> for DbcError {
+    fn from(value: FrameConstructionError) -> Self {
+        DbcError::Frame(value)
+    }
+}
+
+/* Signal */
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ByteOrder {
+    /// Intel byte order, `@1` in DBC files.
+    LittleEndian,
+    /// Motorola byte order, `@0` in DBC files.
+    BigEndian,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ValueType {
+    Unsigned,
+    Signed,
+    /// IEEE 754 single precision, declared by `SIG_VALTYPE_`.
+    Float32,
+    /// IEEE 754 double precision, declared by `SIG_VALTYPE_`.
+    Float64,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Multiplexing {
+    /// The signal is always present.
+    None,
+    /// The signal selects which multiplexed signals are present.
+    Multiplexor,
+    /// The signal is present if the multiplexor has the given raw value.
+    Multiplexed(u64),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Signal {
+    pub name: String,
+    /// Start bit as written in the DBC file. For big endian signals this is the most significant
+    /// bit, for little endian signals the least significant bit.
+    pub start_bit: u16,
+    pub size: u16,
+    pub byte_order: ByteOrder,
+    pub value_type: ValueType,
+    pub factor: f64,
+    pub offset: f64,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub unit: String,
+    pub receivers: Vec<String>,
+    pub multiplexing: Multiplexing,
+    pub values: ValueTable,
+    pub comment: Option<String>,
+}
+
+impl Signal {
+    /// Extracts the raw bits of the signal or `None` if `data` is too short.
+    pub fn raw_value(&self, data: &[u8]) -> Option<u64> {
+        let mut raw = 0u64;
+        let mut position = self.start_bit as usize;
+
+        for i in 0..self.size as usize {
+            let bit = match self.byte_order {
+                ByteOrder::LittleEndian => position + i,
+                ByteOrder::BigEndian => position,
+            };
+            let byte = data.get(bit / 8)?;
+            let value = ((byte >> (bit % 8)) & 0x01) as u64;
+
+            match self.byte_order {
+                ByteOrder::LittleEndian => raw |= value << i,
+                ByteOrder::BigEndian => {
+                    raw = (raw << 1) | value;
+                    position = next_big_endian_bit(position);
+                }
+            }
+        }
+
+        Some(raw)
+    }
+
+    /// Decodes the physical value of the signal or `None` if `data` is too short.
+    pub fn decode(&self, data: &[u8]) -> Option<f64> {
+        self.raw_value(data).map(|raw| self.physical_value(raw))
+    }
+
+    /// Converts raw bits into the physical value by applying factor and offset.
+    pub fn physical_value(&self, raw: u64) -> f64 {
+        let value = match self.value_type {
+            ValueType::Unsigned => raw as f64,
+            ValueType::Signed => self.signed(raw) as f64,
+            ValueType::Float32 => f32::from_bits(raw as u32) as f64,
+            ValueType::Float64 => f64::from_bits(raw),
+        };
+        value * self.factor + self.offset
+    }
+
+    /// Converts a physical value into raw bits or `None` if it does not fit into the signal.
+    pub fn raw_from_physical(&self, value: f64) -> Option<u64> {
+        let scaled = (value - self.offset) / self.factor;
+
+        match self.value_type {
+            ValueType::Float32 => Some((scaled as f32).to_bits() as u64),
+            ValueType::Float64 => Some(scaled.to_bits()),
+            ValueType::Unsigned => {
+                let scaled = scaled.round();
+                let max = (u64::MAX >> (64 - self.size)) as f64;
+                match scaled >= 0.0 && scaled <= max {
+                    true => Some(scaled as u64),
+                    false => None,
+                }
+            }
+            ValueType::Signed => {
+                let scaled = scaled.round();
+                let max = (i64::MAX >> (64 - self.size)) as f64;
+                let min = -max - 1.0;
+                match scaled >= min && scaled <= max {
+                    true => Some((scaled as i64 as u64) & (u64::MAX >> (64 - self.size))),
+                    false => None,
+                }
+            }
+        }
+    }
+
+    /// Description of `raw` taken from the value table of the signal.
+    pub fn value_description(&self, raw: u64) -> Option<&str> {
+        let key = match self.value_type {
+            ValueType::Signed => self.signed(raw),
+            _ => raw as i64,
+        };
+        self.values.get(&key).map(|s| s.as_str())
+    }
+
+    fn signed(&self, raw: u64) -> i64 {
+        let shift = 64 - self.size as u32;
+        ((raw << shift) as i64) >> shift
+    }
+
+    fn insert(&self, data: &mut [u8], raw: u64) -> Option<()> {
+        let mut position = self.start_bit as usize;
+
+        for i in 0..self.size as usize {
+            let (bit, value) = match self.byte_order {
+                ByteOrder::LittleEndian => (position + i, (raw >> i) & 0x01),
+                ByteOrder::BigEndian => (position, (raw >> (self.size as usize - 1 - i)) & 0x01),
+            };
+            let byte = data.get_mut(bit / 8)?;
+            *byte &= !(1 << (bit % 8));
+            *byte |= (value as u8) << (bit % 8);
+
+            if self.byte_order == ByteOrder::BigEndian {
+                position = next_big_endian_bit(position);
+            }
+        }
+
+        Some(())
+    }
+}
+
+/// Next less significant bit of a big endian signal in DBC bit numbering.
+fn next_big_endian_bit(position: usize) -> usize {
+    match position % 8 {
+        0 => position + 15,
+        _ => position - 1,
+    }
+}
+
+/* Message */
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Message {
+    /// CAN-ID without the extended flag used in DBC files.
+    pub id: u32,
+    pub extended: bool,
+    pub name: String,
+    /// Payload size in bytes.
+    pub size: usize,
+    pub sender: String,
+    pub signals: Vec<Signal>,
+    pub comment: Option<String>,
+}
+
+impl Message {
+    /// Identifier as used inside DBC files, i.e. with bit 31 set for extended frames.
+    pub fn raw_id(&self) -> u32 {
+        match self.extended {
+            true => self.id | EXTENDED_FLAG,
+            false => self.id,
+        }
+    }
+
+    pub fn msg_type(&self) -> MessageType {
+        match self.extended {
+            true => MessageType::Extended,
+            false => MessageType::Standard,
+        }
+    }
+
+    pub fn signal(&self, name: &str) -> Option<&Signal> {
+        self.signals.iter().find(|signal| signal.name == name)
+    }
+
+    /// The multiplexor signal of the message, if any.
+    pub fn multiplexor(&self) -> Option<&Signal> {
+        self.signals
+            .iter()
+            .find(|signal| signal.multiplexing == Multiplexing::Multiplexor)
+    }
+
+    /// Decodes all signals present in `data`.
+    ///
+    /// Multiplexed signals are only decoded if the multiplexor selects them. Signals which do not
+    /// fit into `data` are skipped.
+    pub fn decode<'a>(&'a self, data: &[u8]) -> Vec<DecodedSignal<'a>> {
+        let selector = self
+            .multiplexor()
+            .and_then(|multiplexor| multiplexor.raw_value(data));
+
+        self.signals
+            .iter()
+            .filter(|signal| match signal.multiplexing {
+                Multiplexing::Multiplexed(value) => selector == Some(value),
+                _ => true,
+            })
+            .filter_map(|signal| {
+                let raw = signal.raw_value(data)?;
+                Some(DecodedSignal {
+                    signal,
+                    raw,
+                    value: signal.physical_value(raw),
+                })
+            })
+            .collect()
+    }
+
+    /// Encodes the physical signal `values` into a payload of [size](Message::size) bytes.
+    ///
+    /// Signals which are not listed are encoded as raw zero.
+    pub fn encode_data(&self, values: &[(&str, f64)]) -> Result<Vec<u8>, DbcError> {
+        let mut data = vec![0u8; self.size];
+
+        for (name, value) in values {
+            let signal = self
+                .signal(name)
+                .ok_or_else(|| DbcError::UnknownSignal(String::from(*name)))?;
+
+            let in_range = signal.minimum >= signal.maximum
+                || (*value >= signal.minimum && *value <= signal.maximum);
+            let raw = match in_range {
+                true => signal.raw_from_physical(*value),
+                false => None,
+            };
+
+            match raw.and_then(|raw| signal.insert(&mut data, raw)) {
+                Some(_) => {}
+                None => return Err(DbcError::OutOfRange(String::from(*name))),
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Builds a classic CAN frame from physical signal `values`.
+    pub fn encode(&self, values: &[(&str, f64)]) -> Result<CanFrame, DbcError> {
+        let data = self.encode_data(values)?;
+        Ok(CanFrame::new(self.id, self.msg_type(), &data)?)
+    }
+
+    /// Builds a CAN FD frame from physical signal `values`.
+    pub fn encode_fd(&self, values: &[(&str, f64)]) -> Result<CanFdFrame, DbcError> {
+        let data = self.encode_data(values)?;
+        Ok(CanFdFrame::new(self.id, self.msg_type(), &data)?)
+    }
+}
+
+/* DecodedSignal */
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct DecodedSignal<'a> {
+    pub signal: &'a Signal,
+    pub raw: u64,
+    /// Physical value, i.e. the raw value with factor and offset applied.
+    pub value: f64,
+}
+
+impl<'a> DecodedSignal<'a> {
+    pub fn name(&self) -> &'a str {
+        &self.signal.name
+    }
+
+    pub fn unit(&self) -> &'a str {
+        &self.signal.unit
+    }
+
+    pub fn description(&self) -> Option<&'a str> {
+        self.signal.value_description(self.raw)
+    }
+}
+
+/* DecodedMessage */
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct DecodedMessage<'a> {
+    pub message: &'a Message,
+    pub signals: Vec<DecodedSignal<'a>>,
+}
+
+impl<'a> DecodedMessage<'a> {
+    pub fn signal(&self, name: &str) -> Option<&DecodedSignal<'a>> {
+        self.signals.iter().find(|signal| signal.name() == name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<f64> {
+        self.signal(name).map(|signal| signal.value)
+    }
+}
+
+/* Database */
+
+#[derive(Debug, Default, Clone)]
+pub struct Database {
+    pub version: String,
+    pub nodes: Vec<String>,
+    pub messages: Vec<Message>,
+    /// Named value tables declared by `VAL_TABLE_`.
+    pub value_tables: HashMap<String, ValueTable>,
+    pub comment: Option<String>,
+}
+
+impl Database {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Database, DbcError> {
+        let bytes = std::fs::read(path)?;
+        // DBC files are commonly stored as Windows-1252.
+        let text = match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(err) => err.as_bytes().iter().map(|b| char::from(*b)).collect(),
+        };
+        Database::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Database, DbcError> {
+        parser::parse(text)
+    }
+
+    pub fn message(&self, can_id: u32, extended: bool) -> Option<&Message> {
+        self.messages
+            .iter()
+            .find(|message| message.id == can_id && message.extended == extended)
+    }
+
+    pub fn message_by_name(&self, name: &str) -> Option<&Message> {
+        self.messages.iter().find(|message| message.name == name)
+    }
+
+    /// Decodes a received frame or returns `None` if the database does not know its CAN-ID.
+    pub fn decode(&self, frame: &CanFrame) -> Option<DecodedMessage<'_>> {
+        let message = self.message(frame.can_id(), frame.is_extended_frame())?;
+        Some(DecodedMessage {
+            message,
+            signals: message.decode(frame.data()),
+        })
+    }
+
+    /// Decodes a received CAN FD frame or returns `None` if the database does not know its
+    /// CAN-ID.
+    pub fn decode_fd(&self, frame: &CanFdFrame) -> Option<DecodedMessage<'_>> {
+        let message = self.message(frame.can_id(), frame.is_extended_frame())?;
+        Some(DecodedMessage {
+            message,
+            signals: message.decode(frame.data()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DBC: &str = r#"VERSION "1.0"
+
+NS_ :
+	NS_DESC_
+	CM_
+	VAL_
+
+BS_:
+
+BU_: ECU GATEWAY
+
+BO_ 256 EngineData: 8 ECU
+ SG_ EngineSpeed : 0|16@1+ (0.25,0) [0|16383.75] "rpm" GATEWAY
+ SG_ CoolantTemp : 16|8@1- (1,-40) [-40|215] "degC" GATEWAY
+ SG_ Gear : 24|4@1+ (1,0) [0|15] "" GATEWAY
+
+BO_ 2566844926 Motorola: 8 GATEWAY
+ SG_ Pressure : 7|12@0+ (0.1,0) [0|409.5] "bar" ECU
+ SG_ Offset : 11|8@0- (1,0) [0|0] "" ECU
+
+BO_ 512 Muxed: 8 ECU
+ SG_ Selector M : 0|8@1+ (1,0) [0|255] "" GATEWAY
+ SG_ PageA m0 : 8|16@1+ (1,0) [0|65535] "" GATEWAY
+ SG_ PageB m1 : 8|8@1+ (1,0) [0|255] "" GATEWAY
+
+CM_ SG_ 256 EngineSpeed "Crankshaft
+speed";
+VAL_ 256 Gear 0 "Neutral" 1 "First" 15 "Invalid" ;
+VAL_TABLE_ OnOff 0 "Off" 1 "On" ;
+"#;
+
+    #[test]
+    fn database_parse_001() {
+        let database = Database::parse(DBC).unwrap();
+
+        assert_eq!(database.version, "1.0");
+        assert_eq!(database.nodes, vec!["ECU", "GATEWAY"]);
+        assert_eq!(database.messages.len(), 3);
+        assert_eq!(database.value_tables["OnOff"][&1], "On");
+
+        let message = database.message(0x18FEF1FE, true).unwrap();
+        assert_eq!(message.name, "Motorola");
+        assert_eq!(message.signals[0].byte_order, ByteOrder::BigEndian);
+
+        let signal = database.messages[0].signal("EngineSpeed").unwrap();
+        assert_eq!(signal.comment.as_deref(), Some("Crankshaft\nspeed"));
+    }
+
+    #[test]
+    fn database_parse_002() {
+        let text = r#"BO_ 256 EngineData: 8 ECU
+ SG_ Gear : 24|4@1+ (1,0) [0|15] "" GATEWAY
+
+EV_ DoorState: 0 [0|1] "" 0 1 DUMMY_NODE_VECTOR0 Vector__XXX;
+VAL_ DoorState 0 "Closed" 1 "Open" ;
+VAL_ 256 Gear 0 "Neutral" ;
+"#;
+        let database = Database::parse(text).unwrap();
+        let signal = database.messages[0].signal("Gear").unwrap();
+        assert_eq!(signal.values[&0], "Neutral");
+    }
+
+    #[test]
+    fn database_decode_001() {
+        let database = Database::parse(DBC).unwrap();
+        let frame = CanFrame::new(
+            0x100,
+            MessageType::Standard,
+            &[0x40, 0x1F, 0x3C, 0x01, 0, 0, 0, 0],
+        )
+        .unwrap();
+
+        let decoded = database.decode(&frame).unwrap();
+        assert_eq!(decoded.value("EngineSpeed"), Some(2000.0));
+        assert_eq!(decoded.value("CoolantTemp"), Some(20.0));
+        assert_eq!(decoded.signal("Gear").unwrap().description(), Some("First"));
+    }
+
+    #[test]
+    fn database_decode_002() {
+        let database = Database::parse(DBC).unwrap();
+        let frame = CanFrame::new(
+            0x18FEF1FE,
+            MessageType::Extended,
+            &[0x12, 0x3F, 0xF0, 0, 0, 0, 0, 0],
+        )
+        .unwrap();
+
+        let decoded = database.decode(&frame).unwrap();
+        assert_eq!(decoded.signal("Pressure").unwrap().raw, 0x123);
+        assert_eq!(decoded.value("Offset"), Some(-1.0));
+    }
+
+    #[test]
+    fn database_decode_003() {
+        let database = Database::parse(DBC).unwrap();
+        let message = database.message_by_name("Muxed").unwrap();
+
+        let decoded = message.decode(&[1, 0x2A, 0xFF, 0, 0, 0, 0, 0]);
+        let names = decoded.iter().map(|s| s.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Selector", "PageB"]);
+    }
+
+    #[test]
+    fn message_encode_001() {
+        let database = Database::parse(DBC).unwrap();
+
+        let message = database.message_by_name("EngineData").unwrap();
+        let frame = message
+            .encode(&[
+                ("EngineSpeed", 2000.0),
+                ("CoolantTemp", 20.0),
+                ("Gear", 1.0),
+            ])
+            .unwrap();
+        assert_eq!(frame.data(), &[0x40, 0x1F, 0x3C, 0x01, 0, 0, 0, 0]);
+
+        let message = database.message_by_name("Motorola").unwrap();
+        let frame = message
+            .encode_fd(&[("Pressure", 29.1), ("Offset", -1.0)])
+            .unwrap();
+        assert!(frame.is_extended_frame());
+        assert_eq!(frame.can_id(), 0x18FEF1FE);
+        assert_eq!(frame.data(), &[0x12, 0x3F, 0xF0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn message_encode_002() {
+        let database = Database::parse(DBC).unwrap();
+        let message = database.message_by_name("EngineData").unwrap();
+
+        assert!(matches!(
+            message.encode(&[("Gear", 16.0)]),
+            Err(DbcError::OutOfRange(_))
+        ));
+        assert!(matches!(
+            message.encode(&[("Unknown", 0.0)]),
+            Err(DbcError::UnknownSignal(_))
+        ));
+    }
+}
@@ -0,0 +1,426 @@
+//! Line oriented parser for the DBC file format.
+//!
+//! Only the statements needed to decode and encode frames are interpreted: `BU_`, `BO_`, `SG_`,
+//! `VAL_`, `VAL_TABLE_`, `SIG_VALTYPE_` and `CM_`. Every other statement is skipped.
+
+use crate::dbc::{
+    ByteOrder, Database, DbcError, Message, Multiplexing, Signal, ValueTable, ValueType,
+};
+
+/// Identifier of the pseudo message collecting signals which are not assigned to any message.
+const INDEPENDENT_SIGNALS_ID: u32 = 0xC000_0000;
+
+const EXTENDED_FLAG: u32 = 0x8000_0000;
+
+pub(crate) fn parse(text: &str) -> Result<Database, DbcError> {
+    let mut database = Database::default();
+    let mut in_new_symbols = false;
+    let mut statement = String::new();
+    let mut statement_line = 0;
+
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        let trimmed = line.trim();
+
+        if !statement.is_empty() {
+            statement.push('\n');
+            statement.push_str(line);
+            if is_terminated(&statement) {
+                parse_statement(&mut database, &statement, statement_line)?;
+                statement.clear();
+            }
+            continue;
+        }
+
+        if in_new_symbols {
+            if !trimmed.starts_with("BS_") {
+                continue;
+            }
+            in_new_symbols = false;
+        }
+
+        let keyword = trimmed.split([' ', '\t', ':']).next().unwrap_or("");
+        match keyword {
+            "" => {}
+            "NS_" => in_new_symbols = true,
+            "VERSION" => {
+                let mut tokens = Tokens::new(trimmed, number);
+                tokens.keyword("VERSION")?;
+                database.version = tokens.string()?;
+            }
+            "BU_" => {
+                let mut tokens = Tokens::new(trimmed, number);
+                tokens.keyword("BU_")?;
+                tokens.punct(':')?;
+                while let Some(node) = tokens.try_identifier() {
+                    database.nodes.push(node);
+                }
+            }
+            "BO_" => {
+                let message = parse_message(trimmed, number)?;
+                database.messages.push(message);
+            }
+            "SG_" => {
+                let signal = parse_signal(trimmed, number)?;
+                match database.messages.last_mut() {
+                    Some(message) => message.signals.push(signal),
+                    None => return Err(DbcError::Syntax { line: number }),
+                }
+            }
+            "VAL_" | "VAL_TABLE_" | "SIG_VALTYPE_" | "CM_" | "BA_DEF_" | "BA_DEF_DEF_" | "BA_"
+            | "BA_DEF_REL_" | "BA_DEF_DEF_REL_" | "BA_REL_" | "BO_TX_BU_" | "SIG_GROUP_"
+            | "EV_" | "ENVVAR_DATA_" | "SGTYPE_" | "SIG_TYPE_REF_" | "SG_MUL_VAL_" => {
+                statement.push_str(line);
+                statement_line = number;
+                if is_terminated(&statement) {
+                    parse_statement(&mut database, &statement, statement_line)?;
+                    statement.clear();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !statement.is_empty() {
+        return Err(DbcError::Syntax {
+            line: statement_line,
+        });
+    }
+
+    database
+        .messages
+        .retain(|message| message.raw_id() != INDEPENDENT_SIGNALS_ID);
+    Ok(database)
+}
+
+/// Returns `true` if the statement contains a `;` outside of a string literal.
+fn is_terminated(statement: &str) -> bool {
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in statement.chars() {
+        match c {
+            '\\' if quoted => escaped = !escaped,
+            '"' if !escaped => quoted = !quoted,
+            ';' if !quoted => return true,
+            _ => escaped = false,
+        }
+    }
+    false
+}
+
+fn parse_message(line: &str, number: usize) -> Result<Message, DbcError> {
+    let mut tokens = Tokens::new(line, number);
+    tokens.keyword("BO_")?;
+    let id = tokens.unsigned()? as u32;
+    let name = tokens.identifier()?;
+    tokens.punct(':')?;
+    let size = tokens.unsigned()? as usize;
+    let sender = tokens.identifier()?;
+
+    Ok(Message {
+        id: id & !EXTENDED_FLAG,
+        extended: id & EXTENDED_FLAG != 0,
+        name,
+        size,
+        sender,
+        signals: Vec::new(),
+        comment: None,
+    })
+}
+
+fn parse_signal(line: &str, number: usize) -> Result<Signal, DbcError> {
+    let mut tokens = Tokens::new(line, number);
+    tokens.keyword("SG_")?;
+    let name = tokens.identifier()?;
+
+    let multiplexing = match tokens.try_identifier() {
+        None => Multiplexing::None,
+        Some(indicator) if indicator == "M" => Multiplexing::Multiplexor,
+        Some(indicator) => match indicator
+            .strip_prefix('m')
+            .map(|value| value.trim_end_matches('M'))
+            .and_then(|value| value.parse::<u64>().ok())
+        {
+            Some(value) => Multiplexing::Multiplexed(value),
+            None => return Err(DbcError::Syntax { line: number }),
+        },
+    };
+
+    tokens.punct(':')?;
+    let start_bit = tokens.unsigned()? as u16;
+    tokens.punct('|')?;
+    let size = tokens.unsigned()? as u16;
+    tokens.punct('@')?;
+    let byte_order = match tokens.unsigned()? {
+        0 => ByteOrder::BigEndian,
+        1 => ByteOrder::LittleEndian,
+        _ => return Err(DbcError::Syntax { line: number }),
+    };
+    let value_type = match tokens.sign()? {
+        '+' => ValueType::Unsigned,
+        _ => ValueType::Signed,
+    };
+
+    tokens.punct('(')?;
+    let factor = tokens.number()?;
+    tokens.punct(',')?;
+    let offset = tokens.number()?;
+    tokens.punct(')')?;
+    tokens.punct('[')?;
+    let minimum = tokens.number()?;
+    tokens.punct('|')?;
+    let maximum = tokens.number()?;
+    tokens.punct(']')?;
+    let unit = tokens.string()?;
+
+    let mut receivers = Vec::new();
+    while let Some(receiver) = tokens.try_identifier() {
+        receivers.push(receiver);
+        if tokens.try_punct(',').is_none() {
+            break;
+        }
+    }
+
+    if size == 0 || size > 64 {
+        return Err(DbcError::Syntax { line: number });
+    }
+
+    Ok(Signal {
+        name,
+        start_bit,
+        size,
+        byte_order,
+        value_type,
+        factor,
+        offset,
+        minimum,
+        maximum,
+        unit,
+        receivers,
+        multiplexing,
+        values: ValueTable::new(),
+        comment: None,
+    })
+}
+
+fn parse_statement(
+    database: &mut Database,
+    statement: &str,
+    number: usize,
+) -> Result<(), DbcError> {
+    let mut tokens = Tokens::new(statement, number);
+    let keyword = tokens.identifier()?;
+
+    match keyword.as_str() {
+        "VAL_" => {
+            // Value descriptions of environment variables start with the variable name.
+            if tokens.try_identifier().is_some() {
+                return Ok(());
+            }
+            let id = tokens.unsigned()? as u32;
+            let signal_name = tokens.identifier()?;
+            let values = parse_value_descriptions(&mut tokens)?;
+            if let Some(signal) = database
+                .messages
+                .iter_mut()
+                .find(|message| message.raw_id() == id)
+                .and_then(|message| message.signals.iter_mut().find(|s| s.name == signal_name))
+            {
+                signal.values = values;
+            }
+        }
+        "VAL_TABLE_" => {
+            let name = tokens.identifier()?;
+            let values = parse_value_descriptions(&mut tokens)?;
+            database.value_tables.insert(name, values);
+        }
+        "SIG_VALTYPE_" => {
+            let id = tokens.unsigned()? as u32;
+            let signal_name = tokens.identifier()?;
+            tokens.try_punct(':');
+            let value_type = match tokens.unsigned()? {
+                1 => Some(ValueType::Float32),
+                2 => Some(ValueType::Float64),
+                _ => None,
+            };
+            if let Some(signal) = database
+                .messages
+                .iter_mut()
+                .find(|message| message.raw_id() == id)
+                .and_then(|message| message.signals.iter_mut().find(|s| s.name == signal_name))
+            {
+                if let Some(value_type) = value_type {
+                    signal.value_type = value_type;
+                }
+            }
+        }
+        "CM_" => {
+            if let Some(object) = tokens.try_identifier() {
+                match object.as_str() {
+                    "BO_" => {
+                        let id = tokens.unsigned()? as u32;
+                        let comment = tokens.string()?;
+                        if let Some(message) =
+                            database.messages.iter_mut().find(|m| m.raw_id() == id)
+                        {
+                            message.comment = Some(comment);
+                        }
+                    }
+                    "SG_" => {
+                        let id = tokens.unsigned()? as u32;
+                        let signal_name = tokens.identifier()?;
+                        let comment = tokens.string()?;
+                        if let Some(signal) = database
+                            .messages
+                            .iter_mut()
+                            .find(|message| message.raw_id() == id)
+                            .and_then(|message| {
+                                message.signals.iter_mut().find(|s| s.name == signal_name)
+                            })
+                        {
+                            signal.comment = Some(comment);
+                        }
+                    }
+                    _ => {}
+                }
+            } else {
+                database.comment = Some(tokens.string()?);
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn parse_value_descriptions(tokens: &mut Tokens) -> Result<ValueTable, DbcError> {
+    let mut values = ValueTable::new();
+    while tokens.try_punct(';').is_none() {
+        let value = tokens.number()? as i64;
+        let description = tokens.string()?;
+        values.insert(value, description);
+    }
+    Ok(values)
+}
+
+/* Tokens */
+
+/// Minimal tokenizer over a single DBC statement.
+struct Tokens<'a> {
+    rest: &'a str,
+    line: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str, line: usize) -> Tokens<'a> {
+        Tokens { rest: text, line }
+    }
+
+    fn error(&self) -> DbcError {
+        DbcError::Syntax { line: self.line }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), DbcError> {
+        match self.identifier()? == keyword {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    fn try_identifier(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        let mut chars = self.rest.char_indices();
+        match chars.next() {
+            Some((_, c)) if c.is_ascii_alphabetic() || c == '_' => {}
+            _ => return None,
+        }
+        let end = chars
+            .find(|(_, c)| !(c.is_ascii_alphanumeric() || *c == '_'))
+            .map(|(i, _)| i)
+            .unwrap_or(self.rest.len());
+        let identifier = String::from(&self.rest[..end]);
+        self.rest = &self.rest[end..];
+        Some(identifier)
+    }
+
+    fn identifier(&mut self) -> Result<String, DbcError> {
+        self.try_identifier().ok_or_else(|| self.error())
+    }
+
+    fn try_punct(&mut self, punct: char) -> Option<char> {
+        self.skip_whitespace();
+        let rest = self.rest.strip_prefix(punct)?;
+        self.rest = rest;
+        Some(punct)
+    }
+
+    fn punct(&mut self, punct: char) -> Result<(), DbcError> {
+        match self.try_punct(punct) {
+            Some(_) => Ok(()),
+            None => Err(self.error()),
+        }
+    }
+
+    fn sign(&mut self) -> Result<char, DbcError> {
+        match self.try_punct('+').or_else(|| self.try_punct('-')) {
+            Some(sign) => Ok(sign),
+            None => Err(self.error()),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, DbcError> {
+        self.skip_whitespace();
+        let end = self
+            .rest
+            .char_indices()
+            .find(|(i, c)| {
+                !(c.is_ascii_digit()
+                    || *c == '.'
+                    || ((*c == '-' || *c == '+')
+                        && (*i == 0 || self.rest[..*i].ends_with(['e', 'E'])))
+                    || ((*c == 'e' || *c == 'E') && *i > 0))
+            })
+            .map(|(i, _)| i)
+            .unwrap_or(self.rest.len());
+        let number = self.rest[..end].parse::<f64>().map_err(|_| self.error())?;
+        self.rest = &self.rest[end..];
+        Ok(number)
+    }
+
+    fn unsigned(&mut self) -> Result<u64, DbcError> {
+        self.skip_whitespace();
+        let end = self
+            .rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest.len());
+        let number = self.rest[..end].parse::<u64>().map_err(|_| self.error())?;
+        self.rest = &self.rest[end..];
+        Ok(number)
+    }
+
+    fn string(&mut self) -> Result<String, DbcError> {
+        self.skip_whitespace();
+        let rest = self.rest.strip_prefix('"').ok_or_else(|| self.error())?;
+
+        let mut string = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, c)) => string.push(c),
+                    None => break,
+                },
+                '"' => {
+                    self.rest = &rest[i + 1..];
+                    return Ok(string);
+                }
+                c => string.push(c),
+            }
+        }
+        Err(self.error())
+    }
+}
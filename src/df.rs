@@ -485,9 +485,28 @@ impl<T: HasSetAcceptanceFilter11Bit + Channel> SetAcceptanceFilter11Bit for T {
             .iter()
             .map(|x| *x & 0x7_FFu32)
             .fold(0xFF_FF_FF_FFu32, |x, y| x & y);
-        let acceptance_code_data = acceptance_code.to_le_bytes();
 
         let acceptance_mask = ids.iter().map(|x| *x & 0x7_FFu32).fold(0u32, |x, y| x ^ y);
+        self.set_acceptance_code_11bit(acceptance_code, acceptance_mask)
+    }
+}
+
+pub trait SetAcceptanceCode11Bit {
+    /// Accepts IDs equal to `acceptance_code` in all bits not set in `acceptance_mask`.
+    fn set_acceptance_code_11bit(
+        &self,
+        acceptance_code: u32,
+        acceptance_mask: u32,
+    ) -> Result<(), PcanError>;
+}
+
+impl<T: HasSetAcceptanceFilter11Bit + Channel> SetAcceptanceCode11Bit for T {
+    fn set_acceptance_code_11bit(
+        &self,
+        acceptance_code: u32,
+        acceptance_mask: u32,
+    ) -> Result<(), PcanError> {
+        let acceptance_code_data = acceptance_code.to_le_bytes();
         let acceptance_mask_data = acceptance_mask.to_le_bytes();
 
         let mut data = [
@@ -561,12 +580,31 @@ impl<T: HasSetAcceptanceFilter29Bit + Channel> SetAcceptanceFilter29Bit for T {
             .iter()
             .map(|x| *x & 0x1F_FF_FF_FFu32)
             .fold(0xFF_FF_FF_FFu32, |x, y| x & y);
-        let acceptance_code_data = acceptance_code.to_le_bytes();
 
         let acceptance_mask = ids
             .iter()
             .map(|x| *x & 0x1F_FF_FF_FFu32)
             .fold(0u32, |x, y| x ^ y);
+        self.set_acceptance_code_29bit(acceptance_code, acceptance_mask)
+    }
+}
+
+pub trait SetAcceptanceCode29Bit {
+    /// Accepts IDs equal to `acceptance_code` in all bits not set in `acceptance_mask`.
+    fn set_acceptance_code_29bit(
+        &self,
+        acceptance_code: u32,
+        acceptance_mask: u32,
+    ) -> Result<(), PcanError>;
+}
+
+impl<T: HasSetAcceptanceFilter29Bit + Channel> SetAcceptanceCode29Bit for T {
+    fn set_acceptance_code_29bit(
+        &self,
+        acceptance_code: u32,
+        acceptance_mask: u32,
+    ) -> Result<(), PcanError> {
+        let acceptance_code_data = acceptance_code.to_le_bytes();
         let acceptance_mask_data = acceptance_mask.to_le_bytes();
 
         let mut data = [
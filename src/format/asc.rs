@@ -0,0 +1,567 @@
+//! Vector ASCII logs (.asc) as exchanged with CANalyzer and CANoe.
+//!
+//! The header states the start `date`, whether identifiers and data are written in hex or
+//! decimal and whether timestamps are absolute or relative to the previous event. Events other
+//! than CAN and CAN FD frames and error frames are skipped.
+
+use crate::format::{unix_millis, DateTime, Direction, Frame, Record};
+use crate::socket::{dlc_to_len, CanFdFrame, CanFrame, MessageType, Timestamp};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// Flags of CAN FD lines, marking extended data length, bit rate switch and error state.
+const FD_FLAG_EDL: u32 = 0x1000;
+const FD_FLAG_BRS: u32 = 0x2000;
+const FD_FLAG_ESI: u32 = 0x4000;
+
+/* AscError */
+
+#[derive(Debug)]
+pub enum AscError {
+    Io(std::io::Error),
+    /// The given line could not be parsed.
+    Syntax {
+        line: usize,
+    },
+}
+
+impl From<std::io::Error> for AscError {
+    fn from(value: std::io::Error) -> Self {
+        AscError::Io(value)
+    }
+}
+
+/* AscReader */
+
+#[derive(Debug)]
+pub struct AscReader<R> {
+    reader: R,
+    line: usize,
+    buffer: String,
+    hex: bool,
+    relative: bool,
+    /// Time of the previous event, for relative timestamps.
+    previous: u64,
+    start_time: Option<u64>,
+}
+
+impl AscReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<AscReader<BufReader<File>>, AscError> {
+        Ok(AscReader::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> AscReader<R> {
+    pub fn new(reader: R) -> AscReader<R> {
+        AscReader {
+            reader,
+            line: 0,
+            buffer: String::new(),
+            hex: true,
+            relative: false,
+            previous: 0,
+            start_time: None,
+        }
+    }
+
+    /// Start of the measurement in milliseconds since 1970, taken from the `date` header.
+    pub fn start_time(&self) -> Option<u64> {
+        self.start_time
+    }
+
+    fn header(&mut self, tokens: &[&str]) -> bool {
+        match tokens.first() {
+            Some(&"date") => {
+                self.start_time = parse_date(&tokens[1..]);
+                true
+            }
+            Some(&"base") => {
+                self.hex = tokens.get(1) != Some(&"dec");
+                self.relative = tokens.get(3) == Some(&"relative");
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn number(&self, token: &str) -> Option<u32> {
+        match self.hex {
+            true => u32::from_str_radix(token, 16).ok(),
+            false => token.parse().ok(),
+        }
+    }
+
+    fn id(&self, token: &str) -> Option<(u32, MessageType)> {
+        match token.strip_suffix('x') {
+            Some(id) => Some((self.number(id)?, MessageType::Extended)),
+            None => Some((self.number(token)?, MessageType::Standard)),
+        }
+    }
+
+    fn bytes(&self, tokens: &[&str], length: usize) -> Option<Vec<u8>> {
+        let tokens = tokens.get(..length)?;
+        tokens
+            .iter()
+            .map(|token| self.number(token).and_then(|byte| u8::try_from(byte).ok()))
+            .collect()
+    }
+
+    /// Parses an event line, `None` for events which are skipped and `Some(None)` for malformed
+    /// frames.
+    fn event(&self, tokens: &[&str]) -> Option<Option<(u8, Direction, Frame)>> {
+        if tokens.len() < 3 {
+            return None;
+        }
+
+        if tokens[1] == "CANFD" {
+            let bus: u8 = tokens[2].parse().ok()?;
+            let direction = parse_direction(tokens.get(3)?)?;
+            if tokens.get(4) == Some(&"ErrorFrame") {
+                return Some(Some((
+                    bus,
+                    direction,
+                    Frame::Error {
+                        class: 0,
+                        data: Vec::new(),
+                    },
+                )));
+            }
+            return Some(
+                self.fd_frame(&tokens[4..])
+                    .map(|frame| (bus, direction, frame)),
+            );
+        }
+
+        let bus: u8 = tokens[1].parse().ok()?;
+        if tokens[2] == "ErrorFrame" {
+            return Some(Some((
+                bus,
+                Direction::Rx,
+                Frame::Error {
+                    class: 0,
+                    data: Vec::new(),
+                },
+            )));
+        }
+        let direction = parse_direction(tokens.get(3)?)?;
+        Some(
+            self.frame(&tokens[2..])
+                .map(|frame| (bus, direction, frame)),
+        )
+    }
+
+    /// `<id> <dir> d <dlc> <data>` or `<id> <dir> r [<dlc>]`.
+    fn frame(&self, tokens: &[&str]) -> Option<Frame> {
+        let (id, msg_type) = self.id(tokens[0])?;
+        match *tokens.get(2)? {
+            "r" => {
+                let dlc = match tokens.get(3) {
+                    Some(dlc) => dlc.parse().ok()?,
+                    None => 0,
+                };
+                CanFrame::new_remote(id, msg_type, dlc).ok().map(Frame::Can)
+            }
+            "d" => {
+                let dlc: usize = tokens.get(3)?.parse().ok()?;
+                let data = self.bytes(tokens.get(4..)?, dlc.min(8))?;
+                CanFrame::new(id, msg_type, &data).ok().map(Frame::Can)
+            }
+            _ => None,
+        }
+    }
+
+    /// `<id> [<name>] <brs> <esi> <dlc> <length> <data> ...`.
+    fn fd_frame(&self, tokens: &[&str]) -> Option<Frame> {
+        let (id, msg_type) = self.id(tokens.first()?)?;
+        let mut rest = &tokens[1..];
+        // Skip the optional symbolic name of the message.
+        if !matches!(rest.first(), Some(&"0") | Some(&"1")) {
+            rest = rest.get(1..)?;
+        }
+
+        let brs = *rest.first()? == "1";
+        let esi = *rest.get(1)? == "1";
+        let dlc = u8::from_str_radix(rest.get(2)?, 16).ok()?;
+        let length: usize = rest.get(3)?.parse().ok()?;
+        if length != dlc_to_len(dlc) {
+            return None;
+        }
+        let data = self.bytes(rest.get(4..)?, length)?;
+
+        let mut frame = CanFdFrame::new(id, msg_type, &data).ok()?;
+        frame.set_bitrate_switch(brs);
+        frame.set_error_state_indicator(esi);
+        Some(Frame::CanFd(frame))
+    }
+}
+
+impl<R: BufRead> Iterator for AscReader<R> {
+    type Item = Result<Record, AscError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buffer.clear();
+            match self.reader.read_line(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(err) => return Some(Err(AscError::Io(err))),
+            }
+            self.line += 1;
+
+            let line = std::mem::take(&mut self.buffer);
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.is_empty() || tokens[0].starts_with("//") || self.header(&tokens) {
+                self.buffer = line;
+                continue;
+            }
+
+            let time = match parse_time(tokens[0]) {
+                Some(time) => time,
+                None => {
+                    self.buffer = line;
+                    continue;
+                }
+            };
+            let event = self.event(&tokens);
+            self.buffer = line;
+
+            let (bus, direction, frame) = match event {
+                Some(Some(event)) => event,
+                Some(None) => return Some(Err(AscError::Syntax { line: self.line })),
+                None => continue,
+            };
+            let micros = match self.relative {
+                true => self.previous + time,
+                false => time,
+            };
+            self.previous = micros;
+
+            return Some(Ok(Record {
+                timestamp: Timestamp::from_micros(micros),
+                bus,
+                direction,
+                frame,
+            }));
+        }
+    }
+}
+
+/// Seconds with up to nine decimals, converted into microseconds.
+fn parse_time(token: &str) -> Option<u64> {
+    let (seconds, fraction) = token.split_once('.').unwrap_or((token, ""));
+    let seconds: u64 = seconds.parse().ok()?;
+    if !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let mut micros = 0u64;
+    for i in 0..6 {
+        let digit = fraction.as_bytes().get(i).map_or(0, |digit| digit - b'0');
+        micros = micros * 10 + digit as u64;
+    }
+    Some(seconds * 1_000_000 + micros)
+}
+
+fn parse_direction(token: &str) -> Option<Direction> {
+    match token {
+        "Rx" => Some(Direction::Rx),
+        "Tx" => Some(Direction::Tx),
+        _ => None,
+    }
+}
+
+/// `Wed Oct 18 02:08:00.123 pm 2026` or `Wed Oct 18 14:08:00.123 2026`.
+fn parse_date(tokens: &[&str]) -> Option<u64> {
+    let name = *tokens.get(1)?;
+    let month = MONTHS.iter().position(|month| *month == name)? as u32 + 1;
+    let day: u32 = tokens.get(2)?.parse().ok()?;
+
+    let (time, millis) = match tokens.get(3)?.split_once('.') {
+        Some((time, millis)) => (time, millis.parse().ok()?),
+        None => (*tokens.get(3)?, 0),
+    };
+    let mut fields = time.split(':').map(|field| field.parse::<u32>().ok());
+    let mut hour = fields.next()??;
+    let minute = fields.next()??;
+    let second = fields.next()??;
+
+    let year = match *tokens.get(4)? {
+        "am" => {
+            hour %= 12;
+            tokens.get(5)?
+        }
+        "pm" => {
+            hour = hour % 12 + 12;
+            tokens.get(5)?
+        }
+        year => year,
+    };
+
+    let date = DateTime {
+        year: year.parse().ok()?,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        millis,
+    };
+    Some(date.to_unix_millis())
+}
+
+fn format_date(millis: u64) -> String {
+    let date = DateTime::from_unix_millis(millis);
+    let (hour, meridiem) = match date.hour {
+        0 => (12, "am"),
+        1..=11 => (date.hour, "am"),
+        12 => (12, "pm"),
+        hour => (hour - 12, "pm"),
+    };
+    format!(
+        "{} {} {:02} {:02}:{:02}:{:02}.{:03} {} {}",
+        WEEKDAYS[date.weekday() as usize],
+        MONTHS[date.month as usize - 1],
+        date.day,
+        hour,
+        date.minute,
+        date.second,
+        date.millis,
+        meridiem,
+        date.year
+    )
+}
+
+/* AscWriter */
+
+/// Writes records with hex identifiers and absolute timestamps. The footer is written by
+/// [AscWriter::finish] or when the writer is dropped.
+#[derive(Debug)]
+pub struct AscWriter<W: Write> {
+    writer: Option<W>,
+    origin: Option<u64>,
+}
+
+impl AscWriter<BufWriter<File>> {
+    /// Creates a log starting now.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<AscWriter<BufWriter<File>>, AscError> {
+        AscWriter::new(BufWriter::new(File::create(path)?), unix_millis())
+    }
+}
+
+impl<W: Write> AscWriter<W> {
+    /// Writes the header for a measurement started at `start_time` milliseconds since 1970.
+    pub fn new(mut writer: W, start_time: u64) -> Result<AscWriter<W>, AscError> {
+        let date = format_date(start_time);
+        write!(
+            writer,
+            "date {}\n\
+             base hex  timestamps absolute\n\
+             internal events logged\n\
+             // version 13.0.0\n\
+             Begin Triggerblock {}\n\
+             {:>11.6} Start of measurement\n",
+            date, date, 0.0
+        )?;
+
+        Ok(AscWriter {
+            writer: Some(writer),
+            origin: None,
+        })
+    }
+
+    /// Sets the timestamp of a row time of `0.000000`, the first record by default.
+    pub fn set_origin(&mut self, origin: Timestamp) {
+        self.origin = Some(origin.as_micros());
+    }
+
+    /// Writes a frame as `CAN`/`CANFD` row or an error frame as `ErrorFrame` row, status
+    /// records have no row and are skipped.
+    pub fn write_record(&mut self, record: &Record) -> Result<(), AscError> {
+        let micros = record.timestamp.as_micros();
+        let origin = *self.origin.get_or_insert(micros);
+        let offset = micros.saturating_sub(origin);
+        let time = format!("{:>4}.{:06}", offset / 1_000_000, offset % 1_000_000);
+        let direction = match record.direction {
+            Direction::Rx => "Rx",
+            Direction::Tx => "Tx",
+        };
+
+        let line = match &record.frame {
+            Frame::Can(frame) => {
+                let id = format_id(frame.can_id(), frame.is_extended_frame());
+                match frame.is_remote_frame() {
+                    true => format!(
+                        "{} {:<2} {:<15} {}   r {:X}",
+                        time,
+                        record.bus,
+                        id,
+                        direction,
+                        frame.dlc()
+                    ),
+                    false => format!(
+                        "{} {:<2} {:<15} {}   d {:X} {}",
+                        time,
+                        record.bus,
+                        id,
+                        direction,
+                        frame.dlc(),
+                        format_bytes(frame.data())
+                    ),
+                }
+            }
+            Frame::CanFd(frame) => {
+                let mut flags = FD_FLAG_EDL;
+                if frame.is_bitrate_switch() {
+                    flags |= FD_FLAG_BRS;
+                }
+                if frame.is_error_state_indicator() {
+                    flags |= FD_FLAG_ESI;
+                }
+                // Name, data, duration, length, flags, CRC and bit timings.
+                format!(
+                    "{} CANFD {:>3} {} {:>11} {:>32} {} {} {:X} {:>2} {} {:>8} {:>4} {:>8X} 0 0 0 0 0",
+                    time,
+                    record.bus,
+                    direction,
+                    format_id(frame.can_id(), frame.is_extended_frame()),
+                    "",
+                    frame.is_bitrate_switch() as u8,
+                    frame.is_error_state_indicator() as u8,
+                    frame.dlc(),
+                    frame.data().len(),
+                    format_bytes(frame.data()),
+                    0,
+                    0,
+                    flags
+                )
+            }
+            Frame::Error { .. } => format!("{} {:<2} ErrorFrame", time, record.bus),
+            Frame::Status(_) => return Ok(()),
+        };
+
+        if let Some(writer) = self.writer.as_mut() {
+            writeln!(writer, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+
+    /// Writes the footer and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, AscError> {
+        let mut writer = self.writer.take().unwrap();
+        writeln!(writer, "End TriggerBlock")?;
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Drop for AscWriter<W> {
+    fn drop(&mut self) {
+        if let Some(writer) = self.writer.as_mut() {
+            let _ = writeln!(writer, "End TriggerBlock");
+            let _ = writer.flush();
+        }
+    }
+}
+
+fn format_id(can_id: u32, extended: bool) -> String {
+    match extended {
+        true => format!("{:X}x", can_id),
+        false => format!("{:X}", can_id),
+    }
+}
+
+fn format_bytes(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asc_reader_001() {
+        let mut reader = AscReader::new(&include_bytes!("../../tests/fixtures/asc/sample.asc")[..]);
+        let records = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+
+        // 2023-10-18 14:08:00.123 UTC
+        assert_eq!(reader.start_time(), Some(1_697_638_080_123));
+        assert_eq!(records.len(), 5);
+
+        assert_eq!(records[0].timestamp.as_micros(), 12_345);
+        assert_eq!(
+            records[0].frame,
+            Frame::Can(
+                CanFrame::new(0x123, MessageType::Standard, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap()
+            )
+        );
+        assert_eq!(records[1].bus, 2);
+        assert_eq!(records[1].direction, Direction::Tx);
+        assert_eq!(
+            records[1].frame,
+            Frame::Can(CanFrame::new(0x18FF_0001, MessageType::Extended, &[0xAA, 0xBB]).unwrap())
+        );
+        assert!(matches!(&records[2].frame, Frame::Can(frame) if frame.is_remote_frame()));
+        assert_eq!(
+            records[3].frame,
+            Frame::Error {
+                class: 0,
+                data: Vec::new()
+            }
+        );
+        match &records[4].frame {
+            Frame::CanFd(frame) => {
+                assert_eq!(frame.can_id(), 0x7FF);
+                assert!(frame.is_bitrate_switch());
+                assert_eq!(frame.data()[11], 0x0B);
+            }
+            frame => panic!("{:?}", frame),
+        }
+    }
+
+    #[test]
+    fn asc_reader_002() {
+        let text = "base dec  timestamps relative\n\
+                    0.5 1 291 Rx d 2 10 255\n\
+                    0.25 1 291x Rx d 1 16\n\
+                    0.25 1 291 Rx d 2 10\n";
+        let mut reader = AscReader::new(text.as_bytes());
+
+        let first = reader.next().unwrap().unwrap();
+        assert_eq!(first.frame.can_id(), Some(291));
+        assert!(matches!(&first.frame, Frame::Can(frame) if frame.data() == [10, 255]));
+        let second = reader.next().unwrap().unwrap();
+        assert_eq!(second.timestamp.as_micros(), 750_000);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(AscError::Syntax { line: 4 }))
+        ));
+    }
+
+    #[test]
+    fn asc_writer_001() {
+        let records = AscReader::new(&include_bytes!("../../tests/fixtures/asc/sample.asc")[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let mut writer = AscWriter::new(Vec::new(), 1_697_638_080_123).unwrap();
+        writer.set_origin(Timestamp::default());
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        let text = writer.finish().unwrap();
+        assert!(text.starts_with(b"date Wed Oct 18 02:08:00.123 pm 2023\n"));
+
+        let mut reader = AscReader::new(&text[..]);
+        let read = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(reader.start_time(), Some(1_697_638_080_123));
+        assert_eq!(read, records);
+    }
+}
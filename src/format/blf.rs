@@ -0,0 +1,776 @@
+//! Vector binary logging format (.blf) as recorded by CANalyzer, CANoe and Vector loggers.
+//!
+//! The file header is followed by objects, most of them packed into zlib compressed log
+//! containers. Objects may span containers, the reader keeps only the current container in
+//! memory. CAN, CAN FD and error frame objects are read, other objects are skipped.
+
+use crate::format::{unix_millis, DateTime, Direction, Frame, Record};
+use crate::socket::{dlc_to_len, len_to_dlc, CanFdFrame, CanFrame, MessageType, Timestamp};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const FILE_HEADER_SIZE: usize = 144;
+const OBJECT_HEADER_BASE_SIZE: usize = 16;
+const OBJECT_HEADER_V1_SIZE: usize = 16;
+const OBJECT_HEADER_V2_SIZE: usize = 24;
+const CONTAINER_HEADER_SIZE: usize = 16;
+/// Uncompressed size of the written log containers.
+const CONTAINER_SIZE: usize = 128 * 1024;
+
+const CAN_MESSAGE: u32 = 1;
+const CAN_ERROR: u32 = 2;
+const LOG_CONTAINER: u32 = 10;
+const CAN_ERROR_EXT: u32 = 73;
+const CAN_MESSAGE2: u32 = 86;
+const CAN_FD_MESSAGE: u32 = 100;
+const CAN_FD_MESSAGE_64: u32 = 101;
+
+const NO_COMPRESSION: u16 = 0;
+const ZLIB_DEFLATE: u16 = 2;
+
+/// Object timestamp units.
+const TIME_TEN_MICS: u32 = 0x1;
+const TIME_ONE_NANS: u32 = 0x2;
+
+const CAN_MSG_EXT: u32 = 0x8000_0000;
+const CAN_FLAG_TX: u8 = 0x01;
+const CAN_FLAG_REMOTE: u8 = 0x80;
+const FD_FLAG_EDL: u8 = 0x01;
+const FD_FLAG_BRS: u8 = 0x02;
+const FD_FLAG_ESI: u8 = 0x04;
+const FD64_FLAG_REMOTE: u32 = 0x0010;
+const FD64_FLAG_EDL: u32 = 0x1000;
+const FD64_FLAG_BRS: u32 = 0x2000;
+const FD64_FLAG_ESI: u32 = 0x4000;
+
+/* BlfError */
+
+#[derive(Debug)]
+pub enum BlfError {
+    Io(std::io::Error),
+    /// The file header is missing or broken.
+    InvalidHeader,
+    /// The object at the given offset of the uncompressed object stream is broken.
+    InvalidObject {
+        offset: u64,
+    },
+    UnsupportedCompression(u16),
+}
+
+impl From<std::io::Error> for BlfError {
+    fn from(value: std::io::Error) -> Self {
+        BlfError::Io(value)
+    }
+}
+
+/* BlfReader */
+
+#[derive(Debug)]
+pub struct BlfReader<R> {
+    reader: R,
+    /// Uncompressed objects of the current container, starting at `position`.
+    buffer: Vec<u8>,
+    position: usize,
+    /// Offset of `buffer` in the uncompressed object stream.
+    offset: u64,
+    start_time: Option<u64>,
+    object_count: u32,
+    done: bool,
+}
+
+impl BlfReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<BlfReader<BufReader<File>>, BlfError> {
+        BlfReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> BlfReader<R> {
+    /// Reads the file header.
+    pub fn new(mut reader: R) -> Result<BlfReader<R>, BlfError> {
+        let mut header = [0; FILE_HEADER_SIZE];
+        reader.read_exact(&mut header[..8])?;
+        if &header[..4] != b"LOGG" {
+            return Err(BlfError::InvalidHeader);
+        }
+        let size = u32_at(&header, 4) as usize;
+        if size < 72 {
+            return Err(BlfError::InvalidHeader);
+        }
+        let mut rest = vec![0; size - 8];
+        reader.read_exact(&mut rest)?;
+        header[8..72].copy_from_slice(&rest[..64]);
+
+        Ok(BlfReader {
+            reader,
+            buffer: Vec::new(),
+            position: 0,
+            offset: 0,
+            start_time: parse_system_time(&header[40..56]),
+            object_count: u32_at(&header, 32),
+            done: false,
+        })
+    }
+
+    /// Start of the measurement in milliseconds since 1970.
+    pub fn start_time(&self) -> Option<u64> {
+        self.start_time
+    }
+
+    /// Number of objects stated by the file header, zero if the recording was not finished.
+    pub fn object_count(&self) -> u32 {
+        self.object_count
+    }
+
+    /// Appends the next top level object to the buffer, returns false at the end of the file.
+    fn fill(&mut self) -> Result<bool, BlfError> {
+        let mut header = [0; OBJECT_HEADER_BASE_SIZE];
+        if !read_all(&mut self.reader, &mut header)? {
+            return Ok(false);
+        }
+        let invalid = BlfError::InvalidObject {
+            offset: self.offset + self.buffer.len() as u64,
+        };
+        if &header[..4] != b"LOBJ" {
+            return Err(invalid);
+        }
+        let size = u32_at(&header, 8) as usize;
+        if size < OBJECT_HEADER_BASE_SIZE {
+            return Err(invalid);
+        }
+        let mut body = vec![0; size - OBJECT_HEADER_BASE_SIZE];
+        self.reader.read_exact(&mut body)?;
+        // Padding, missing after the last object of some files.
+        read_all(&mut self.reader, &mut [0; 4][..size % 4])?;
+
+        self.buffer.drain(..self.position);
+        self.offset += self.position as u64;
+        self.position = 0;
+
+        if u32_at(&header, 12) != LOG_CONTAINER {
+            self.buffer.extend_from_slice(&header);
+            self.buffer.extend_from_slice(&body);
+            return Ok(true);
+        }
+        if body.len() < CONTAINER_HEADER_SIZE {
+            return Err(invalid);
+        }
+        let data = &body[CONTAINER_HEADER_SIZE..];
+        match u16_at(&body, 0) {
+            NO_COMPRESSION => self.buffer.extend_from_slice(data),
+            ZLIB_DEFLATE => {
+                let uncompressed = u32_at(&body, 8) as u64;
+                ZlibDecoder::new(data)
+                    .take(uncompressed)
+                    .read_to_end(&mut self.buffer)?;
+            }
+            method => return Err(BlfError::UnsupportedCompression(method)),
+        }
+        Ok(true)
+    }
+
+    /// Parses the next buffered object, `None` if it is not completely buffered yet.
+    fn object(&mut self) -> Option<Result<Option<Record>, BlfError>> {
+        // Objects are padded, the signature marks the start of the next one.
+        let data = &self.buffer[self.position..];
+        let skip = data.windows(4).take(8).position(|window| window == b"LOBJ");
+        let start = match skip {
+            Some(skip) => self.position + skip,
+            None if data.len() >= 12 => {
+                return Some(Err(BlfError::InvalidObject {
+                    offset: self.offset + self.position as u64,
+                }))
+            }
+            None => return None,
+        };
+        let data = &self.buffer[start..];
+        if data.len() < OBJECT_HEADER_BASE_SIZE {
+            return None;
+        }
+        let size = u32_at(data, 8) as usize;
+        if data.len() < size {
+            return None;
+        }
+
+        let record = parse_object(&data[..size]);
+        self.position = start + size.max(OBJECT_HEADER_BASE_SIZE);
+        match record {
+            Some(record) => Some(Ok(record)),
+            None => Some(Err(BlfError::InvalidObject {
+                offset: self.offset + start as u64,
+            })),
+        }
+    }
+}
+
+impl<R: Read> Iterator for BlfReader<R> {
+    type Item = Result<Record, BlfError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.object() {
+                Some(Ok(Some(record))) => return Some(Ok(record)),
+                Some(Ok(None)) => continue,
+                Some(Err(err)) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+                None => {}
+            }
+
+            match self.fill() {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.done = true;
+                    // Anything but padding left means the file is truncated.
+                    if self.buffer[self.position..].iter().any(|byte| *byte != 0) {
+                        return Some(Err(BlfError::InvalidObject {
+                            offset: self.offset + self.position as u64,
+                        }));
+                    }
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Reads the whole buffer, returns false if the reader is at its end.
+fn read_all<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<bool, BlfError> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+            Ok(count) => filled += count,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(true)
+}
+
+/// Parses an object, `Some(None)` for objects which are no frames.
+fn parse_object(object: &[u8]) -> Option<Option<Record>> {
+    if object.len() < OBJECT_HEADER_BASE_SIZE {
+        return None;
+    }
+    let header_size = u16_at(object, 4) as usize;
+    let kind = u32_at(object, 12);
+    if !matches!(
+        kind,
+        CAN_MESSAGE | CAN_MESSAGE2 | CAN_ERROR | CAN_ERROR_EXT | CAN_FD_MESSAGE | CAN_FD_MESSAGE_64
+    ) {
+        return Some(None);
+    }
+
+    // Both header versions start with the flags and place the timestamp at the same offset.
+    let minimum = match u16_at(object, 6) {
+        1 => OBJECT_HEADER_BASE_SIZE + OBJECT_HEADER_V1_SIZE,
+        2 => OBJECT_HEADER_BASE_SIZE + OBJECT_HEADER_V2_SIZE,
+        _ => return None,
+    };
+    if object.len() < minimum || header_size < minimum {
+        return None;
+    }
+    let (flags, raw) = (u32_at(object, 16), u64_at(object, 24));
+    let micros = match flags {
+        TIME_TEN_MICS => raw * 10,
+        TIME_ONE_NANS => raw / 1000,
+        _ => return None,
+    };
+    let body = object.get(header_size..)?;
+
+    let (bus, direction, frame) = match kind {
+        CAN_MESSAGE | CAN_MESSAGE2 => {
+            let body = body.get(..16)?;
+            let flags = body[2];
+            let (id, msg_type) = split_id(u32_at(body, 4));
+            let frame = match flags & CAN_FLAG_REMOTE != 0 {
+                true => CanFrame::new_remote(id, msg_type, body[3]).ok()?,
+                false => {
+                    let length = (body[3] as usize).min(8);
+                    CanFrame::new(id, msg_type, &body[8..8 + length]).ok()?
+                }
+            };
+            (
+                u16_at(body, 0),
+                tx(flags & CAN_FLAG_TX != 0),
+                Frame::Can(frame),
+            )
+        }
+        CAN_ERROR => (
+            u16_at(body.get(..4)?, 0),
+            Direction::Rx,
+            Frame::Error {
+                class: 0,
+                data: Vec::new(),
+            },
+        ),
+        CAN_ERROR_EXT => {
+            let body = body.get(..32)?;
+            let length = (body[10] as usize).min(8);
+            (
+                u16_at(body, 0),
+                Direction::Rx,
+                Frame::Error {
+                    class: 0,
+                    data: body[24..24 + length].to_vec(),
+                },
+            )
+        }
+        CAN_FD_MESSAGE => {
+            let body = body.get(..84)?;
+            let (id, msg_type) = split_id(u32_at(body, 4));
+            let (flags, fd_flags) = (body[2], body[13]);
+            let frame = match fd_flags & FD_FLAG_EDL != 0 {
+                true => {
+                    let length = dlc_to_len(body[3]).min(body[14] as usize).min(64);
+                    let mut frame = CanFdFrame::new(id, msg_type, &body[20..20 + length]).ok()?;
+                    frame.set_bitrate_switch(fd_flags & FD_FLAG_BRS != 0);
+                    frame.set_error_state_indicator(fd_flags & FD_FLAG_ESI != 0);
+                    Frame::CanFd(frame)
+                }
+                false => Frame::Can(classic(
+                    id,
+                    msg_type,
+                    flags & CAN_FLAG_REMOTE != 0,
+                    body[3],
+                    &body[20..],
+                )?),
+            };
+            (u16_at(body, 0), tx(flags & CAN_FLAG_TX != 0), frame)
+        }
+        _ => {
+            // CAN_FD_MESSAGE_64
+            let header = body.get(..40)?;
+            let length = (header[2] as usize).min(64);
+            let data = body.get(40..40 + length)?;
+            let (id, msg_type) = split_id(u32_at(header, 4));
+            let flags = u32_at(header, 12);
+            let frame = match flags & FD64_FLAG_EDL != 0 {
+                true => {
+                    let length = dlc_to_len(header[1]).min(length);
+                    let mut frame = CanFdFrame::new(id, msg_type, &data[..length]).ok()?;
+                    frame.set_bitrate_switch(flags & FD64_FLAG_BRS != 0);
+                    frame.set_error_state_indicator(flags & FD64_FLAG_ESI != 0);
+                    Frame::CanFd(frame)
+                }
+                false => Frame::Can(classic(
+                    id,
+                    msg_type,
+                    flags & FD64_FLAG_REMOTE != 0,
+                    header[1],
+                    data,
+                )?),
+            };
+            (header[0] as u16, tx(header[34] == 1), frame)
+        }
+    };
+
+    Some(Some(Record {
+        timestamp: Timestamp::from_micros(micros),
+        bus: bus.clamp(1, u8::MAX as u16) as u8,
+        direction,
+        frame,
+    }))
+}
+
+fn classic(id: u32, msg_type: MessageType, remote: bool, dlc: u8, data: &[u8]) -> Option<CanFrame> {
+    match remote {
+        true => CanFrame::new_remote(id, msg_type, dlc).ok(),
+        false => CanFrame::new(id, msg_type, data.get(..(dlc as usize).min(8))?).ok(),
+    }
+}
+
+fn split_id(raw: u32) -> (u32, MessageType) {
+    match raw & CAN_MSG_EXT != 0 {
+        true => (raw & !CAN_MSG_EXT, MessageType::Extended),
+        false => (raw, MessageType::Standard),
+    }
+}
+
+fn tx(tx: bool) -> Direction {
+    match tx {
+        true => Direction::Tx,
+        false => Direction::Rx,
+    }
+}
+
+/// Windows SYSTEMTIME: year, month, weekday, day, hour, minute, second and milliseconds.
+fn parse_system_time(bytes: &[u8]) -> Option<u64> {
+    let field = |index: usize| u16_at(bytes, 2 * index) as u32;
+    if field(0) == 0 {
+        return None;
+    }
+    let date = DateTime {
+        year: field(0) as i64,
+        month: field(1),
+        day: field(3),
+        hour: field(4),
+        minute: field(5),
+        second: field(6),
+        millis: field(7),
+    };
+    Some(date.to_unix_millis())
+}
+
+fn system_time(millis: u64) -> [u8; 16] {
+    let date = DateTime::from_unix_millis(millis);
+    let fields = [
+        date.year as u16,
+        date.month as u16,
+        date.weekday() as u16,
+        date.day as u16,
+        date.hour as u16,
+        date.minute as u16,
+        date.second as u16,
+        date.millis as u16,
+    ];
+    let mut bytes = [0; 16];
+    for (chunk, field) in bytes.chunks_mut(2).zip(fields) {
+        chunk.copy_from_slice(&field.to_le_bytes());
+    }
+    bytes
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+/* BlfWriter */
+
+/// Writes classic frames as CAN_MESSAGE2, CAN FD frames as CAN_FD_MESSAGE_64 and error frames
+/// as CAN_ERROR_EXT objects into zlib compressed containers. The file header is completed by
+/// [BlfWriter::finish] or when the writer is dropped.
+#[derive(Debug)]
+pub struct BlfWriter<W: Write + Seek> {
+    writer: Option<W>,
+    /// Objects not yet written in a container.
+    buffer: Vec<u8>,
+    origin: Option<u64>,
+    start_time: u64,
+    /// Latest object time in microseconds since the start.
+    last: u64,
+    object_count: u32,
+    uncompressed_size: u64,
+}
+
+impl BlfWriter<BufWriter<File>> {
+    /// Creates a log starting now.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<BlfWriter<BufWriter<File>>, BlfError> {
+        BlfWriter::new(BufWriter::new(File::create(path)?), unix_millis())
+    }
+}
+
+impl<W: Write + Seek> BlfWriter<W> {
+    /// Writes a preliminary header for a measurement started at `start_time` milliseconds
+    /// since 1970.
+    pub fn new(mut writer: W, start_time: u64) -> Result<BlfWriter<W>, BlfError> {
+        let mut blf = BlfWriter {
+            writer: None,
+            buffer: Vec::with_capacity(CONTAINER_SIZE),
+            origin: None,
+            start_time,
+            last: 0,
+            object_count: 0,
+            uncompressed_size: FILE_HEADER_SIZE as u64,
+        };
+        writer.write_all(&blf.header(0))?;
+        blf.writer = Some(writer);
+        Ok(blf)
+    }
+
+    /// Sets the timestamp object times are counted from, by default the first record. Use
+    /// [Timestamp::default] for timestamps which are already relative to the measurement start.
+    pub fn set_origin(&mut self, origin: Timestamp) {
+        self.origin = Some(origin.as_micros());
+    }
+
+    /// Writes a frame as `CAN_MESSAGE2`, `CAN_FD_MESSAGE_64` or `CAN_ERROR_EXT` object. BLF has no
+    /// object for status records, they are skipped.
+    pub fn write_record(&mut self, record: &Record) -> Result<(), BlfError> {
+        let micros = record.timestamp.as_micros();
+        let origin = *self.origin.get_or_insert(micros);
+        let offset = micros.saturating_sub(origin);
+        let bus = record.bus as u16;
+        let is_tx = record.direction == Direction::Tx;
+
+        let (kind, body) = match &record.frame {
+            Frame::Can(frame) => {
+                let mut flags = 0;
+                if is_tx {
+                    flags |= CAN_FLAG_TX;
+                }
+                if frame.is_remote_frame() {
+                    flags |= CAN_FLAG_REMOTE;
+                }
+                let mut body = Vec::with_capacity(24);
+                body.extend_from_slice(&bus.to_le_bytes());
+                body.extend_from_slice(&[flags, frame.dlc()]);
+                body.extend_from_slice(
+                    &raw_id(frame.can_id(), frame.is_extended_frame()).to_le_bytes(),
+                );
+                let mut data = [0; 8];
+                if !frame.is_remote_frame() {
+                    data[..frame.data().len()].copy_from_slice(frame.data());
+                }
+                body.extend_from_slice(&data);
+                // Frame length, bit count and reserved bytes.
+                body.extend_from_slice(&[0; 8]);
+                (CAN_MESSAGE2, body)
+            }
+            Frame::CanFd(frame) => {
+                let mut flags = FD64_FLAG_EDL;
+                if frame.is_bitrate_switch() {
+                    flags |= FD64_FLAG_BRS;
+                }
+                if frame.is_error_state_indicator() {
+                    flags |= FD64_FLAG_ESI;
+                }
+                let data = frame.data();
+                let mut body = Vec::with_capacity(40 + data.len());
+                body.extend_from_slice(&[record.bus, frame.dlc(), data.len() as u8, 0]);
+                body.extend_from_slice(
+                    &raw_id(frame.can_id(), frame.is_extended_frame()).to_le_bytes(),
+                );
+                body.extend_from_slice(&0u32.to_le_bytes());
+                body.extend_from_slice(&flags.to_le_bytes());
+                // Bit timings, bit rate switch and CRC delimiter offsets and bit count.
+                body.extend_from_slice(&[0; 18]);
+                body.extend_from_slice(&[is_tx as u8, 0]);
+                // CRC
+                body.extend_from_slice(&0u32.to_le_bytes());
+                body.extend_from_slice(data);
+                (CAN_FD_MESSAGE_64, body)
+            }
+            Frame::Error { data, .. } => {
+                let length = data.len().min(8);
+                let mut body = Vec::with_capacity(32);
+                body.extend_from_slice(&bus.to_le_bytes());
+                body.extend_from_slice(&[0; 8]);
+                body.extend_from_slice(&[len_to_dlc(length), 0]);
+                body.extend_from_slice(&[0; 12]);
+                let mut bytes = [0; 8];
+                bytes[..length].copy_from_slice(&data[..length]);
+                body.extend_from_slice(&bytes);
+                (CAN_ERROR_EXT, body)
+            }
+            Frame::Status(_) => return Ok(()),
+        };
+
+        let size = OBJECT_HEADER_BASE_SIZE + OBJECT_HEADER_V1_SIZE + body.len();
+        self.buffer.extend_from_slice(b"LOBJ");
+        self.buffer.extend_from_slice(
+            &((OBJECT_HEADER_BASE_SIZE + OBJECT_HEADER_V1_SIZE) as u16).to_le_bytes(),
+        );
+        self.buffer.extend_from_slice(&1u16.to_le_bytes());
+        self.buffer.extend_from_slice(&(size as u32).to_le_bytes());
+        self.buffer.extend_from_slice(&kind.to_le_bytes());
+        self.buffer.extend_from_slice(&TIME_ONE_NANS.to_le_bytes());
+        // Client index and object version.
+        self.buffer.extend_from_slice(&[0; 4]);
+        self.buffer
+            .extend_from_slice(&(offset * 1000).to_le_bytes());
+        self.buffer.extend_from_slice(&body);
+        self.buffer.resize(self.buffer.len() + size % 4, 0);
+
+        self.object_count += 1;
+        self.last = self.last.max(offset);
+        while self.buffer.len() >= CONTAINER_SIZE {
+            self.write_container(CONTAINER_SIZE)?;
+        }
+        Ok(())
+    }
+
+    /// Compresses the first `length` buffered bytes into a container, objects may continue in
+    /// the next container.
+    fn write_container(&mut self, length: usize) -> Result<(), BlfError> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&self.buffer[..length])?;
+        let compressed = encoder.finish()?;
+        self.buffer.drain(..length);
+
+        let size = OBJECT_HEADER_BASE_SIZE + CONTAINER_HEADER_SIZE + compressed.len();
+        let mut object = Vec::with_capacity(size + 3);
+        object.extend_from_slice(b"LOBJ");
+        object.extend_from_slice(&(OBJECT_HEADER_BASE_SIZE as u16).to_le_bytes());
+        object.extend_from_slice(&1u16.to_le_bytes());
+        object.extend_from_slice(&(size as u32).to_le_bytes());
+        object.extend_from_slice(&LOG_CONTAINER.to_le_bytes());
+        object.extend_from_slice(&ZLIB_DEFLATE.to_le_bytes());
+        object.extend_from_slice(&[0; 6]);
+        object.extend_from_slice(&(length as u32).to_le_bytes());
+        object.extend_from_slice(&[0; 4]);
+        object.extend_from_slice(&compressed);
+        object.resize(size + size % 4, 0);
+
+        self.uncompressed_size += (OBJECT_HEADER_BASE_SIZE + CONTAINER_HEADER_SIZE + length) as u64;
+        if let Some(writer) = self.writer.as_mut() {
+            writer.write_all(&object)?;
+        }
+        Ok(())
+    }
+
+    fn header(&self, file_size: u64) -> [u8; FILE_HEADER_SIZE] {
+        let mut header = [0; FILE_HEADER_SIZE];
+        header[..4].copy_from_slice(b"LOGG");
+        header[4..8].copy_from_slice(&(FILE_HEADER_SIZE as u32).to_le_bytes());
+        // Application and binary log versions.
+        header[8..16].copy_from_slice(&[0, 0, 0, 0, 4, 7, 1, 0]);
+        header[16..24].copy_from_slice(&file_size.to_le_bytes());
+        header[24..32].copy_from_slice(&self.uncompressed_size.to_le_bytes());
+        header[32..36].copy_from_slice(&self.object_count.to_le_bytes());
+        header[40..56].copy_from_slice(&system_time(self.start_time));
+        header[56..72].copy_from_slice(&system_time(self.start_time + self.last / 1000));
+        header
+    }
+
+    fn complete(&mut self) -> Result<(), BlfError> {
+        if !self.buffer.is_empty() {
+            self.write_container(self.buffer.len())?;
+        }
+        let header = self.header(0);
+        if let Some(writer) = self.writer.as_mut() {
+            let file_size = writer.stream_position()?;
+            let mut header = header;
+            header[16..24].copy_from_slice(&file_size.to_le_bytes());
+            writer.seek(SeekFrom::Start(0))?;
+            writer.write_all(&header)?;
+            writer.seek(SeekFrom::Start(file_size))?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered objects, completes the file header and returns the underlying
+    /// writer.
+    pub fn finish(mut self) -> Result<W, BlfError> {
+        let result = self.complete();
+        let writer = self.writer.take().unwrap();
+        result.map(|_| writer)
+    }
+}
+
+impl<W: Write + Seek> Drop for BlfWriter<W> {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            let _ = self.complete();
+        }
+    }
+}
+
+fn raw_id(can_id: u32, extended: bool) -> u32 {
+    match extended {
+        true => can_id | CAN_MSG_EXT,
+        false => can_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::test_records;
+    use std::io::Cursor;
+
+    #[test]
+    fn blf_writer_001() {
+        let mut writer = BlfWriter::new(Cursor::new(Vec::new()), 1_697_638_080_123).unwrap();
+        writer.set_origin(Timestamp::default());
+        for record in &test_records() {
+            writer.write_record(record).unwrap();
+        }
+        writer
+            .write_record(&Record::new(
+                Timestamp::from_micros(3_500),
+                Frame::Status(String::from("BUSHEAVY")),
+            ))
+            .unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        assert_eq!(u64_at(&bytes, 16), bytes.len() as u64);
+
+        let mut reader = BlfReader::new(&bytes[..]).unwrap();
+        assert_eq!(reader.start_time(), Some(1_697_638_080_123));
+        assert_eq!(reader.object_count(), 4);
+        let read = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(read, test_records());
+    }
+
+    #[test]
+    fn blf_writer_002() {
+        // Enough frames for several containers with objects spanning them.
+        let frame = CanFdFrame::new(0x7FF, MessageType::Standard, &[0x55; 64]).unwrap();
+        let mut writer = BlfWriter::new(Cursor::new(Vec::new()), 0).unwrap();
+        for index in 0..10_000u64 {
+            let record = Record::new(Timestamp::from_micros(index * 100), Frame::CanFd(frame));
+            writer.write_record(&record).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+        assert!(bytes.len() < CONTAINER_SIZE);
+
+        let mut count = 0;
+        for (index, record) in BlfReader::new(&bytes[..]).unwrap().enumerate() {
+            let record = record.unwrap();
+            assert_eq!(record.timestamp.as_micros(), index as u64 * 100);
+            assert_eq!(record.frame, Frame::CanFd(frame));
+            count += 1;
+        }
+        assert_eq!(count, 10_000);
+    }
+
+    #[test]
+    fn blf_reader_001() {
+        // Uncompressed CAN_MESSAGE in 10 µs units next to an unknown object.
+        let mut bytes = vec![0; FILE_HEADER_SIZE];
+        bytes[..4].copy_from_slice(b"LOGG");
+        bytes[4..8].copy_from_slice(&(FILE_HEADER_SIZE as u32).to_le_bytes());
+        for (kind, size) in [(CAN_MESSAGE, 48u32), (65, 36)] {
+            bytes.extend_from_slice(b"LOBJ");
+            bytes.extend_from_slice(&32u16.to_le_bytes());
+            bytes.extend_from_slice(&1u16.to_le_bytes());
+            bytes.extend_from_slice(&size.to_le_bytes());
+            bytes.extend_from_slice(&kind.to_le_bytes());
+            bytes.extend_from_slice(&TIME_TEN_MICS.to_le_bytes());
+            bytes.extend_from_slice(&[0; 4]);
+            bytes.extend_from_slice(&250u64.to_le_bytes());
+            bytes.extend_from_slice(&[1, 0, CAN_FLAG_TX, 2]);
+            bytes.extend_from_slice(&(0x1234 | CAN_MSG_EXT).to_le_bytes());
+            bytes.extend_from_slice(&[0xDE, 0xAD, 0, 0, 0, 0, 0, 0]);
+        }
+        bytes.truncate(bytes.len() - 12);
+
+        let mut reader = BlfReader::new(&bytes[..]).unwrap();
+        assert_eq!(reader.start_time(), None);
+        let record = reader.next().unwrap().unwrap();
+        assert_eq!(record.timestamp.as_micros(), 2_500);
+        assert_eq!(record.direction, Direction::Tx);
+        assert_eq!(
+            record.frame,
+            Frame::Can(CanFrame::new(0x1234, MessageType::Extended, &[0xDE, 0xAD]).unwrap())
+        );
+        assert!(reader.next().is_none());
+
+        assert!(matches!(
+            BlfReader::new(&b"LOBJ\x90\x00\x00\x00"[..]),
+            Err(BlfError::InvalidHeader)
+        ));
+        bytes.truncate(bytes.len() - 40);
+        let mut reader = BlfReader::new(&bytes[..]).unwrap();
+        assert!(matches!(reader.next(), Some(Err(BlfError::Io(_)))));
+    }
+}
@@ -0,0 +1,289 @@
+//! Log files of `candump -l` from the SocketCAN can-utils, also read by `canplayer`.
+//!
+//! Every line holds the time in seconds since 1970, the interface and the frame in compact
+//! notation, e.g. `(1618033988.123456) can0 123#DEADBEEF`. Interfaces are numbered as buses in
+//! order of their first appearance.
+
+use crate::format::{Direction, Frame, Record};
+use crate::socket::{parse_data, CanFdFrame, CanFrame, Timestamp};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Error flag of SocketCAN identifiers, error frames carry the error class in the identifier.
+const CAN_ERR_FLAG: u32 = 0x2000_0000;
+const CAN_ERR_MASK: u32 = 0x1FFF_FFFF;
+
+/* CandumpError */
+
+#[derive(Debug)]
+pub enum CandumpError {
+    Io(std::io::Error),
+    /// The given line could not be parsed.
+    Syntax {
+        line: usize,
+    },
+}
+
+impl From<std::io::Error> for CandumpError {
+    fn from(value: std::io::Error) -> Self {
+        CandumpError::Io(value)
+    }
+}
+
+/* CandumpReader */
+
+#[derive(Debug)]
+pub struct CandumpReader<R> {
+    reader: R,
+    line: usize,
+    buffer: String,
+    interfaces: Vec<String>,
+}
+
+impl CandumpReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<CandumpReader<BufReader<File>>, CandumpError> {
+        Ok(CandumpReader::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> CandumpReader<R> {
+    pub fn new(reader: R) -> CandumpReader<R> {
+        CandumpReader {
+            reader,
+            line: 0,
+            buffer: String::new(),
+            interfaces: Vec::new(),
+        }
+    }
+
+    /// Interface name of `bus`, as far as the file has been read.
+    pub fn interface(&self, bus: u8) -> Option<&str> {
+        self.interfaces
+            .get((bus as usize).checked_sub(1)?)
+            .map(|name| name.as_str())
+    }
+
+    fn bus(&mut self, interface: &str) -> u8 {
+        match self.interfaces.iter().position(|name| name == interface) {
+            Some(index) => index as u8 + 1,
+            None => {
+                self.interfaces.push(String::from(interface));
+                self.interfaces.len() as u8
+            }
+        }
+    }
+
+    fn row(&mut self, line: &str) -> Option<Record> {
+        let mut tokens = line.split_whitespace();
+        let timestamp = parse_time(tokens.next()?)?;
+        let bus = self.bus(tokens.next()?);
+        let frame = parse_frame(tokens.next()?)?;
+        // Some can-utils versions append the direction.
+        let direction = match tokens.next() {
+            Some("T") => Direction::Tx,
+            _ => Direction::Rx,
+        };
+
+        Some(Record {
+            timestamp,
+            bus,
+            direction,
+            frame,
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for CandumpReader<R> {
+    type Item = Result<Record, CandumpError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buffer.clear();
+            match self.reader.read_line(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(err) => return Some(Err(CandumpError::Io(err))),
+            }
+            self.line += 1;
+
+            let line = std::mem::take(&mut self.buffer);
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let record = self.row(trimmed);
+            self.buffer = line;
+
+            return match record {
+                Some(record) => Some(Ok(record)),
+                None => Some(Err(CandumpError::Syntax { line: self.line })),
+            };
+        }
+    }
+}
+
+/// Time `(seconds.micros)` since 1970.
+fn parse_time(token: &str) -> Option<Timestamp> {
+    let token = token.strip_prefix('(')?.strip_suffix(')')?;
+    let (seconds, fraction) = token.split_once('.')?;
+    let seconds: u64 = seconds.parse().ok()?;
+    if fraction.len() != 6 {
+        return None;
+    }
+    let micros: u64 = fraction.parse().ok()?;
+    Some(Timestamp::from_micros(seconds * 1_000_000 + micros))
+}
+
+fn parse_frame(token: &str) -> Option<Frame> {
+    if token.contains("##") {
+        return token.parse::<CanFdFrame>().ok().map(Frame::CanFd);
+    }
+
+    // Error frames set the error flag above the identifier range, which frames reject.
+    let (id, data) = token.split_once('#')?;
+    let can_id = u32::from_str_radix(id, 16).ok()?;
+    if id.len() == 8 && can_id & CAN_ERR_FLAG != 0 {
+        let data = parse_data(data).ok().filter(|data| data.len() <= 8)?;
+        return Some(Frame::Error {
+            class: can_id & CAN_ERR_MASK,
+            data,
+        });
+    }
+    token.parse::<CanFrame>().ok().map(Frame::Can)
+}
+
+/* CandumpWriter */
+
+#[derive(Debug)]
+pub struct CandumpWriter<W: Write> {
+    writer: W,
+    interfaces: Vec<String>,
+}
+
+impl CandumpWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<CandumpWriter<BufWriter<File>>, CandumpError> {
+        Ok(CandumpWriter::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> CandumpWriter<W> {
+    pub fn new(writer: W) -> CandumpWriter<W> {
+        CandumpWriter {
+            writer,
+            interfaces: Vec::new(),
+        }
+    }
+
+    /// Names the interface of `bus`, buses without name are written as `can0`, `can1`, ...
+    pub fn set_interface(&mut self, bus: u8, name: &str) {
+        let index = (bus as usize).saturating_sub(1);
+        if self.interfaces.len() <= index {
+            self.interfaces.resize(index + 1, String::new());
+        }
+        self.interfaces[index] = String::from(name);
+    }
+
+    fn interface(&self, bus: u8) -> String {
+        let index = (bus as usize).saturating_sub(1);
+        match self.interfaces.get(index) {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => format!("can{}", index),
+        }
+    }
+
+    /// Writes a frame with its timestamp taken as time since 1970. Status records can not be
+    /// represented and are skipped.
+    pub fn write_record(&mut self, record: &Record) -> Result<(), CandumpError> {
+        let frame = match &record.frame {
+            Frame::Can(frame) => frame.to_string(),
+            Frame::CanFd(frame) => frame.to_string(),
+            Frame::Error { class, data } => {
+                let mut frame = format!("{:08X}#", CAN_ERR_FLAG | (class & CAN_ERR_MASK));
+                for byte in data.iter().take(8) {
+                    frame.push_str(&format!("{:02X}", byte));
+                }
+                frame
+            }
+            Frame::Status(_) => return Ok(()),
+        };
+
+        let micros = record.timestamp.as_micros();
+        writeln!(
+            self.writer,
+            "({}.{:06}) {} {}",
+            micros / 1_000_000,
+            micros % 1_000_000,
+            self.interface(record.bus),
+            frame
+        )?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), CandumpError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::socket::MessageType;
+
+    const LOG: &str = "(1618033988.123456) can0 123#DEADBEEF\n\
+                       (1618033988.123500) vcan1 18FF0001##1000102030405060708090A0B\n\
+                       (1618033988.124000) can0 7FF#R2\n\
+                       (1618033988.125000) can0 20000004#0000080000000000\n";
+
+    #[test]
+    fn candump_reader_001() {
+        let mut reader = CandumpReader::new(LOG.as_bytes());
+        let records = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].timestamp.as_micros(), 1_618_033_988_123_456);
+        assert_eq!(
+            records[0].frame,
+            Frame::Can(
+                CanFrame::new(0x123, MessageType::Standard, &[0xDE, 0xAD, 0xBE, 0xEF]).unwrap()
+            )
+        );
+        assert_eq!(records[1].bus, 2);
+        assert!(matches!(&records[1].frame, Frame::CanFd(frame) if frame.is_bitrate_switch()));
+        assert!(matches!(&records[2].frame, Frame::Can(frame) if frame.is_remote_frame()));
+        assert_eq!(
+            records[3].frame,
+            Frame::Error {
+                class: 0x04,
+                data: vec![0, 0, 8, 0, 0, 0, 0, 0]
+            }
+        );
+        assert_eq!(reader.interface(2), Some("vcan1"));
+        assert_eq!(reader.interface(3), None);
+
+        let mut reader = CandumpReader::new("(1618033988.1) can0 123#00\n".as_bytes());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(CandumpError::Syntax { line: 1 }))
+        ));
+    }
+
+    #[test]
+    fn candump_writer_001() {
+        let records = CandumpReader::new(LOG.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let mut writer = CandumpWriter::new(Vec::new());
+        writer.set_interface(2, "vcan1");
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), LOG);
+    }
+}
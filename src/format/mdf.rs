@@ -0,0 +1,1168 @@
+//! ASAM MDF 4 measurement files with CAN frames stored following the ASAM bus logging
+//! convention.
+//!
+//! Frames are stored in the channel groups `CAN_DataFrame`, `CAN_RemoteFrame` and
+//! `CAN_ErrorFrame`, each with a `Timestamp` master channel in seconds since the start of the
+//! measurement and a structure of `BusChannel`, `ID`, `IDE`, `DLC`, `DataLength`, `Dir`, `EDL`,
+//! `BRS`, `ESI` and `DataBytes`. The writer adds the groups with an acquisition source for every
+//! bus channel and stores them in a single unsorted data group, so records can be streamed into
+//! the file. The reader reads uncompressed data groups written this way or sorted by channel
+//! group.
+
+use crate::format::{unix_millis, Direction, Frame, Record};
+use crate::socket::{dlc_to_len, CanFdFrame, CanFrame, MessageType, Timestamp};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const ID_BLOCK_SIZE: usize = 64;
+const BLOCK_HEADER_SIZE: usize = 24;
+/// Largest metadata block read, data blocks are streamed.
+const MAX_BLOCK_SIZE: u64 = 1 << 24;
+/// Flags of unfinished files, cycle counters and the length of the last data block are not
+/// updated yet.
+const UNFINISHED_FLAGS: u16 = 0x1 | 0x4;
+
+const CN_TYPE_FIXED: u8 = 0;
+const CN_TYPE_MASTER: u8 = 2;
+const CN_SYNC_TIME: u8 = 1;
+const DATA_TYPE_UNSIGNED: u8 = 0;
+const DATA_TYPE_FLOAT: u8 = 4;
+const DATA_TYPE_BYTE_ARRAY: u8 = 10;
+const CN_FLAG_BUS_EVENT: u32 = 0x400;
+const CG_FLAG_VLSD: u16 = 0x1;
+const CG_FLAG_BUS_EVENT: u16 = 0x2;
+const CG_FLAG_PLAIN_BUS_EVENT: u16 = 0x4;
+const SI_TYPE_BUS: u8 = 2;
+const SI_BUS_CAN: u8 = 2;
+
+/// Size of the frame fields following the timestamp of every record.
+const FRAME_HEADER_SIZE: usize = 8;
+
+/// Channel groups of the bus logging convention.
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Group {
+    Data,
+    Remote,
+    Error,
+}
+
+impl Group {
+    const ALL: [Group; 3] = [Group::Data, Group::Remote, Group::Error];
+
+    fn name(self) -> &'static str {
+        match self {
+            Group::Data => "CAN_DataFrame",
+            Group::Remote => "CAN_RemoteFrame",
+            Group::Error => "CAN_ErrorFrame",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Group> {
+        Group::ALL.into_iter().find(|group| group.name() == name)
+    }
+
+    /// Record identifier of the group for the bus channel with the given index.
+    fn record_id(self, bus_index: usize) -> u8 {
+        (3 * bus_index) as u8 + self as u8 + 1
+    }
+
+    /// Number of data bytes stored per record.
+    fn data_bytes(self) -> usize {
+        match self {
+            Group::Data => 64,
+            Group::Remote => 0,
+            Group::Error => 8,
+        }
+    }
+
+    fn record_size(self) -> usize {
+        8 + FRAME_HEADER_SIZE + self.data_bytes()
+    }
+
+    /// Signals of the frame structure as name, byte offset, bit offset and bit count.
+    fn signals(self) -> Vec<(&'static str, u32, u8, u32)> {
+        let mut signals = vec![
+            ("BusChannel", 8, 0, 8),
+            ("ID", 9, 0, 29),
+            ("IDE", 12, 7, 1),
+            ("DLC", 13, 0, 4),
+            ("DataLength", 14, 0, 7),
+            ("Dir", 15, 0, 1),
+        ];
+        if self == Group::Data {
+            signals.extend([("EDL", 15, 1, 1), ("BRS", 15, 2, 1), ("ESI", 15, 3, 1)]);
+        }
+        if self.data_bytes() > 0 {
+            signals.push(("DataBytes", 16, 0, self.data_bytes() as u32 * 8));
+        }
+        signals
+    }
+}
+
+/* MdfError */
+
+#[derive(Debug)]
+pub enum MdfError {
+    Io(std::io::Error),
+    /// The block at the given file offset is missing or broken.
+    InvalidBlock {
+        offset: u64,
+    },
+    UnsupportedVersion(String),
+    /// The file uses a feature the reader does not implement, e.g. compressed data blocks.
+    Unsupported(String),
+}
+
+impl From<std::io::Error> for MdfError {
+    fn from(value: std::io::Error) -> Self {
+        MdfError::Io(value)
+    }
+}
+
+/* MdfWriter */
+
+/// Most bus channels of a file, every bus channel takes three one byte record identifiers.
+const MAX_BUSES: usize = 85;
+
+/// Channel groups of a bus channel.
+#[derive(Debug)]
+struct BusGroups {
+    bus: u8,
+    /// File offsets of the channel groups.
+    groups: [u64; 3],
+    cycle_counts: [u64; 3],
+}
+
+/// Writes records into a single unsorted data group. The first record of a bus channel adds its
+/// acquisition source and channel groups, followed by a new data block for the next records.
+/// Cycle counters and the length of the last data block are completed by [MdfWriter::finish]
+/// or when the writer is dropped, until then the file is marked as unfinished.
+#[derive(Debug)]
+pub struct MdfWriter<W: Write + Seek> {
+    writer: Option<W>,
+    origin: Option<u64>,
+    /// File offsets of the data group and of the unit of the master channels.
+    data_group: u64,
+    unit: u64,
+    buses: Vec<BusGroups>,
+    /// File offsets and data lengths of the data blocks, records go into the last one.
+    data_blocks: Vec<(u64, u64)>,
+    /// Length of the file written so far.
+    end: u64,
+}
+
+impl MdfWriter<BufWriter<File>> {
+    /// Creates a file starting now.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<MdfWriter<BufWriter<File>>, MdfError> {
+        MdfWriter::new(BufWriter::new(File::create(path)?), unix_millis())
+    }
+}
+
+impl<W: Write + Seek> MdfWriter<W> {
+    /// Writes the file structure for a measurement started at `start_time` milliseconds since
+    /// 1970.
+    pub fn new(mut writer: W, start_time: u64) -> Result<MdfWriter<W>, MdfError> {
+        let mut blocks = Blocks::default();
+        blocks.bytes.extend_from_slice(b"UnFinMF 4.10    PCAN-Bsc");
+        blocks.bytes.resize(28, 0);
+        blocks.bytes.extend_from_slice(&410u16.to_le_bytes());
+        blocks.bytes.resize(60, 0);
+        blocks
+            .bytes
+            .extend_from_slice(&UNFINISHED_FLAGS.to_le_bytes());
+        blocks.bytes.resize(ID_BLOCK_SIZE, 0);
+
+        let nanos = start_time * 1_000_000;
+        let mut data = nanos.to_le_bytes().to_vec();
+        // Time zone, daylight saving, time flags and class, flags and start angle and distance.
+        data.resize(32, 0);
+        let header = blocks.push(b"##HD", &[0; 6], &data);
+
+        let comment = format!(
+            "<FHcomment><TX>created</TX><tool_id>pcan-basic</tool_id>\
+             <tool_vendor>pcan-basic</tool_vendor><tool_version>{}</tool_version></FHcomment>",
+            env!("CARGO_PKG_VERSION")
+        );
+        let comment = blocks.text(b"##MD", &comment);
+        let mut data = nanos.to_le_bytes().to_vec();
+        data.resize(16, 0);
+        let history = blocks.push(b"##FH", &[0, comment], &data);
+        let unit = blocks.text(b"##TX", "s");
+
+        // One byte record identifiers, the channel groups and data blocks follow with the buses.
+        let data_group = blocks.push(b"##DG", &[0; 4], &[1, 0, 0, 0, 0, 0, 0, 0]);
+        blocks.link(header, 0, data_group);
+        blocks.link(header, 1, history);
+        writer.write_all(&blocks.bytes)?;
+
+        Ok(MdfWriter {
+            writer: Some(writer),
+            origin: None,
+            data_group,
+            unit,
+            buses: Vec::new(),
+            data_blocks: Vec::new(),
+            end: blocks.bytes.len() as u64,
+        })
+    }
+
+    /// Sets the timestamp of the measurement start the `Timestamp` master channel counts from,
+    /// by default the first record.
+    pub fn set_origin(&mut self, origin: Timestamp) {
+        self.origin = Some(origin.as_micros());
+    }
+
+    /// Appends a frame to the record of its channel group, status records have no group and are
+    /// skipped.
+    pub fn write_record(&mut self, record: &Record) -> Result<(), MdfError> {
+        let micros = record.timestamp.as_micros();
+        let origin = *self.origin.get_or_insert(micros);
+        let offset = micros.saturating_sub(origin);
+
+        let (group, id, extended, dlc, data, flags) = match &record.frame {
+            Frame::Can(frame) if frame.is_remote_frame() => (
+                Group::Remote,
+                frame.can_id(),
+                frame.is_extended_frame(),
+                frame.dlc(),
+                &[][..],
+                0,
+            ),
+            Frame::Can(frame) => (
+                Group::Data,
+                frame.can_id(),
+                frame.is_extended_frame(),
+                frame.dlc(),
+                frame.data(),
+                0,
+            ),
+            Frame::CanFd(frame) => (
+                Group::Data,
+                frame.can_id(),
+                frame.is_extended_frame(),
+                frame.dlc(),
+                frame.data(),
+                0x02 | (frame.is_bitrate_switch() as u8) << 2
+                    | (frame.is_error_state_indicator() as u8) << 3,
+            ),
+            Frame::Error { data, .. } => {
+                let data = &data[..data.len().min(8)];
+                (Group::Error, 0, false, data.len() as u8, data, 0)
+            }
+            Frame::Status(_) => return Ok(()),
+        };
+        let direction = match record.direction {
+            Direction::Rx => 0,
+            Direction::Tx => 1,
+        };
+        let index = match self
+            .buses
+            .iter()
+            .position(|groups| groups.bus == record.bus)
+        {
+            Some(index) => index,
+            None => self.add_bus(record.bus)?,
+        };
+
+        let mut bytes = Vec::with_capacity(1 + group.record_size());
+        bytes.push(group.record_id(index));
+        bytes.extend_from_slice(&(offset as f64 / 1_000_000.0).to_le_bytes());
+        bytes.push(record.bus);
+        bytes.extend_from_slice(&(id | (extended as u32) << 31).to_le_bytes());
+        bytes.extend_from_slice(&[dlc, data.len() as u8, direction | flags]);
+        bytes.extend_from_slice(data);
+        bytes.resize(1 + group.record_size(), 0);
+
+        if let Some(writer) = self.writer.as_mut() {
+            writer.write_all(&bytes)?;
+        }
+        self.buses[index].cycle_counts[group as usize] += 1;
+        if let Some((_, length)) = self.data_blocks.last_mut() {
+            *length += bytes.len() as u64;
+        }
+        self.end += bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Appends the acquisition source and the channel groups of `bus` and a new data block,
+    /// returns the index of the bus.
+    fn add_bus(&mut self, bus: u8) -> Result<usize, MdfError> {
+        let index = self.buses.len();
+        if index == MAX_BUSES {
+            return Err(MdfError::Unsupported(format!(
+                "more than {} bus channels",
+                MAX_BUSES
+            )));
+        }
+
+        let start = (self.end + 7) & !7;
+        let mut blocks = Blocks {
+            base: start,
+            bytes: Vec::new(),
+        };
+        let source_name = blocks.text(b"##TX", &format!("CAN{}", bus));
+        let source_path = blocks.text(b"##TX", "PCAN-Basic");
+        let source = blocks.push(
+            b"##SI",
+            &[source_name, source_path, 0],
+            &[SI_TYPE_BUS, SI_BUS_CAN, 0, 0, 0, 0, 0, 0],
+        );
+
+        let mut groups = [0; 3];
+        let mut next_group = 0;
+        for group in Group::ALL.into_iter().rev() {
+            let mut next = 0;
+            for (name, byte_offset, bit_offset, bit_count) in group.signals().into_iter().rev() {
+                let data_type = match name {
+                    "DataBytes" => DATA_TYPE_BYTE_ARRAY,
+                    _ => DATA_TYPE_UNSIGNED,
+                };
+                let channel = Channel {
+                    name: format!("{}.{}", group.name(), name),
+                    kind: CN_TYPE_FIXED,
+                    sync: 0,
+                    data_type,
+                    byte_offset,
+                    bit_offset,
+                    bit_count,
+                    flags: 0,
+                };
+                next = blocks.channel(&channel, [next, 0, source, 0]);
+            }
+            let frame = Channel {
+                name: String::from(group.name()),
+                kind: CN_TYPE_FIXED,
+                sync: 0,
+                data_type: DATA_TYPE_BYTE_ARRAY,
+                byte_offset: 8,
+                bit_offset: 0,
+                bit_count: (group.record_size() as u32 - 8) * 8,
+                flags: CN_FLAG_BUS_EVENT,
+            };
+            let frame = blocks.channel(&frame, [0, next, source, 0]);
+            let timestamp = Channel {
+                name: String::from("Timestamp"),
+                kind: CN_TYPE_MASTER,
+                sync: CN_SYNC_TIME,
+                data_type: DATA_TYPE_FLOAT,
+                byte_offset: 0,
+                bit_offset: 0,
+                bit_count: 64,
+                flags: 0,
+            };
+            let timestamp = blocks.channel(&timestamp, [frame, 0, 0, self.unit]);
+
+            let name = blocks.text(b"##TX", group.name());
+            let mut data = (group.record_id(index) as u64).to_le_bytes().to_vec();
+            data.extend_from_slice(&0u64.to_le_bytes());
+            data.extend_from_slice(&(CG_FLAG_BUS_EVENT | CG_FLAG_PLAIN_BUS_EVENT).to_le_bytes());
+            data.extend_from_slice(&(b'.' as u16).to_le_bytes());
+            data.extend_from_slice(&[0; 4]);
+            data.extend_from_slice(&(group.record_size() as u32).to_le_bytes());
+            data.extend_from_slice(&0u32.to_le_bytes());
+            next_group = blocks.push(b"##CG", &[next_group, timestamp, name, source, 0, 0], &data);
+            groups[group as usize] = next_group;
+        }
+
+        // Data blocks after the first are listed with their offsets in the record data.
+        let count = self.data_blocks.len() + 1;
+        let list = match count {
+            1 => None,
+            _ => {
+                let mut links = vec![0];
+                links.extend(self.data_blocks.iter().map(|(block, _)| *block));
+                links.push(0);
+                let mut data = vec![0; 4];
+                data.extend_from_slice(&(count as u32).to_le_bytes());
+                let mut position = 0u64;
+                for (_, length) in &self.data_blocks {
+                    data.extend_from_slice(&position.to_le_bytes());
+                    position += length;
+                }
+                data.extend_from_slice(&position.to_le_bytes());
+                Some(blocks.push(b"##DL", &links, &data))
+            }
+        };
+        // The data block length is completed by the next bus or when finishing.
+        let data_block = blocks.push(b"##DT", &[], &[]);
+        if let Some(list) = list {
+            blocks.link(list, count, data_block);
+        }
+
+        if let Some(writer) = self.writer.as_mut() {
+            if let Some((block, length)) = self.data_blocks.last() {
+                writer.seek(SeekFrom::Start(block + 8))?;
+                writer.write_all(&(BLOCK_HEADER_SIZE as u64 + length).to_le_bytes())?;
+            }
+            // Appended to the channel groups of the previous bus.
+            let previous = match self.buses.last() {
+                Some(previous) => previous.groups[Group::Error as usize],
+                None => self.data_group + 8,
+            };
+            writer.seek(SeekFrom::Start(previous + BLOCK_HEADER_SIZE as u64))?;
+            writer.write_all(&next_group.to_le_bytes())?;
+            writer.seek(SeekFrom::Start(
+                self.data_group + BLOCK_HEADER_SIZE as u64 + 16,
+            ))?;
+            writer.write_all(&list.unwrap_or(data_block).to_le_bytes())?;
+
+            writer.seek(SeekFrom::Start(self.end))?;
+            writer.write_all(&vec![0; (start - self.end) as usize])?;
+            writer.write_all(&blocks.bytes)?;
+        }
+        self.end = start + blocks.bytes.len() as u64;
+        self.buses.push(BusGroups {
+            bus,
+            groups,
+            cycle_counts: [0; 3],
+        });
+        self.data_blocks.push((data_block, 0));
+        Ok(index)
+    }
+
+    fn complete(&mut self) -> Result<(), MdfError> {
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+
+        if let Some((block, length)) = self.data_blocks.last() {
+            writer.seek(SeekFrom::Start(block + 8))?;
+            writer.write_all(&(BLOCK_HEADER_SIZE as u64 + length).to_le_bytes())?;
+        }
+        for bus in &self.buses {
+            for (group, count) in bus.groups.iter().zip(bus.cycle_counts) {
+                // The cycle counter follows the six links and the record identifier.
+                writer.seek(SeekFrom::Start(group + BLOCK_HEADER_SIZE as u64 + 56))?;
+                writer.write_all(&count.to_le_bytes())?;
+            }
+        }
+        writer.seek(SeekFrom::Start(0))?;
+        writer.write_all(b"MDF     ")?;
+        writer.seek(SeekFrom::Start(60))?;
+        writer.write_all(&0u16.to_le_bytes())?;
+
+        writer.seek(SeekFrom::Start(self.end))?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Completes the file and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, MdfError> {
+        let result = self.complete();
+        let writer = self.writer.take().unwrap();
+        result.map(|_| writer)
+    }
+}
+
+impl<W: Write + Seek> Drop for MdfWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.complete();
+    }
+}
+
+/// Channel block fields.
+#[derive(Debug)]
+struct Channel {
+    name: String,
+    kind: u8,
+    sync: u8,
+    data_type: u8,
+    byte_offset: u32,
+    bit_offset: u8,
+    bit_count: u32,
+    flags: u32,
+}
+
+/// Metadata blocks written from the file offset `base`, placed at 8 byte aligned offsets.
+#[derive(Debug, Default)]
+struct Blocks {
+    base: u64,
+    bytes: Vec<u8>,
+}
+
+impl Blocks {
+    fn push(&mut self, id: &[u8; 4], links: &[u64], data: &[u8]) -> u64 {
+        let offset = self.base + self.bytes.len() as u64;
+        let padded = (data.len() + 7) & !7;
+        let length = BLOCK_HEADER_SIZE + 8 * links.len() + padded;
+
+        self.bytes.extend_from_slice(id);
+        self.bytes.extend_from_slice(&[0; 4]);
+        self.bytes.extend_from_slice(&(length as u64).to_le_bytes());
+        self.bytes
+            .extend_from_slice(&(links.len() as u64).to_le_bytes());
+        for link in links {
+            self.bytes.extend_from_slice(&link.to_le_bytes());
+        }
+        self.bytes.extend_from_slice(data);
+        self.bytes.resize((offset - self.base) as usize + length, 0);
+        offset
+    }
+
+    /// Zero terminated text or XML block.
+    fn text(&mut self, id: &[u8; 4], text: &str) -> u64 {
+        let mut data = text.as_bytes().to_vec();
+        data.push(0);
+        self.push(id, &[], &data)
+    }
+
+    /// Channel with the links to the next channel, the structure members, the source and the
+    /// unit.
+    fn channel(&mut self, channel: &Channel, [next, composition, source, unit]: [u64; 4]) -> u64 {
+        let name = self.text(b"##TX", &channel.name);
+        let mut data = vec![
+            channel.kind,
+            channel.sync,
+            channel.data_type,
+            channel.bit_offset,
+        ];
+        data.extend_from_slice(&channel.byte_offset.to_le_bytes());
+        data.extend_from_slice(&channel.bit_count.to_le_bytes());
+        data.extend_from_slice(&channel.flags.to_le_bytes());
+        // Invalidation bit, precision, attachments and value ranges and limits.
+        data.resize(72, 0);
+        self.push(
+            b"##CN",
+            &[next, composition, name, source, 0, 0, unit, 0],
+            &data,
+        )
+    }
+
+    fn link(&mut self, block: u64, index: usize, value: u64) {
+        let at = (block - self.base) as usize + BLOCK_HEADER_SIZE + 8 * index;
+        self.bytes[at..at + 8].copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+/* MdfReader */
+
+/// Position of a signal within a record.
+#[derive(Debug, Copy, Clone)]
+struct Signal {
+    byte_offset: usize,
+    bit_offset: u32,
+    bit_count: u32,
+    data_type: u8,
+}
+
+#[derive(Debug, Default)]
+struct Layout {
+    timestamp: Option<Signal>,
+    bus: Option<Signal>,
+    id: Option<Signal>,
+    ide: Option<Signal>,
+    dlc: Option<Signal>,
+    length: Option<Signal>,
+    dir: Option<Signal>,
+    edl: Option<Signal>,
+    brs: Option<Signal>,
+    esi: Option<Signal>,
+    data: Option<Signal>,
+}
+
+#[derive(Debug)]
+struct ChannelGroup {
+    record_id: u64,
+    /// Record size without the record identifier.
+    size: usize,
+    vlsd: bool,
+    /// Bus logging group and its signals.
+    layout: Option<(Group, Layout)>,
+}
+
+#[derive(Debug)]
+struct DataGroup {
+    record_id_size: usize,
+    groups: Vec<ChannelGroup>,
+    /// File offsets and lengths of the record data.
+    sections: Vec<(u64, u64)>,
+}
+
+/// Reads the bus logging channel groups. Records of other channel groups are skipped, data
+/// groups are read one after another.
+#[derive(Debug)]
+pub struct MdfReader<R> {
+    reader: R,
+    start_time: u64,
+    data_groups: Vec<DataGroup>,
+    /// Current data group, section and offset within the section.
+    data_group: usize,
+    section: usize,
+    position: u64,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl MdfReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MdfReader<BufReader<File>>, MdfError> {
+        MdfReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> MdfReader<R> {
+    /// Reads the file structure.
+    pub fn new(mut reader: R) -> Result<MdfReader<R>, MdfError> {
+        let mut id = [0; ID_BLOCK_SIZE];
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut id)?;
+        if &id[..8] != b"MDF     " && &id[..8] != b"UnFinMF " {
+            return Err(MdfError::InvalidBlock { offset: 0 });
+        }
+        let version = u16::from_le_bytes([id[28], id[29]]);
+        if version < 400 {
+            let name = String::from_utf8_lossy(&id[8..16]);
+            return Err(MdfError::UnsupportedVersion(name.trim().to_string()));
+        }
+
+        let header = read_block(&mut reader, ID_BLOCK_SIZE as u64, b"##HD")?;
+        let start_time = u64_at(&header.data, 0).ok_or(invalid(ID_BLOCK_SIZE as u64))?;
+
+        let mut data_groups = Vec::new();
+        let mut next = header.link(0);
+        while next != 0 {
+            let block = read_block(&mut reader, next, b"##DG")?;
+            let record_id_size = *block.data.first().ok_or(invalid(next))? as usize;
+            if !matches!(record_id_size, 0 | 1 | 2 | 4 | 8) {
+                return Err(invalid(next));
+            }
+
+            let mut groups = Vec::new();
+            let mut group = block.link(1);
+            while group != 0 {
+                let cg = read_block(&mut reader, group, b"##CG")?;
+                groups.push(read_channel_group(&mut reader, &cg, group)?);
+                group = cg.link(0);
+            }
+            let sections = read_sections(&mut reader, block.link(2))?;
+
+            data_groups.push(DataGroup {
+                record_id_size,
+                groups,
+                sections,
+            });
+            next = block.link(0);
+        }
+
+        Ok(MdfReader {
+            reader,
+            start_time: start_time / 1_000_000,
+            data_groups,
+            data_group: 0,
+            section: 0,
+            position: 0,
+            buffer: Vec::new(),
+            done: false,
+        })
+    }
+
+    /// Start of the measurement in milliseconds since 1970.
+    pub fn start_time(&self) -> u64 {
+        self.start_time
+    }
+
+    /// Reads record bytes of the current data group across its data blocks, returns false at
+    /// the end of the data group.
+    fn read_data(&mut self, length: usize) -> Result<bool, MdfError> {
+        self.buffer.clear();
+        let sections = &self.data_groups[self.data_group].sections;
+        while self.buffer.len() < length {
+            let (offset, size) = match sections.get(self.section) {
+                Some(section) => *section,
+                None if self.buffer.is_empty() => return Ok(false),
+                None => return Err(MdfError::Io(std::io::ErrorKind::UnexpectedEof.into())),
+            };
+            if self.position == size {
+                self.section += 1;
+                self.position = 0;
+                continue;
+            }
+            let count = ((length - self.buffer.len()) as u64).min(size - self.position);
+            self.reader.seek(SeekFrom::Start(offset + self.position))?;
+            let start = self.buffer.len();
+            self.buffer.resize(start + count as usize, 0);
+            self.reader.read_exact(&mut self.buffer[start..])?;
+            self.position += count;
+        }
+        Ok(true)
+    }
+
+    /// Reads the next record of the current data group, `Some(None)` for skipped records.
+    fn record(&mut self) -> Result<Option<Option<Record>>, MdfError> {
+        let data_group = &self.data_groups[self.data_group];
+        let record_id_size = data_group.record_id_size;
+        let record_id = match record_id_size {
+            0 => 0,
+            size => {
+                if !self.read_data(size)? {
+                    return Ok(None);
+                }
+                let mut bytes = [0; 8];
+                bytes[..size].copy_from_slice(&self.buffer);
+                u64::from_le_bytes(bytes)
+            }
+        };
+
+        let data_group = &self.data_groups[self.data_group];
+        let index = match record_id_size {
+            0 if data_group.groups.len() == 1 => 0,
+            _ => data_group
+                .groups
+                .iter()
+                .position(|group| group.record_id == record_id)
+                .ok_or(MdfError::Unsupported(format!("record id {}", record_id)))?,
+        };
+        let size = match data_group.groups[index].vlsd {
+            true => {
+                if !self.read_data(4)? {
+                    return Err(MdfError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+                }
+                u32::from_le_bytes(self.buffer[..4].try_into().unwrap()) as usize
+            }
+            false => data_group.groups[index].size,
+        };
+        if !self.read_data(size)? {
+            return match record_id_size {
+                0 => Ok(None),
+                _ => Err(MdfError::Io(std::io::ErrorKind::UnexpectedEof.into())),
+            };
+        }
+
+        let record = match &self.data_groups[self.data_group].groups[index].layout {
+            Some((group, layout)) => decode(*group, layout, &self.buffer)?,
+            None => return Ok(Some(None)),
+        };
+        Ok(Some(Some(record)))
+    }
+}
+
+impl<R: Read + Seek> Iterator for MdfReader<R> {
+    type Item = Result<Record, MdfError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done && self.data_group < self.data_groups.len() {
+            match self.record() {
+                Ok(Some(Some(record))) => return Some(Ok(record)),
+                Ok(Some(None)) => {}
+                Ok(None) => {
+                    self.data_group += 1;
+                    self.section = 0;
+                    self.position = 0;
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Block header, links and data.
+#[derive(Debug)]
+struct Block {
+    links: Vec<u64>,
+    data: Vec<u8>,
+}
+
+impl Block {
+    fn link(&self, index: usize) -> u64 {
+        self.links.get(index).copied().unwrap_or(0)
+    }
+}
+
+fn invalid(offset: u64) -> MdfError {
+    MdfError::InvalidBlock { offset }
+}
+
+/// Reads the identifier, length and link count of a block.
+fn read_header<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+) -> Result<([u8; 4], u64, u64), MdfError> {
+    let mut header = [0; BLOCK_HEADER_SIZE];
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(&mut header)?;
+    let id = [header[0], header[1], header[2], header[3]];
+    let length = u64::from_le_bytes(header[8..16].try_into().unwrap());
+    let links = u64::from_le_bytes(header[16..24].try_into().unwrap());
+    if length < BLOCK_HEADER_SIZE as u64 + 8 * links {
+        return Err(invalid(offset));
+    }
+    Ok((id, length, links))
+}
+
+fn read_block<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    expected: &[u8; 4],
+) -> Result<Block, MdfError> {
+    let (id, length, links) = read_header(reader, offset)?;
+    if &id != expected || length > MAX_BLOCK_SIZE {
+        return Err(invalid(offset));
+    }
+    let mut bytes = vec![0; length as usize - BLOCK_HEADER_SIZE];
+    reader.read_exact(&mut bytes)?;
+    let data = bytes.split_off(8 * links as usize);
+    let links = bytes
+        .chunks_exact(8)
+        .map(|link| u64::from_le_bytes(link.try_into().unwrap()))
+        .collect();
+    Ok(Block { links, data })
+}
+
+fn read_text<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<String, MdfError> {
+    if offset == 0 {
+        return Ok(String::new());
+    }
+    let (id, _, _) = read_header(reader, offset)?;
+    let block = read_block(reader, offset, &id)?;
+    if &id != b"##TX" && &id != b"##MD" {
+        return Err(invalid(offset));
+    }
+    let end = block
+        .data
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(block.data.len());
+    Ok(String::from_utf8_lossy(&block.data[..end]).into_owned())
+}
+
+fn read_channel_group<R: Read + Seek>(
+    reader: &mut R,
+    block: &Block,
+    offset: u64,
+) -> Result<ChannelGroup, MdfError> {
+    if block.data.len() < 32 {
+        return Err(invalid(offset));
+    }
+    let flags = u16::from_le_bytes([block.data[16], block.data[17]]);
+    let data_bytes = u32::from_le_bytes(block.data[24..28].try_into().unwrap());
+    let inval_bytes = u32::from_le_bytes(block.data[28..32].try_into().unwrap());
+
+    let name = read_text(reader, block.link(2))?;
+    let layout = match Group::from_name(&name) {
+        Some(group) => {
+            let mut layout = Layout::default();
+            read_channels(reader, block.link(1), data_bytes as usize, &mut layout)?;
+            Some((group, layout))
+        }
+        None => None,
+    };
+
+    Ok(ChannelGroup {
+        record_id: u64_at(&block.data, 0).unwrap_or(0),
+        size: data_bytes as usize + inval_bytes as usize,
+        vlsd: flags & CG_FLAG_VLSD != 0,
+        layout,
+    })
+}
+
+/// Collects the signals of a channel list and its structures by the last part of their names.
+fn read_channels<R: Read + Seek>(
+    reader: &mut R,
+    mut next: u64,
+    record_size: usize,
+    layout: &mut Layout,
+) -> Result<(), MdfError> {
+    while next != 0 {
+        let block = read_block(reader, next, b"##CN")?;
+        if block.data.len() < 16 {
+            return Err(invalid(next));
+        }
+        let signal = Signal {
+            byte_offset: u32::from_le_bytes(block.data[4..8].try_into().unwrap()) as usize,
+            bit_offset: block.data[3] as u32,
+            bit_count: u32::from_le_bytes(block.data[8..12].try_into().unwrap()),
+            data_type: block.data[2],
+        };
+        // Signals have to lie within the record for the bit operations of the reader.
+        let bits = signal.bit_offset as u64 + signal.bit_count as u64;
+        if signal.bit_offset > 7 || signal.byte_offset as u64 * 8 + bits > record_size as u64 * 8 {
+            return Err(invalid(next));
+        }
+        let name = read_text(reader, block.link(2))?;
+        let field = match block.data[0] {
+            CN_TYPE_MASTER => Some(&mut layout.timestamp),
+            _ => match name.rsplit(['.', '/']).next().unwrap_or("") {
+                "BusChannel" => Some(&mut layout.bus),
+                "ID" => Some(&mut layout.id),
+                "IDE" => Some(&mut layout.ide),
+                "DLC" => Some(&mut layout.dlc),
+                "DataLength" => Some(&mut layout.length),
+                "Dir" => Some(&mut layout.dir),
+                "EDL" => Some(&mut layout.edl),
+                "BRS" => Some(&mut layout.brs),
+                "ESI" => Some(&mut layout.esi),
+                "DataBytes" => Some(&mut layout.data),
+                _ => None,
+            },
+        };
+        if let Some(field) = field {
+            *field = Some(signal);
+        }
+
+        read_channels(reader, block.link(1), record_size, layout)?;
+        next = block.link(0);
+    }
+    Ok(())
+}
+
+/// File offsets and lengths of the records of a data group, from a data block or a list of
+/// data blocks.
+fn read_sections<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<Vec<(u64, u64)>, MdfError> {
+    let mut sections = Vec::new();
+    if offset == 0 {
+        return Ok(sections);
+    }
+    let (id, _, _) = read_header(reader, offset)?;
+    match &id {
+        b"##DT" => {
+            let (_, length, _) = read_header(reader, offset)?;
+            sections.push((
+                offset + BLOCK_HEADER_SIZE as u64,
+                length - BLOCK_HEADER_SIZE as u64,
+            ));
+        }
+        b"##DL" => {
+            let mut next = offset;
+            while next != 0 {
+                let list = read_block(reader, next, b"##DL")?;
+                for link in list.links.iter().skip(1) {
+                    sections.extend(read_sections(reader, *link)?);
+                }
+                next = list.link(0);
+            }
+        }
+        _ => {
+            let id = String::from_utf8_lossy(&id).into_owned();
+            return Err(MdfError::Unsupported(id));
+        }
+    }
+    Ok(sections)
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        bytes.get(offset..offset + 8)?.try_into().unwrap(),
+    ))
+}
+
+/// Unsigned integer value of a signal.
+fn value(record: &[u8], signal: Option<Signal>) -> Option<u64> {
+    let signal = signal?;
+    let mut bytes = [0; 8];
+    let record = record.get(signal.byte_offset..)?;
+    let available = record.len().min(8);
+    bytes[..available].copy_from_slice(&record[..available]);
+    let value = u64::from_le_bytes(bytes) >> signal.bit_offset;
+    match signal.bit_count {
+        64.. => Some(value),
+        count => Some(value & ((1 << count) - 1)),
+    }
+}
+
+fn bytes(record: &[u8], signal: Option<Signal>) -> &[u8] {
+    match signal {
+        Some(signal) => {
+            let end = (signal.byte_offset + signal.bit_count as usize / 8).min(record.len());
+            record.get(signal.byte_offset..end).unwrap_or(&[])
+        }
+        None => &[],
+    }
+}
+
+fn decode(group: Group, layout: &Layout, record: &[u8]) -> Result<Record, MdfError> {
+    let invalid = || MdfError::Unsupported(format!("{} record", group.name()));
+
+    let seconds = match layout.timestamp {
+        Some(signal) if signal.data_type == DATA_TYPE_FLOAT && signal.bit_count == 64 => {
+            let start = signal.byte_offset;
+            let bytes = record.get(start..start + 8).ok_or_else(invalid)?;
+            f64::from_le_bytes(bytes.try_into().unwrap())
+        }
+        Some(signal) if signal.data_type == DATA_TYPE_FLOAT && signal.bit_count == 32 => {
+            let start = signal.byte_offset;
+            let bytes = record.get(start..start + 4).ok_or_else(invalid)?;
+            f32::from_le_bytes(bytes.try_into().unwrap()) as f64
+        }
+        Some(signal) => value(record, Some(signal)).unwrap_or(0) as f64,
+        None => 0.0,
+    };
+
+    let id = value(record, layout.id).unwrap_or(0) as u32;
+    let msg_type = match value(record, layout.ide) {
+        Some(1) => MessageType::Extended,
+        _ => MessageType::Standard,
+    };
+    let dlc = value(record, layout.dlc).unwrap_or(0) as u8;
+    let data = bytes(record, layout.data);
+    let length = value(record, layout.length)
+        .map(|length| length as usize)
+        .unwrap_or(dlc_to_len(dlc))
+        .min(data.len());
+    let flag = |signal| value(record, signal) == Some(1);
+
+    let frame = match group {
+        Group::Data if flag(layout.edl) => {
+            let mut frame =
+                CanFdFrame::new(id, msg_type, &data[..length]).map_err(|_| invalid())?;
+            frame.set_bitrate_switch(flag(layout.brs));
+            frame.set_error_state_indicator(flag(layout.esi));
+            Frame::CanFd(frame)
+        }
+        Group::Data => {
+            Frame::Can(CanFrame::new(id, msg_type, &data[..length.min(8)]).map_err(|_| invalid())?)
+        }
+        Group::Remote => {
+            Frame::Can(CanFrame::new_remote(id, msg_type, dlc).map_err(|_| invalid())?)
+        }
+        Group::Error => Frame::Error {
+            class: 0,
+            data: data[..length].to_vec(),
+        },
+    };
+
+    Ok(Record {
+        timestamp: Timestamp::from_micros((seconds.max(0.0) * 1_000_000.0).round() as u64),
+        bus: value(record, layout.bus)
+            .unwrap_or(1)
+            .clamp(1, u8::MAX as u64) as u8,
+        direction: match flag(layout.dir) {
+            true => Direction::Tx,
+            false => Direction::Rx,
+        },
+        frame,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::test_records;
+    use std::io::Cursor;
+
+    #[test]
+    fn mdf_writer_001() {
+        let mut esi = CanFdFrame::new(0x7FF, MessageType::Standard, &[0x55; 64]).unwrap();
+        esi.set_error_state_indicator(true);
+        let extended = CanFrame::new(0x1FFF_FFFF, MessageType::Extended, &[0xFF; 8]).unwrap();
+        let empty = CanFdFrame::new(0x001, MessageType::Standard, &[]).unwrap();
+        let records = vec![
+            Record::new(Timestamp::from_micros(10), Frame::CanFd(esi)),
+            Record {
+                timestamp: Timestamp::from_micros(20),
+                bus: 3,
+                direction: Direction::Tx,
+                frame: Frame::Can(extended),
+            },
+            Record::new(Timestamp::from_micros(4_000_000_000), Frame::CanFd(empty)),
+        ];
+
+        let mut writer = MdfWriter::new(Cursor::new(Vec::new()), 1_697_638_080_123).unwrap();
+        writer.set_origin(Timestamp::default());
+        for record in records.iter().chain(&test_records()) {
+            writer.write_record(record).unwrap();
+        }
+        writer
+            .write_record(&Record::new(
+                Timestamp::from_micros(3_500),
+                Frame::Status(String::from("BUSHEAVY")),
+            ))
+            .unwrap();
+        let buses = writer
+            .buses
+            .iter()
+            .map(|groups| (groups.bus, groups.groups))
+            .collect::<Vec<_>>();
+        let mut file = writer.finish().unwrap();
+        assert_eq!(&file.get_ref()[..8], b"MDF     ");
+
+        // Data, remote and error frames are counted in the channel groups of their bus.
+        let cycles = buses
+            .iter()
+            .map(|(bus, groups)| {
+                let counts = groups.map(|group| {
+                    u64_at(file.get_ref(), group as usize + BLOCK_HEADER_SIZE + 56).unwrap()
+                });
+                (*bus, counts)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(cycles, [(1, [3, 1, 1]), (3, [1, 0, 0]), (2, [1, 0, 0])]);
+        let sources = file.get_ref().windows(4).filter(|id| id == b"##SI");
+        assert_eq!(sources.count(), 3);
+        assert!(file.get_ref().windows(5).any(|text| text == b"CAN3\0"));
+
+        let mut reader = MdfReader::new(&mut file).unwrap();
+        assert_eq!(reader.start_time(), 1_697_638_080_123);
+        let read = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(read[..3], records);
+        assert_eq!(read[3..], test_records());
+    }
+
+    #[test]
+    fn mdf_writer_002() {
+        let mut file = Cursor::new(Vec::new());
+        {
+            let mut writer = MdfWriter::new(&mut file, 0).unwrap();
+            for record in &test_records() {
+                writer.write_record(record).unwrap();
+            }
+        }
+
+        // Completed when dropped, with the cycle counters of all groups.
+        let bytes = file.get_ref();
+        assert_eq!(u16::from_le_bytes([bytes[60], bytes[61]]), 0);
+        let reader = MdfReader::new(Cursor::new(bytes)).unwrap();
+        let counts = reader.data_groups[0]
+            .groups
+            .iter()
+            .map(|group| (group.layout.as_ref().unwrap().0, group.size))
+            .collect::<Vec<_>>();
+        let groups = [(Group::Data, 80), (Group::Remote, 16), (Group::Error, 24)];
+        assert_eq!(counts, [groups, groups].concat());
+        let read = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(read.len(), 4);
+        assert_eq!(read[0].timestamp.as_micros(), 0);
+        assert_eq!(read[3].timestamp.as_micros(), 1_500);
+
+        assert!(matches!(
+            MdfReader::new(Cursor::new(vec![0; 64])),
+            Err(MdfError::InvalidBlock { offset: 0 })
+        ));
+    }
+
+    #[test]
+    fn mdf_reader_001() {
+        let mut writer = MdfWriter::new(Cursor::new(Vec::new()), 0).unwrap();
+        for record in &test_records() {
+            writer.write_record(record).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+        let channels = bytes
+            .windows(4)
+            .enumerate()
+            .filter(|(_, id)| *id == b"##CN")
+            .map(|(offset, _)| {
+                let links = u64_at(&bytes, offset + 16).unwrap() as usize;
+                offset + BLOCK_HEADER_SIZE + 8 * links
+            })
+            .collect::<Vec<_>>();
+
+        // Bit offsets beyond a byte and signals past the end of the record are rejected.
+        let mut broken = bytes.clone();
+        broken[channels[1] + 3] = 64;
+        assert!(matches!(
+            MdfReader::new(Cursor::new(broken)),
+            Err(MdfError::InvalidBlock { .. })
+        ));
+        let mut broken = bytes.clone();
+        broken[channels[1] + 4..channels[1] + 8].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(matches!(
+            MdfReader::new(Cursor::new(broken)),
+            Err(MdfError::InvalidBlock { .. })
+        ));
+    }
+}
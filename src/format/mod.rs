@@ -0,0 +1,171 @@
+//! Reading and writing of recorded CAN traffic in common log file formats.
+//!
+//! All readers yield [Record]s in file order, so recordings can be converted between formats by
+//! chaining a reader with a writer.
+
+pub mod asc;
+pub mod blf;
+pub mod candump;
+pub mod mdf;
+pub mod trc;
+
+use crate::socket::{CanFdFrame, CanFrame, Timestamp};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Direction {
+    Rx,
+    Tx,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Frame {
+    Can(CanFrame),
+    CanFd(CanFdFrame),
+    /// Error frame with the error information bytes reported by the interface. SocketCAN error
+    /// frames keep the error class bits of their identifier (`CAN_ERR_*` without `CAN_ERR_FLAG`)
+    /// in `class`, which is zero for all other sources.
+    Error {
+        class: u32,
+        data: Vec<u8>,
+    },
+    /// Row describing a bus state or event instead of a frame, e.g. `BUSHEAVY`.
+    Status(String),
+}
+
+impl Frame {
+    /// CAN-ID of data and remote frames.
+    pub fn can_id(&self) -> Option<u32> {
+        match self {
+            Frame::Can(frame) => Some(frame.can_id()),
+            Frame::CanFd(frame) => Some(frame.can_id()),
+            _ => None,
+        }
+    }
+}
+
+/// Single row of a recording.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Record {
+    /// Time since the start of the recording.
+    pub timestamp: Timestamp,
+    /// Bus number, counting from 1.
+    pub bus: u8,
+    pub direction: Direction,
+    pub frame: Frame,
+}
+
+impl Record {
+    /// Received frame on bus 1.
+    pub fn new(timestamp: Timestamp, frame: Frame) -> Record {
+        Record {
+            timestamp,
+            bus: 1,
+            direction: Direction::Rx,
+            frame,
+        }
+    }
+}
+
+/// Calendar date and time in UTC, used for the headers of the written formats.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub(crate) struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub millis: u32,
+}
+
+impl DateTime {
+    pub fn from_unix_millis(millis: u64) -> DateTime {
+        let days = (millis / 86_400_000) as i64;
+        let time = millis % 86_400_000;
+
+        // Civil date from days since 1970-01-01, see H. Hinnant's date algorithms.
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        DateTime {
+            year,
+            month,
+            day,
+            hour: (time / 3_600_000) as u32,
+            minute: (time / 60_000 % 60) as u32,
+            second: (time / 1000 % 60) as u32,
+            millis: (time % 1000) as u32,
+        }
+    }
+
+    pub fn to_unix_millis(self) -> u64 {
+        // Days since 1970-01-01 from the civil date, inverse of from_unix_millis.
+        let year = if self.month <= 2 {
+            self.year - 1
+        } else {
+            self.year
+        };
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = self.month as i64;
+        let mp = if month > 2 { month - 3 } else { month + 9 };
+        let doy = (153 * mp + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146_097 + doe - 719_468;
+
+        let time = ((self.hour * 60 + self.minute) * 60 + self.second) as i64 * 1000;
+        (days * 86_400_000 + time + self.millis as i64).max(0) as u64
+    }
+
+    /// Day of the week, 0 for Sunday.
+    pub fn weekday(&self) -> u32 {
+        ((self.to_unix_millis() / 86_400_000 + 4) % 7) as u32
+    }
+}
+
+/// Milliseconds since 1970-01-01 UTC.
+pub(crate) fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Classic, CAN FD, remote and error frame for the round trips of the writers.
+#[cfg(test)]
+pub(crate) fn test_records() -> Vec<Record> {
+    use crate::socket::MessageType;
+
+    let mut fd = CanFdFrame::new(0x18FF_0001, MessageType::Extended, &[0xAA; 12]).unwrap();
+    fd.set_bitrate_switch(true);
+    vec![
+        Record::new(
+            Timestamp::from_micros(1_500),
+            Frame::Can(CanFrame::new(0x123, MessageType::Standard, &[1, 2, 3]).unwrap()),
+        ),
+        Record {
+            timestamp: Timestamp::from_micros(2_000),
+            bus: 2,
+            direction: Direction::Tx,
+            frame: Frame::CanFd(fd),
+        },
+        Record::new(
+            Timestamp::from_micros(2_500),
+            Frame::Can(CanFrame::new_remote(0x1FFF_FFFF, MessageType::Extended, 4).unwrap()),
+        ),
+        Record::new(
+            Timestamp::from_micros(3_000),
+            Frame::Error {
+                class: 0,
+                data: vec![0, 8],
+            },
+        ),
+    ]
+}
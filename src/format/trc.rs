@@ -0,0 +1,954 @@
+//! PCAN trace files (.trc) as written by PCAN-View and the PCAN-Basic driver trace.
+//!
+//! Versions 1.0 to 1.3 use a fixed column layout, versions 2.0 and 2.1 describe their columns
+//! in the `$COLUMNS` header line.
+
+use crate::error::PcanError;
+use crate::format::{unix_millis, DateTime, Direction, Frame, Record};
+use crate::socket::{dlc_to_len, CanFdFrame, CanFrame, MessageType, RecvCan, Timestamp};
+use crate::trace::TraceFile;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/* TrcError */
+
+#[derive(Debug)]
+pub enum TrcError {
+    Io(std::io::Error),
+    Pcan(PcanError),
+    /// The given line could not be parsed.
+    Syntax {
+        line: usize,
+    },
+    /// The `$FILEVERSION` header names an unknown version.
+    UnsupportedVersion(String),
+}
+
+impl From<std::io::Error> for TrcError {
+    fn from(value: std::io::Error) -> Self {
+        TrcError::Io(value)
+    }
+}
+
+/* TrcVersion */
+
+#[derive(Debug, PartialEq, Copy, Clone, PartialOrd)]
+pub enum TrcVersion {
+    V1_0,
+    V1_1,
+    V1_2,
+    V1_3,
+    V2_0,
+    V2_1,
+}
+
+impl TrcVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrcVersion::V1_0 => "1.0",
+            TrcVersion::V1_1 => "1.1",
+            TrcVersion::V1_2 => "1.2",
+            TrcVersion::V1_3 => "1.3",
+            TrcVersion::V2_0 => "2.0",
+            TrcVersion::V2_1 => "2.1",
+        }
+    }
+
+    /// Column layout used if a version 2 file has no `$COLUMNS` header.
+    fn default_columns(&self) -> &'static str {
+        match self {
+            TrcVersion::V2_0 => "N,O,T,I,d,l,D",
+            _ => "N,O,T,B,I,d,R,L,D",
+        }
+    }
+}
+
+impl TryFrom<&str> for TrcVersion {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "1.0" => Ok(TrcVersion::V1_0),
+            "1.1" => Ok(TrcVersion::V1_1),
+            "1.2" => Ok(TrcVersion::V1_2),
+            "1.3" => Ok(TrcVersion::V1_3),
+            "2.0" => Ok(TrcVersion::V2_0),
+            "2.1" => Ok(TrcVersion::V2_1),
+            _ => Err(()),
+        }
+    }
+}
+
+/* TrcReader */
+
+/// Streaming reader yielding one [Record] per trace row. Only the current line is held in memory.
+#[derive(Debug)]
+pub struct TrcReader<R> {
+    reader: R,
+    line: usize,
+    buffer: Vec<u8>,
+    version: TrcVersion,
+    columns: Vec<char>,
+    start_time: Option<f64>,
+}
+
+impl TrcReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<TrcReader<BufReader<File>>, TrcError> {
+        Ok(TrcReader::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> TrcReader<R> {
+    pub fn new(reader: R) -> TrcReader<R> {
+        TrcReader {
+            reader,
+            line: 0,
+            buffer: Vec::new(),
+            version: TrcVersion::V1_0,
+            columns: Vec::new(),
+            start_time: None,
+        }
+    }
+
+    /// Version of the file, files without `$FILEVERSION` header are version 1.0. Only valid once
+    /// the header has been read, i.e. after the first record.
+    pub fn version(&self) -> TrcVersion {
+        self.version
+    }
+
+    /// Start of the recording in days since 1899-12-30, as stored in the `$STARTTIME` header.
+    pub fn start_time(&self) -> Option<f64> {
+        self.start_time
+    }
+
+    fn header(&mut self, line: &str) -> Result<(), TrcError> {
+        let (key, value) = match line.trim_start_matches(';').trim().split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => return Ok(()),
+        };
+
+        match key {
+            "$FILEVERSION" => {
+                self.version = TrcVersion::try_from(value)
+                    .map_err(|_| TrcError::UnsupportedVersion(String::from(value)))?;
+                if self.columns.is_empty() && self.version >= TrcVersion::V2_0 {
+                    self.columns = parse_columns(self.version.default_columns());
+                }
+            }
+            "$STARTTIME" => self.start_time = value.parse().ok(),
+            "$COLUMNS" => self.columns = parse_columns(value),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn row(&self, line: &str) -> Option<Record> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match self.version {
+            TrcVersion::V2_0 | TrcVersion::V2_1 => parse_row_v2(&self.columns, &tokens),
+            version => parse_row_v1(version, &tokens),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for TrcReader<R> {
+    type Item = Result<Record, TrcError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buffer.clear();
+            match self.reader.read_until(b'\n', &mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(err) => return Some(Err(TrcError::Io(err))),
+            }
+            self.line += 1;
+
+            let line = String::from_utf8_lossy(&self.buffer).into_owned();
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with(';') {
+                match self.header(line) {
+                    Ok(_) => continue,
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+
+            return match self.row(line) {
+                Some(record) => Some(Ok(record)),
+                None => Some(Err(TrcError::Syntax { line: self.line })),
+            };
+        }
+    }
+}
+
+fn parse_columns(value: &str) -> Vec<char> {
+    value
+        .split(',')
+        .filter_map(|column| column.trim().chars().next())
+        .collect()
+}
+
+/// Time offset in milliseconds, with up to three decimals.
+fn parse_offset(token: &str) -> Option<Timestamp> {
+    let millis: f64 = token.parse().ok()?;
+    match millis >= 0.0 {
+        true => Some(Timestamp::from_micros((millis * 1000.0).round() as u64)),
+        false => None,
+    }
+}
+
+fn parse_id(token: &str) -> Option<(u32, MessageType)> {
+    let id = u32::from_str_radix(token, 16).ok()?;
+    match token.len() > 4 || id > 0x7FF {
+        true => Some((id, MessageType::Extended)),
+        false => Some((id, MessageType::Standard)),
+    }
+}
+
+fn parse_direction(token: &str) -> Option<Direction> {
+    match token {
+        "Rx" => Some(Direction::Rx),
+        "Tx" => Some(Direction::Tx),
+        _ => None,
+    }
+}
+
+fn parse_bytes(tokens: &[&str]) -> Option<Vec<u8>> {
+    tokens
+        .iter()
+        .map(|token| u8::from_str_radix(token, 16).ok())
+        .collect()
+}
+
+/// Data field of a version 1 row: data bytes, `RTR` or `ERROR` followed by the error bytes.
+fn parse_data_v1(id: &str, dlc: &str, data: &[&str]) -> Option<Frame> {
+    let dlc: u8 = dlc.parse().ok()?;
+    match data.first() {
+        Some(&"RTR") => {
+            let (id, msg_type) = parse_id(id)?;
+            CanFrame::new_remote(id, msg_type, dlc).ok().map(Frame::Can)
+        }
+        Some(&"ERROR") => parse_bytes(&data[1..]).map(|data| Frame::Error { class: 0, data }),
+        _ => {
+            let (id, msg_type) = parse_id(id)?;
+            let bytes = parse_bytes(data)?;
+            if bytes.len() != dlc as usize {
+                return None;
+            }
+            CanFrame::new(id, msg_type, &bytes).ok().map(Frame::Can)
+        }
+    }
+}
+
+fn parse_row_v1(version: TrcVersion, tokens: &[&str]) -> Option<Record> {
+    if !tokens.first()?.ends_with(')') {
+        return None;
+    }
+    let timestamp = parse_offset(tokens.get(1)?)?;
+
+    if version == TrcVersion::V1_0 {
+        let frame = parse_data_v1(tokens.get(2)?, tokens.get(3)?, &tokens[4..])?;
+        return Some(Record::new(timestamp, frame));
+    }
+
+    let (bus, rest) = match version {
+        TrcVersion::V1_1 => (1, &tokens[2..]),
+        _ => (tokens.get(2)?.parse().ok()?, tokens.get(3..)?),
+    };
+    let kind = *rest.first()?;
+
+    let (direction, frame) = match kind {
+        "Rx" | "Tx" => {
+            let (id, dlc, data) = match version {
+                TrcVersion::V1_3 => (*rest.get(1)?, *rest.get(3)?, rest.get(4..)?),
+                _ => (*rest.get(1)?, *rest.get(2)?, rest.get(3..)?),
+            };
+            (parse_direction(kind)?, parse_data_v1(id, dlc, data)?)
+        }
+        "Error" => {
+            let start = match version {
+                TrcVersion::V1_3 => 4,
+                _ => 3,
+            };
+            let data: Vec<&str> = rest
+                .get(start..)?
+                .iter()
+                .copied()
+                .filter(|token| *token != "ERROR")
+                .collect();
+            (
+                Direction::Rx,
+                Frame::Error {
+                    class: 0,
+                    data: parse_bytes(&data)?,
+                },
+            )
+        }
+        // Warnings and other bus events, e.g. `Warng FFFFFFFF 4 00 00 00 08 BUSHEAVY`.
+        _ => (Direction::Rx, Frame::Status(rest.join(" "))),
+    };
+
+    Some(Record {
+        timestamp,
+        bus,
+        direction,
+        frame,
+    })
+}
+
+/// Row types of version 2 files describing frames, all others are passed on as status.
+const FRAME_TYPES: &[&str] = &["DT", "RR", "FD", "FB", "FE", "BI", "ER"];
+
+fn parse_row_v2(columns: &[char], tokens: &[&str]) -> Option<Record> {
+    let mut timestamp = None;
+    let mut kind = "DT";
+    let mut bus = 1u8;
+    let mut id = None;
+    let mut direction = Direction::Rx;
+    let mut dlc = None;
+    let mut length = None;
+    let mut data: &[&str] = &[];
+
+    let mut position = 0;
+    for column in columns {
+        if *column == 'D' {
+            data = tokens.get(position..).unwrap_or(&[]);
+            break;
+        }
+        let token = match tokens.get(position) {
+            Some(token) => *token,
+            // Remote frames and status rows may omit trailing columns.
+            None => break,
+        };
+        position += 1;
+
+        match column {
+            'O' => timestamp = parse_offset(token),
+            'T' => {
+                kind = token;
+                if !FRAME_TYPES.contains(&kind) {
+                    break;
+                }
+            }
+            'B' => bus = token.parse().ok()?,
+            'I' => id = Some(token),
+            'd' => direction = parse_direction(token)?,
+            'L' => dlc = token.parse::<u8>().ok(),
+            'l' => length = token.parse::<usize>().ok(),
+            _ => {}
+        }
+    }
+
+    let timestamp = timestamp?;
+    let frame = match kind {
+        "DT" | "RR" | "FD" | "FB" | "FE" | "BI" => {
+            let (id, msg_type) = parse_id(id?)?;
+            let fd = !matches!(kind, "DT" | "RR");
+            let length = match (length, dlc) {
+                (Some(length), _) => length,
+                (None, Some(dlc)) if fd => dlc_to_len(dlc),
+                (None, Some(dlc)) => dlc.min(8) as usize,
+                (None, None) => data.len(),
+            };
+
+            if kind == "RR" {
+                let frame = CanFrame::new_remote(id, msg_type, dlc.unwrap_or(length as u8)).ok()?;
+                Frame::Can(frame)
+            } else {
+                let bytes = parse_bytes(data)?;
+                if bytes.len() != length {
+                    return None;
+                }
+                match fd {
+                    true => {
+                        let mut frame = CanFdFrame::new(id, msg_type, &bytes).ok()?;
+                        frame.set_bitrate_switch(matches!(kind, "FB" | "BI"));
+                        frame.set_error_state_indicator(matches!(kind, "FE" | "BI"));
+                        Frame::CanFd(frame)
+                    }
+                    false => Frame::Can(CanFrame::new(id, msg_type, &bytes).ok()?),
+                }
+            }
+        }
+        "ER" => Frame::Error {
+            class: 0,
+            data: parse_bytes(data)?,
+        },
+        // Status, error counter and event rows keep their own layout after the type.
+        _ => Frame::Status(tokens[position - 1..].join(" ")),
+    };
+
+    Some(Record {
+        timestamp,
+        bus,
+        direction,
+        frame,
+    })
+}
+
+/* TraceWriter */
+
+/// Writes records into .trc 2.1 files, following the file policies of the driver trace.
+///
+/// * [TraceFile::Single] writes into `path` and stops once the size limit is reached.
+/// * [TraceFile::Segmented] continues in `name_2.trc`, `name_3.trc`, ... once the limit is
+///   reached, starting with `name_1.trc`.
+/// * [TraceFile::Date] and [TraceFile::Time] add the start date `YYYYMMDD` or time `HHMMSS` to
+///   the file name.
+/// * [TraceFile::Overwrite] replaces an existing file, all other policies refuse to.
+#[derive(Debug)]
+pub struct TraceWriter {
+    base: PathBuf,
+    policy: TraceFile,
+    limit: u64,
+    started: DateTime,
+    start_time: f64,
+    file: Option<BufWriter<File>>,
+    path: PathBuf,
+    segment: u32,
+    written: u64,
+    number: u64,
+    origin: Option<u64>,
+    full: bool,
+}
+
+impl TraceWriter {
+    /// Size limit used for a size of zero, like the driver.
+    pub const DEFAULT_SIZE_MB: u8 = 10;
+    pub const MAX_SIZE_MB: u8 = 100;
+
+    /// Creates the first trace file, `size_mb` limits each file like [SetTraceSize](crate::trace::SetTraceSize).
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        policy: TraceFile,
+        size_mb: u8,
+    ) -> Result<TraceWriter, TrcError> {
+        let size_mb = match size_mb {
+            0 => Self::DEFAULT_SIZE_MB,
+            size_mb => size_mb.min(Self::MAX_SIZE_MB),
+        };
+        let now = unix_millis();
+
+        let mut writer = TraceWriter {
+            base: path.as_ref().to_path_buf(),
+            policy,
+            limit: size_mb as u64 * 1024 * 1024,
+            started: DateTime::from_unix_millis(now),
+            // OLE automation date, days since 1899-12-30.
+            start_time: now as f64 / 86_400_000.0 + 25_569.0,
+            file: None,
+            path: PathBuf::new(),
+            segment: 0,
+            written: 0,
+            number: 0,
+            origin: None,
+            full: false,
+        };
+        writer.open_next()?;
+        Ok(writer)
+    }
+
+    /// File currently written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns `true` once the size limit is reached and further records are dropped.
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    /// Sets the timestamp written as time offset zero. Defaults to the timestamp of the first
+    /// record, use [Timestamp::default] to keep timestamps which are already relative.
+    pub fn set_origin(&mut self, origin: Timestamp) {
+        self.origin = Some(origin.as_micros());
+    }
+
+    fn file_name(&self) -> PathBuf {
+        let stem = self
+            .base
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let extension = self
+            .base
+            .extension()
+            .map(|extension| extension.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("trc"));
+        let started = &self.started;
+
+        let name = match self.policy {
+            TraceFile::Single | TraceFile::Overwrite => return self.base.clone(),
+            TraceFile::Segmented => format!("{}_{}.{}", stem, self.segment, extension),
+            TraceFile::Date => format!(
+                "{}_{:04}{:02}{:02}.{}",
+                stem, started.year, started.month, started.day, extension
+            ),
+            TraceFile::Time => format!(
+                "{}_{:02}{:02}{:02}.{}",
+                stem, started.hour, started.minute, started.second, extension
+            ),
+        };
+        self.base.with_file_name(name)
+    }
+
+    fn open_next(&mut self) -> Result<(), TrcError> {
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        self.segment += 1;
+        self.path = self.file_name();
+
+        let file = match self.policy {
+            TraceFile::Overwrite => File::create(&self.path)?,
+            _ => OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&self.path)?,
+        };
+        self.file = Some(BufWriter::new(file));
+        self.written = 0;
+        self.number = 0;
+
+        let started = self.started;
+        let header = format!(
+            ";$FILEVERSION=2.1\n\
+             ;$STARTTIME={:.10}\n\
+             ;$COLUMNS=N,O,T,B,I,d,R,L,D\n\
+             ;\n\
+             ;   {}\n\
+             ;   Start time: {:02}.{:02}.{:04} {:02}:{:02}:{:02}.{:03}.0\n\
+             ;   Generated by pcan-basic\n\
+             ;-------------------------------------------------------------------------------\n\
+             ;   Message   Time    Type    ID     Rx/Tx\n\
+             ;   Number    Offset  |  Bus  [hex]  |  Reserved\n\
+             ;   |         [ms]    |  |    |      |  |  Data Length Code\n\
+             ;   |         |       |  |    |      |  |  |    Data [hex] ...\n\
+             ;   |         |       |  |    |      |  |  |    |\n\
+             ;---+-- ------+------ +- +- --+----- +- +- +--- +- -- -- -- -- -- -- --\n",
+            self.start_time,
+            self.path.display(),
+            started.day,
+            started.month,
+            started.year,
+            started.hour,
+            started.minute,
+            started.second,
+            started.millis,
+        );
+        self.write_line(&header)
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), TrcError> {
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(line.as_bytes())?;
+            self.written += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Makes room for `length` bytes, returns `false` if the line has to be dropped.
+    fn reserve(&mut self, length: usize) -> Result<bool, TrcError> {
+        if self.full {
+            return Ok(false);
+        }
+        if self.written + length as u64 <= self.limit {
+            return Ok(true);
+        }
+        match self.policy {
+            TraceFile::Segmented => {
+                self.open_next()?;
+                Ok(true)
+            }
+            _ => {
+                self.full = true;
+                if let Some(mut file) = self.file.take() {
+                    file.flush()?;
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Adds a comment line, which is ignored by readers.
+    pub fn write_comment(&mut self, text: &str) -> Result<(), TrcError> {
+        let mut lines = String::new();
+        for line in text.lines() {
+            lines.push_str(";   ");
+            lines.push_str(line);
+            lines.push('\n');
+        }
+        match self.reserve(lines.len())? {
+            true => self.write_line(&lines),
+            false => Ok(()),
+        }
+    }
+
+    pub fn write_record(&mut self, record: &Record) -> Result<(), TrcError> {
+        let micros = record.timestamp.as_micros();
+        let origin = *self.origin.get_or_insert(micros);
+        let offset = micros.saturating_sub(origin);
+
+        let segment = self.segment;
+        let mut line = format_row(self.number + 1, offset, record);
+        if !self.reserve(line.len())? {
+            return Ok(());
+        }
+        // Rows are numbered per file.
+        if self.segment != segment {
+            line = format_row(1, offset, record);
+        }
+        self.number += 1;
+        self.write_line(&line)
+    }
+
+    /// Writes all frames pending in the receive queue of `socket` and returns their number.
+    pub fn record<S: RecvCan>(&mut self, socket: &S) -> Result<usize, TrcError> {
+        let mut count = 0;
+        loop {
+            let (frame, timestamp) = match socket.recv() {
+                Ok(received) => received,
+                Err(PcanError::QrcvEmpty) => return Ok(count),
+                Err(err) => return Err(TrcError::Pcan(err)),
+            };
+            self.write_record(&Record::new(timestamp, Frame::Can(frame)))?;
+            count += 1;
+        }
+    }
+
+    pub fn flush(&mut self) -> Result<(), TrcError> {
+        if let Some(file) = self.file.as_mut() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TraceWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+fn format_id(can_id: u32, extended: bool) -> String {
+    match extended {
+        true => format!("{:08X}", can_id),
+        false => format!("{:04X}", can_id),
+    }
+}
+
+fn format_bytes(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_row(number: u64, offset: u64, record: &Record) -> String {
+    let prefix = format!("{:>7} {:>9}.{:03}", number, offset / 1000, offset % 1000);
+    let direction = match record.direction {
+        Direction::Rx => "Rx",
+        Direction::Tx => "Tx",
+    };
+
+    let (kind, id, dlc, data) = match &record.frame {
+        Frame::Can(frame) if frame.is_remote_frame() => (
+            "RR",
+            format_id(frame.can_id(), frame.is_extended_frame()),
+            frame.dlc(),
+            String::new(),
+        ),
+        Frame::Can(frame) => (
+            "DT",
+            format_id(frame.can_id(), frame.is_extended_frame()),
+            frame.dlc(),
+            format_bytes(frame.data()),
+        ),
+        Frame::CanFd(frame) => {
+            let kind = match (frame.is_bitrate_switch(), frame.is_error_state_indicator()) {
+                (false, false) => "FD",
+                (true, false) => "FB",
+                (false, true) => "FE",
+                (true, true) => "BI",
+            };
+            (
+                kind,
+                format_id(frame.can_id(), frame.is_extended_frame()),
+                frame.dlc(),
+                format_bytes(frame.data()),
+            )
+        }
+        Frame::Error { data, .. } => (
+            "ER",
+            String::from("-"),
+            data.len() as u8,
+            format_bytes(data),
+        ),
+        Frame::Status(text) => return format!("{} {}\n", prefix, text),
+    };
+
+    let mut line = format!(
+        "{} {} {:<2} {:>8} {} - {:<2}",
+        prefix, kind, record.bus, id, direction, dlc
+    );
+    if !data.is_empty() {
+        line.push_str("   ");
+        line.push_str(&data);
+    }
+    line.push('\n');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(fixture: &[u8]) -> (TrcVersion, Vec<Record>) {
+        let mut reader = TrcReader::new(fixture);
+        let records = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        (reader.version(), records)
+    }
+
+    fn can(id: u32, msg_type: MessageType, data: &[u8]) -> Frame {
+        Frame::Can(CanFrame::new(id, msg_type, data).unwrap())
+    }
+
+    #[test]
+    fn trc_reader_v1_0() {
+        let (version, records) = read(include_bytes!("../../tests/fixtures/trc/v1_0.trc"));
+
+        assert_eq!(version, TrcVersion::V1_0);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].timestamp.as_micros(), 1_841_000);
+        assert_eq!(records[0].frame, can(0x1, MessageType::Standard, &[0; 8]));
+        assert_eq!(
+            records[1].frame,
+            can(0x100, MessageType::Extended, &[0x11, 0x22])
+        );
+        match &records[2].frame {
+            Frame::Can(frame) => assert!(frame.is_remote_frame() && frame.dlc() == 4),
+            frame => panic!("{:?}", frame),
+        }
+        assert_eq!(records[3].frame, can(0x1, MessageType::Standard, &[]));
+    }
+
+    #[test]
+    fn trc_reader_v1_1() {
+        let (version, records) = read(include_bytes!("../../tests/fixtures/trc/v1_1.trc"));
+
+        assert_eq!(version, TrcVersion::V1_1);
+        assert_eq!(records[1].timestamp.as_micros(), 1_842_300);
+        assert_eq!(records[1].direction, Direction::Tx);
+        assert_eq!(
+            records[1].frame,
+            can(0x18EF_FF01, MessageType::Extended, &[1, 2, 3])
+        );
+        assert_eq!(
+            records[2].frame,
+            Frame::Status(String::from("Warng FFFFFFFF 4 00 00 00 08 BUSHEAVY"))
+        );
+        assert!(matches!(&records[3].frame, Frame::Can(frame) if frame.is_remote_frame()));
+    }
+
+    #[test]
+    fn trc_reader_v1_2() {
+        let (version, records) = read(include_bytes!("../../tests/fixtures/trc/v1_2.trc"));
+
+        assert_eq!(version, TrcVersion::V1_2);
+        assert_eq!(records[1].bus, 2);
+        assert_eq!(records[1].frame, can(0x2, MessageType::Standard, &[0xFF]));
+    }
+
+    #[test]
+    fn trc_reader_v1_3() {
+        let mut reader = TrcReader::new(&include_bytes!("../../tests/fixtures/trc/v1_3.trc")[..]);
+        let records = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(reader.version(), TrcVersion::V1_3);
+        assert_eq!(reader.start_time(), Some(37207.7270));
+        assert_eq!(
+            records[1].frame,
+            can(0x2, MessageType::Extended, &[0xAB, 0xCD])
+        );
+        assert_eq!(
+            records[2].frame,
+            Frame::Error {
+                class: 0,
+                data: vec![0x00, 0x19, 0x08, 0x08]
+            }
+        );
+    }
+
+    #[test]
+    fn trc_reader_v2_0() {
+        let (version, records) = read(include_bytes!("../../tests/fixtures/trc/v2_0.trc"));
+
+        assert_eq!(version, TrcVersion::V2_0);
+        assert_eq!(records[0].timestamp.as_micros(), 1_059_900);
+        assert_eq!(
+            records[0].frame,
+            can(0x300, MessageType::Standard, &[0, 0, 0, 0, 4, 0, 0])
+        );
+        match &records[1].frame {
+            Frame::CanFd(frame) => {
+                assert!(frame.is_extended_frame());
+                assert!(frame.is_bitrate_switch());
+                assert!(!frame.is_error_state_indicator());
+                assert_eq!(frame.data().len(), 12);
+            }
+            frame => panic!("{:?}", frame),
+        }
+        assert!(matches!(&records[2].frame, Frame::Can(frame) if frame.dlc() == 2));
+    }
+
+    #[test]
+    fn trc_reader_v2_1() {
+        let (version, records) = read(include_bytes!("../../tests/fixtures/trc/v2_1.trc"));
+
+        assert_eq!(version, TrcVersion::V2_1);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[1].bus, 2);
+        assert_eq!(records[1].direction, Direction::Tx);
+        match &records[1].frame {
+            Frame::CanFd(frame) => {
+                assert!(frame.is_bitrate_switch() && frame.is_error_state_indicator());
+                assert_eq!(frame.data()[11], 0x0C);
+            }
+            frame => panic!("{:?}", frame),
+        }
+        assert_eq!(
+            records[2].frame,
+            Frame::Error {
+                class: 0,
+                data: vec![4, 0, 0, 0, 0]
+            }
+        );
+        assert_eq!(
+            records[3].frame,
+            Frame::Status(String::from("ST 1 - Rx - 4 00 00 00 08"))
+        );
+    }
+
+    #[test]
+    fn trc_reader_error_001() {
+        let text = ";$FILEVERSION=2.1\n      1      1059.900 DT 1 0300 Rx - 2 00\n";
+        let mut reader = TrcReader::new(text.as_bytes());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(TrcError::Syntax { line: 2 }))
+        ));
+
+        let mut reader = TrcReader::new(";$FILEVERSION=3.0\n".as_bytes());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(TrcError::UnsupportedVersion(_)))
+        ));
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pcan_basic_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn trace_writer_001() {
+        let dir = temp_dir("trace_writer_001");
+        let path = dir.join("trace.trc");
+
+        let mut fd = CanFdFrame::new(0x18FF_0001, MessageType::Extended, &[0xAA; 12]).unwrap();
+        fd.set_bitrate_switch(true);
+        let records = vec![
+            Record::new(
+                Timestamp::from_micros(1_000_000),
+                can(0x300, MessageType::Standard, &[1, 2, 3]),
+            ),
+            Record {
+                timestamp: Timestamp::from_micros(1_000_250),
+                bus: 2,
+                direction: Direction::Tx,
+                frame: Frame::CanFd(fd),
+            },
+            Record::new(
+                Timestamp::from_micros(1_001_000),
+                Frame::Can(CanFrame::new_remote(0x100, MessageType::Standard, 2).unwrap()),
+            ),
+            Record::new(
+                Timestamp::from_micros(1_002_000),
+                Frame::Error {
+                    class: 0,
+                    data: vec![4, 0, 0, 0, 0],
+                },
+            ),
+        ];
+
+        let mut writer = TraceWriter::create(&path, TraceFile::Single, 0).unwrap();
+        writer.write_comment("first comment").unwrap();
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        drop(writer);
+
+        let mut reader = TrcReader::open(&path).unwrap();
+        let read = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(reader.version(), TrcVersion::V2_1);
+        assert!(reader.start_time().is_some());
+        assert_eq!(read.len(), 4);
+        assert_eq!(read[0].timestamp.as_micros(), 0);
+        assert_eq!(read[1].timestamp.as_micros(), 250);
+        for (read, written) in read.iter().zip(&records) {
+            assert_eq!(read.bus, written.bus);
+            assert_eq!(read.direction, written.direction);
+            assert_eq!(read.frame, written.frame);
+        }
+
+        // Existing files are only replaced with the overwrite policy.
+        assert!(TraceWriter::create(&path, TraceFile::Single, 0).is_err());
+        assert!(TraceWriter::create(&path, TraceFile::Overwrite, 0).is_ok());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn trace_writer_002() {
+        let dir = temp_dir("trace_writer_002");
+        let record = Record::new(
+            Timestamp::default(),
+            can(0x1, MessageType::Standard, &[0; 8]),
+        );
+
+        let mut writer = TraceWriter::create(dir.join("seg.trc"), TraceFile::Segmented, 1).unwrap();
+        writer.set_origin(Timestamp::default());
+        for _ in 0..20_000 {
+            writer.write_record(&record).unwrap();
+        }
+        assert_eq!(writer.path(), dir.join("seg_2.trc"));
+        drop(writer);
+
+        let first = TrcReader::open(dir.join("seg_1.trc")).unwrap().count();
+        let second = TrcReader::open(dir.join("seg_2.trc")).unwrap().count();
+        assert_eq!(first + second, 20_000);
+        assert!(std::fs::metadata(dir.join("seg_1.trc")).unwrap().len() <= 1024 * 1024);
+
+        // Records are dropped from the one that does not fit on.
+        let path = dir.join("single.trc");
+        let mut writer = TraceWriter::create(&path, TraceFile::Single, 1).unwrap();
+        let mut written = 0;
+        for _ in 0..20_000 {
+            writer.write_record(&record).unwrap();
+            if !writer.is_full() {
+                written += 1;
+            }
+        }
+        assert!(writer.is_full());
+        drop(writer);
+        assert!(written < 20_000);
+        assert_eq!(TrcReader::open(&path).unwrap().count(), written);
+        assert!(std::fs::metadata(&path).unwrap().len() <= 1024 * 1024);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,457 @@
+//! Forwarding of traffic between two channels with routing rules.
+//!
+//! Frames received on either side are matched against the rules in order. The first matching
+//! rule blocks the frame or forwards it after rate limiting, translating its identifier and
+//! rewriting its payload. Frames matching no rule are forwarded unchanged unless unmatched frames
+//! are blocked. Both sides are [ReplaySink]s, so any socket can be connected through a
+//! [CanSink](crate::replay::CanSink) or, keeping the CAN FD flags, a
+//! [CanFdSink](crate::replay::CanFdSink).
+
+use crate::error::PcanError;
+use crate::format::Frame;
+use crate::replay::ReplaySink;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Interval in which both sides are polled while no frames are pending.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Direction frames are forwarded in.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Route {
+    AToB,
+    BToA,
+}
+
+impl Route {
+    fn index(self) -> usize {
+        match self {
+            Route::AToB => 0,
+            Route::BToA => 1,
+        }
+    }
+}
+
+/* Rule */
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Action {
+    Forward,
+    Block,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct Rewrite {
+    index: usize,
+    mask: u8,
+    value: u8,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rule {
+    name: String,
+    id: u32,
+    mask: u32,
+    action: Action,
+    extended: Option<bool>,
+    route: Option<Route>,
+    translation: Option<u32>,
+    rewrites: Vec<Rewrite>,
+    min_interval: Option<Duration>,
+}
+
+impl Rule {
+    fn new(name: &str, id: u32, mask: u32, action: Action) -> Rule {
+        Rule {
+            name: String::from(name),
+            id,
+            mask,
+            action,
+            extended: None,
+            route: None,
+            translation: None,
+            rewrites: Vec::new(),
+            min_interval: None,
+        }
+    }
+
+    /// Forwards frames whose identifier equals `id` in the bits set in `mask`.
+    pub fn forward(name: &str, id: u32, mask: u32) -> Rule {
+        Rule::new(name, id, mask, Action::Forward)
+    }
+
+    /// Drops frames whose identifier equals `id` in the bits set in `mask`.
+    pub fn block(name: &str, id: u32, mask: u32) -> Rule {
+        Rule::new(name, id, mask, Action::Block)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Matches only standard or only extended frames.
+    pub fn with_extended(mut self, extended: bool) -> Rule {
+        self.extended = Some(extended);
+        self
+    }
+
+    /// Matches only frames forwarded in the direction `route`.
+    pub fn with_route(mut self, route: Route) -> Rule {
+        self.route = Some(route);
+        self
+    }
+
+    /// Replaces the identifier bits set in the mask by those of `id`, so a whole range can be
+    /// moved.
+    pub fn with_translation(mut self, id: u32) -> Rule {
+        self.translation = Some(id);
+        self
+    }
+
+    /// Replaces the bits set in `mask` of data byte `index` by those of `value`. Frames with
+    /// fewer data bytes are forwarded unchanged.
+    pub fn with_rewrite(mut self, index: usize, mask: u8, value: u8) -> Rule {
+        self.rewrites.push(Rewrite { index, mask, value });
+        self
+    }
+
+    /// Drops frames arriving within `interval` after the last forwarded frame of the rule in
+    /// the same direction.
+    pub fn with_min_interval(mut self, interval: Duration) -> Rule {
+        self.min_interval = Some(interval);
+        self
+    }
+
+    fn matches(&self, route: Route, id: u32, extended: bool) -> bool {
+        (id & self.mask) == (self.id & self.mask)
+            && self.extended.is_none_or(|value| value == extended)
+            && self.route.is_none_or(|value| value == route)
+    }
+
+    fn apply(&self, frame: &Frame) -> Frame {
+        let translate = |id: u32| match self.translation {
+            Some(to) => (id & !self.mask) | (to & self.mask),
+            None => id,
+        };
+        let mut frame = frame.clone();
+        let data = match &mut frame {
+            Frame::Can(frame) => {
+                frame.set_can_id(translate(frame.can_id()));
+                frame.mut_data()
+            }
+            Frame::CanFd(frame) => {
+                frame.set_can_id(translate(frame.can_id()));
+                frame.mut_data()
+            }
+            Frame::Error { .. } | Frame::Status(_) => &mut [],
+        };
+        for rewrite in &self.rewrites {
+            if let Some(byte) = data.get_mut(rewrite.index) {
+                *byte = (*byte & !rewrite.mask) | (rewrite.value & rewrite.mask);
+            }
+        }
+        frame
+    }
+}
+
+/// Formats the rule like `speed: 0C0/7FF forward a-b, translate 1C0, rewrite 0:F0:A0`.
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:03X}/{:03X}", self.name, self.id, self.mask)?;
+        match self.action {
+            Action::Forward => f.write_str(" forward")?,
+            Action::Block => f.write_str(" block")?,
+        }
+        match self.route {
+            Some(Route::AToB) => f.write_str(" a-b")?,
+            Some(Route::BToA) => f.write_str(" b-a")?,
+            None => {}
+        }
+        match self.extended {
+            Some(true) => f.write_str(", extended")?,
+            Some(false) => f.write_str(", standard")?,
+            None => {}
+        }
+        if let Some(id) = self.translation {
+            write!(f, ", translate {:03X}", id)?;
+        }
+        for rewrite in &self.rewrites {
+            let Rewrite { index, mask, value } = rewrite;
+            write!(f, ", rewrite {:X}:{:02X}:{:02X}", index, mask, value)?;
+        }
+        if let Some(interval) = self.min_interval {
+            write!(f, ", min interval {} ms", interval.as_millis())?;
+        }
+        Ok(())
+    }
+}
+
+/* Gateway */
+
+/// Counters of a rule.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct RuleCounter {
+    pub name: String,
+    /// Frames matched by the rule.
+    pub hits: u64,
+    pub forwarded: u64,
+    /// Frames blocked or dropped by the rate limit.
+    pub dropped: u64,
+}
+
+/// Counters of a gateway.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct GatewayReport {
+    /// Counters in the order of the rules.
+    pub rules: Vec<RuleCounter>,
+    /// Frames matching no rule.
+    pub unmatched: u64,
+    /// Frames not forwarded because sending failed, e.g. with a full transmit queue.
+    pub send_errors: u64,
+    /// Bus state and overrun errors reported while receiving.
+    pub receive_errors: u64,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Gateway {
+    rules: Vec<Rule>,
+    block_unmatched: bool,
+    /// Time of the last forwarded frame per rule and route.
+    last: Vec<[Option<Instant>; 2]>,
+    report: GatewayReport,
+}
+
+impl Gateway {
+    /// Forwards all frames unchanged until rules are added.
+    pub fn new() -> Gateway {
+        Gateway::default()
+    }
+
+    /// Appends a rule, the first matching rule handles a frame.
+    pub fn with_rule(mut self, rule: Rule) -> Gateway {
+        self.report.rules.push(RuleCounter {
+            name: rule.name.clone(),
+            ..RuleCounter::default()
+        });
+        self.last.push([None; 2]);
+        self.rules.push(rule);
+        self
+    }
+
+    /// Drops frames matching no rule instead of forwarding them.
+    pub fn with_block_unmatched(mut self) -> Gateway {
+        self.block_unmatched = true;
+        self
+    }
+
+    pub fn report(&self) -> &GatewayReport {
+        &self.report
+    }
+
+    /// Frame to forward for `frame` received at `now` on the source side of `route`, `None` if
+    /// it is dropped. Error frames and status records are never forwarded.
+    pub fn process(&mut self, route: Route, frame: &Frame, now: Instant) -> Option<Frame> {
+        let (id, extended) = match frame {
+            Frame::Can(frame) => (frame.can_id(), frame.is_extended_frame()),
+            Frame::CanFd(frame) => (frame.can_id(), frame.is_extended_frame()),
+            Frame::Error { .. } | Frame::Status(_) => return None,
+        };
+
+        let index = match self
+            .rules
+            .iter()
+            .position(|rule| rule.matches(route, id, extended))
+        {
+            Some(index) => index,
+            None => {
+                self.report.unmatched += 1;
+                return match self.block_unmatched {
+                    true => None,
+                    false => Some(frame.clone()),
+                };
+            }
+        };
+
+        let rule = &self.rules[index];
+        let counter = &mut self.report.rules[index];
+        let last = &mut self.last[index][route.index()];
+        counter.hits += 1;
+        let limited = match (rule.min_interval, *last) {
+            (Some(interval), Some(last)) => now.saturating_duration_since(last) < interval,
+            _ => false,
+        };
+        if rule.action == Action::Block || limited {
+            counter.dropped += 1;
+            return None;
+        }
+
+        counter.forwarded += 1;
+        *last = Some(now);
+        Some(rule.apply(frame))
+    }
+
+    /// Forwards the frames pending on both sides, returns whether any frame was pending.
+    pub fn poll(
+        &mut self,
+        a: &mut dyn ReplaySink,
+        b: &mut dyn ReplaySink,
+    ) -> Result<bool, PcanError> {
+        let pending = self.forward(Route::AToB, a, b)?;
+        Ok(self.forward(Route::BToA, b, a)? || pending)
+    }
+
+    /// Forwards frames between `a` and `b` until `running` is cleared.
+    pub fn run(
+        &mut self,
+        a: &mut dyn ReplaySink,
+        b: &mut dyn ReplaySink,
+        running: &AtomicBool,
+    ) -> Result<(), PcanError> {
+        while running.load(Ordering::Relaxed) {
+            if !self.poll(a, b)? {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+        Ok(())
+    }
+
+    fn forward(
+        &mut self,
+        route: Route,
+        from: &mut dyn ReplaySink,
+        to: &mut dyn ReplaySink,
+    ) -> Result<bool, PcanError> {
+        let mut pending = false;
+        loop {
+            let frame = match from.recv_frame() {
+                Ok(Some(frame)) => frame,
+                Ok(None) => return Ok(pending),
+                Err(
+                    PcanError::BusLight
+                    | PcanError::BusHeavy
+                    | PcanError::BusPassive
+                    | PcanError::BusOff
+                    | PcanError::Overrun
+                    | PcanError::QOverrun,
+                ) => {
+                    // Reported once per state change, the next frames are still received.
+                    self.report.receive_errors += 1;
+                    return Ok(pending);
+                }
+                Err(err) => return Err(err),
+            };
+            pending = true;
+            if let Some(frame) = self.process(route, &frame, Instant::now()) {
+                if to.send_frame(&frame).is_err() {
+                    self.report.send_errors += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::MemorySink;
+    use crate::socket::{CanFdFrame, CanFrame, MessageType};
+    use std::collections::VecDeque;
+
+    fn frame(id: u32, data: &[u8]) -> Frame {
+        Frame::Can(CanFrame::new(id, MessageType::Standard, data).unwrap())
+    }
+
+    #[test]
+    fn gateway_001() {
+        let mut gateway = Gateway::new()
+            .with_rule(Rule::block("diagnostics", 0x7E0, 0x7F0))
+            .with_rule(
+                Rule::forward("range", 0x100, 0x700)
+                    .with_translation(0x300)
+                    .with_rewrite(0, 0xF0, 0xA0)
+                    .with_rewrite(8, 0xFF, 0xFF),
+            )
+            .with_rule(Rule::forward("b only", 0x200, 0x7FF).with_route(Route::BToA))
+            .with_block_unmatched();
+        let now = Instant::now();
+
+        assert_eq!(gateway.process(Route::AToB, &frame(0x7E8, &[1]), now), None);
+        assert_eq!(
+            gateway.process(Route::AToB, &frame(0x123, &[0x12, 0x34]), now),
+            Some(frame(0x323, &[0xA2, 0x34]))
+        );
+        assert_eq!(gateway.process(Route::AToB, &frame(0x200, &[]), now), None);
+        assert_eq!(
+            gateway.process(Route::BToA, &frame(0x200, &[]), now),
+            Some(frame(0x200, &[]))
+        );
+        assert_eq!(
+            gateway.process(
+                Route::AToB,
+                &Frame::Error {
+                    class: 0,
+                    data: vec![0]
+                },
+                now
+            ),
+            None
+        );
+
+        let report = gateway.report();
+        assert_eq!(report.rules[0].dropped, 1);
+        assert_eq!(report.rules[1].forwarded, 1);
+        assert_eq!(report.rules[2].hits, 1);
+        assert_eq!(report.unmatched, 1);
+        assert_eq!(
+            gateway.rules[1].to_string(),
+            "range: 100/700 forward, translate 300, rewrite 0:F0:A0, rewrite 8:FF:FF"
+        );
+    }
+
+    #[test]
+    fn gateway_002() {
+        let mut gateway = Gateway::new().with_rule(
+            Rule::forward("limited", 0x100, 0x7FF).with_min_interval(Duration::from_millis(100)),
+        );
+        let now = Instant::now();
+        let frame = frame(0x100, &[1]);
+
+        assert!(gateway.process(Route::AToB, &frame, now).is_some());
+        let soon = now + Duration::from_millis(50);
+        assert!(gateway.process(Route::AToB, &frame, soon).is_none());
+        assert!(gateway.process(Route::BToA, &frame, soon).is_some());
+        let later = now + Duration::from_millis(100);
+        assert!(gateway.process(Route::AToB, &frame, later).is_some());
+        assert_eq!(gateway.report().rules[0].dropped, 1);
+    }
+
+    #[test]
+    fn gateway_003() {
+        let mut fd = CanFdFrame::new(0x100, MessageType::Extended, &[0; 12]).unwrap();
+        fd.set_bitrate_switch(true);
+        let mut a = MemorySink::new();
+        a.pending = VecDeque::from([frame(0x100, &[1]), Frame::CanFd(fd)]);
+        let mut b = MemorySink::new();
+        b.pending = VecDeque::from([frame(0x200, &[2])]);
+
+        let mut gateway =
+            Gateway::new().with_rule(Rule::forward("moved", 0x100, 0xFFF).with_translation(0x101));
+        assert!(gateway.poll(&mut a, &mut b).unwrap());
+        assert!(!gateway.poll(&mut a, &mut b).unwrap());
+
+        assert_eq!(a.sent, vec![frame(0x200, &[2])]);
+        assert_eq!(b.sent.len(), 2);
+        assert_eq!(b.sent[0], frame(0x101, &[1]));
+        match &b.sent[1] {
+            Frame::CanFd(frame) => {
+                assert_eq!(frame.can_id(), 0x101);
+                assert!(frame.is_fd_frame());
+                assert!(frame.is_bitrate_switch());
+                assert_eq!(frame.data().len(), 12);
+            }
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+        assert_eq!(gateway.report().rules[0].forwarded, 2);
+    }
+}
@@ -0,0 +1,367 @@
+//! Binary GVRET protocol of SavvyCAN, as spoken by the GVRET and ESP32RET firmwares.
+//!
+//! Every command starts with `0xF1` followed by the command byte and its arguments in
+//! little-endian byte order. A [Decoder] splits the bytes sent by SavvyCAN into [Request]s, the
+//! `encode_*` functions build the answers. Frames carry a 32-bit timestamp in microseconds, the
+//! CAN-ID with bit 31 set for extended frames and the bus number in the upper nibble of the
+//! length. Remote and CAN FD frames are not part of the protocol.
+
+use crate::socket::{CanFrame, MessageType, EXTENDED_MASK};
+
+const START: u8 = 0xF1;
+
+const BUILD_CAN_FRAME: u8 = 0x00;
+const TIME_SYNC: u8 = 0x01;
+const GET_DIG_INPUTS: u8 = 0x02;
+const GET_ANALOG_INPUTS: u8 = 0x03;
+const SET_DIG_OUTPUTS: u8 = 0x04;
+const SETUP_CANBUS: u8 = 0x05;
+const GET_CANBUS_PARAMS: u8 = 0x06;
+const GET_DEVICE_INFO: u8 = 0x07;
+const SET_SINGLEWIRE_MODE: u8 = 0x08;
+const KEEP_ALIVE: u8 = 0x09;
+const SET_SYSTEM_TYPE: u8 = 0x0A;
+const ECHO_CAN_FRAME: u8 = 0x0B;
+const GET_NUM_BUSES: u8 = 0x0C;
+const GET_EXT_BUSES: u8 = 0x0D;
+const SET_EXT_BUSES: u8 = 0x0E;
+
+const EXTENDED_FLAG: u32 = 0x8000_0000;
+
+/// Build number reported as device information.
+const BUILD: u16 = 618;
+const EEPROM_VERSION: u8 = 0x20;
+
+/* Bus configuration */
+
+/// Configuration of a bus as set up and reported by SavvyCAN.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct BusConfig {
+    pub enabled: bool,
+    pub listen_only: bool,
+    /// Bitrate in bits per second.
+    pub speed: u32,
+}
+
+impl BusConfig {
+    /// Configuration of a `SETUP_CANBUS` value, where zero disables the bus and bit 31 signals
+    /// that bit 30 enables the bus and bit 29 selects listen-only mode.
+    fn from_setup(value: u32) -> BusConfig {
+        if value == 0 {
+            return BusConfig {
+                enabled: false,
+                listen_only: false,
+                speed: 0,
+            };
+        }
+        let (enabled, listen_only) = match value & 0x8000_0000 != 0 {
+            true => (value & 0x4000_0000 != 0, value & 0x2000_0000 != 0),
+            false => (true, false),
+        };
+        BusConfig {
+            enabled,
+            listen_only,
+            speed: (value & 0x000F_FFFF).min(1_000_000),
+        }
+    }
+}
+
+/* Request */
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Request {
+    /// Transmits `frame` on the bus with the number `bus`.
+    Frame {
+        bus: u8,
+        frame: CanFrame,
+    },
+    /// Returns `frame` as if it was received on `bus`.
+    EchoFrame {
+        bus: u8,
+        frame: CanFrame,
+    },
+    TimeSync,
+    DigitalInputs,
+    /// Configurations of the first two buses, see [encode_bus_params].
+    SetupBuses([BusConfig; 2]),
+    BusParams,
+    DeviceInfo,
+    KeepAlive,
+    NumBuses,
+    ExtBuses,
+    /// Commands without answer for hardware a PCAN channel does not have, e.g. digital outputs.
+    Ignored(u8),
+}
+
+/// Splits the bytes received from SavvyCAN into requests, skipping bytes outside of commands,
+/// e.g. the `0xE7` switching into binary mode.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Size of the command at the start of the buffer, `None` if more bytes are needed to tell.
+    fn command_size(&self) -> Option<usize> {
+        let arguments = match *self.buffer.get(1)? {
+            BUILD_CAN_FRAME | ECHO_CAN_FRAME => {
+                // ID, bus, length, data and checksum.
+                let length = (*self.buffer.get(7)? & 0x0F) as usize;
+                6 + length.min(8) + 1
+            }
+            SET_DIG_OUTPUTS | SET_SINGLEWIRE_MODE | SET_SYSTEM_TYPE => 1,
+            SETUP_CANBUS => 8,
+            SET_EXT_BUSES => 12,
+            _ => 0,
+        };
+        Some(2 + arguments)
+    }
+
+    /// Returns the next complete request, `None` if more bytes are needed.
+    pub fn next_request(&mut self) -> Option<Request> {
+        loop {
+            let start = self.buffer.iter().position(|byte| *byte == START);
+            self.buffer.drain(..start.unwrap_or(self.buffer.len()));
+            let size = self.command_size()?;
+            if self.buffer.len() < size {
+                return None;
+            }
+            let command = self.buffer.drain(..size).collect::<Vec<_>>();
+            if let Some(request) = parse_request(&command) {
+                return Some(request);
+            }
+        }
+    }
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+/// Request of a complete command, `None` for unknown commands and invalid frames.
+fn parse_request(command: &[u8]) -> Option<Request> {
+    let request = match command[1] {
+        BUILD_CAN_FRAME | ECHO_CAN_FRAME => {
+            let id = read_u32(&command[2..6]);
+            let (can_id, msg_type) = match id & EXTENDED_FLAG != 0 {
+                true => (id & EXTENDED_MASK, MessageType::Extended),
+                false => (id, MessageType::Standard),
+            };
+            let bus = command[6] & 0x03;
+            let length = (command[7] & 0x0F).min(8) as usize;
+            let frame = CanFrame::new(can_id, msg_type, &command[8..8 + length]).ok()?;
+            if frame.can_id() != can_id {
+                return None;
+            }
+            match command[1] {
+                BUILD_CAN_FRAME => Request::Frame { bus, frame },
+                _ => Request::EchoFrame { bus, frame },
+            }
+        }
+        TIME_SYNC => Request::TimeSync,
+        GET_DIG_INPUTS => Request::DigitalInputs,
+        SETUP_CANBUS => Request::SetupBuses([
+            BusConfig::from_setup(read_u32(&command[2..6])),
+            BusConfig::from_setup(read_u32(&command[6..10])),
+        ]),
+        GET_CANBUS_PARAMS => Request::BusParams,
+        GET_DEVICE_INFO => Request::DeviceInfo,
+        KEEP_ALIVE => Request::KeepAlive,
+        GET_NUM_BUSES => Request::NumBuses,
+        GET_EXT_BUSES => Request::ExtBuses,
+        GET_ANALOG_INPUTS | SET_DIG_OUTPUTS | SET_SINGLEWIRE_MODE | SET_SYSTEM_TYPE
+        | SET_EXT_BUSES => Request::Ignored(command[1]),
+        _ => return None,
+    };
+    Some(request)
+}
+
+/* Answers */
+
+/// Frame received on the bus with the number `bus` at `micros`.
+pub fn encode_frame(bus: u8, frame: &CanFrame, micros: u32) -> Vec<u8> {
+    let mut id = frame.can_id();
+    if frame.is_extended_frame() {
+        id |= EXTENDED_FLAG;
+    }
+    let mut data = vec![START, BUILD_CAN_FRAME];
+    data.extend_from_slice(&micros.to_le_bytes());
+    data.extend_from_slice(&id.to_le_bytes());
+    data.push(frame.dlc() | (bus << 4));
+    data.extend_from_slice(frame.data());
+    data.push(0);
+    data
+}
+
+pub fn encode_time_sync(micros: u32) -> Vec<u8> {
+    let mut data = vec![START, TIME_SYNC];
+    data.extend_from_slice(&micros.to_le_bytes());
+    data
+}
+
+pub fn encode_digital_inputs() -> Vec<u8> {
+    vec![START, GET_DIG_INPUTS, 0, 0]
+}
+
+/// Configurations of the first two buses, missing buses are reported as disabled.
+pub fn encode_bus_params(buses: &[BusConfig]) -> Vec<u8> {
+    let mut data = vec![START, GET_CANBUS_PARAMS];
+    for index in 0..2 {
+        let (flags, speed) = match buses.get(index) {
+            Some(bus) => (bus.enabled as u8 | (bus.listen_only as u8) << 4, bus.speed),
+            None => (0, 0),
+        };
+        data.push(flags);
+        data.extend_from_slice(&speed.to_le_bytes());
+    }
+    data
+}
+
+pub fn encode_device_info() -> Vec<u8> {
+    let build = BUILD.to_le_bytes();
+    vec![
+        START,
+        GET_DEVICE_INFO,
+        build[0],
+        build[1],
+        EEPROM_VERSION,
+        0,
+        0,
+        0,
+    ]
+}
+
+pub fn encode_keep_alive() -> Vec<u8> {
+    vec![START, KEEP_ALIVE, 0xDE, 0xAD]
+}
+
+pub fn encode_num_buses(count: u8) -> Vec<u8> {
+    vec![START, GET_NUM_BUSES, count]
+}
+
+/// Single-wire and LIN buses, which are all disabled.
+pub fn encode_ext_buses() -> Vec<u8> {
+    let mut data = vec![START, GET_EXT_BUSES];
+    data.extend_from_slice(&[0; 15]);
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoder_001() {
+        let mut decoder = Decoder::new();
+        decoder.push(&[0xE7, 0xE7, START, GET_NUM_BUSES, START, BUILD_CAN_FRAME]);
+        assert_eq!(decoder.next_request(), Some(Request::NumBuses));
+        assert_eq!(decoder.next_request(), None);
+
+        decoder.push(&[0x23, 0x01, 0x00, 0x00, 0x01, 0x02, 0xDE]);
+        assert_eq!(decoder.next_request(), None);
+        decoder.push(&[0xAD, 0x00, START, KEEP_ALIVE]);
+        let frame = CanFrame::new(0x123, MessageType::Standard, &[0xDE, 0xAD]).unwrap();
+        assert_eq!(
+            decoder.next_request(),
+            Some(Request::Frame { bus: 1, frame })
+        );
+        assert_eq!(decoder.next_request(), Some(Request::KeepAlive));
+
+        // Lengths above 8 are clamped as in the command size.
+        decoder.push(&[START, BUILD_CAN_FRAME, 0x23, 0x01, 0x00, 0x00, 0x00, 0x0F]);
+        decoder.push(&[1, 2, 3, 4, 5, 6, 7, 8, 0x00, START, KEEP_ALIVE]);
+        let frame = CanFrame::new(0x123, MessageType::Standard, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(
+            decoder.next_request(),
+            Some(Request::Frame {
+                bus: 0,
+                frame: frame.unwrap()
+            })
+        );
+        assert_eq!(decoder.next_request(), Some(Request::KeepAlive));
+    }
+
+    #[test]
+    fn decoder_002() {
+        let mut decoder = Decoder::new();
+        let mut command = vec![START, SETUP_CANBUS];
+        command.extend_from_slice(&(0xE000_0000u32 | 250_000).to_le_bytes());
+        command.extend_from_slice(&500_000u32.to_le_bytes());
+        decoder.push(&command);
+        assert_eq!(
+            decoder.next_request(),
+            Some(Request::SetupBuses([
+                BusConfig {
+                    enabled: true,
+                    listen_only: true,
+                    speed: 250_000
+                },
+                BusConfig {
+                    enabled: true,
+                    listen_only: false,
+                    speed: 500_000
+                },
+            ]))
+        );
+
+        // Unknown commands are skipped.
+        decoder.push(&[START, 0x7F, START, GET_EXT_BUSES]);
+        assert_eq!(decoder.next_request(), Some(Request::ExtBuses));
+    }
+
+    #[test]
+    fn encode_frame_001() {
+        let frame = CanFrame::new(0x18DA_F110, MessageType::Extended, &[1, 2, 3]).unwrap();
+        assert_eq!(
+            encode_frame(1, &frame, 0x0102_0304),
+            [
+                START,
+                BUILD_CAN_FRAME,
+                0x04,
+                0x03,
+                0x02,
+                0x01,
+                0x10,
+                0xF1,
+                0xDA,
+                0x98,
+                0x13,
+                1,
+                2,
+                3,
+                0
+            ]
+        );
+
+        let buses = [BusConfig {
+            enabled: true,
+            listen_only: true,
+            speed: 500_000,
+        }];
+        assert_eq!(
+            encode_bus_params(&buses),
+            [
+                START,
+                GET_CANBUS_PARAMS,
+                0x11,
+                0x20,
+                0xA1,
+                0x07,
+                0x00,
+                0,
+                0,
+                0,
+                0,
+                0
+            ]
+        );
+    }
+}
@@ -373,6 +373,27 @@ impl ChannelInformation {
         let s = string.trim_matches(char::from(0));
         String::from(s)
     }
+
+    /// Handle of the channel, convertible into a bus.
+    pub fn channel_handle(&self) -> u16 {
+        self.channel_information.channel_handle
+    }
+
+    pub fn device_type(&self) -> u8 {
+        self.channel_information.device_type
+    }
+
+    pub fn controller_number(&self) -> u8 {
+        self.channel_information.controller_number
+    }
+
+    pub fn device_id(&self) -> u32 {
+        self.channel_information.device_id
+    }
+
+    pub fn channel_condition(&self) -> Option<ChannelConditionStatus> {
+        ChannelConditionStatus::try_from(self.channel_information.channel_condition).ok()
+    }
 }
 
 pub fn attached_channels() -> Result<Vec<ChannelInformation>, PcanError> {
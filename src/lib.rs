@@ -3,15 +3,30 @@
 
 #[warn(dead_code)]
 pub mod bus;
+pub mod canopen;
+pub mod capture;
 mod channel;
+pub mod dbc;
 pub mod df;
 pub mod error;
+pub mod format;
+pub mod gateway;
+pub mod gvret;
 pub mod hw;
 pub mod info;
 pub mod io;
 pub mod log;
+pub mod nmea2000;
+pub mod obd;
+pub mod replay;
+pub mod slcan;
 pub mod socket;
+#[cfg(all(feature = "socketcan", target_os = "linux"))]
+pub mod socketcan;
 pub mod special;
+pub mod stats;
 pub mod trace;
+pub mod tunnel;
+pub mod xcp;
 
 use pcan_basic_sys as pcan;
@@ -0,0 +1,309 @@
+//! Bridge between the log file of the driver and [tracing], enabled by the `tracing` feature.
+//!
+//! [DriverLogBridge] follows the log file and emits every new line as event with the target
+//! [TARGET]. Function calls carry the `function` field, parameters and exits the `channel` of
+//! the call and exits the returned `code`. Exits with an error are emitted as warnings.
+//!
+//! [DriverLogLayer] writes events of the application into the log file of the driver with
+//! [log_text], so markers of the application appear next to the driver calls.
+
+use crate::error::PcanError;
+use crate::log::file::{LogEntry, LogMessage, LogTail};
+use crate::log::{log_location, log_text};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Target of the events emitted for lines of the driver log.
+pub const TARGET: &str = "pcan_basic::driver";
+
+/// Emits an event with a level only known at runtime.
+macro_rules! event {
+    ($level:expr, $($arguments:tt)+) => {
+        match $level {
+            Level::ERROR => tracing::error!(target: TARGET, $($arguments)+),
+            Level::WARN => tracing::warn!(target: TARGET, $($arguments)+),
+            Level::INFO => tracing::info!(target: TARGET, $($arguments)+),
+            Level::DEBUG => tracing::debug!(target: TARGET, $($arguments)+),
+            _ => tracing::trace!(target: TARGET, $($arguments)+),
+        }
+    };
+}
+
+/* DriverLogBridge */
+
+#[derive(Debug)]
+pub struct DriverLogBridge {
+    tail: LogTail,
+    /// Channel parameter of the latest call of every function.
+    channels: HashMap<String, String>,
+}
+
+impl DriverLogBridge {
+    /// Follows the log file in `location`. Lines written before are not emitted.
+    pub fn new<P: AsRef<Path>>(location: P) -> DriverLogBridge {
+        let mut tail = LogTail::in_location(location);
+        let _ = tail.poll();
+        DriverLogBridge {
+            tail,
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Follows the log file in the log location currently configured in the driver.
+    pub fn from_log_location() -> Result<DriverLogBridge, PcanError> {
+        Ok(DriverLogBridge::new(log_location()?))
+    }
+
+    /// Emits the lines written since the last call and returns their number.
+    pub fn poll(&mut self) -> Result<usize, std::io::Error> {
+        let entries = self.tail.poll()?;
+        let count = entries.len();
+        for entry in entries {
+            self.emit(entry);
+        }
+        Ok(count)
+    }
+
+    fn emit(&mut self, entry: LogEntry) {
+        match entry.message {
+            LogMessage::Entry { function } => {
+                event!(Level::TRACE, function = %function, "entry")
+            }
+            LogMessage::Parameters {
+                function,
+                parameters,
+            } => {
+                let channel = parameters
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case("channel"))
+                    .map(|(_, value)| value.clone())
+                    .unwrap_or_default();
+                let mut text = String::new();
+                for (name, value) in &parameters {
+                    let separator = if text.is_empty() { "" } else { ", " };
+                    let _ = write!(text, "{}{}: {}", separator, name, value);
+                }
+                event!(Level::DEBUG, function = %function, channel = %channel, "{}", text);
+                self.channels.insert(function, channel);
+            }
+            LogMessage::Exit { function, result } => {
+                let channel = self.channels.remove(&function).unwrap_or_default();
+                match result {
+                    Ok(()) => {
+                        event!(Level::TRACE, function = %function, channel = %channel, code = 0u32, "exit")
+                    }
+                    Err(err) => {
+                        let message = format!("exit with {:?}", err);
+                        let code = u32::from(err);
+                        event!(Level::WARN, function = %function, channel = %channel, code, "{}", message)
+                    }
+                }
+            }
+            LogMessage::Text(text) => event!(Level::INFO, "{}", text),
+        }
+    }
+
+    /// Polls the log file every `interval` on a separate thread until the handle is dropped.
+    pub fn spawn(mut self, interval: Duration) -> BridgeHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = std::thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                if let Err(err) = self.poll() {
+                    event!(Level::ERROR, "reading the driver log failed: {}", err);
+                }
+                std::thread::sleep(interval);
+            }
+        });
+
+        BridgeHandle {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Stops the polling thread of a bridge when dropped.
+#[derive(Debug)]
+pub struct BridgeHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for BridgeHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/* DriverLogLayer */
+
+/// Layer writing events up to a maximum level into the log file of the driver. Events emitted
+/// by [DriverLogBridge] are skipped.
+#[derive(Debug)]
+pub struct DriverLogLayer {
+    level: Level,
+    write: fn(&str) -> Result<(), PcanError>,
+}
+
+impl DriverLogLayer {
+    /// Writes events of level info and above.
+    pub fn new() -> DriverLogLayer {
+        DriverLogLayer::with_level(Level::INFO)
+    }
+
+    /// Writes events of `level` and above.
+    pub fn with_level(level: Level) -> DriverLogLayer {
+        DriverLogLayer {
+            level,
+            write: |text| log_text(text),
+        }
+    }
+}
+
+impl Default for DriverLogLayer {
+    fn default() -> DriverLogLayer {
+        DriverLogLayer::new()
+    }
+}
+
+impl<S: Subscriber> Layer<S> for DriverLogLayer {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        let metadata = event.metadata();
+        // Levels compare by verbosity, trace being the greatest.
+        if *metadata.level() > self.level || metadata.target().starts_with(TARGET) {
+            return;
+        }
+
+        let mut visitor = TextVisitor::default();
+        event.record(&mut visitor);
+        let text = format!(
+            "[{}] {}: {}",
+            metadata.level(),
+            metadata.target(),
+            visitor.text
+        );
+        let _ = (self.write)(&text);
+    }
+}
+
+/// Formats the message followed by the other fields as `name=value`.
+#[derive(Debug, Default)]
+struct TextVisitor {
+    text: String,
+    fields: String,
+}
+
+impl Visit for TextVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.text, "{:?}{}", value, self.fields);
+                self.fields.clear();
+            }
+            name if self.text.is_empty() => {
+                let _ = write!(self.fields, " {}={:?}", name, value);
+            }
+            name => {
+                let _ = write!(self.text, " {}={:?}", name, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::registry::Registry;
+
+    /// Collects the level and text of all events.
+    #[derive(Default)]
+    struct Collect {
+        events: Arc<Mutex<Vec<(Level, String)>>>,
+    }
+
+    impl<S: Subscriber> Layer<S> for Collect {
+        fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+            let mut visitor = TextVisitor::default();
+            event.record(&mut visitor);
+            let level = *event.metadata().level();
+            self.events.lock().unwrap().push((level, visitor.text));
+        }
+    }
+
+    static WRITTEN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[test]
+    fn driver_log_layer_001() {
+        let layer = DriverLogLayer {
+            level: Level::INFO,
+            write: |text| {
+                WRITTEN.lock().unwrap().push(String::from(text));
+                Ok(())
+            },
+        };
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "app", step = 3, "calibration done");
+            tracing::debug!(target: "app", "not written");
+            tracing::warn!(target: TARGET, "not written");
+        });
+
+        assert_eq!(
+            *WRITTEN.lock().unwrap(),
+            vec![String::from("[INFO] app: calibration done step=3")]
+        );
+    }
+
+    #[test]
+    fn driver_log_bridge_001() {
+        let directory = std::env::temp_dir().join(format!("pcan_bridge_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(crate::log::file::LOG_FILE_NAME);
+        std::fs::write(&path, "19.10.2023 10:15:01.000 - before the bridge\n").unwrap();
+
+        let collect = Collect::default();
+        let events = collect.events.clone();
+        let mut bridge = DriverLogBridge::new(&directory);
+        let mut text = std::fs::read_to_string(&path).unwrap();
+        text.push_str(
+            "19.10.2023 10:15:02.123 - CAN_Read() Entry\n\
+             19.10.2023 10:15:02.123 - CAN_Read() Channel: 0x51\n\
+             19.10.2023 10:15:02.124 - CAN_Read() Exit with error 0x20\n",
+        );
+        std::fs::write(&path, text).unwrap();
+
+        let subscriber = Registry::default().with(collect);
+        let count = tracing::subscriber::with_default(subscriber, || bridge.poll().unwrap());
+        assert_eq!(count, 3);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                (Level::TRACE, String::from("entry function=CAN_Read")),
+                (
+                    Level::DEBUG,
+                    String::from("Channel: 0x51 function=CAN_Read channel=0x51")
+                ),
+                (
+                    Level::WARN,
+                    String::from("exit with QrcvEmpty function=CAN_Read channel=0x51 code=32")
+                ),
+            ]
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,400 @@
+//! Parsing of the log file written by the PCAN-Basic driver when logging is enabled.
+//!
+//! Every line starts with the local date and time, followed by `-` and a message. Messages of
+//! API calls name the function and state whether the function was entered, which parameters it
+//! got or with which status code it returned, e.g.
+//!
+//! ```text
+//! 19.10.2023 10:15:02.123 - CAN_Initialize() Entry
+//! 19.10.2023 10:15:02.123 - CAN_Initialize() Channel: 0x51, Btr0Btr1: 0x1C, HwType: 0x0
+//! 19.10.2023 10:15:02.150 - CAN_Initialize() Exit with error 0x0
+//! ```
+//!
+//! Other lines, e.g. texts written by [log_text](crate::log::log_text), are kept as text.
+
+use crate::error::{PcanError, PcanOkError};
+use crate::format::DateTime;
+use std::fs::{File, Metadata};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Name of the log file within the log location.
+pub const LOG_FILE_NAME: &str = "PCANBasic.log";
+
+#[derive(Debug, PartialEq)]
+pub enum LogMessage {
+    Entry {
+        function: String,
+    },
+    Parameters {
+        function: String,
+        /// Parameter names and values as written.
+        parameters: Vec<(String, String)>,
+    },
+    Exit {
+        function: String,
+        /// Status code returned by the function, [PcanError::Unknown] for unknown codes.
+        result: Result<(), PcanError>,
+    },
+    Text(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogEntry {
+    /// Local time written by the driver, in milliseconds since 1970.
+    pub timestamp: Option<u64>,
+    pub message: LogMessage,
+}
+
+impl LogEntry {
+    /// Parses a single line, `None` for empty lines.
+    pub fn parse(line: &str) -> Option<LogEntry> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+
+        let (timestamp, message) = match line.split_once(" - ") {
+            Some((time, message)) => match parse_time(time) {
+                Some(timestamp) => (Some(timestamp), message.trim()),
+                None => (None, line),
+            },
+            None => (None, line),
+        };
+
+        Some(LogEntry {
+            timestamp,
+            message: parse_message(message),
+        })
+    }
+}
+
+/// Date and time as `dd.mm.yyyy hh:mm:ss.mmm`, other separators are accepted as well.
+fn parse_time(time: &str) -> Option<u64> {
+    let fields = time
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|field| !field.is_empty())
+        .map(|field| field.parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    if fields.len() != 6 && fields.len() != 7 {
+        return None;
+    }
+
+    let date = DateTime {
+        year: fields[2] as i64,
+        month: fields[1],
+        day: fields[0],
+        hour: fields[3],
+        minute: fields[4],
+        second: fields[5],
+        millis: fields.get(6).copied().unwrap_or(0),
+    };
+    if !(1..=12).contains(&date.month) || !(1..=31).contains(&date.day) || date.hour > 23 {
+        return None;
+    }
+    Some(date.to_unix_millis())
+}
+
+fn parse_message(message: &str) -> LogMessage {
+    let text = || LogMessage::Text(String::from(message));
+
+    // Function names start with the API prefix and may be followed by parentheses or a colon.
+    if !message.starts_with("CAN_") {
+        return text();
+    }
+    let end = message
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(message.len());
+    let function = String::from(&message[..end]);
+    let rest = message[end..]
+        .trim_start_matches("()")
+        .trim_start_matches(':')
+        .trim();
+    let lower = rest.to_ascii_lowercase();
+
+    if lower == "entry" || lower == "enter" {
+        return LogMessage::Entry { function };
+    }
+    if lower.starts_with("exit") || lower.starts_with("leave") {
+        let code = lower
+            .find("0x")
+            .map(|index| &lower[index + 2..])
+            .map(|hex| {
+                let end = hex
+                    .find(|c: char| !c.is_ascii_hexdigit())
+                    .unwrap_or(hex.len());
+                &hex[..end]
+            })
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+        let result = match code.map(PcanOkError::try_from) {
+            Some(Ok(PcanOkError::Ok)) => Ok(()),
+            Some(Ok(PcanOkError::Err(err))) => Err(err),
+            Some(Err(_)) => Err(PcanError::Unknown),
+            // Exits without status code succeeded.
+            None => Ok(()),
+        };
+        return LogMessage::Exit { function, result };
+    }
+
+    let parameters = rest
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .filter_map(|parameter| {
+            let (name, value) = parameter.split_once(':').or(parameter.split_once('='))?;
+            Some((String::from(name.trim()), String::from(value.trim())))
+        })
+        .collect::<Vec<_>>();
+    match parameters.is_empty() {
+        true => text(),
+        false => LogMessage::Parameters {
+            function,
+            parameters,
+        },
+    }
+}
+
+/* LogReader */
+
+/// Reads the entries of a complete log file.
+#[derive(Debug)]
+pub struct LogReader<R> {
+    reader: R,
+    buffer: String,
+}
+
+impl LogReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<LogReader<BufReader<File>>, std::io::Error> {
+        Ok(LogReader::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> LogReader<R> {
+    pub fn new(reader: R) -> LogReader<R> {
+        LogReader {
+            reader,
+            buffer: String::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for LogReader<R> {
+    type Item = Result<LogEntry, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buffer.clear();
+            match self.reader.read_line(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(err) => return Some(Err(err)),
+            }
+            if let Some(entry) = LogEntry::parse(&self.buffer) {
+                return Some(Ok(entry));
+            }
+        }
+    }
+}
+
+/* LogTail */
+
+/// Identity of a file, telling a replaced file from one that grew: the device and inode on
+/// Unix, the creation time elsewhere.
+#[cfg(unix)]
+type FileIdentity = (u64, u64);
+#[cfg(not(unix))]
+type FileIdentity = std::time::SystemTime;
+
+#[cfg(unix)]
+fn file_identity(metadata: &Metadata) -> Option<FileIdentity> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_identity(metadata: &Metadata) -> Option<FileIdentity> {
+    metadata.created().ok()
+}
+
+/// Follows a log file while the driver writes it. The file is read from the start again when
+/// it was truncated or replaced, as happens when the driver starts a new log.
+#[derive(Debug)]
+pub struct LogTail {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+    /// Identity of the file read by `reader`.
+    identity: Option<FileIdentity>,
+    position: u64,
+    /// Incomplete last line.
+    partial: String,
+}
+
+impl LogTail {
+    /// Follows the file at `path`, which does not need to exist yet.
+    pub fn new<P: AsRef<Path>>(path: P) -> LogTail {
+        LogTail {
+            path: path.as_ref().to_path_buf(),
+            reader: None,
+            identity: None,
+            position: 0,
+            partial: String::new(),
+        }
+    }
+
+    /// Follows the log file in the given log location.
+    pub fn in_location<P: AsRef<Path>>(location: P) -> LogTail {
+        LogTail::new(location.as_ref().join(LOG_FILE_NAME))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the entries of all lines completed since the last call.
+    pub fn poll(&mut self) -> Result<Vec<LogEntry>, std::io::Error> {
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                self.reader = None;
+                return Ok(Vec::new());
+            }
+            Err(err) => return Err(err),
+        };
+        if metadata.len() < self.position
+            || self.reader.is_none()
+            || file_identity(&metadata) != self.identity
+        {
+            let file = File::open(&self.path)?;
+            self.identity = file_identity(&file.metadata()?);
+            self.reader = Some(BufReader::new(file));
+            self.position = 0;
+            self.partial.clear();
+        }
+
+        let reader = self.reader.as_mut().unwrap();
+        let mut entries = Vec::new();
+        loop {
+            let count = reader.read_line(&mut self.partial)?;
+            if count == 0 || !self.partial.ends_with('\n') {
+                self.position += count as u64;
+                return Ok(entries);
+            }
+            self.position += count as u64;
+            entries.extend(LogEntry::parse(&self.partial));
+            self.partial.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "PCAN-Basic Log file\n\
+                       \n\
+                       19.10.2023 10:15:02.123 - CAN_Initialize() Entry\n\
+                       19.10.2023 10:15:02.123 - CAN_Initialize() Channel: 0x51, Btr0Btr1: 0x1C\n\
+                       19.10.2023 10:15:02.150 - CAN_Initialize() Exit with error 0x0\n\
+                       19.10.2023 10:15:03.000 - CAN_Read(): Exit with error 0x20 (QRCVEMPTY)\n\
+                       19.10.2023 10:15:04.500 - measurement started\n";
+
+    #[test]
+    fn log_reader_001() {
+        let entries = LogReader::new(LOG.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(entries.len(), 6);
+        assert_eq!(
+            entries[0],
+            LogEntry {
+                timestamp: None,
+                message: LogMessage::Text(String::from("PCAN-Basic Log file")),
+            }
+        );
+        // 2023-10-19 10:15:02.123
+        assert_eq!(entries[1].timestamp, Some(1_697_710_502_123));
+        assert_eq!(
+            entries[1].message,
+            LogMessage::Entry {
+                function: String::from("CAN_Initialize")
+            }
+        );
+        assert_eq!(
+            entries[2].message,
+            LogMessage::Parameters {
+                function: String::from("CAN_Initialize"),
+                parameters: vec![
+                    (String::from("Channel"), String::from("0x51")),
+                    (String::from("Btr0Btr1"), String::from("0x1C")),
+                ],
+            }
+        );
+        assert_eq!(
+            entries[3].message,
+            LogMessage::Exit {
+                function: String::from("CAN_Initialize"),
+                result: Ok(()),
+            }
+        );
+        assert_eq!(
+            entries[4].message,
+            LogMessage::Exit {
+                function: String::from("CAN_Read"),
+                result: Err(PcanError::QrcvEmpty),
+            }
+        );
+        assert_eq!(
+            entries[5].message,
+            LogMessage::Text(String::from("measurement started"))
+        );
+    }
+
+    #[test]
+    fn log_tail_001() {
+        let directory = std::env::temp_dir().join(format!("pcan_log_tail_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut tail = LogTail::in_location(&directory);
+        assert_eq!(tail.poll().unwrap(), vec![]);
+
+        let mut lines = LOG.lines().skip(2).map(|line| format!("{}\n", line));
+        std::fs::write(tail.path(), lines.next().unwrap()).unwrap();
+        assert_eq!(tail.poll().unwrap().len(), 1);
+
+        // Incomplete lines are returned once completed.
+        let mut text = std::fs::read_to_string(tail.path()).unwrap();
+        let line = lines.next().unwrap();
+        text.push_str(&line[..10]);
+        std::fs::write(tail.path(), &text).unwrap();
+        assert_eq!(tail.poll().unwrap(), vec![]);
+        text.push_str(&line[10..]);
+        std::fs::write(tail.path(), &text).unwrap();
+        let entries = tail.poll().unwrap();
+        assert!(matches!(
+            entries[..],
+            [LogEntry {
+                message: LogMessage::Parameters { .. },
+                ..
+            }]
+        ));
+
+        // A new, shorter file is read from the start.
+        std::fs::write(tail.path(), lines.next().unwrap()).unwrap();
+        let entries = tail.poll().unwrap();
+        assert!(matches!(
+            entries[..],
+            [LogEntry {
+                message: LogMessage::Exit { .. },
+                ..
+            }]
+        ));
+
+        // So is a longer file replacing it.
+        let replacement = directory.join("replacement.log");
+        std::fs::write(&replacement, LOG).unwrap();
+        std::fs::rename(&replacement, tail.path()).unwrap();
+        assert_eq!(tail.poll().unwrap().len(), 6);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
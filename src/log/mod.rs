@@ -1,3 +1,9 @@
+//! Logging of the PCAN-Basic driver into `PCANBasic.log` within the log location.
+
+#[cfg(feature = "tracing")]
+pub mod bridge;
+pub mod file;
+
 use crate::error::{PcanError, PcanOkError};
 use crate::pcan;
 use std::ffi::c_void;
@@ -156,6 +162,11 @@ impl From<LogFunction> for u32 {
 }
 
 pub fn log_configuration() -> Result<LogFunction, PcanError> {
+    LogFunction::try_from(raw_log_configuration()?).map_err(|_| PcanError::Unknown)
+}
+
+/// Configuration bitmask, which may combine several [LogFunction] values.
+fn raw_log_configuration() -> Result<u32, PcanError> {
     let mut data = [0u8; 4];
     let code = unsafe {
         pcan::CAN_GetValue(
@@ -167,20 +178,18 @@ pub fn log_configuration() -> Result<LogFunction, PcanError> {
     };
 
     match PcanOkError::try_from(code) {
-        Ok(PcanOkError::Ok) => {
-            let code = u32::from_le_bytes(data);
-            match LogFunction::try_from(code) {
-                Ok(log_config) => Ok(log_config),
-                Err(_) => Err(PcanError::Unknown),
-            }
-        }
+        Ok(PcanOkError::Ok) => Ok(u32::from_le_bytes(data)),
         Ok(PcanOkError::Err(err)) => Err(err),
         Err(_) => Err(PcanError::Unknown),
     }
 }
 
 pub fn configure_log(config: LogFunction) -> Result<(), PcanError> {
-    let mut data = u32::from(config).to_le_bytes();
+    configure_raw_log(u32::from(config))
+}
+
+fn configure_raw_log(config: u32) -> Result<(), PcanError> {
+    let mut data = config.to_le_bytes();
     let code = unsafe {
         pcan::CAN_SetValue(
             pcan::PCAN_NONEBUS as u16,
@@ -216,3 +225,48 @@ pub fn log_text<S: AsRef<str>>(text: S) -> Result<(), PcanError> {
         Err(_) => Err(PcanError::Unknown),
     }
 }
+
+/* LOG GUARD */
+
+/// Enables logging while alive and restores the previous logging status, log location and log
+/// configuration when dropped.
+#[derive(Debug)]
+pub struct LogGuard {
+    logging: bool,
+    location: PathBuf,
+    /// Raw configuration, which may combine several functions.
+    configuration: u32,
+}
+
+impl LogGuard {
+    /// Logs the function calls selected by `config` into the log file in `location`.
+    pub fn new<P: AsRef<Path>>(location: P, config: LogFunction) -> Result<LogGuard, PcanError> {
+        let guard = LogGuard {
+            logging: is_logging()?,
+            location: log_location()?,
+            configuration: raw_log_configuration()?,
+        };
+        // Settings changed before a failure are restored by dropping the guard.
+        set_log_location(location)?;
+        configure_log(config)?;
+        set_logging(true)?;
+        Ok(guard)
+    }
+
+    /// Path of the log file written while the guard is alive.
+    pub fn log_file(&self) -> Result<PathBuf, PcanError> {
+        Ok(log_location()?.join(file::LOG_FILE_NAME))
+    }
+}
+
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        let _ = set_logging(self.logging);
+        if self.location.as_os_str().is_empty() {
+            let _ = set_default_log_location();
+        } else {
+            let _ = set_log_location(&self.location);
+        }
+        let _ = configure_raw_log(self.configuration);
+    }
+}
@@ -0,0 +1,178 @@
+//! Reassembly of fast-packet PGNs, which spread up to 223 bytes across up to 32 frames.
+//!
+//! The first data byte of every frame holds a 3-bit sequence counter and a 5-bit frame counter.
+//! Frame 0 carries the total length followed by 6 data bytes, every further frame 7 data bytes.
+
+use std::collections::HashMap;
+
+/// Largest payload of a fast-packet message.
+pub const MAX_LENGTH: usize = 223;
+
+/// PGNs of the core set transferred as fast-packet.
+const FAST_PACKET_PGNS: &[u32] = &[
+    126208, 126464, 126720, 126983, 126984, 126985, 126986, 126987, 126988, 126996, 126998, 127233,
+    127237, 127489, 127496, 127497, 127498, 127503, 127504, 127506, 127507, 127509, 127510, 127511,
+    127512, 127513, 127514, 128275, 128520, 129029, 129038, 129039, 129040, 129041, 129044, 129045,
+    129284, 129285, 129301, 129302, 129538, 129540, 129541, 129542, 129545, 129547, 129549, 129551,
+    129556, 129792, 129793, 129794, 129795, 129796, 129797, 129798, 129799, 129800, 129801, 129802,
+    129803, 129804, 129805, 129806, 129807, 129808, 129809, 129810, 130052, 130053, 130054, 130060,
+    130061, 130064, 130065, 130066, 130067, 130068, 130069, 130070, 130071, 130072, 130073, 130074,
+    130320, 130321, 130322, 130323, 130324, 130567, 130577, 130578, 130816,
+];
+
+/// Returns `true` if `pgn` is transferred as fast-packet.
+pub fn is_fast_packet(pgn: u32) -> bool {
+    FAST_PACKET_PGNS.contains(&pgn)
+}
+
+#[derive(Debug)]
+struct Sequence {
+    length: usize,
+    data: Vec<u8>,
+    next_frame: u8,
+    last_micros: u64,
+}
+
+/// Reassembles fast-packet sequences of all sources. Sequences of different sources, PGNs or
+/// sequence counters may be interleaved.
+#[derive(Debug)]
+pub struct FastPacketAssembler {
+    sequences: HashMap<(u8, u32, u8), Sequence>,
+    timeout_micros: u64,
+}
+
+impl FastPacketAssembler {
+    /// Largest gap between two frames of a sequence.
+    pub const DEFAULT_TIMEOUT_MICROS: u64 = 750_000;
+
+    pub fn new() -> FastPacketAssembler {
+        FastPacketAssembler {
+            sequences: HashMap::new(),
+            timeout_micros: Self::DEFAULT_TIMEOUT_MICROS,
+        }
+    }
+
+    pub fn set_timeout_micros(&mut self, timeout_micros: u64) {
+        self.timeout_micros = timeout_micros;
+    }
+
+    /// Number of incomplete sequences.
+    pub fn pending(&self) -> usize {
+        self.sequences.len()
+    }
+
+    /// Adds the frame of `source` received at `micros` and returns the payload once the
+    /// sequence is complete. Sequences with missing frames or exceeding the timeout are dropped.
+    pub fn push(&mut self, source: u8, pgn: u32, data: &[u8], micros: u64) -> Option<Vec<u8>> {
+        let timeout = self.timeout_micros;
+        self.sequences
+            .retain(|_, sequence| micros.saturating_sub(sequence.last_micros) <= timeout);
+
+        let (&header, payload) = data.split_first()?;
+        let key = (source, pgn, header >> 5);
+        let frame = header & 0x1F;
+
+        if frame == 0 {
+            let (&length, payload) = payload.split_first()?;
+            let length = length as usize;
+            if length > MAX_LENGTH {
+                self.sequences.remove(&key);
+                return None;
+            }
+            let take = length.min(payload.len());
+            if take == length {
+                self.sequences.remove(&key);
+                return Some(payload[..take].to_vec());
+            }
+            self.sequences.insert(
+                key,
+                Sequence {
+                    length,
+                    data: payload[..take].to_vec(),
+                    next_frame: 1,
+                    last_micros: micros,
+                },
+            );
+            return None;
+        }
+
+        let sequence = self.sequences.get_mut(&key)?;
+        if frame != sequence.next_frame {
+            self.sequences.remove(&key);
+            return None;
+        }
+        let take = (sequence.length - sequence.data.len()).min(payload.len());
+        sequence.data.extend_from_slice(&payload[..take]);
+        sequence.next_frame += 1;
+        sequence.last_micros = micros;
+
+        if sequence.data.len() < sequence.length {
+            return None;
+        }
+        self.sequences.remove(&key).map(|sequence| sequence.data)
+    }
+}
+
+impl Default for FastPacketAssembler {
+    fn default() -> Self {
+        FastPacketAssembler::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_001() {
+        let mut assembler = FastPacketAssembler::new();
+        let data: Vec<u8> = (0..20).collect();
+
+        // Sequences of two sources interleaved.
+        assert_eq!(
+            assembler.push(1, 129029, &[0x20, 20, 0, 1, 2, 3, 4, 5], 0),
+            None
+        );
+        assert_eq!(
+            assembler.push(2, 129029, &[0x40, 9, 9, 9, 9, 9, 9, 9], 0),
+            None
+        );
+        assert_eq!(
+            assembler.push(1, 129029, &[0x21, 6, 7, 8, 9, 10, 11, 12], 10),
+            None
+        );
+        assert_eq!(
+            assembler.push(2, 129029, &[0x41, 9, 9, 9, 0xFF, 0xFF, 0xFF, 0xFF], 10),
+            Some(vec![9; 9])
+        );
+        assert_eq!(
+            assembler.push(1, 129029, &[0x22, 13, 14, 15, 16, 17, 18, 19], 20),
+            Some(data)
+        );
+        assert_eq!(assembler.pending(), 0);
+    }
+
+    #[test]
+    fn push_002() {
+        let mut assembler = FastPacketAssembler::new();
+
+        // Timeout between frames.
+        assert_eq!(
+            assembler.push(1, 127489, &[0x00, 10, 0, 1, 2, 3, 4, 5], 0),
+            None
+        );
+        assert_eq!(
+            assembler.push(1, 127489, &[0x01, 6, 7, 8, 9], 800_000),
+            None
+        );
+        assert_eq!(assembler.pending(), 0);
+
+        // Missing frame.
+        assert_eq!(
+            assembler.push(1, 127489, &[0x00, 20, 0, 1, 2, 3, 4, 5], 0),
+            None
+        );
+        assert_eq!(assembler.push(1, 127489, &[0x02, 6, 7, 8, 9], 10), None);
+        assert_eq!(assembler.pending(), 0);
+    }
+}
@@ -0,0 +1,236 @@
+//! NMEA 2000 reception on top of [RecvCan].
+//!
+//! Messages are carried in 29-bit frames with J1939 style identifiers. Single frame PGNs are
+//! passed through, fast-packet PGNs are reassembled per source address.
+
+pub mod fast_packet;
+pub mod pgn;
+
+pub use fast_packet::FastPacketAssembler;
+pub use pgn::PgnValue;
+
+use crate::error::PcanError;
+use crate::socket::{CanFrame, RecvCan, Timestamp};
+
+/// Destination of PGNs sent to all nodes.
+pub const GLOBAL_ADDRESS: u8 = 0xFF;
+
+/* Identifier */
+
+/// Fields of a 29-bit J1939 identifier.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Identifier {
+    pub priority: u8,
+    pub pgn: u32,
+    pub source: u8,
+    pub destination: u8,
+}
+
+impl Identifier {
+    pub fn from_can_id(can_id: u32) -> Identifier {
+        let data_page = (can_id >> 24) & 0x03;
+        let pdu_format = (can_id >> 16) & 0xFF;
+        let pdu_specific = (can_id >> 8) & 0xFF;
+
+        // PDU1 format addresses a single node with the PDU specific field.
+        let (pgn, destination) = match pdu_format < 240 {
+            true => ((data_page << 16) | (pdu_format << 8), pdu_specific as u8),
+            false => (
+                (data_page << 16) | (pdu_format << 8) | pdu_specific,
+                GLOBAL_ADDRESS,
+            ),
+        };
+
+        Identifier {
+            priority: ((can_id >> 26) & 0x07) as u8,
+            pgn,
+            source: can_id as u8,
+            destination,
+        }
+    }
+
+    pub fn to_can_id(&self) -> u32 {
+        let mut can_id =
+            ((self.priority as u32 & 0x07) << 26) | (self.pgn << 8) | self.source as u32;
+        if (self.pgn >> 8) & 0xFF < 240 {
+            can_id = (can_id & !0xFF00) | ((self.destination as u32) << 8);
+        }
+        can_id
+    }
+}
+
+/* Message */
+
+/// Complete NMEA 2000 message, reassembled if sent as fast-packet.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Message {
+    pub id: Identifier,
+    /// Time the last frame of the message was received.
+    pub timestamp: Timestamp,
+    pub data: Vec<u8>,
+}
+
+impl Message {
+    pub fn pgn(&self) -> u32 {
+        self.id.pgn
+    }
+
+    pub fn source(&self) -> u8 {
+        self.id.source
+    }
+
+    /// Decoded value or `None` if the PGN is not part of the core set.
+    pub fn decode(&self) -> Option<PgnValue> {
+        pgn::decode(self.id.pgn, &self.data)
+    }
+}
+
+/* Nmea2000Reader */
+
+#[derive(Debug)]
+pub struct Nmea2000Reader<'a, S> {
+    socket: &'a S,
+    assembler: FastPacketAssembler,
+}
+
+impl<'a, S: RecvCan> Nmea2000Reader<'a, S> {
+    pub fn new(socket: &'a S) -> Nmea2000Reader<'a, S> {
+        Nmea2000Reader {
+            socket,
+            assembler: FastPacketAssembler::new(),
+        }
+    }
+
+    pub fn assembler_mut(&mut self) -> &mut FastPacketAssembler {
+        &mut self.assembler
+    }
+
+    /// Feeds a received frame and returns the message it completes. Standard frames are ignored.
+    pub fn push(&mut self, frame: &CanFrame, timestamp: Timestamp) -> Option<Message> {
+        if !frame.is_extended_frame() {
+            return None;
+        }
+        let id = Identifier::from_can_id(frame.can_id());
+
+        let data = match fast_packet::is_fast_packet(id.pgn) {
+            true => self
+                .assembler
+                .push(id.source, id.pgn, frame.data(), timestamp.as_micros())?,
+            false => frame.data().to_vec(),
+        };
+        Some(Message {
+            id,
+            timestamp,
+            data,
+        })
+    }
+
+    /// Reads frames until a message is complete or returns `None` once the receive queue is empty.
+    pub fn recv(&mut self) -> Result<Option<Message>, PcanError> {
+        loop {
+            let (frame, timestamp) = match self.socket.recv() {
+                Ok(received) => received,
+                Err(PcanError::QrcvEmpty) => return Ok(None),
+                Err(err) => return Err(err),
+            };
+            if let Some(message) = self.push(&frame, timestamp) {
+                return Ok(Some(message));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::socket::mock::MockSocket;
+    use crate::socket::MessageType;
+
+    fn frame(can_id: u32, data: &[u8]) -> CanFrame {
+        CanFrame::new(can_id, MessageType::Extended, data).unwrap()
+    }
+
+    #[test]
+    fn identifier_001() {
+        let id = Identifier::from_can_id(0x09F8_0123);
+        assert_eq!(id.priority, 2);
+        assert_eq!(id.pgn, 129025);
+        assert_eq!(id.source, 0x23);
+        assert_eq!(id.destination, GLOBAL_ADDRESS);
+        assert_eq!(id.to_can_id(), 0x09F8_0123);
+
+        // PDU1 format, ISO request to address 0x10.
+        let id = Identifier::from_can_id(0x18EA_1023);
+        assert_eq!(id.pgn, 59904);
+        assert_eq!(id.destination, 0x10);
+        assert_eq!(id.to_can_id(), 0x18EA_1023);
+    }
+
+    #[test]
+    fn recv_001() {
+        let socket = MockSocket::new(|_| Vec::new());
+        socket.pending.borrow_mut().extend([
+            // Position 52.37 N, 4.89 E.
+            frame(
+                0x09F8_0123,
+                &[0x20, 0x07, 0x37, 0x1F, 0xA0, 0x27, 0xEA, 0x02],
+            ),
+            // Vessel heading 1.2 rad, magnetic.
+            frame(
+                0x09F1_1223,
+                &[0x00, 0xE0, 0x2E, 0xFF, 0x7F, 0xFF, 0x7F, 0xFD],
+            ),
+            // Engine dynamic parameters split into 4 frames.
+            frame(0x09F2_0123, &[0x60, 26, 0x00, 0xE8, 0x03, 0x2E, 0x0E, 0xAC]),
+            frame(
+                0x09F2_0123,
+                &[0x61, 0x8A, 0xFF, 0x7F, 0x10, 0x0E, 0x00, 0x00],
+            ),
+            frame(
+                0x09F2_0123,
+                &[0x62, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+            ),
+            frame(
+                0x09F2_0123,
+                &[0x63, 0x00, 0x00, 0x00, 0x00, 0x32, 0x7F, 0xFF],
+            ),
+        ]);
+        let mut reader = Nmea2000Reader::new(&socket);
+
+        match reader.recv().unwrap().unwrap().decode() {
+            Some(PgnValue::PositionRapidUpdate(position)) => {
+                assert!((position.latitude.unwrap() - 52.37).abs() < 1e-6);
+                assert!((position.longitude.unwrap() - 4.89).abs() < 1e-6);
+            }
+            value => panic!("{:?}", value),
+        }
+
+        match reader.recv().unwrap().unwrap().decode() {
+            Some(PgnValue::VesselHeading(heading)) => {
+                assert!((heading.heading.unwrap() - 1.2).abs() < 1e-9);
+                assert_eq!(heading.deviation, None);
+                assert_eq!(heading.reference, Some(pgn::DirectionReference::Magnetic));
+            }
+            value => panic!("{:?}", value),
+        }
+
+        let message = reader.recv().unwrap().unwrap();
+        assert_eq!(message.pgn(), 127489);
+        assert_eq!(message.data.len(), 26);
+        match message.decode() {
+            Some(PgnValue::EngineDynamic(engine)) => {
+                assert_eq!(engine.oil_pressure, Some(100_000.0));
+                assert!((engine.oil_temperature.unwrap() - 363.0).abs() < 1e-9);
+                assert!((engine.coolant_temperature.unwrap() - 355.0).abs() < 1e-9);
+                assert_eq!(engine.alternator_potential, None);
+                assert!((engine.fuel_rate.unwrap() - 360.0).abs() < 1e-9);
+                assert_eq!(engine.total_hours, Some(0));
+                assert_eq!(engine.load, Some(50));
+                assert_eq!(engine.torque, None);
+            }
+            value => panic!("{:?}", value),
+        }
+
+        assert_eq!(reader.recv(), Ok(None));
+    }
+}
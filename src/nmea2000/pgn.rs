@@ -0,0 +1,219 @@
+//! Decoding of a core set of PGNs. Fields reporting "data not available" decode to `None`.
+//!
+//! Units follow NMEA 2000: angles in radians, temperatures in kelvin and pressures in pascal.
+
+pub const PGN_VESSEL_HEADING: u32 = 127250;
+pub const PGN_ENGINE_RAPID_UPDATE: u32 = 127488;
+pub const PGN_ENGINE_DYNAMIC: u32 = 127489;
+pub const PGN_POSITION_RAPID_UPDATE: u32 = 129025;
+pub const PGN_COG_SOG_RAPID_UPDATE: u32 = 129026;
+pub const PGN_GNSS_POSITION: u32 = 129029;
+
+fn u8_at(data: &[u8], offset: usize) -> Option<u8> {
+    data.get(offset).copied().filter(|value| *value != u8::MAX)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]])).filter(|value| *value != u16::MAX)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?)).filter(|value| *value != u32::MAX)
+}
+
+fn i8_at(data: &[u8], offset: usize) -> Option<i8> {
+    data.get(offset)
+        .map(|value| *value as i8)
+        .filter(|value| *value != i8::MAX)
+}
+
+fn i16_at(data: &[u8], offset: usize) -> Option<i16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(i16::from_le_bytes([bytes[0], bytes[1]])).filter(|value| *value != i16::MAX)
+}
+
+fn i32_at(data: &[u8], offset: usize) -> Option<i32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(i32::from_le_bytes(bytes.try_into().ok()?)).filter(|value| *value != i32::MAX)
+}
+
+fn i64_at(data: &[u8], offset: usize) -> Option<i64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(i64::from_le_bytes(bytes.try_into().ok()?)).filter(|value| *value != i64::MAX)
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum DirectionReference {
+    True,
+    Magnetic,
+}
+
+impl DirectionReference {
+    fn decode(bits: u8) -> Option<DirectionReference> {
+        match bits & 0x03 {
+            0 => Some(DirectionReference::True),
+            1 => Some(DirectionReference::Magnetic),
+            _ => None,
+        }
+    }
+}
+
+/// PGN 129025.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Position {
+    /// Degrees, positive north.
+    pub latitude: Option<f64>,
+    /// Degrees, positive east.
+    pub longitude: Option<f64>,
+}
+
+/// PGN 129026.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct CogSog {
+    pub sid: Option<u8>,
+    pub reference: Option<DirectionReference>,
+    /// Course over ground in radians.
+    pub cog: Option<f64>,
+    /// Speed over ground in m/s.
+    pub sog: Option<f64>,
+}
+
+/// PGN 129029.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct GnssPosition {
+    pub sid: Option<u8>,
+    /// Days since 1970-01-01.
+    pub date: Option<u16>,
+    /// Seconds since midnight.
+    pub time: Option<f64>,
+    /// Degrees, positive north.
+    pub latitude: Option<f64>,
+    /// Degrees, positive east.
+    pub longitude: Option<f64>,
+    /// Meters above the WGS-84 ellipsoid.
+    pub altitude: Option<f64>,
+    pub satellites: Option<u8>,
+    pub hdop: Option<f64>,
+    pub pdop: Option<f64>,
+}
+
+/// PGN 127250.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Heading {
+    pub sid: Option<u8>,
+    /// Radians.
+    pub heading: Option<f64>,
+    /// Radians.
+    pub deviation: Option<f64>,
+    /// Radians.
+    pub variation: Option<f64>,
+    pub reference: Option<DirectionReference>,
+}
+
+/// PGN 127488.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct EngineRapid {
+    pub instance: u8,
+    /// Revolutions per minute.
+    pub speed: Option<f64>,
+    /// Pascal.
+    pub boost_pressure: Option<f64>,
+    /// Percent.
+    pub tilt_trim: Option<i8>,
+}
+
+/// PGN 127489.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct EngineDynamic {
+    pub instance: u8,
+    /// Pascal.
+    pub oil_pressure: Option<f64>,
+    /// Kelvin.
+    pub oil_temperature: Option<f64>,
+    /// Kelvin.
+    pub coolant_temperature: Option<f64>,
+    /// Volt.
+    pub alternator_potential: Option<f64>,
+    /// Liters per hour.
+    pub fuel_rate: Option<f64>,
+    /// Seconds.
+    pub total_hours: Option<u32>,
+    /// Pascal.
+    pub coolant_pressure: Option<f64>,
+    /// Pascal.
+    pub fuel_pressure: Option<f64>,
+    /// Percent.
+    pub load: Option<i8>,
+    /// Percent.
+    pub torque: Option<i8>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum PgnValue {
+    VesselHeading(Heading),
+    EngineRapidUpdate(EngineRapid),
+    EngineDynamic(EngineDynamic),
+    PositionRapidUpdate(Position),
+    CogSogRapidUpdate(CogSog),
+    GnssPosition(GnssPosition),
+}
+
+/// Decodes the payload of `pgn` or returns `None` if the PGN is unknown or the payload too short.
+pub fn decode(pgn: u32, data: &[u8]) -> Option<PgnValue> {
+    match pgn {
+        PGN_VESSEL_HEADING if data.len() >= 8 => Some(PgnValue::VesselHeading(Heading {
+            sid: u8_at(data, 0),
+            heading: u16_at(data, 1).map(|value| value as f64 * 1e-4),
+            deviation: i16_at(data, 3).map(|value| value as f64 * 1e-4),
+            variation: i16_at(data, 5).map(|value| value as f64 * 1e-4),
+            reference: DirectionReference::decode(data[7]),
+        })),
+        PGN_ENGINE_RAPID_UPDATE if data.len() >= 6 => {
+            Some(PgnValue::EngineRapidUpdate(EngineRapid {
+                instance: data[0],
+                speed: u16_at(data, 1).map(|value| value as f64 * 0.25),
+                boost_pressure: u16_at(data, 3).map(|value| value as f64 * 100.0),
+                tilt_trim: i8_at(data, 5),
+            }))
+        }
+        PGN_ENGINE_DYNAMIC if data.len() >= 26 => Some(PgnValue::EngineDynamic(EngineDynamic {
+            instance: data[0],
+            oil_pressure: u16_at(data, 1).map(|value| value as f64 * 100.0),
+            oil_temperature: u16_at(data, 3).map(|value| value as f64 * 0.1),
+            coolant_temperature: u16_at(data, 5).map(|value| value as f64 * 0.01),
+            alternator_potential: i16_at(data, 7).map(|value| value as f64 * 0.01),
+            fuel_rate: i16_at(data, 9).map(|value| value as f64 * 0.1),
+            total_hours: u32_at(data, 11),
+            coolant_pressure: u16_at(data, 15).map(|value| value as f64 * 100.0),
+            fuel_pressure: u16_at(data, 17).map(|value| value as f64 * 1000.0),
+            load: i8_at(data, 24),
+            torque: i8_at(data, 25),
+        })),
+        PGN_POSITION_RAPID_UPDATE if data.len() >= 8 => {
+            Some(PgnValue::PositionRapidUpdate(Position {
+                latitude: i32_at(data, 0).map(|value| value as f64 * 1e-7),
+                longitude: i32_at(data, 4).map(|value| value as f64 * 1e-7),
+            }))
+        }
+        PGN_COG_SOG_RAPID_UPDATE if data.len() >= 6 => Some(PgnValue::CogSogRapidUpdate(CogSog {
+            sid: u8_at(data, 0),
+            reference: DirectionReference::decode(data[1]),
+            cog: u16_at(data, 2).map(|value| value as f64 * 1e-4),
+            sog: u16_at(data, 4).map(|value| value as f64 * 0.01),
+        })),
+        PGN_GNSS_POSITION if data.len() >= 38 => Some(PgnValue::GnssPosition(GnssPosition {
+            sid: u8_at(data, 0),
+            date: u16_at(data, 1),
+            time: u32_at(data, 3).map(|value| value as f64 * 1e-4),
+            latitude: i64_at(data, 7).map(|value| value as f64 * 1e-16),
+            longitude: i64_at(data, 15).map(|value| value as f64 * 1e-16),
+            altitude: i64_at(data, 23).map(|value| value as f64 * 1e-6),
+            satellites: u8_at(data, 33),
+            hdop: i16_at(data, 34).map(|value| value as f64 * 0.01),
+            pdop: i16_at(data, 36).map(|value| value as f64 * 0.01),
+        })),
+        _ => None,
+    }
+}
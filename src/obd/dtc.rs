@@ -0,0 +1,102 @@
+//! Diagnostic trouble codes as reported by services 0x03, 0x07 and 0x0A.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum DtcCategory {
+    /// `P` codes.
+    Powertrain,
+    /// `C` codes.
+    Chassis,
+    /// `B` codes.
+    Body,
+    /// `U` codes.
+    Network,
+}
+
+impl DtcCategory {
+    pub fn letter(&self) -> char {
+        match self {
+            DtcCategory::Powertrain => 'P',
+            DtcCategory::Chassis => 'C',
+            DtcCategory::Body => 'B',
+            DtcCategory::Network => 'U',
+        }
+    }
+}
+
+/// Two byte trouble code, displayed as e.g. `P0123`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Dtc {
+    code: u16,
+}
+
+impl Dtc {
+    pub fn new(code: u16) -> Dtc {
+        Dtc { code }
+    }
+
+    pub fn from_bytes(high: u8, low: u8) -> Dtc {
+        Dtc::new(u16::from_be_bytes([high, low]))
+    }
+
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    pub fn category(&self) -> DtcCategory {
+        match self.code >> 14 {
+            0 => DtcCategory::Powertrain,
+            1 => DtcCategory::Chassis,
+            2 => DtcCategory::Body,
+            _ => DtcCategory::Network,
+        }
+    }
+}
+
+impl fmt::Display for Dtc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{:03X}",
+            self.category().letter(),
+            (self.code >> 12) & 0x03,
+            self.code & 0x0F_FF
+        )
+    }
+}
+
+impl TryFrom<&str> for Dtc {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut chars = value.chars();
+        let category = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('P') => 0u16,
+            Some('C') => 1,
+            Some('B') => 2,
+            Some('U') => 3,
+            _ => return Err(()),
+        };
+
+        let rest = chars.as_str();
+        if rest.len() != 4 || !rest.is_ascii() {
+            return Err(());
+        }
+        let digit = rest[..1].parse::<u16>().map_err(|_| ())?;
+        if digit > 3 {
+            return Err(());
+        }
+        let tail = u16::from_str_radix(&rest[1..], 16).map_err(|_| ())?;
+
+        Ok(Dtc::new((category << 14) | (digit << 12) | tail))
+    }
+}
+
+/// Decodes a list of two byte trouble codes, skipping `0x0000` padding entries.
+pub fn decode_dtcs(data: &[u8]) -> Vec<Dtc> {
+    data.chunks_exact(2)
+        .map(|pair| Dtc::from_bytes(pair[0], pair[1]))
+        .filter(|dtc| dtc.code() != 0)
+        .collect()
+}
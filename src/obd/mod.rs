@@ -0,0 +1,532 @@
+//! OBD-II (SAE J1979) diagnostics on top of [SendCan] and [RecvCan].
+//!
+//! Requests are sent to the functional address, so every emission related ECU may answer.
+//! Responses spanning multiple frames are reassembled according to ISO 15765-2 (ISO-TP).
+
+pub mod dtc;
+pub mod pid;
+
+pub use dtc::{Dtc, DtcCategory};
+
+use crate::error::PcanError;
+use crate::socket::{CanFrame, MessageType, RecvCan, SendCan};
+use std::collections::{BTreeMap, HashMap};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+pub const SERVICE_CURRENT_DATA: u8 = 0x01;
+pub const SERVICE_FREEZE_FRAME_DATA: u8 = 0x02;
+pub const SERVICE_STORED_DTCS: u8 = 0x03;
+pub const SERVICE_CLEAR_DTCS: u8 = 0x04;
+pub const SERVICE_VEHICLE_INFORMATION: u8 = 0x09;
+
+pub const INFO_TYPE_VIN: u8 = 0x02;
+
+const FUNCTIONAL_ID_STANDARD: u32 = 0x7DF;
+const FUNCTIONAL_ID_EXTENDED: u32 = 0x18DB_33F1;
+const TESTER_ADDRESS: u32 = 0xF1;
+
+const NEGATIVE_RESPONSE: u8 = 0x7F;
+const RESPONSE_PENDING: u8 = 0x78;
+const POSITIVE_RESPONSE_OFFSET: u8 = 0x40;
+
+const PADDING: u8 = 0x00;
+
+/* Addressing */
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Addressing {
+    /// 11-bit identifiers, requests to `0x7DF` and responses from `0x7E8` to `0x7EF`.
+    Standard,
+    /// 29-bit identifiers, requests to `0x18DB33F1` and responses from `0x18DAF1xx`.
+    Extended,
+}
+
+impl Addressing {
+    fn msg_type(&self) -> MessageType {
+        match self {
+            Addressing::Standard => MessageType::Standard,
+            Addressing::Extended => MessageType::Extended,
+        }
+    }
+
+    fn functional_id(&self) -> u32 {
+        match self {
+            Addressing::Standard => FUNCTIONAL_ID_STANDARD,
+            Addressing::Extended => FUNCTIONAL_ID_EXTENDED,
+        }
+    }
+
+    fn is_response(&self, frame: &CanFrame) -> bool {
+        match self {
+            Addressing::Standard => {
+                !frame.is_extended_frame() && (0x7E8..=0x7EF).contains(&frame.can_id())
+            }
+            Addressing::Extended => {
+                frame.is_extended_frame()
+                    && frame.can_id() & 0x1FFF_FF00 == 0x18DA_0000 | (TESTER_ADDRESS << 8)
+            }
+        }
+    }
+
+    /// Physical request identifier of the ECU answering with `response_id`.
+    fn physical_id(&self, response_id: u32) -> u32 {
+        match self {
+            Addressing::Standard => response_id - 8,
+            Addressing::Extended => 0x18DA_0000 | ((response_id & 0xFF) << 8) | TESTER_ADDRESS,
+        }
+    }
+}
+
+/* ObdError */
+
+#[derive(Debug, PartialEq)]
+pub enum ObdError {
+    Pcan(PcanError),
+    /// The request does not fit into a single frame.
+    InvalidRequest,
+    /// No ECU answered within the timeout.
+    NoResponse,
+    /// All ECUs rejected the request with the given negative response code.
+    NegativeResponse(u8),
+    /// A response was too short or malformed.
+    InvalidResponse,
+}
+
+impl From<PcanError> for ObdError {
+    fn from(value: PcanError) -> Self {
+        ObdError::Pcan(value)
+    }
+}
+
+/* Responses */
+
+/// Complete, reassembled response of a single ECU.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EcuResponse {
+    /// CAN-ID the ECU responded with.
+    pub ecu: u32,
+    /// Response starting with the service identifier.
+    pub data: Vec<u8>,
+}
+
+impl EcuResponse {
+    pub fn is_negative(&self) -> bool {
+        self.data.first() == Some(&NEGATIVE_RESPONSE)
+    }
+
+    pub fn negative_response_code(&self) -> Option<u8> {
+        match self.is_negative() {
+            true => self.data.get(2).copied(),
+            false => None,
+        }
+    }
+}
+
+/// Value of a service 0x01 or 0x02 parameter as reported by a single ECU.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PidValue {
+    pub ecu: u32,
+    pub pid: u8,
+    /// Data bytes following the PID.
+    pub data: Vec<u8>,
+}
+
+impl PidValue {
+    /// Physical value or `None` if no formula is known for the PID.
+    pub fn value(&self) -> Option<f64> {
+        pid::definition(self.pid)?.decode(&self.data)
+    }
+
+    pub fn unit(&self) -> Option<&'static str> {
+        pid::definition(self.pid).map(|definition| definition.unit)
+    }
+}
+
+/// Multi-frame transfer of a single ECU in progress.
+struct Transfer {
+    length: usize,
+    data: Vec<u8>,
+    sequence_number: u8,
+}
+
+/* ObdClient */
+
+#[derive(Debug)]
+pub struct ObdClient<'a, S> {
+    socket: &'a S,
+    addressing: Addressing,
+    timeout: Duration,
+}
+
+impl<'a, S: SendCan + RecvCan> ObdClient<'a, S> {
+    /// Time to wait for responses after the last frame received from any ECU.
+    const DEFAULT_TIMEOUT: Duration = Duration::from_millis(100);
+    /// Extended timeout after an ECU signalled that its response is pending.
+    const PENDING_TIMEOUT: Duration = Duration::from_millis(5000);
+
+    pub fn new(socket: &'a S, addressing: Addressing) -> ObdClient<'a, S> {
+        ObdClient {
+            socket,
+            addressing,
+            timeout: Self::DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn addressing(&self) -> Addressing {
+        self.addressing
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Sends a functional request and collects the responses of all ECUs, including negative
+    /// responses.
+    pub fn request(&self, service: u8, parameters: &[u8]) -> Result<Vec<EcuResponse>, ObdError> {
+        let mut request = vec![1 + parameters.len() as u8, service];
+        request.extend_from_slice(parameters);
+        if request.len() > 8 {
+            return Err(ObdError::InvalidRequest);
+        }
+        self.send(self.addressing.functional_id(), &request)?;
+
+        let mut responses = Vec::new();
+        let mut transfers: HashMap<u32, Transfer> = HashMap::new();
+        let mut deadline = Instant::now() + self.timeout;
+
+        while Instant::now() < deadline {
+            let frame = match self.socket.recv_frame() {
+                Ok(frame) => frame,
+                Err(PcanError::QrcvEmpty) => {
+                    sleep(Duration::from_millis(1));
+                    continue;
+                }
+                Err(err) => return Err(ObdError::Pcan(err)),
+            };
+
+            if !self.addressing.is_response(&frame) || frame.dlc() == 0 {
+                continue;
+            }
+
+            let ecu = frame.can_id();
+            let data = frame.data();
+            let payload = match data[0] >> 4 {
+                0x0 => {
+                    let length = (data[0] & 0x0F) as usize;
+                    match data.get(1..1 + length) {
+                        Some(payload) => payload.to_vec(),
+                        None => continue,
+                    }
+                }
+                0x1 => {
+                    if data.len() < 8 {
+                        continue;
+                    }
+                    let length = (((data[0] & 0x0F) as usize) << 8) | data[1] as usize;
+                    if length < data.len() - 2 {
+                        continue;
+                    }
+                    transfers.insert(
+                        ecu,
+                        Transfer {
+                            length,
+                            data: data[2..].to_vec(),
+                            sequence_number: 1,
+                        },
+                    );
+                    // Continue to send, no block size and no separation time.
+                    self.send(self.addressing.physical_id(ecu), &[0x30, 0x00, 0x00])?;
+                    deadline = Instant::now() + self.timeout;
+                    continue;
+                }
+                0x2 => {
+                    let transfer = match transfers.get_mut(&ecu) {
+                        Some(transfer) => transfer,
+                        None => continue,
+                    };
+                    if data[0] & 0x0F != transfer.sequence_number {
+                        transfers.remove(&ecu);
+                        continue;
+                    }
+                    let remaining = transfer.length - transfer.data.len();
+                    let take = remaining.min(data.len() - 1);
+                    transfer.data.extend_from_slice(&data[1..1 + take]);
+                    transfer.sequence_number = (transfer.sequence_number + 1) & 0x0F;
+                    deadline = Instant::now() + self.timeout;
+
+                    if transfer.data.len() < transfer.length {
+                        continue;
+                    }
+                    transfers.remove(&ecu).unwrap().data
+                }
+                _ => continue,
+            };
+
+            let is_positive = payload.first() == Some(&(service + POSITIVE_RESPONSE_OFFSET));
+            let is_negative =
+                payload.len() >= 3 && payload[0] == NEGATIVE_RESPONSE && payload[1] == service;
+
+            if is_negative && payload[2] == RESPONSE_PENDING {
+                deadline = Instant::now() + Self::PENDING_TIMEOUT;
+            } else if is_positive || is_negative {
+                responses.push(EcuResponse { ecu, data: payload });
+            }
+        }
+
+        Ok(responses)
+    }
+
+    /// Sends a functional request and returns the positive responses with the service identifier
+    /// and the first `echo` parameters stripped.
+    fn positive(
+        &self,
+        service: u8,
+        parameters: &[u8],
+        echo: usize,
+    ) -> Result<Vec<EcuResponse>, ObdError> {
+        let responses = self.request(service, parameters)?;
+        let (positive, negative): (Vec<_>, Vec<_>) =
+            responses.into_iter().partition(|r| !r.is_negative());
+
+        if positive.is_empty() {
+            return match negative.first().and_then(|r| r.negative_response_code()) {
+                Some(code) => Err(ObdError::NegativeResponse(code)),
+                None => Err(ObdError::NoResponse),
+            };
+        }
+
+        positive
+            .into_iter()
+            .map(|response| {
+                if response.data.len() < 1 + echo
+                    || response.data[1..1 + echo] != parameters[..echo]
+                {
+                    return Err(ObdError::InvalidResponse);
+                }
+                Ok(EcuResponse {
+                    ecu: response.ecu,
+                    data: response.data[1 + echo..].to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    /// Service 0x01, requests the current value of `pid` from all ECUs.
+    pub fn current_data(&self, pid: u8) -> Result<Vec<PidValue>, ObdError> {
+        let responses = self.positive(SERVICE_CURRENT_DATA, &[pid], 1)?;
+        Ok(responses
+            .into_iter()
+            .map(|response| PidValue {
+                ecu: response.ecu,
+                pid,
+                data: response.data,
+            })
+            .collect())
+    }
+
+    /// Service 0x02, requests the value of `pid` stored in freeze frame `frame`.
+    pub fn freeze_frame_data(&self, pid: u8, frame: u8) -> Result<Vec<PidValue>, ObdError> {
+        let responses = self.positive(SERVICE_FREEZE_FRAME_DATA, &[pid, frame], 2)?;
+        Ok(responses
+            .into_iter()
+            .map(|response| PidValue {
+                ecu: response.ecu,
+                pid,
+                data: response.data,
+            })
+            .collect())
+    }
+
+    /// Queries the support PIDs 0x00, 0x20, ... and returns the supported PIDs of each ECU.
+    pub fn supported_pids(&self) -> Result<BTreeMap<u32, Vec<u8>>, ObdError> {
+        let mut supported: BTreeMap<u32, Vec<u8>> = BTreeMap::new();
+        let mut base = 0x00u8;
+
+        loop {
+            let responses = match self.current_data(base) {
+                Ok(responses) => responses,
+                Err(ObdError::NoResponse) | Err(ObdError::NegativeResponse(_)) if base != 0 => {
+                    break
+                }
+                Err(err) => return Err(err),
+            };
+
+            for response in responses {
+                let pids = pid::decode_supported(base, &response.data);
+                supported.entry(response.ecu).or_default().extend(pids);
+            }
+
+            let next = match base.checked_add(0x20) {
+                Some(next) => next,
+                None => break,
+            };
+            if !supported.values().any(|pids| pids.contains(&next)) {
+                break;
+            }
+            base = next;
+        }
+
+        Ok(supported)
+    }
+
+    /// Service 0x03, requests the stored emission related trouble codes of all ECUs.
+    pub fn stored_dtcs(&self) -> Result<Vec<(u32, Vec<Dtc>)>, ObdError> {
+        let responses = self.positive(SERVICE_STORED_DTCS, &[], 0)?;
+        responses
+            .into_iter()
+            .map(|response| {
+                // On CAN, the number of trouble codes precedes the list.
+                let (count, codes) = response
+                    .data
+                    .split_first()
+                    .ok_or(ObdError::InvalidResponse)?;
+                let dtcs = dtc::decode_dtcs(codes);
+                if dtcs.len() != *count as usize {
+                    return Err(ObdError::InvalidResponse);
+                }
+                Ok((response.ecu, dtcs))
+            })
+            .collect()
+    }
+
+    /// Service 0x04, clears all emission related diagnostic information and returns the ECUs
+    /// which confirmed.
+    pub fn clear_dtcs(&self) -> Result<Vec<u32>, ObdError> {
+        let responses = self.positive(SERVICE_CLEAR_DTCS, &[], 0)?;
+        Ok(responses.into_iter().map(|response| response.ecu).collect())
+    }
+
+    /// Service 0x09, requests vehicle information `info_type`.
+    ///
+    /// The data of each response starts after the number of data items.
+    pub fn vehicle_information(&self, info_type: u8) -> Result<Vec<EcuResponse>, ObdError> {
+        let responses = self.positive(SERVICE_VEHICLE_INFORMATION, &[info_type], 1)?;
+        responses
+            .into_iter()
+            .map(|response| match response.data.split_first() {
+                Some((_, data)) => Ok(EcuResponse {
+                    ecu: response.ecu,
+                    data: data.to_vec(),
+                }),
+                None => Err(ObdError::InvalidResponse),
+            })
+            .collect()
+    }
+
+    /// Reads the vehicle identification number from the first responding ECU.
+    pub fn vin(&self) -> Result<String, ObdError> {
+        let responses = self.vehicle_information(INFO_TYPE_VIN)?;
+        let response = responses.first().ok_or(ObdError::NoResponse)?;
+
+        let vin = response
+            .data
+            .iter()
+            .filter(|b| b.is_ascii_alphanumeric())
+            .map(|b| char::from(*b))
+            .collect::<String>();
+        match vin.len() {
+            17 => Ok(vin),
+            _ => Err(ObdError::InvalidResponse),
+        }
+    }
+
+    fn send(&self, can_id: u32, data: &[u8]) -> Result<(), ObdError> {
+        let mut padded = [PADDING; 8];
+        padded[..data.len()].copy_from_slice(data);
+        let frame = CanFrame::new(can_id, self.addressing.msg_type(), &padded).unwrap();
+        self.socket.send(frame)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::socket::mock::MockSocket;
+
+    fn frame(can_id: u32, data: &[u8]) -> CanFrame {
+        let msg_type = match can_id > 0x7FF {
+            true => MessageType::Extended,
+            false => MessageType::Standard,
+        };
+        let mut padded = [0u8; 8];
+        padded[..data.len()].copy_from_slice(data);
+        CanFrame::new(can_id, msg_type, &padded).unwrap()
+    }
+
+    #[test]
+    fn obd_current_data_001() {
+        let socket = MockSocket::new(|request| match request.data()[..3] {
+            [0x02, 0x01, 0x0C] => vec![
+                frame(0x7E8, &[0x04, 0x41, 0x0C, 0x1A, 0xF8]),
+                frame(0x7E9, &[0x04, 0x41, 0x0C, 0x0F, 0xA0]),
+            ],
+            _ => vec![],
+        });
+        let client = ObdClient::new(&socket, Addressing::Standard);
+
+        let values = client.current_data(0x0C).unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].ecu, 0x7E8);
+        assert_eq!(values[0].value(), Some(1726.0));
+        assert_eq!(values[1].value(), Some(1000.0));
+        assert_eq!(values[1].unit(), Some("rpm"));
+        assert_eq!(socket.sent.borrow()[0].can_id(), 0x7DF);
+    }
+
+    #[test]
+    fn obd_vin_001() {
+        let socket = MockSocket::new(|request| match (request.can_id(), request.data()[0]) {
+            (0x18DB33F1, 0x02) => vec![frame(
+                0x18DAF110,
+                &[0x10, 0x14, 0x49, 0x02, 0x01, b'W', b'P', b'0'],
+            )],
+            (0x18DA10F1, 0x30) => vec![
+                frame(
+                    0x18DAF110,
+                    &[0x21, b'Z', b'Z', b'Z', b'9', b'9', b'Z', b'T'],
+                ),
+                frame(
+                    0x18DAF110,
+                    &[0x22, b'S', b'3', b'9', b'2', b'1', b'2', b'4'],
+                ),
+            ],
+            _ => vec![],
+        });
+        let client = ObdClient::new(&socket, Addressing::Extended);
+
+        assert_eq!(client.vin(), Ok(String::from("WP0ZZZ99ZTS392124")));
+        assert_eq!(socket.sent.borrow().len(), 2);
+    }
+
+    #[test]
+    fn obd_stored_dtcs_001() {
+        let socket = MockSocket::new(|request| match request.data()[..2] {
+            [0x01, 0x03] => vec![frame(0x7E8, &[0x06, 0x43, 0x02, 0x01, 0x43, 0xC1, 0x01])],
+            _ => vec![],
+        });
+        let client = ObdClient::new(&socket, Addressing::Standard);
+
+        let dtcs = client.stored_dtcs().unwrap();
+        let codes = dtcs[0]
+            .1
+            .iter()
+            .map(|dtc| dtc.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(codes, vec!["P0143", "U0101"]);
+        assert_eq!(Dtc::try_from("U0101"), Ok(dtcs[0].1[1]));
+    }
+
+    #[test]
+    fn obd_clear_dtcs_001() {
+        let socket = MockSocket::new(|_| vec![frame(0x7E8, &[0x03, 0x7F, 0x04, 0x22])]);
+        let client = ObdClient::new(&socket, Addressing::Standard);
+
+        assert_eq!(client.clear_dtcs(), Err(ObdError::NegativeResponse(0x22)));
+    }
+}
@@ -0,0 +1,252 @@
+//! Conversion of service 0x01 and 0x02 parameter IDs (PIDs) into physical values.
+//!
+//! Formulas follow SAE J1979 where `A`, `B`, ... denote the data bytes following the PID.
+
+/// Definition of a parameter ID with the formula converting its data bytes.
+#[derive(Debug)]
+pub struct PidDefinition {
+    pub pid: u8,
+    pub name: &'static str,
+    pub unit: &'static str,
+    /// Number of data bytes following the PID.
+    pub bytes: usize,
+    formula: fn(&[u8]) -> f64,
+}
+
+impl PidDefinition {
+    /// Converts the data bytes following the PID or returns `None` if too few are given.
+    pub fn decode(&self, data: &[u8]) -> Option<f64> {
+        match data.len() >= self.bytes {
+            true => Some((self.formula)(&data[..self.bytes])),
+            false => None,
+        }
+    }
+}
+
+fn a(data: &[u8]) -> f64 {
+    data[0] as f64
+}
+
+fn ab(data: &[u8]) -> f64 {
+    u16::from_be_bytes([data[0], data[1]]) as f64
+}
+
+fn percent(data: &[u8]) -> f64 {
+    a(data) * 100.0 / 255.0
+}
+
+fn temperature(data: &[u8]) -> f64 {
+    a(data) - 40.0
+}
+
+fn fuel_trim(data: &[u8]) -> f64 {
+    (a(data) - 128.0) * 100.0 / 128.0
+}
+
+const PIDS: &[PidDefinition] = &[
+    PidDefinition {
+        pid: 0x04,
+        name: "Calculated engine load",
+        unit: "%",
+        bytes: 1,
+        formula: percent,
+    },
+    PidDefinition {
+        pid: 0x05,
+        name: "Engine coolant temperature",
+        unit: "°C",
+        bytes: 1,
+        formula: temperature,
+    },
+    PidDefinition {
+        pid: 0x06,
+        name: "Short term fuel trim bank 1",
+        unit: "%",
+        bytes: 1,
+        formula: fuel_trim,
+    },
+    PidDefinition {
+        pid: 0x07,
+        name: "Long term fuel trim bank 1",
+        unit: "%",
+        bytes: 1,
+        formula: fuel_trim,
+    },
+    PidDefinition {
+        pid: 0x08,
+        name: "Short term fuel trim bank 2",
+        unit: "%",
+        bytes: 1,
+        formula: fuel_trim,
+    },
+    PidDefinition {
+        pid: 0x09,
+        name: "Long term fuel trim bank 2",
+        unit: "%",
+        bytes: 1,
+        formula: fuel_trim,
+    },
+    PidDefinition {
+        pid: 0x0A,
+        name: "Fuel pressure",
+        unit: "kPa",
+        bytes: 1,
+        formula: |data| a(data) * 3.0,
+    },
+    PidDefinition {
+        pid: 0x0B,
+        name: "Intake manifold absolute pressure",
+        unit: "kPa",
+        bytes: 1,
+        formula: a,
+    },
+    PidDefinition {
+        pid: 0x0C,
+        name: "Engine speed",
+        unit: "rpm",
+        bytes: 2,
+        formula: |data| ab(data) / 4.0,
+    },
+    PidDefinition {
+        pid: 0x0D,
+        name: "Vehicle speed",
+        unit: "km/h",
+        bytes: 1,
+        formula: a,
+    },
+    PidDefinition {
+        pid: 0x0E,
+        name: "Timing advance",
+        unit: "°",
+        bytes: 1,
+        formula: |data| a(data) / 2.0 - 64.0,
+    },
+    PidDefinition {
+        pid: 0x0F,
+        name: "Intake air temperature",
+        unit: "°C",
+        bytes: 1,
+        formula: temperature,
+    },
+    PidDefinition {
+        pid: 0x10,
+        name: "Mass air flow rate",
+        unit: "g/s",
+        bytes: 2,
+        formula: |data| ab(data) / 100.0,
+    },
+    PidDefinition {
+        pid: 0x11,
+        name: "Throttle position",
+        unit: "%",
+        bytes: 1,
+        formula: percent,
+    },
+    PidDefinition {
+        pid: 0x1F,
+        name: "Run time since engine start",
+        unit: "s",
+        bytes: 2,
+        formula: ab,
+    },
+    PidDefinition {
+        pid: 0x21,
+        name: "Distance traveled with MIL on",
+        unit: "km",
+        bytes: 2,
+        formula: ab,
+    },
+    PidDefinition {
+        pid: 0x2F,
+        name: "Fuel tank level input",
+        unit: "%",
+        bytes: 1,
+        formula: percent,
+    },
+    PidDefinition {
+        pid: 0x31,
+        name: "Distance traveled since codes cleared",
+        unit: "km",
+        bytes: 2,
+        formula: ab,
+    },
+    PidDefinition {
+        pid: 0x33,
+        name: "Absolute barometric pressure",
+        unit: "kPa",
+        bytes: 1,
+        formula: a,
+    },
+    PidDefinition {
+        pid: 0x42,
+        name: "Control module voltage",
+        unit: "V",
+        bytes: 2,
+        formula: |data| ab(data) / 1000.0,
+    },
+    PidDefinition {
+        pid: 0x46,
+        name: "Ambient air temperature",
+        unit: "°C",
+        bytes: 1,
+        formula: temperature,
+    },
+    PidDefinition {
+        pid: 0x5C,
+        name: "Engine oil temperature",
+        unit: "°C",
+        bytes: 1,
+        formula: temperature,
+    },
+    PidDefinition {
+        pid: 0x5E,
+        name: "Engine fuel rate",
+        unit: "L/h",
+        bytes: 2,
+        formula: |data| ab(data) / 20.0,
+    },
+];
+
+/// Looks up the definition of a PID or returns `None` if its formula is not known.
+pub fn definition(pid: u8) -> Option<&'static PidDefinition> {
+    PIDS.iter().find(|definition| definition.pid == pid)
+}
+
+/// Returns `true` if `pid` queries the bit mask of supported PIDs, i.e. 0x00, 0x20, 0x40, ...
+pub fn is_support_pid(pid: u8) -> bool {
+    pid & 0x1F == 0
+}
+
+/// Decodes the bit mask returned for a support PID into the list of supported PIDs.
+pub fn decode_supported(base: u8, data: &[u8]) -> Vec<u8> {
+    let mut supported = Vec::new();
+    for (i, byte) in data.iter().take(4).enumerate() {
+        for bit in 0..8 {
+            if byte & (0x80 >> bit) == 0 {
+                continue;
+            }
+            if let Some(pid) = base.checked_add(1 + (i * 8 + bit) as u8) {
+                supported.push(pid);
+            }
+        }
+    }
+    supported
+}
+
+/// Monitor status since DTCs cleared, reported by PID 0x01.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct MonitorStatus {
+    /// Malfunction indicator lamp.
+    pub mil: bool,
+    pub dtc_count: u8,
+}
+
+impl MonitorStatus {
+    pub fn decode(data: &[u8]) -> Option<MonitorStatus> {
+        let a = *data.first()?;
+        Some(MonitorStatus {
+            mil: a & 0x80 != 0,
+            dtc_count: a & 0x7F,
+        })
+    }
+}
@@ -0,0 +1,463 @@
+//! Replay of recorded traffic with the original inter-frame timing.
+//!
+//! Records of any log file reader are sent onto sinks, one sink per bus. Frames are sent when
+//! their offset to the first record, divided by the speed factor, has elapsed. Identifiers and
+//! buses can be remapped and responses received in the meantime can be recorded.
+
+use crate::error::PcanError;
+use crate::format::{Direction, Frame, Record};
+use crate::socket::{
+    CanFdFrame, CanFrame, MessageType, RecvCan, RecvCanFd, SendCan, SendCanFd, Timestamp,
+};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Interval in which sinks are polled for responses while waiting for the next frame.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/* ReplaySink */
+
+/// Destination of replayed frames on a single bus.
+pub trait ReplaySink {
+    fn send_frame(&mut self, frame: &Frame) -> Result<(), PcanError>;
+    /// Returns the next received frame or `None` if no frame is pending.
+    fn recv_frame(&mut self) -> Result<Option<Frame>, PcanError>;
+}
+
+/// Sends classic CAN frames onto a socket, CAN FD frames are rejected.
+#[derive(Debug)]
+pub struct CanSink<'a, S> {
+    socket: &'a S,
+}
+
+impl<'a, S: SendCan + RecvCan> CanSink<'a, S> {
+    pub fn new(socket: &'a S) -> CanSink<'a, S> {
+        CanSink { socket }
+    }
+}
+
+impl<'a, S: SendCan + RecvCan> ReplaySink for CanSink<'a, S> {
+    fn send_frame(&mut self, frame: &Frame) -> Result<(), PcanError> {
+        match frame {
+            Frame::Can(frame) => self.socket.send(*frame),
+            _ => Err(PcanError::IllData),
+        }
+    }
+
+    fn recv_frame(&mut self) -> Result<Option<Frame>, PcanError> {
+        match self.socket.recv_frame() {
+            Ok(frame) => Ok(Some(Frame::Can(frame))),
+            Err(PcanError::QrcvEmpty) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Sends CAN FD frames with [SendCanFd] and classic frames with [SendCan] onto a socket
+/// initialized for CAN FD.
+#[derive(Debug)]
+pub struct CanFdSink<'a, S> {
+    socket: &'a S,
+}
+
+impl<'a, S: SendCan + SendCanFd + RecvCanFd> CanFdSink<'a, S> {
+    pub fn new(socket: &'a S) -> CanFdSink<'a, S> {
+        CanFdSink { socket }
+    }
+}
+
+impl<'a, S: SendCan + SendCanFd + RecvCanFd> ReplaySink for CanFdSink<'a, S> {
+    fn send_frame(&mut self, frame: &Frame) -> Result<(), PcanError> {
+        match frame {
+            Frame::Can(frame) => self.socket.send(*frame),
+            Frame::CanFd(frame) => self.socket.send_fd(*frame),
+            _ => Err(PcanError::IllData),
+        }
+    }
+
+    fn recv_frame(&mut self) -> Result<Option<Frame>, PcanError> {
+        match self.socket.recv_fd_frame() {
+            Ok(frame) => Ok(Some(Frame::CanFd(frame))),
+            Err(PcanError::QrcvEmpty) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// In-memory sink for dry runs, keeping every sent frame and answering with queued frames.
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    pub sent: Vec<Frame>,
+    pub pending: VecDeque<Frame>,
+}
+
+impl MemorySink {
+    pub fn new() -> MemorySink {
+        MemorySink::default()
+    }
+}
+
+impl ReplaySink for MemorySink {
+    fn send_frame(&mut self, frame: &Frame) -> Result<(), PcanError> {
+        self.sent.push(frame.clone());
+        Ok(())
+    }
+
+    fn recv_frame(&mut self) -> Result<Option<Frame>, PcanError> {
+        Ok(self.pending.pop_front())
+    }
+}
+
+/* Replay */
+
+/// Outcome of a replay.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ReplayReport {
+    /// Number of sent frames over all loops.
+    pub sent: usize,
+    /// Number of records not sent, i.e. error frames, status records and records of buses
+    /// without sink.
+    pub skipped: usize,
+    /// Received frames with their time since the start of the replay, if recording is enabled.
+    pub responses: Vec<Record>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    records: Vec<Record>,
+    speed: f64,
+    /// Number of passes, `None` repeats until a sink fails.
+    loops: Option<u32>,
+    record: bool,
+    ids: HashMap<u32, u32>,
+    buses: HashMap<u8, u8>,
+}
+
+impl Replay {
+    /// Replays the records once in real time.
+    pub fn new<I: IntoIterator<Item = Record>>(records: I) -> Replay {
+        Replay {
+            records: records.into_iter().collect(),
+            speed: 1.0,
+            loops: Some(1),
+            record: false,
+            ids: HashMap::new(),
+            buses: HashMap::new(),
+        }
+    }
+
+    /// Divides all inter-frame times by `speed`, zero sends all frames without delay.
+    pub fn with_speed(mut self, speed: f64) -> Replay {
+        self.speed = speed;
+        self
+    }
+
+    /// Replays the records `loops` times.
+    pub fn with_loops(mut self, loops: u32) -> Replay {
+        self.loops = Some(loops);
+        self
+    }
+
+    /// Repeats the records until a sink fails, which is the only way [Replay::run] returns. A
+    /// [MemorySink] never fails, the sinks have to fail e.g. once a stop flag is set.
+    pub fn forever(mut self) -> Replay {
+        self.loops = None;
+        self
+    }
+
+    /// Collects the frames received by the sinks during the replay.
+    pub fn with_recording(mut self) -> Replay {
+        self.record = true;
+        self
+    }
+
+    /// Sends frames recorded with identifier `from` with identifier `to`.
+    pub fn with_id_map(mut self, from: u32, to: u32) -> Replay {
+        self.ids.insert(from, to);
+        self
+    }
+
+    /// Sends frames recorded on bus `from` onto the sink of bus `to`.
+    pub fn with_bus_map(mut self, from: u8, to: u8) -> Replay {
+        self.buses.insert(from, to);
+        self
+    }
+
+    /// Replays the records onto `sinks`, the sink of bus n at index n - 1.
+    pub fn run(&self, sinks: &mut [&mut dyn ReplaySink]) -> Result<ReplayReport, PcanError> {
+        let mut report = ReplayReport::default();
+        let first = match self.records.first() {
+            Some(record) => record.timestamp.as_micros(),
+            None => return Ok(report),
+        };
+        let last = self
+            .records
+            .iter()
+            .map(|record| record.timestamp.as_micros())
+            .max()
+            .unwrap_or(first);
+
+        let start = Instant::now();
+        let mut pass = 0;
+        while self.loops.is_none_or(|loops| pass < loops) {
+            // Loops follow each other without gap, one pass lasts from the first to the last
+            // record.
+            let pass_offset = pass as u64 * (last - first);
+            for record in &self.records {
+                let offset = pass_offset + record.timestamp.as_micros().saturating_sub(first);
+                self.wait(start, Timestamp::from_micros(offset), sinks, &mut report)?;
+
+                let bus = *self.buses.get(&record.bus).unwrap_or(&record.bus);
+                let sink = match (bus as usize)
+                    .checked_sub(1)
+                    .and_then(|index| sinks.get_mut(index))
+                {
+                    Some(sink) => sink,
+                    None => {
+                        report.skipped += 1;
+                        continue;
+                    }
+                };
+                match self.remap(&record.frame)? {
+                    Some(frame) => {
+                        sink.send_frame(&frame)?;
+                        report.sent += 1;
+                    }
+                    None => report.skipped += 1,
+                }
+            }
+            pass += 1;
+        }
+
+        self.poll(start, sinks, &mut report)?;
+        Ok(report)
+    }
+
+    /// Waits until `offset` after `start` has elapsed, scaled by the speed factor.
+    fn wait(
+        &self,
+        start: Instant,
+        offset: Timestamp,
+        sinks: &mut [&mut dyn ReplaySink],
+        report: &mut ReplayReport,
+    ) -> Result<(), PcanError> {
+        if self.speed <= 0.0 {
+            return self.poll(start, sinks, report);
+        }
+        let due = start + Duration::from_micros(offset.as_micros()).div_f64(self.speed);
+        loop {
+            self.poll(start, sinks, report)?;
+            let now = Instant::now();
+            if now >= due {
+                return Ok(());
+            }
+            match self.record {
+                true => std::thread::sleep((due - now).min(POLL_INTERVAL)),
+                false => std::thread::sleep(due - now),
+            }
+        }
+    }
+
+    /// Collects pending responses of all sinks if recording is enabled.
+    fn poll(
+        &self,
+        start: Instant,
+        sinks: &mut [&mut dyn ReplaySink],
+        report: &mut ReplayReport,
+    ) -> Result<(), PcanError> {
+        if !self.record {
+            return Ok(());
+        }
+        for (index, sink) in sinks.iter_mut().enumerate() {
+            while let Some(frame) = sink.recv_frame()? {
+                let elapsed = start.elapsed().as_micros() as u64;
+                report.responses.push(Record {
+                    timestamp: Timestamp::from_micros(elapsed),
+                    bus: index as u8 + 1,
+                    direction: Direction::Rx,
+                    frame,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Frame to send for a recorded frame, `None` for records which are not sent.
+    fn remap(&self, frame: &Frame) -> Result<Option<Frame>, PcanError> {
+        let id = match frame.can_id() {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        let to = match self.ids.get(&id) {
+            Some(to) => *to,
+            None => return Ok(Some(frame.clone())),
+        };
+
+        let frame = match frame {
+            Frame::Can(frame) if frame.is_remote_frame() => {
+                let msg_type = msg_type(frame.is_extended_frame());
+                CanFrame::new_remote(to, msg_type, frame.dlc()).map(Frame::Can)
+            }
+            Frame::Can(frame) => {
+                let msg_type = msg_type(frame.is_extended_frame());
+                CanFrame::new(to, msg_type, frame.data()).map(Frame::Can)
+            }
+            Frame::CanFd(frame) => {
+                let msg_type = msg_type(frame.is_extended_frame());
+                CanFdFrame::new(to, msg_type, frame.data()).map(|mut remapped| {
+                    remapped.set_bitrate_switch(frame.is_bitrate_switch());
+                    remapped.set_error_state_indicator(frame.is_error_state_indicator());
+                    Frame::CanFd(remapped)
+                })
+            }
+            _ => return Ok(None),
+        };
+        frame.map(Some).map_err(|_| PcanError::IllParamVal)
+    }
+}
+
+fn msg_type(extended: bool) -> MessageType {
+    match extended {
+        true => MessageType::Extended,
+        false => MessageType::Standard,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::socket::mock::MockSocket;
+
+    fn frame(id: u32, data: &[u8]) -> Frame {
+        Frame::Can(CanFrame::new(id, MessageType::Standard, data).unwrap())
+    }
+
+    fn record(micros: u64, bus: u8, frame: Frame) -> Record {
+        Record {
+            timestamp: Timestamp::from_micros(micros),
+            bus,
+            direction: Direction::Tx,
+            frame,
+        }
+    }
+
+    #[test]
+    fn replay_001() {
+        let replay = Replay::new(vec![
+            record(1_000_000, 1, frame(0x100, &[1])),
+            record(1_000_100, 2, frame(0x200, &[2])),
+            record(
+                1_000_200,
+                1,
+                Frame::Error {
+                    class: 0,
+                    data: vec![0],
+                },
+            ),
+            record(1_000_300, 3, frame(0x300, &[3])),
+        ])
+        .with_speed(0.0)
+        .with_loops(2)
+        .with_id_map(0x100, 0x101)
+        .with_bus_map(2, 1);
+
+        let mut sink = MemorySink::new();
+        let report = replay.run(&mut [&mut sink]).unwrap();
+        assert_eq!(report.sent, 4);
+        assert_eq!(report.skipped, 4);
+        assert_eq!(
+            sink.sent,
+            vec![
+                frame(0x101, &[1]),
+                frame(0x200, &[2]),
+                frame(0x101, &[1]),
+                frame(0x200, &[2])
+            ]
+        );
+    }
+
+    #[test]
+    fn replay_002() {
+        let replay = Replay::new(vec![
+            record(500, 1, frame(0x100, &[1])),
+            record(20_500, 1, frame(0x100, &[2])),
+        ])
+        .with_speed(2.0);
+
+        let mut sink = MemorySink::new();
+        let start = Instant::now();
+        replay.run(&mut [&mut sink]).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        assert_eq!(sink.sent.len(), 2);
+    }
+
+    #[test]
+    fn replay_003() {
+        let socket = MockSocket::new(|frame| {
+            vec![CanFrame::new(frame.can_id() + 8, MessageType::Standard, frame.data()).unwrap()]
+        });
+        let replay = Replay::new(vec![
+            record(0, 1, frame(0x7DF, &[2, 1, 0])),
+            record(1_000, 1, frame(0x7DF, &[2, 1, 0x0D])),
+        ])
+        .with_recording();
+
+        let report = replay.run(&mut [&mut CanSink::new(&socket)]).unwrap();
+        assert_eq!(socket.sent.borrow().len(), 2);
+        let responses = report
+            .responses
+            .iter()
+            .map(|record| record.frame.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            responses,
+            vec![frame(0x7E7, &[2, 1, 0]), frame(0x7E7, &[2, 1, 0x0D])]
+        );
+        assert!(report.responses[1].timestamp.as_micros() >= 1_000);
+
+        let fd = CanFdFrame::new(0x100, MessageType::Standard, &[0; 12]).unwrap();
+        let replay = Replay::new(vec![record(0, 1, Frame::CanFd(fd))]);
+        assert_eq!(
+            replay.run(&mut [&mut CanSink::new(&socket)]),
+            Err(PcanError::IllData)
+        );
+    }
+
+    /// Sink failing once `limit` frames are sent.
+    struct LimitSink {
+        sent: usize,
+        limit: usize,
+    }
+
+    impl ReplaySink for LimitSink {
+        fn send_frame(&mut self, _: &Frame) -> Result<(), PcanError> {
+            if self.sent == self.limit {
+                return Err(PcanError::QxmtFull);
+            }
+            self.sent += 1;
+            Ok(())
+        }
+
+        fn recv_frame(&mut self) -> Result<Option<Frame>, PcanError> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn replay_004() {
+        let replay = Replay::new(vec![
+            record(0, 1, frame(0x100, &[1])),
+            record(100, 1, frame(0x100, &[2])),
+        ])
+        .with_speed(0.0);
+
+        let mut sink = MemorySink::new();
+        let report = replay.clone().with_loops(0).run(&mut [&mut sink]).unwrap();
+        assert_eq!(report, ReplayReport::default());
+
+        let mut sink = LimitSink { sent: 0, limit: 7 };
+        let result = replay.forever().run(&mut [&mut sink]);
+        assert_eq!(result, Err(PcanError::QxmtFull));
+        assert_eq!(sink.sent, 7);
+    }
+}
@@ -0,0 +1,269 @@
+//! Lawicel SLCAN ASCII protocol of serial CAN adapters, e.g. the CANUSB.
+//!
+//! Commands are terminated by a carriage return and answered with a carriage return, a bell
+//! (`0x07`) reports an error. Frames are written like `t1232DEAD` for a standard frame with the
+//! ID `123` and two data bytes, `T` for extended frames with eight ID digits and `r`/`R` for
+//! remote frames, optionally followed by four hex digits of a millisecond timestamp.
+
+use crate::socket::{Baudrate, BusState, CanFrame, MessageType};
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// Answer to a successful command.
+pub const OK: &[u8] = b"\r";
+/// Answer to a failed or unknown command.
+pub const ERROR: &[u8] = b"\x07";
+
+/// Bitrates of the setup commands `S0` to `S8`.
+const SETUP_BITRATES: [Baudrate; 9] = [
+    Baudrate::Baud10K,
+    Baudrate::Baud20K,
+    Baudrate::Baud50K,
+    Baudrate::Baud100K,
+    Baudrate::Baud125K,
+    Baudrate::Baud250K,
+    Baudrate::Baud500K,
+    Baudrate::Baud800K,
+    Baudrate::Baud1M,
+];
+
+/// Bitrates whose bit timing registers `BTR0BTR1` are accepted by the `s` command.
+const BTR_BITRATES: [Baudrate; 14] = [
+    Baudrate::Baud1M,
+    Baudrate::Baud800K,
+    Baudrate::Baud500K,
+    Baudrate::Baud250K,
+    Baudrate::Baud125K,
+    Baudrate::Baud100K,
+    Baudrate::Baud95K,
+    Baudrate::Baud83,
+    Baudrate::Baud50K,
+    Baudrate::Baud47K,
+    Baudrate::Baud33K,
+    Baudrate::Baud20K,
+    Baudrate::Baud10K,
+    Baudrate::Baud5K,
+];
+
+/* Status flags of the F command */
+
+const FLAG_ERROR_WARNING: u8 = 0x04;
+const FLAG_DATA_OVERRUN: u8 = 0x08;
+const FLAG_ERROR_PASSIVE: u8 = 0x20;
+const FLAG_BUS_ERROR: u8 = 0x80;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Command {
+    /// `Sn` with one of the standard bitrates or `sxxyy` with the bit timing registers of a
+    /// [Baudrate].
+    Setup(Baudrate),
+    /// `O` opens the channel.
+    Open,
+    /// `C` closes the channel.
+    Close,
+    /// `t`, `T`, `r` or `R` transmits a frame.
+    Transmit(CanFrame),
+    /// `F` reads the status flags.
+    Status,
+    /// `V` reads the hardware and software version.
+    Version,
+    /// `N` reads the serial number.
+    SerialNumber,
+    /// `Z0` or `Z1` disables or enables the timestamps of received frames.
+    Timestamps(bool),
+}
+
+/// The line is no supported command.
+#[derive(Debug, PartialEq)]
+pub struct ParseCommandError;
+
+impl fmt::Display for ParseCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unsupported SLCAN command")
+    }
+}
+
+impl std::error::Error for ParseCommandError {}
+
+fn parse_hex(digits: &str) -> Result<u32, ParseCommandError> {
+    match digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+        true => u32::from_str_radix(digits, 16).map_err(|_| ParseCommandError),
+        false => Err(ParseCommandError),
+    }
+}
+
+fn parse_frame(
+    line: &str,
+    msg_type: MessageType,
+    remote: bool,
+) -> Result<Command, ParseCommandError> {
+    let id_len = match msg_type {
+        MessageType::Standard => 3,
+        MessageType::Extended => 8,
+    };
+    let id = line.get(1..1 + id_len).ok_or(ParseCommandError)?;
+    let dlc = line.get(1 + id_len..2 + id_len).ok_or(ParseCommandError)?;
+    let (can_id, dlc) = (parse_hex(id)?, parse_hex(dlc)? as usize);
+    let data = &line[2 + id_len..];
+
+    let frame = if remote {
+        if !data.is_empty() {
+            return Err(ParseCommandError);
+        }
+        CanFrame::new_remote(can_id, msg_type, dlc as u8)
+    } else {
+        if data.len() != dlc * 2 {
+            return Err(ParseCommandError);
+        }
+        let data = data
+            .as_bytes()
+            .chunks(2)
+            .map(|digits| match std::str::from_utf8(digits) {
+                Ok(digits) => parse_hex(digits).map(|byte| byte as u8),
+                Err(_) => Err(ParseCommandError),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        CanFrame::new(can_id, msg_type, &data)
+    };
+    match frame {
+        Ok(frame) if frame.can_id() == can_id => Ok(Command::Transmit(frame)),
+        _ => Err(ParseCommandError),
+    }
+}
+
+impl FromStr for Command {
+    type Err = ParseCommandError;
+
+    /// Parses a command line without the terminating carriage return.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (command, arguments) = match s.chars().next() {
+            Some(command) => (command, &s[command.len_utf8()..]),
+            None => return Err(ParseCommandError),
+        };
+        match (command, arguments) {
+            ('S', digit) if digit.len() == 1 => {
+                let index = parse_hex(digit)? as usize;
+                SETUP_BITRATES
+                    .get(index)
+                    .map(|baudrate| Command::Setup(*baudrate))
+                    .ok_or(ParseCommandError)
+            }
+            ('s', registers) if registers.len() == 4 => {
+                let registers = parse_hex(registers)? as u16;
+                BTR_BITRATES
+                    .into_iter()
+                    .find(|baudrate| u16::from(*baudrate) == registers)
+                    .map(Command::Setup)
+                    .ok_or(ParseCommandError)
+            }
+            ('O', "") => Ok(Command::Open),
+            ('C', "") => Ok(Command::Close),
+            ('F', "") => Ok(Command::Status),
+            ('V', "") => Ok(Command::Version),
+            ('N', "") => Ok(Command::SerialNumber),
+            ('Z', "0") => Ok(Command::Timestamps(false)),
+            ('Z', "1") => Ok(Command::Timestamps(true)),
+            ('t', _) => parse_frame(s, MessageType::Standard, false),
+            ('T', _) => parse_frame(s, MessageType::Extended, false),
+            ('r', _) => parse_frame(s, MessageType::Standard, true),
+            ('R', _) => parse_frame(s, MessageType::Extended, true),
+            _ => Err(ParseCommandError),
+        }
+    }
+}
+
+/// Answer to a transmitted frame, `z` for standard and `Z` for extended frames.
+pub fn transmit_answer(frame: &CanFrame) -> &'static [u8] {
+    match frame.is_extended_frame() {
+        true => b"Z\r",
+        false => b"z\r",
+    }
+}
+
+/// Received `frame` with the carriage return, optionally with a timestamp in milliseconds,
+/// which wraps after a minute.
+pub fn format_frame(frame: &CanFrame, timestamp_millis: Option<u64>) -> String {
+    let mut line = String::new();
+    let kind = match (frame.is_extended_frame(), frame.is_remote_frame()) {
+        (false, false) => 't',
+        (true, false) => 'T',
+        (false, true) => 'r',
+        (true, true) => 'R',
+    };
+    line.push(kind);
+    let _ = match frame.is_extended_frame() {
+        true => write!(line, "{:08X}{:X}", frame.can_id(), frame.dlc()),
+        false => write!(line, "{:03X}{:X}", frame.can_id(), frame.dlc()),
+    };
+    if !frame.is_remote_frame() {
+        for byte in frame.data() {
+            let _ = write!(line, "{:02X}", byte);
+        }
+    }
+    if let Some(millis) = timestamp_millis {
+        let _ = write!(line, "{:04X}", millis % 60_000);
+    }
+    line.push('\r');
+    line
+}
+
+/// Answer to the `F` command for the bus state and whether received frames were lost.
+pub fn format_status(state: BusState, overrun: bool) -> String {
+    let mut flags = match state {
+        BusState::Ok => 0,
+        BusState::Light | BusState::Heavy => FLAG_ERROR_WARNING,
+        BusState::Passive => FLAG_ERROR_PASSIVE,
+        BusState::Off => FLAG_BUS_ERROR,
+    };
+    if overrun {
+        flags |= FLAG_DATA_OVERRUN;
+    }
+    format!("F{:02X}\r", flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command_001() {
+        assert_eq!("S6".parse(), Ok(Command::Setup(Baudrate::Baud500K)));
+        assert_eq!("S8".parse(), Ok(Command::Setup(Baudrate::Baud1M)));
+        assert_eq!("s001C".parse(), Ok(Command::Setup(Baudrate::Baud500K)));
+        assert_eq!("S9".parse::<Command>(), Err(ParseCommandError));
+        assert_eq!("s1234".parse::<Command>(), Err(ParseCommandError));
+        assert_eq!("O".parse(), Ok(Command::Open));
+        assert_eq!("Z1".parse(), Ok(Command::Timestamps(true)));
+        assert_eq!("X".parse::<Command>(), Err(ParseCommandError));
+        assert_eq!("".parse::<Command>(), Err(ParseCommandError));
+    }
+
+    #[test]
+    fn parse_command_002() {
+        let frame = CanFrame::new(0x123, MessageType::Standard, &[0xDE, 0xAD]).unwrap();
+        assert_eq!("t1232DEAD".parse(), Ok(Command::Transmit(frame)));
+        let frame = CanFrame::new(0x18DA_F110, MessageType::Extended, &[]).unwrap();
+        assert_eq!("T18DAF1100".parse(), Ok(Command::Transmit(frame)));
+        let frame = CanFrame::new_remote(0x7DF, MessageType::Standard, 8).unwrap();
+        assert_eq!("r7DF8".parse(), Ok(Command::Transmit(frame)));
+
+        assert_eq!("t1232DE".parse::<Command>(), Err(ParseCommandError));
+        assert_eq!("t8001".parse::<Command>(), Err(ParseCommandError));
+        assert_eq!("t1239".parse::<Command>(), Err(ParseCommandError));
+        assert_eq!("t12+0".parse::<Command>(), Err(ParseCommandError));
+        // Invalid UTF-8 read from the serial port is replaced by U+FFFD.
+        assert_eq!("t1232A\u{FFFD}".parse::<Command>(), Err(ParseCommandError));
+        assert_eq!("t1232A\u{e9}B".parse::<Command>(), Err(ParseCommandError));
+    }
+
+    #[test]
+    fn format_frame_001() {
+        let frame = CanFrame::new(0x123, MessageType::Standard, &[0xDE, 0xAD]).unwrap();
+        assert_eq!(format_frame(&frame, None), "t1232DEAD\r");
+        assert_eq!(format_frame(&frame, Some(61_000)), "t1232DEAD03E8\r");
+        let frame = CanFrame::new_remote(0x18DA_F110, MessageType::Extended, 3).unwrap();
+        assert_eq!(format_frame(&frame, None), "R18DAF1103\r");
+
+        assert_eq!(format_status(BusState::Passive, true), "F28\r");
+    }
+}
@@ -0,0 +1,53 @@
+//! In-memory socket for unit tests of protocols built on top of [SendCan] and [RecvCan].
+
+use crate::error::PcanError;
+use crate::socket::{CanFrame, RecvCan, SendCan, Timestamp};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+type Responder = Box<dyn FnMut(&CanFrame) -> Vec<CanFrame>>;
+
+/// Socket recording every sent frame and answering it with the frames returned by a responder.
+pub(crate) struct MockSocket {
+    pub sent: RefCell<Vec<CanFrame>>,
+    pub pending: RefCell<VecDeque<CanFrame>>,
+    responder: RefCell<Responder>,
+}
+
+impl MockSocket {
+    pub fn new<F: FnMut(&CanFrame) -> Vec<CanFrame> + 'static>(responder: F) -> MockSocket {
+        MockSocket {
+            sent: RefCell::new(Vec::new()),
+            pending: RefCell::new(VecDeque::new()),
+            responder: RefCell::new(Box::new(responder)),
+        }
+    }
+
+    /// Socket answering the n-th sent frame with the n-th entry of `responses`.
+    pub fn with_responses(responses: Vec<Vec<CanFrame>>) -> MockSocket {
+        let mut responses = VecDeque::from(responses);
+        MockSocket::new(move |_| responses.pop_front().unwrap_or_default())
+    }
+}
+
+impl SendCan for MockSocket {
+    fn send(&self, frame: CanFrame) -> Result<(), PcanError> {
+        self.sent.borrow_mut().push(frame);
+        let responses = (self.responder.borrow_mut())(&frame);
+        self.pending.borrow_mut().extend(responses);
+        Ok(())
+    }
+}
+
+impl RecvCan for MockSocket {
+    fn recv(&self) -> Result<(CanFrame, Timestamp), PcanError> {
+        self.recv_frame().map(|frame| (frame, Timestamp::default()))
+    }
+
+    fn recv_frame(&self) -> Result<CanFrame, PcanError> {
+        self.pending
+            .borrow_mut()
+            .pop_front()
+            .ok_or(PcanError::QrcvEmpty)
+    }
+}
@@ -9,14 +9,35 @@ pub mod pcc;
 pub mod pci;
 pub mod usb;
 
+#[cfg(test)]
+pub(crate) mod mock;
+mod notation;
+
+pub(crate) use notation::parse_data;
+pub use notation::ParseFrameError;
+
 use crate::bus::Bus;
+use crate::channel::Channel;
+use crate::df::{
+    HasAcceptanceFilter11Bit, HasAcceptanceFilter29Bit, HasAllowEchoFrames, HasAllowErrorFrames,
+    HasAllowRTRFrames, HasAllowStatusFrames, HasMessageFilter, HasReceiveStatus,
+    HasSetAcceptanceFilter11Bit, HasSetAcceptanceFilter29Bit, HasSetAllowEchoFrames,
+    HasSetAllowErrorFrames, HasSetAllowRTRFrames, HasSetAllowStatusFrames, HasSetMessageFilter,
+    HasSetReceiveStatus,
+};
 use crate::error::{PcanError, PcanOkError};
+use crate::info::{HasDataBusSpeed, HasFirmwareVersion, HasNominalBusSpeed};
 use crate::pcan;
+use crate::special::{HasListenOnly, HasSetListenOnly};
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::c_char;
+use std::str::FromStr;
 
 pub const STANDARD_MASK: u32 = 0x07_FF;
 pub const EXTENDED_MASK: u32 = 0x1F_FF_FF_FF;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum MessageType {
     Standard,
     Extended,
@@ -70,14 +91,40 @@ impl CanFrame {
         }
     }
 
+    /// Remote transmission request for `dlc` data bytes.
+    pub fn new_remote(
+        can_id: u32,
+        msg_type: MessageType,
+        dlc: u8,
+    ) -> Result<CanFrame, FrameConstructionError> {
+        if dlc as usize > Self::MAX_DLC {
+            return Err(FrameConstructionError::TooMuchData);
+        }
+
+        let mut frame = CanFrame::new(can_id, msg_type, &[])?;
+        frame.frame.MSGTYPE |= pcan::PCAN_MESSAGE_RTR as u8;
+        frame.frame.LEN = dlc;
+        Ok(frame)
+    }
+
     pub fn is_standard_frame(&self) -> bool {
-        self.frame.MSGTYPE & pcan::PCAN_MESSAGE_STANDARD as u8 != 0
+        // PCAN_MESSAGE_STANDARD is zero and can not be tested as flag.
+        !self.is_extended_frame()
     }
 
     pub fn is_extended_frame(&self) -> bool {
         self.frame.MSGTYPE & pcan::PCAN_MESSAGE_EXTENDED as u8 != 0
     }
 
+    pub fn is_remote_frame(&self) -> bool {
+        self.frame.MSGTYPE & pcan::PCAN_MESSAGE_RTR as u8 != 0
+    }
+
+    /// Error frame received with error frames allowed, the CAN-ID holds the error type.
+    pub fn is_error_frame(&self) -> bool {
+        self.frame.MSGTYPE & pcan::PCAN_MESSAGE_ERRFRAME as u8 != 0
+    }
+
     pub fn can_id(&self) -> u32 {
         if self.is_standard_frame() {
             self.frame.ID & STANDARD_MASK
@@ -86,6 +133,14 @@ impl CanFrame {
         }
     }
 
+    /// Replaces the CAN-ID, keeping the frame type and flags.
+    pub fn set_can_id(&mut self, can_id: u32) {
+        self.frame.ID = match self.is_extended_frame() {
+            true => can_id & EXTENDED_MASK,
+            false => can_id & STANDARD_MASK,
+        };
+    }
+
     pub fn dlc(&self) -> u8 {
         self.frame.LEN
     }
@@ -153,16 +208,16 @@ impl CanFdFrame {
                 MessageType::Standard => Ok(CanFdFrame {
                     frame: pcan::TPCANMsgFD {
                         ID: can_id & STANDARD_MASK,
-                        MSGTYPE: pcan::PCAN_MESSAGE_STANDARD as u8,
-                        DLC: data.len() as u8,
+                        MSGTYPE: (pcan::PCAN_MESSAGE_STANDARD | pcan::PCAN_MESSAGE_FD) as u8,
+                        DLC: len_to_dlc(data.len()),
                         DATA: frame_data,
                     },
                 }),
                 MessageType::Extended => Ok(CanFdFrame {
                     frame: pcan::TPCANMsgFD {
-                        ID: can_id & STANDARD_MASK,
-                        MSGTYPE: pcan::PCAN_MESSAGE_STANDARD as u8,
-                        DLC: data.len() as u8,
+                        ID: can_id & EXTENDED_MASK,
+                        MSGTYPE: (pcan::PCAN_MESSAGE_EXTENDED | pcan::PCAN_MESSAGE_FD) as u8,
+                        DLC: len_to_dlc(data.len()),
                         DATA: frame_data,
                     },
                 }),
@@ -171,14 +226,45 @@ impl CanFdFrame {
     }
 
     pub fn is_standard_frame(&self) -> bool {
-        self.frame.MSGTYPE & pcan::PCAN_MESSAGE_STANDARD as u8 != 0
+        // PCAN_MESSAGE_STANDARD is zero and can not be tested as flag.
+        !self.is_extended_frame()
     }
 
     pub fn is_extended_frame(&self) -> bool {
-        if self.frame.MSGTYPE & pcan::PCAN_MESSAGE_EXTENDED as u8 != 0 {
-            true
-        } else {
-            false
+        self.frame.MSGTYPE & pcan::PCAN_MESSAGE_EXTENDED as u8 != 0
+    }
+
+    /// Data bytes are sent with the data bit rate.
+    pub fn is_bitrate_switch(&self) -> bool {
+        self.frame.MSGTYPE & pcan::PCAN_MESSAGE_BRS as u8 != 0
+    }
+
+    pub fn set_bitrate_switch(&mut self, value: bool) {
+        match value {
+            true => self.frame.MSGTYPE |= pcan::PCAN_MESSAGE_BRS as u8,
+            false => self.frame.MSGTYPE &= !(pcan::PCAN_MESSAGE_BRS as u8),
+        }
+    }
+
+    /// Whether the frame uses the CAN FD format, classic frames are also read by `recv_fd`.
+    pub fn is_fd_frame(&self) -> bool {
+        self.frame.MSGTYPE & pcan::PCAN_MESSAGE_FD as u8 != 0
+    }
+
+    /// Error frame received with error frames allowed, the CAN-ID holds the error type.
+    pub fn is_error_frame(&self) -> bool {
+        self.frame.MSGTYPE & pcan::PCAN_MESSAGE_ERRFRAME as u8 != 0
+    }
+
+    /// The transmitting node is error passive.
+    pub fn is_error_state_indicator(&self) -> bool {
+        self.frame.MSGTYPE & pcan::PCAN_MESSAGE_ESI as u8 != 0
+    }
+
+    pub fn set_error_state_indicator(&mut self, value: bool) {
+        match value {
+            true => self.frame.MSGTYPE |= pcan::PCAN_MESSAGE_ESI as u8,
+            false => self.frame.MSGTYPE &= !(pcan::PCAN_MESSAGE_ESI as u8),
         }
     }
 
@@ -190,17 +276,71 @@ impl CanFdFrame {
         }
     }
 
+    /// Replaces the CAN-ID, keeping the frame type and flags.
+    pub fn set_can_id(&mut self, can_id: u32) {
+        self.frame.ID = match self.is_extended_frame() {
+            true => can_id & EXTENDED_MASK,
+            false => can_id & STANDARD_MASK,
+        };
+    }
+
+    /// Data length code, see [dlc_to_len] for the number of data bytes.
     pub fn dlc(&self) -> u8 {
         self.frame.DLC
     }
 
     pub fn data(&self) -> &[u8] {
-        &self.frame.DATA[0..self.dlc() as usize]
+        &self.frame.DATA[0..dlc_to_len(self.dlc())]
     }
 
     pub fn mut_data(&mut self) -> &mut [u8] {
-        let dlc = self.dlc();
-        &mut self.frame.DATA[0..dlc as usize]
+        let len = dlc_to_len(self.dlc());
+        &mut self.frame.DATA[0..len]
+    }
+}
+
+/// Classic frame, e.g. to be sent on a channel opened in FD mode.
+impl From<CanFrame> for CanFdFrame {
+    fn from(value: CanFrame) -> Self {
+        let mut data = [0u8; 64];
+        data[..8].copy_from_slice(&value.frame.DATA);
+        CanFdFrame {
+            frame: pcan::TPCANMsgFD {
+                ID: value.frame.ID,
+                MSGTYPE: value.frame.MSGTYPE,
+                DLC: value.frame.LEN,
+                DATA: data,
+            },
+        }
+    }
+}
+
+/// Number of data bytes of a CAN FD frame with the data length code `dlc`.
+pub fn dlc_to_len(dlc: u8) -> usize {
+    match dlc {
+        0..=8 => dlc as usize,
+        9 => 12,
+        10 => 16,
+        11 => 20,
+        12 => 24,
+        13 => 32,
+        14 => 48,
+        _ => 64,
+    }
+}
+
+/// Smallest data length code of a CAN FD frame able to carry `len` data bytes. CAN FD frames
+/// only support 0 to 8, 12, 16, 20, 24, 32, 48 and 64 data bytes, other lengths are padded.
+pub fn len_to_dlc(len: usize) -> u8 {
+    match len {
+        0..=8 => len as u8,
+        9..=12 => 9,
+        13..=16 => 10,
+        17..=20 => 11,
+        21..=24 => 12,
+        25..=32 => 13,
+        33..=48 => 14,
+        _ => 15,
     }
 }
 
@@ -237,6 +377,25 @@ pub struct Timestamp {
     timestamp: pcan::TPCANTimestamp,
 }
 
+impl Timestamp {
+    pub fn from_micros(micros: u64) -> Timestamp {
+        let millis = micros / 1000;
+        Timestamp {
+            timestamp: pcan::TPCANTimestamp {
+                micros: (micros % 1000) as u16,
+                millis: millis as u32,
+                millis_overflow: (millis >> 32) as u16,
+            },
+        }
+    }
+
+    /// Total number of microseconds represented by the timestamp.
+    pub fn as_micros(&self) -> u64 {
+        let millis = ((self.timestamp.millis_overflow as u64) << 32) | self.timestamp.millis as u64;
+        millis * 1000 + self.timestamp.micros as u64
+    }
+}
+
 impl Default for Timestamp {
     fn default() -> Timestamp {
         Timestamp {
@@ -267,6 +426,7 @@ impl PartialEq for Timestamp {
     }
 }
 
+/// Socket of any bus type, e.g. of an [AnyBus](crate::bus::AnyBus) chosen at runtime.
 #[derive(Debug, PartialEq)]
 pub struct CanSocket {
     handle: u16,
@@ -283,8 +443,96 @@ impl CanSocket {
             Err(_) => Err(PcanError::Unknown),
         }
     }
+
+    /// Opens the bus in FD mode. The bitrate is given in the notation of the driver, e.g.
+    /// [FD_BITRATE_500K_2M].
+    pub fn open_fd<T: Bus>(bus: T, bitrate: &str) -> Result<CanSocket, PcanError> {
+        let handle = bus.channel();
+        let mut bitrate = CString::new(bitrate)
+            .map_err(|_| PcanError::IllParamVal)?
+            .into_bytes_with_nul();
+        let code = unsafe { pcan::CAN_InitializeFD(handle, bitrate.as_mut_ptr() as *mut c_char) };
+
+        match PcanOkError::try_from(code) {
+            Ok(PcanOkError::Ok) => Ok(CanSocket { handle }),
+            Ok(PcanOkError::Err(err)) => Err(err),
+            Err(_) => Err(PcanError::Unknown),
+        }
+    }
+
+    pub fn clear(&self) -> Result<(), PcanError> {
+        let code = unsafe { pcan::CAN_Reset(self.handle) };
+
+        match PcanOkError::try_from(code) {
+            Ok(PcanOkError::Ok) => Ok(()),
+            Ok(PcanOkError::Err(err)) => Err(err),
+            Err(_) => Err(PcanError::Unknown),
+        }
+    }
+}
+
+/// FD bitrate of 500 kbit/s in the arbitration and 2 Mbit/s in the data phase at 80 MHz.
+pub const FD_BITRATE_500K_2M: &str = "f_clock_mhz=80, nom_brp=2, nom_tseg1=63, nom_tseg2=16, \
+                                      nom_sjw=16, data_brp=2, data_tseg1=15, data_tseg2=4, \
+                                      data_sjw=4";
+
+impl Drop for CanSocket {
+    fn drop(&mut self) {
+        unsafe { pcan::CAN_Uninitialize(self.handle) };
+    }
+}
+
+impl Socket for CanSocket {
+    fn handle(&self) -> u16 {
+        self.handle
+    }
 }
 
+impl Channel for CanSocket {
+    fn channel(&self) -> u16 {
+        self.handle
+    }
+}
+
+impl HasRecvCan for CanSocket {}
+impl HasSendCan for CanSocket {}
+
+impl HasRecvCanFd for CanSocket {}
+impl HasSendCanFd for CanSocket {}
+
+impl HasNominalBusSpeed for CanSocket {}
+
+impl HasDataBusSpeed for CanSocket {}
+
+impl HasFirmwareVersion for CanSocket {}
+
+impl HasListenOnly for CanSocket {}
+impl HasSetListenOnly for CanSocket {}
+
+impl HasMessageFilter for CanSocket {}
+impl HasSetMessageFilter for CanSocket {}
+
+impl HasReceiveStatus for CanSocket {}
+impl HasSetReceiveStatus for CanSocket {}
+
+impl HasAllowStatusFrames for CanSocket {}
+impl HasSetAllowStatusFrames for CanSocket {}
+
+impl HasAllowRTRFrames for CanSocket {}
+impl HasSetAllowRTRFrames for CanSocket {}
+
+impl HasAllowErrorFrames for CanSocket {}
+impl HasSetAllowErrorFrames for CanSocket {}
+
+impl HasAllowEchoFrames for CanSocket {}
+impl HasSetAllowEchoFrames for CanSocket {}
+
+impl HasAcceptanceFilter11Bit for CanSocket {}
+impl HasSetAcceptanceFilter11Bit for CanSocket {}
+
+impl HasAcceptanceFilter29Bit for CanSocket {}
+impl HasSetAcceptanceFilter29Bit for CanSocket {}
+
 trait HasRecvCan {}
 
 pub trait RecvCan {
@@ -311,13 +559,27 @@ pub trait SendCanFd {
     fn send_fd(&self, frame: CanFdFrame) -> Result<(), PcanError>;
 }
 
+/// State of the bus as reported by `CAN_GetStatus`, ordered from error-free to bus-off.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum BusState {
+    Ok,
+    Light,
+    Heavy,
+    Passive,
+    Off,
+}
+
+pub trait BusStatus {
+    fn bus_status(&self) -> Result<BusState, PcanError>;
+}
+
 trait Socket {
     fn handle(&self) -> u16;
 }
 
 /* Baudrate */
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Baudrate {
     Baud1M,
     Baud800K,
@@ -357,6 +619,88 @@ impl From<Baudrate> for u16 {
     }
 }
 
+const BAUDRATES: [Baudrate; 14] = [
+    Baudrate::Baud1M,
+    Baudrate::Baud800K,
+    Baudrate::Baud500K,
+    Baudrate::Baud250K,
+    Baudrate::Baud125K,
+    Baudrate::Baud100K,
+    Baudrate::Baud95K,
+    Baudrate::Baud83,
+    Baudrate::Baud50K,
+    Baudrate::Baud47K,
+    Baudrate::Baud33K,
+    Baudrate::Baud20K,
+    Baudrate::Baud10K,
+    Baudrate::Baud5K,
+];
+
+impl Baudrate {
+    /// Returns the bitrate whose [`bits_per_second`](Self::bits_per_second) equals `bits`.
+    pub fn from_bits_per_second(bits: u32) -> Option<Baudrate> {
+        BAUDRATES
+            .iter()
+            .copied()
+            .find(|baudrate| baudrate.bits_per_second() == bits)
+    }
+
+    pub fn bits_per_second(&self) -> u32 {
+        match self {
+            Baudrate::Baud1M => 1_000_000,
+            Baudrate::Baud800K => 800_000,
+            Baudrate::Baud500K => 500_000,
+            Baudrate::Baud250K => 250_000,
+            Baudrate::Baud125K => 125_000,
+            Baudrate::Baud100K => 100_000,
+            Baudrate::Baud95K => 95_238,
+            Baudrate::Baud83 => 83_333,
+            Baudrate::Baud50K => 50_000,
+            Baudrate::Baud47K => 47_619,
+            Baudrate::Baud33K => 33_333,
+            Baudrate::Baud20K => 20_000,
+            Baudrate::Baud10K => 10_000,
+            Baudrate::Baud5K => 5_000,
+        }
+    }
+}
+
+/// The text does not denote a supported bitrate.
+#[derive(Debug, PartialEq)]
+pub struct ParseBaudrateError;
+
+impl fmt::Display for ParseBaudrateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unsupported bitrate, expected e.g. 500k or 1M")
+    }
+}
+
+impl std::error::Error for ParseBaudrateError {}
+
+impl FromStr for Baudrate {
+    type Err = ParseBaudrateError;
+
+    /// Parses bitrates like `500k`, `1M`, `83.333k` or `125000`. The value must lie within
+    /// 1 bit/s of a supported bitrate, so `83.333k` matches 83333 bit/s but `83.9k` is rejected.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim().to_ascii_lowercase();
+        let (number, factor) = match text.strip_suffix('m') {
+            Some(number) => (number, 1_000_000.0),
+            None => match text.strip_suffix('k') {
+                Some(number) => (number, 1_000.0),
+                None => (text.as_str(), 1.0),
+            },
+        };
+        let bitrate = number.parse::<f64>().map_err(|_| ParseBaudrateError)? * factor;
+
+        BAUDRATES
+            .iter()
+            .copied()
+            .find(|baudrate| (bitrate - baudrate.bits_per_second() as f64).abs() <= 1.0)
+            .ok_or(ParseBaudrateError)
+    }
+}
+
 /* CanRead trait implementation */
 
 impl<T: HasRecvCan + Socket> RecvCan for T {
@@ -471,6 +815,31 @@ impl<T: HasSendCanFd + Socket> SendCanFd for T {
     }
 }
 
+/* BusStatus trait implementation */
+
+impl<T: Socket> BusStatus for T {
+    fn bus_status(&self) -> Result<BusState, PcanError> {
+        let code = unsafe { pcan::CAN_GetStatus(self.handle()) };
+
+        // The driver may combine several bus error flags, the most severe one wins.
+        if code & pcan::PCAN_ERROR_BUSOFF != 0 {
+            Ok(BusState::Off)
+        } else if code & pcan::PCAN_ERROR_BUSPASSIVE != 0 {
+            Ok(BusState::Passive)
+        } else if code & pcan::PCAN_ERROR_BUSHEAVY != 0 {
+            Ok(BusState::Heavy)
+        } else if code & pcan::PCAN_ERROR_BUSLIGHT != 0 {
+            Ok(BusState::Light)
+        } else {
+            match PcanOkError::try_from(code) {
+                Ok(PcanOkError::Ok) => Ok(BusState::Ok),
+                Ok(PcanOkError::Err(err)) => Err(err),
+                Err(_) => Err(PcanError::Unknown),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -511,6 +880,19 @@ mod tests {
             CanFrame::new(0x20, MessageType::Extended, &[0, 1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
     }
 
+    #[test]
+    fn can_frame_new_remote_001() {
+        let can_frame = CanFrame::new_remote(0x20, MessageType::Extended, 4).unwrap();
+
+        assert!(can_frame.is_remote_frame());
+        assert!(can_frame.is_extended_frame());
+        assert_eq!(can_frame.dlc(), 4);
+        assert!(!CanFrame::new(0x20, MessageType::Standard, &[])
+            .unwrap()
+            .is_remote_frame());
+        assert!(CanFrame::new_remote(0x20, MessageType::Standard, 9).is_err());
+    }
+
     /* CAN FD FRAME */
 
     #[test]
@@ -542,6 +924,89 @@ mod tests {
             CanFdFrame::new(0x20, MessageType::Standard, &(0..65u8).collect::<Vec<_>>()).unwrap();
     }
 
+    #[test]
+    fn can_fd_frame_new_005() {
+        let can_frame = CanFdFrame::new(0x1234, MessageType::Extended, &[0xAA; 10]).unwrap();
+
+        assert!(can_frame.is_extended_frame());
+        assert_eq!(can_frame.can_id(), 0x1234);
+        assert_eq!(can_frame.dlc(), 9);
+        assert_eq!(can_frame.data().len(), 12);
+        assert_eq!(&can_frame.data()[10..], &[0, 0]);
+    }
+
+    #[test]
+    fn baudrate_from_str_001() {
+        assert_eq!("500k".parse(), Ok(Baudrate::Baud500K));
+        assert_eq!("1M".parse(), Ok(Baudrate::Baud1M));
+        assert_eq!("83.333k".parse(), Ok(Baudrate::Baud83));
+        assert_eq!("125000".parse(), Ok(Baudrate::Baud125K));
+        assert_eq!("2M".parse::<Baudrate>(), Err(ParseBaudrateError));
+        assert_eq!("fast".parse::<Baudrate>(), Err(ParseBaudrateError));
+    }
+
+    #[test]
+    fn baudrate_from_str_002() {
+        assert_eq!("95.238k".parse(), Ok(Baudrate::Baud95K));
+        assert_eq!("47619".parse(), Ok(Baudrate::Baud47K));
+        assert_eq!("500.9k".parse::<Baudrate>(), Err(ParseBaudrateError));
+        assert_eq!("500999".parse::<Baudrate>(), Err(ParseBaudrateError));
+        assert_eq!("83.9k".parse::<Baudrate>(), Err(ParseBaudrateError));
+        assert_eq!("-500k".parse::<Baudrate>(), Err(ParseBaudrateError));
+        assert_eq!("nan".parse::<Baudrate>(), Err(ParseBaudrateError));
+    }
+
+    #[test]
+    fn baudrate_from_bits_per_second_001() {
+        assert_eq!(
+            Baudrate::from_bits_per_second(83_333),
+            Some(Baudrate::Baud83)
+        );
+        assert_eq!(
+            Baudrate::from_bits_per_second(1_000_000),
+            Some(Baudrate::Baud1M)
+        );
+        assert_eq!(Baudrate::from_bits_per_second(83_000), None);
+        assert_eq!(Baudrate::from_bits_per_second(0), None);
+    }
+
+    #[test]
+    fn can_fd_frame_from_001() {
+        let frame = CanFrame::new(0x1234, MessageType::Extended, &[1, 2, 3]).unwrap();
+        let frame = CanFdFrame::from(frame);
+
+        assert!(frame.is_extended_frame());
+        assert!(!frame.is_fd_frame());
+        assert!(!frame.is_bitrate_switch());
+        assert_eq!(frame.can_id(), 0x1234);
+        assert_eq!(frame.data(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn set_can_id_001() {
+        let mut frame = CanFdFrame::new(0x100, MessageType::Standard, &[1]).unwrap();
+        frame.set_bitrate_switch(true);
+        frame.set_can_id(0x1234);
+
+        assert_eq!(frame.can_id(), 0x234);
+        assert!(frame.is_fd_frame());
+        assert!(frame.is_bitrate_switch());
+
+        let mut frame = CanFrame::new_remote(0x100, MessageType::Extended, 4).unwrap();
+        frame.set_can_id(0x1234_5678);
+        assert_eq!(frame.can_id(), 0x1234_5678);
+        assert!(frame.is_remote_frame());
+    }
+
+    #[test]
+    fn timestamp_micros_001() {
+        let micros = (1u64 << 32) * 1000 + 123_456;
+        let timestamp = Timestamp::from_micros(micros);
+
+        assert_eq!(timestamp.as_micros(), micros);
+        assert_eq!(timestamp, Timestamp::from_micros(micros));
+    }
+
     #[test]
     #[should_panic]
     fn can_fd_frame_new_004() {
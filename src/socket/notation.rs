@@ -0,0 +1,245 @@
+//! Compact `ID#DATA` notation of the SocketCAN can-utils.
+//!
+//! * `123#DEADBEEF` standard frame, `0000ABCD#00` extended frame with eight ID digits
+//! * `123#R` and `123#R4` remote frames, optionally with the requested length
+//! * `123##1DEADBEEF` CAN FD frame, the digit after `##` holds the flags BRS `1` and ESI `2`
+//!
+//! Data bytes may be separated by dots, e.g. `123#DE.AD.BE.EF`.
+
+use crate::socket::{
+    CanFdFrame, CanFrame, FrameConstructionError, MessageType, EXTENDED_MASK, STANDARD_MASK,
+};
+use std::fmt;
+use std::str::FromStr;
+
+const FLAG_BRS: u8 = 0x01;
+const FLAG_ESI: u8 = 0x02;
+
+#[derive(Debug, PartialEq)]
+pub enum ParseFrameError {
+    /// The `#` separator is missing or the frame type does not match, e.g. `##` for a [CanFrame].
+    InvalidFormat,
+    InvalidId,
+    InvalidData,
+    Frame(FrameConstructionError),
+}
+
+impl fmt::Display for ParseFrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseFrameError::InvalidFormat => f.write_str("expected ID#DATA or ID##FLAGS DATA"),
+            ParseFrameError::InvalidId => f.write_str("expected an ID of 3 or 8 hex digits"),
+            ParseFrameError::InvalidData => f.write_str("expected data as pairs of hex digits"),
+            ParseFrameError::Frame(FrameConstructionError::TooMuchData) => {
+                f.write_str("too many data bytes")
+            }
+            ParseFrameError::Frame(FrameConstructionError::CanIdMessageTypeMismatch) => {
+                f.write_str("ID does not fit the frame type")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseFrameError {}
+
+impl From<FrameConstructionError> for ParseFrameError {
+    fn from(value: FrameConstructionError) -> Self {
+        ParseFrameError::Frame(value)
+    }
+}
+
+fn parse_id(id: &str) -> Result<(u32, MessageType), ParseFrameError> {
+    let (msg_type, mask) = match id.len() {
+        1..=3 => (MessageType::Standard, STANDARD_MASK),
+        8 => (MessageType::Extended, EXTENDED_MASK),
+        _ => return Err(ParseFrameError::InvalidId),
+    };
+    if !id.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(ParseFrameError::InvalidId);
+    }
+    match u32::from_str_radix(id, 16) {
+        Ok(can_id) if can_id <= mask => Ok((can_id, msg_type)),
+        _ => Err(ParseFrameError::InvalidId),
+    }
+}
+
+pub(crate) fn parse_data(data: &str) -> Result<Vec<u8>, ParseFrameError> {
+    let digits: Vec<u8> = data.bytes().filter(|byte| *byte != b'.').collect();
+    if digits.len() & 1 != 0 {
+        return Err(ParseFrameError::InvalidData);
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or(ParseFrameError::InvalidData)
+        })
+        .collect()
+}
+
+fn write_id(f: &mut fmt::Formatter<'_>, can_id: u32, extended: bool) -> fmt::Result {
+    match extended {
+        true => write!(f, "{:08X}", can_id),
+        false => write!(f, "{:03X}", can_id),
+    }
+}
+
+/* CanFrame */
+
+impl FromStr for CanFrame {
+    type Err = ParseFrameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (id, data) = s.split_once('#').ok_or(ParseFrameError::InvalidFormat)?;
+        if data.starts_with('#') {
+            return Err(ParseFrameError::InvalidFormat);
+        }
+        let (can_id, msg_type) = parse_id(id)?;
+
+        match data.strip_prefix('R') {
+            Some(length) => {
+                let dlc = match length {
+                    "" => 0,
+                    length => length.parse().map_err(|_| ParseFrameError::InvalidData)?,
+                };
+                Ok(CanFrame::new_remote(can_id, msg_type, dlc)?)
+            }
+            None => Ok(CanFrame::new(can_id, msg_type, &parse_data(data)?)?),
+        }
+    }
+}
+
+impl fmt::Display for CanFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_id(f, self.can_id(), self.is_extended_frame())?;
+        if self.is_remote_frame() {
+            return match self.dlc() {
+                0 => write!(f, "#R"),
+                dlc => write!(f, "#R{}", dlc),
+            };
+        }
+        write!(f, "#")?;
+        for byte in self.data() {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/* CanFdFrame */
+
+impl FromStr for CanFdFrame {
+    type Err = ParseFrameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (id, data) = s.split_once("##").ok_or(ParseFrameError::InvalidFormat)?;
+        let (can_id, msg_type) = parse_id(id)?;
+
+        let mut chars = data.chars();
+        let flags = chars
+            .next()
+            .and_then(|flags| flags.to_digit(16))
+            .ok_or(ParseFrameError::InvalidData)? as u8;
+
+        let mut frame = CanFdFrame::new(can_id, msg_type, &parse_data(chars.as_str())?)?;
+        frame.set_bitrate_switch(flags & FLAG_BRS != 0);
+        frame.set_error_state_indicator(flags & FLAG_ESI != 0);
+        Ok(frame)
+    }
+}
+
+impl fmt::Display for CanFdFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags = 0;
+        if self.is_bitrate_switch() {
+            flags |= FLAG_BRS;
+        }
+        if self.is_error_state_indicator() {
+            flags |= FLAG_ESI;
+        }
+
+        write_id(f, self.can_id(), self.is_extended_frame())?;
+        write!(f, "##{:X}", flags)?;
+        for byte in self.data() {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_frame_from_str_001() {
+        let frame: CanFrame = "123#DEADBEEF".parse().unwrap();
+        assert_eq!(
+            frame,
+            CanFrame::new(0x123, MessageType::Standard, &[0xDE, 0xAD, 0xBE, 0xEF]).unwrap()
+        );
+        assert_eq!(frame.to_string(), "123#DEADBEEF");
+
+        let frame: CanFrame = "0000ABCD#11.22".parse().unwrap();
+        assert!(frame.is_extended_frame());
+        assert_eq!(frame.to_string(), "0000ABCD#1122");
+
+        let frame: CanFrame = "7FF#R4".parse().unwrap();
+        assert!(frame.is_remote_frame());
+        assert_eq!(frame.dlc(), 4);
+        assert_eq!(frame.to_string(), "7FF#R4");
+        assert_eq!("7FF#R".parse::<CanFrame>().unwrap().to_string(), "7FF#R");
+        assert_eq!("001#".parse::<CanFrame>().unwrap().to_string(), "001#");
+    }
+
+    #[test]
+    fn can_frame_from_str_002() {
+        assert_eq!(
+            "123DEAD".parse::<CanFrame>(),
+            Err(ParseFrameError::InvalidFormat)
+        );
+        assert_eq!(
+            "123##0DEAD".parse::<CanFrame>(),
+            Err(ParseFrameError::InvalidFormat)
+        );
+        assert_eq!(
+            "12345#00".parse::<CanFrame>(),
+            Err(ParseFrameError::InvalidId)
+        );
+        assert_eq!(
+            "123#0".parse::<CanFrame>(),
+            Err(ParseFrameError::InvalidData)
+        );
+        assert_eq!(
+            "123#001122334455667788".parse::<CanFrame>(),
+            Err(ParseFrameError::Frame(FrameConstructionError::TooMuchData))
+        );
+    }
+
+    #[test]
+    fn can_frame_from_str_003() {
+        for text in ["FFF#", "800#", "FFFFFFFF#", "+12#"] {
+            assert_eq!(text.parse::<CanFrame>(), Err(ParseFrameError::InvalidId));
+        }
+        assert!("1FFFFFFF#".parse::<CanFrame>().is_ok());
+    }
+
+    #[test]
+    fn can_fd_frame_from_str_001() {
+        let frame: CanFdFrame = "18FF0001##3000102030405060708090A0B".parse().unwrap();
+        assert!(frame.is_extended_frame());
+        assert!(frame.is_bitrate_switch());
+        assert!(frame.is_error_state_indicator());
+        assert_eq!(frame.data().len(), 12);
+        assert_eq!(frame.to_string(), "18FF0001##3000102030405060708090A0B");
+
+        let frame: CanFdFrame = "123##0AA".parse().unwrap();
+        assert_eq!(frame.to_string(), "123##0AA");
+        assert_eq!(
+            "123#AA".parse::<CanFdFrame>(),
+            Err(ParseFrameError::InvalidFormat)
+        );
+    }
+}
@@ -0,0 +1,658 @@
+//! Bridge between a PCAN channel and a SocketCAN interface, e.g. `vcan0` or `can0`.
+//!
+//! Frames are forwarded in both directions, so tools like Wireshark or the can-utils observe and
+//! inject the traffic of a PCAN channel. Data and remote frames keep their identifier type, CAN FD
+//! frames keep the bit rate switch and error state indicator. PCAN error frames and bus state
+//! changes are sent as SocketCAN error frames, error frames received from SocketCAN are not
+//! forwarded to the PCAN channel. A virtual interface is created with
+//!
+//! ```text
+//! modprobe vcan
+//! ip link add dev vcan0 type vcan
+//! ip link set vcan0 mtu 72 up
+//! ```
+//!
+//! where the MTU of 72 bytes enables CAN FD frames.
+
+use crate::error::PcanError;
+use crate::format::Frame;
+use crate::replay::ReplaySink;
+use crate::socket::{CanFdFrame, CanFrame, MessageType};
+use std::ffi::CString;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Interval in which both sides are polled while no frames are pending.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+const CAN_EFF_FLAG: u32 = 0x8000_0000;
+const CAN_RTR_FLAG: u32 = 0x4000_0000;
+const CAN_ERR_FLAG: u32 = 0x2000_0000;
+const CAN_EFF_MASK: u32 = 0x1FFF_FFFF;
+const CAN_ERR_MASK: u32 = 0x1FFF_FFFF;
+const CAN_SFF_MASK: u32 = 0x0000_07FF;
+
+const CANFD_BRS: u8 = 0x01;
+const CANFD_ESI: u8 = 0x02;
+
+/// Size of a classic frame and of a CAN FD frame.
+const CAN_MTU: usize = 16;
+const CANFD_MTU: usize = 72;
+
+/* Error classes and details, see linux/can/error.h */
+
+const CAN_ERR_CRTL: u32 = 0x0004;
+const CAN_ERR_PROT: u32 = 0x0008;
+const CAN_ERR_BUSOFF: u32 = 0x0040;
+const CAN_ERR_CNT: u32 = 0x0200;
+
+const CAN_ERR_CRTL_RX_OVERFLOW: u8 = 0x01;
+const CAN_ERR_CRTL_WARNING: u8 = 0x04 | 0x08;
+const CAN_ERR_CRTL_PASSIVE: u8 = 0x10 | 0x20;
+
+const CAN_ERR_PROT_BIT: u8 = 0x01;
+const CAN_ERR_PROT_FORM: u8 = 0x02;
+const CAN_ERR_PROT_STUFF: u8 = 0x04;
+const CAN_ERR_PROT_TX: u8 = 0x80;
+
+/* SocketCanError */
+
+#[derive(Debug)]
+pub enum SocketCanError {
+    Io(io::Error),
+    Pcan(PcanError),
+    /// No interface with the given name exists.
+    InvalidInterface,
+}
+
+impl From<io::Error> for SocketCanError {
+    fn from(value: io::Error) -> Self {
+        SocketCanError::Io(value)
+    }
+}
+
+impl From<PcanError> for SocketCanError {
+    fn from(value: PcanError) -> Self {
+        SocketCanError::Pcan(value)
+    }
+}
+
+/* RawFrame */
+
+/// Layout of `struct canfd_frame`, whose first 16 bytes match `struct can_frame`.
+#[repr(C)]
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct RawFrame {
+    can_id: u32,
+    len: u8,
+    flags: u8,
+    res0: u8,
+    res1: u8,
+    data: [u8; 64],
+}
+
+impl Default for RawFrame {
+    fn default() -> Self {
+        RawFrame {
+            can_id: 0,
+            len: 0,
+            flags: 0,
+            res0: 0,
+            res1: 0,
+            data: [0; 64],
+        }
+    }
+}
+
+fn raw_id(can_id: u32, extended: bool) -> u32 {
+    match extended {
+        true => can_id | CAN_EFF_FLAG,
+        false => can_id,
+    }
+}
+
+/// Error frame of the error `class` with the controller and protocol details and the optional
+/// `(rx, tx)` error counters.
+fn raw_error(class: u32, controller: u8, protocol: u8, counters: Option<(u8, u8)>) -> RawFrame {
+    let mut raw = RawFrame {
+        can_id: CAN_ERR_FLAG | class,
+        len: 8,
+        ..RawFrame::default()
+    };
+    raw.data[1] = controller;
+    raw.data[2] = protocol;
+    if let Some((rx, tx)) = counters {
+        raw.can_id |= CAN_ERR_CNT;
+        raw.data[6] = tx;
+        raw.data[7] = rx;
+    }
+    raw
+}
+
+/// Error frame of a PCAN error frame with the error type `kind`, the direction and the error
+/// counters in the data bytes `[direction, capture, rx counter, tx counter]`.
+fn raw_pcan_error(kind: u32, data: &[u8]) -> RawFrame {
+    let mut protocol = match kind {
+        1 => CAN_ERR_PROT_BIT,
+        2 => CAN_ERR_PROT_FORM,
+        4 => CAN_ERR_PROT_STUFF,
+        _ => 0,
+    };
+    if data.first().is_some_and(|direction| *direction != 0) {
+        protocol |= CAN_ERR_PROT_TX;
+    }
+    let counters = match data {
+        [_, _, rx, tx, ..] => Some((*rx, *tx)),
+        _ => None,
+    };
+    raw_error(CAN_ERR_PROT, 0, protocol, counters)
+}
+
+/// Error frame for a bus state or overrun reported by the PCAN channel.
+fn raw_pcan_state(err: &PcanError) -> Option<RawFrame> {
+    match err {
+        PcanError::BusLight => Some(raw_error(CAN_ERR_CRTL, CAN_ERR_CRTL_WARNING, 0, None)),
+        PcanError::BusHeavy | PcanError::BusPassive => {
+            Some(raw_error(CAN_ERR_CRTL, CAN_ERR_CRTL_PASSIVE, 0, None))
+        }
+        PcanError::BusOff => Some(raw_error(CAN_ERR_BUSOFF, 0, 0, None)),
+        PcanError::Overrun | PcanError::QOverrun => {
+            Some(raw_error(CAN_ERR_CRTL, CAN_ERR_CRTL_RX_OVERFLOW, 0, None))
+        }
+        _ => None,
+    }
+}
+
+/// SocketCAN frame and its size for `frame`, `None` for status records.
+fn encode(frame: &Frame) -> Option<(RawFrame, usize)> {
+    let mut raw = RawFrame::default();
+    match frame {
+        Frame::Can(frame) if frame.is_error_frame() => {
+            Some((raw_pcan_error(frame.can_id(), frame.data()), CAN_MTU))
+        }
+        Frame::Can(frame) => {
+            raw.can_id = raw_id(frame.can_id(), frame.is_extended_frame());
+            if frame.is_remote_frame() {
+                raw.can_id |= CAN_RTR_FLAG;
+            }
+            raw.len = frame.dlc();
+            raw.data[..frame.data().len()].copy_from_slice(frame.data());
+            Some((raw, CAN_MTU))
+        }
+        Frame::CanFd(frame) if frame.is_error_frame() => {
+            Some((raw_pcan_error(frame.can_id(), frame.data()), CAN_MTU))
+        }
+        Frame::CanFd(frame) => {
+            raw.can_id = raw_id(frame.can_id(), frame.is_extended_frame());
+            raw.len = frame.data().len() as u8;
+            raw.data[..frame.data().len()].copy_from_slice(frame.data());
+            if !frame.is_fd_frame() {
+                return Some((raw, CAN_MTU));
+            }
+            if frame.is_bitrate_switch() {
+                raw.flags |= CANFD_BRS;
+            }
+            if frame.is_error_state_indicator() {
+                raw.flags |= CANFD_ESI;
+            }
+            Some((raw, CANFD_MTU))
+        }
+        Frame::Error { class, data } if *class != 0 => {
+            let len = data.len().min(8);
+            raw.can_id = CAN_ERR_FLAG | (class & CAN_ERR_MASK);
+            raw.len = len as u8;
+            raw.data[..len].copy_from_slice(&data[..len]);
+            Some((raw, CAN_MTU))
+        }
+        Frame::Error { data, .. } => {
+            // Error information bytes as recorded in trace files: [type, direction, capture, rx
+            // counter, tx counter].
+            let kind = data.first().copied().unwrap_or(0) as u32;
+            Some((raw_pcan_error(kind, data.get(1..).unwrap_or(&[])), CAN_MTU))
+        }
+        Frame::Status(_) => None,
+    }
+}
+
+/// Frame of the SocketCAN frame `raw` read with `size` bytes, `None` if it is malformed.
+fn decode(raw: &RawFrame, size: usize) -> Option<Frame> {
+    let msg_type = match raw.can_id & CAN_EFF_FLAG != 0 {
+        true => MessageType::Extended,
+        false => MessageType::Standard,
+    };
+    let can_id = match msg_type {
+        MessageType::Extended => raw.can_id & CAN_EFF_MASK,
+        MessageType::Standard => raw.can_id & CAN_SFF_MASK,
+    };
+
+    match size {
+        CAN_MTU if raw.can_id & CAN_ERR_FLAG != 0 => Some(Frame::Error {
+            class: raw.can_id & CAN_ERR_MASK,
+            data: raw.data[..raw.len.min(8) as usize].to_vec(),
+        }),
+        CAN_MTU if raw.can_id & CAN_RTR_FLAG != 0 => {
+            CanFrame::new_remote(can_id, msg_type, raw.len.min(8))
+                .ok()
+                .map(Frame::Can)
+        }
+        CAN_MTU => CanFrame::new(can_id, msg_type, &raw.data[..raw.len.min(8) as usize])
+            .ok()
+            .map(Frame::Can),
+        CANFD_MTU => {
+            let mut frame =
+                CanFdFrame::new(can_id, msg_type, raw.data.get(..raw.len as usize)?).ok()?;
+            frame.set_bitrate_switch(raw.flags & CANFD_BRS != 0);
+            frame.set_error_state_indicator(raw.flags & CANFD_ESI != 0);
+            Some(Frame::CanFd(frame))
+        }
+        _ => None,
+    }
+}
+
+/* SocketCan */
+
+/// Non-blocking raw socket bound to a SocketCAN interface, receiving CAN FD and error frames.
+#[derive(Debug)]
+pub struct SocketCan {
+    fd: OwnedFd,
+    interface: String,
+}
+
+impl SocketCan {
+    pub fn open(interface: &str) -> Result<SocketCan, SocketCanError> {
+        let name = CString::new(interface).map_err(|_| SocketCanError::InvalidInterface)?;
+        let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if index == 0 {
+            return Err(SocketCanError::InvalidInterface);
+        }
+
+        let fd = unsafe {
+            libc::socket(
+                libc::PF_CAN,
+                libc::SOCK_RAW | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+                libc::CAN_RAW,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        let socket = SocketCan {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            interface: String::from(interface),
+        };
+        socket.set_option(libc::CAN_RAW_FD_FRAMES, 1u32)?;
+        socket.set_option(libc::CAN_RAW_ERR_FILTER, CAN_ERR_MASK)?;
+
+        let mut address: libc::sockaddr_can = unsafe { mem::zeroed() };
+        address.can_family = libc::AF_CAN as libc::sa_family_t;
+        address.can_ifindex = index as libc::c_int;
+        let code = unsafe {
+            libc::bind(
+                socket.fd.as_raw_fd(),
+                &address as *const libc::sockaddr_can as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_can>() as libc::socklen_t,
+            )
+        };
+        match code {
+            0 => Ok(socket),
+            _ => Err(io::Error::last_os_error().into()),
+        }
+    }
+
+    fn set_option(&self, name: libc::c_int, value: u32) -> Result<(), SocketCanError> {
+        let code = unsafe {
+            libc::setsockopt(
+                self.fd.as_raw_fd(),
+                libc::SOL_CAN_RAW,
+                name,
+                &value as *const u32 as *const libc::c_void,
+                mem::size_of::<u32>() as libc::socklen_t,
+            )
+        };
+        match code {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error().into()),
+        }
+    }
+
+    pub fn interface(&self) -> &str {
+        &self.interface
+    }
+
+    /// Sends `frame`, status records are ignored. CAN FD frames require an MTU of 72 bytes.
+    pub fn send(&self, frame: &Frame) -> Result<(), SocketCanError> {
+        match encode(frame) {
+            Some((raw, size)) => self.write(&raw, size),
+            None => Ok(()),
+        }
+    }
+
+    fn write(&self, raw: &RawFrame, size: usize) -> Result<(), SocketCanError> {
+        let written = unsafe {
+            libc::write(
+                self.fd.as_raw_fd(),
+                raw as *const RawFrame as *const libc::c_void,
+                size,
+            )
+        };
+        match written {
+            -1 => Err(io::Error::last_os_error().into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the next received frame or `None` if no frame is pending.
+    pub fn recv(&self) -> Result<Option<Frame>, SocketCanError> {
+        loop {
+            let mut raw = RawFrame::default();
+            let read = unsafe {
+                libc::read(
+                    self.fd.as_raw_fd(),
+                    &mut raw as *mut RawFrame as *mut libc::c_void,
+                    CANFD_MTU,
+                )
+            };
+            if read < 0 {
+                let err = io::Error::last_os_error();
+                return match err.kind() {
+                    io::ErrorKind::WouldBlock => Ok(None),
+                    _ => Err(err.into()),
+                };
+            }
+            if let Some(frame) = decode(&raw, read as usize) {
+                return Ok(Some(frame));
+            }
+        }
+    }
+}
+
+impl AsRawFd for SocketCan {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+/* Bridge */
+
+/// Counters of a bridge.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct BridgeReport {
+    /// Frames, error frames and bus state changes sent to the SocketCAN interface.
+    pub to_socketcan: u64,
+    /// Frames sent to the PCAN channel.
+    pub to_pcan: u64,
+    /// Frames not forwarded, e.g. error frames from SocketCAN or frames rejected while sending.
+    pub dropped: u64,
+}
+
+/// Forwards frames between a PCAN channel, connected through a
+/// [CanSink](crate::replay::CanSink) or a [CanFdSink](crate::replay::CanFdSink), and a
+/// [SocketCan] interface.
+#[derive(Debug, Default, Clone)]
+pub struct Bridge {
+    report: BridgeReport,
+    /// Error frame of the last bus state of the PCAN channel.
+    state: Option<RawFrame>,
+}
+
+impl Bridge {
+    pub fn new() -> Bridge {
+        Bridge::default()
+    }
+
+    pub fn report(&self) -> &BridgeReport {
+        &self.report
+    }
+
+    /// Forwards the frames pending on both sides, returns whether any frame was pending.
+    pub fn poll(
+        &mut self,
+        pcan: &mut dyn ReplaySink,
+        socketcan: &SocketCan,
+    ) -> Result<bool, SocketCanError> {
+        let pending = self.forward_to_socketcan(pcan, socketcan)?;
+        Ok(self.forward_to_pcan(socketcan, pcan)? || pending)
+    }
+
+    /// Forwards frames between both sides until `running` is cleared.
+    pub fn run(
+        &mut self,
+        pcan: &mut dyn ReplaySink,
+        socketcan: &SocketCan,
+        running: &AtomicBool,
+    ) -> Result<(), SocketCanError> {
+        while running.load(Ordering::Relaxed) {
+            if !self.poll(pcan, socketcan)? {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+        Ok(())
+    }
+
+    fn forward_to_socketcan(
+        &mut self,
+        pcan: &mut dyn ReplaySink,
+        socketcan: &SocketCan,
+    ) -> Result<bool, SocketCanError> {
+        let mut pending = false;
+        loop {
+            let frame = match pcan.recv_frame() {
+                Ok(Some(frame)) => frame,
+                Ok(None) => return Ok(pending),
+                Err(err) => {
+                    // The next frames are received by the next poll.
+                    if let Some(raw) = self.pcan_state(err)? {
+                        self.write(socketcan, &raw, CAN_MTU);
+                    }
+                    return Ok(pending);
+                }
+            };
+            pending = true;
+            if let Some((raw, size)) = encode(&frame) {
+                self.write(socketcan, &raw, size);
+            }
+        }
+    }
+
+    /// Error frame to send for an error of the PCAN channel. Bus states are sent once per
+    /// change, overruns every time.
+    fn pcan_state(&mut self, err: PcanError) -> Result<Option<RawFrame>, SocketCanError> {
+        let overrun = matches!(err, PcanError::Overrun | PcanError::QOverrun);
+        let Some(raw) = raw_pcan_state(&err) else {
+            return Err(err.into());
+        };
+        if !overrun {
+            if self.state == Some(raw) {
+                return Ok(None);
+            }
+            self.state = Some(raw);
+        }
+        Ok(Some(raw))
+    }
+
+    fn write(&mut self, socketcan: &SocketCan, raw: &RawFrame, size: usize) {
+        match socketcan.write(raw, size) {
+            Ok(()) => self.report.to_socketcan += 1,
+            Err(_) => self.report.dropped += 1,
+        }
+    }
+
+    fn forward_to_pcan(
+        &mut self,
+        socketcan: &SocketCan,
+        pcan: &mut dyn ReplaySink,
+    ) -> Result<bool, SocketCanError> {
+        let mut pending = false;
+        while let Some(frame) = socketcan.recv()? {
+            pending = true;
+            if matches!(frame, Frame::Error { .. }) {
+                self.report.dropped += 1;
+                continue;
+            }
+            match pcan.send_frame(&frame) {
+                Ok(()) => self.report.to_pcan += 1,
+                Err(_) => self.report.dropped += 1,
+            }
+        }
+        Ok(pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::MemorySink;
+    use std::time::Instant;
+
+    fn round_trip(frame: Frame) -> Frame {
+        let (raw, size) = encode(&frame).unwrap();
+        decode(&raw, size).unwrap()
+    }
+
+    #[test]
+    fn raw_frame_001() {
+        assert_eq!(mem::size_of::<RawFrame>(), CANFD_MTU);
+    }
+
+    #[test]
+    fn encode_001() {
+        let frame = CanFrame::new(0x123, MessageType::Standard, &[1, 2, 3]).unwrap();
+        let (raw, size) = encode(&Frame::Can(frame)).unwrap();
+        assert_eq!((raw.can_id, raw.len, size), (0x123, 3, CAN_MTU));
+        assert_eq!(round_trip(Frame::Can(frame)), Frame::Can(frame));
+
+        let frame = CanFrame::new(0x18DA_F110, MessageType::Extended, &[0xAA; 8]).unwrap();
+        let (raw, _) = encode(&Frame::Can(frame)).unwrap();
+        assert_eq!(raw.can_id, 0x98DA_F110);
+        assert_eq!(round_trip(Frame::Can(frame)), Frame::Can(frame));
+
+        let frame = CanFrame::new_remote(0x7DF, MessageType::Standard, 8).unwrap();
+        let (raw, _) = encode(&Frame::Can(frame)).unwrap();
+        assert_eq!((raw.can_id, raw.len), (0x4000_07DF, 8));
+        assert_eq!(round_trip(Frame::Can(frame)), Frame::Can(frame));
+
+        assert!(encode(&Frame::Status(String::from("BUSHEAVY"))).is_none());
+    }
+
+    #[test]
+    fn encode_002() {
+        let mut frame = CanFdFrame::new(0x1F0, MessageType::Standard, &[7; 12]).unwrap();
+        frame.set_bitrate_switch(true);
+        let (raw, size) = encode(&Frame::CanFd(frame)).unwrap();
+        assert_eq!((raw.len, raw.flags, size), (12, CANFD_BRS, CANFD_MTU));
+        assert_eq!(round_trip(Frame::CanFd(frame)), Frame::CanFd(frame));
+
+        // Classic frames read with recv_fd stay classic frames.
+        let classic = CanFrame::new(0x100, MessageType::Standard, &[1, 2]).unwrap();
+        let (raw, size) = encode(&Frame::CanFd(CanFdFrame::from(classic))).unwrap();
+        assert_eq!((raw.len, size), (2, CAN_MTU));
+    }
+
+    #[test]
+    fn encode_003() {
+        let (raw, size) = encode(&Frame::Error {
+            class: 0,
+            data: vec![4, 1, 0, 0x10, 0x80],
+        })
+        .unwrap();
+        assert_eq!(raw.can_id, CAN_ERR_FLAG | CAN_ERR_PROT | CAN_ERR_CNT);
+        assert_eq!(raw.data[2], CAN_ERR_PROT_STUFF | CAN_ERR_PROT_TX);
+        assert_eq!((raw.data[6], raw.data[7], size), (0x80, 0x10, CAN_MTU));
+
+        let raw = raw_pcan_state(&PcanError::BusOff).unwrap();
+        assert_eq!(raw.can_id, CAN_ERR_FLAG | CAN_ERR_BUSOFF);
+        assert!(raw_pcan_state(&PcanError::IllHw).is_none());
+    }
+
+    #[test]
+    fn decode_001() {
+        let raw = RawFrame {
+            can_id: CAN_ERR_FLAG | CAN_ERR_CRTL,
+            len: 8,
+            ..RawFrame::default()
+        };
+        let frame = decode(&raw, CAN_MTU).unwrap();
+        assert_eq!(
+            frame,
+            Frame::Error {
+                class: CAN_ERR_CRTL,
+                data: vec![0; 8]
+            }
+        );
+        // SocketCAN error frames are written back unchanged.
+        let (encoded, size) = encode(&frame).unwrap();
+        assert_eq!(
+            (encoded.can_id, encoded.len, size),
+            (raw.can_id, 8, CAN_MTU)
+        );
+        assert_eq!(decode(&raw, 8), None);
+
+        let raw = RawFrame {
+            len: 65,
+            ..RawFrame::default()
+        };
+        assert_eq!(decode(&raw, CANFD_MTU), None);
+    }
+
+    #[test]
+    fn bridge_001() {
+        let mut bridge = Bridge::new();
+        assert!(bridge.pcan_state(PcanError::BusLight).unwrap().is_some());
+        assert_eq!(bridge.pcan_state(PcanError::BusLight).unwrap(), None);
+        assert!(bridge.pcan_state(PcanError::Overrun).unwrap().is_some());
+        assert!(bridge.pcan_state(PcanError::Overrun).unwrap().is_some());
+        assert_eq!(bridge.pcan_state(PcanError::BusLight).unwrap(), None);
+        assert!(bridge.pcan_state(PcanError::BusOff).unwrap().is_some());
+        assert!(matches!(
+            bridge.pcan_state(PcanError::IllHw),
+            Err(SocketCanError::Pcan(PcanError::IllHw))
+        ));
+    }
+
+    /// Needs a virtual interface `vcan0` with an MTU of 72 bytes, see the module docs.
+    #[test]
+    #[ignore]
+    fn bridge_vcan_001() {
+        let classic = Frame::Can(CanFrame::new(0x123, MessageType::Standard, &[1, 2, 3]).unwrap());
+        let mut fd = CanFdFrame::new(0x18DA_F110, MessageType::Extended, &[5; 20]).unwrap();
+        fd.set_bitrate_switch(true);
+        let fd = Frame::CanFd(fd);
+        let error = Frame::Error {
+            class: CAN_ERR_CRTL,
+            data: vec![0, CAN_ERR_CRTL_PASSIVE, 0, 0, 0, 0, 0, 0],
+        };
+        let frames = [classic, fd, error];
+
+        let bridged = SocketCan::open("vcan0").unwrap();
+        let peer = SocketCan::open("vcan0").unwrap();
+        let mut pcan = MemorySink::new();
+        pcan.pending = frames.iter().cloned().collect();
+        let mut bridge = Bridge::new();
+
+        let mut received = Vec::new();
+        for frame in &frames {
+            peer.send(frame).unwrap();
+        }
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while (received.len() < frames.len() || pcan.sent.len() < 2) && Instant::now() < deadline {
+            bridge.poll(&mut pcan, &bridged).unwrap();
+            while let Some(frame) = peer.recv().unwrap() {
+                received.push(frame);
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+
+        // Both directions keep the frames, error frames are only forwarded to SocketCAN.
+        assert_eq!(received, frames);
+        assert_eq!(pcan.sent, frames[..2]);
+        let report = BridgeReport {
+            to_socketcan: 3,
+            to_pcan: 2,
+            dropped: 1,
+        };
+        assert_eq!(bridge.report(), &report);
+    }
+}
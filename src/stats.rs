@@ -0,0 +1,436 @@
+//! Bus load, frame rates and per-ID timing of the traffic on one channel.
+//!
+//! [BusStatistics] takes the frames with their timestamps, received from any socket or read from
+//! a recording, and summarizes the last window of time in a [Snapshot]. The bus time of a frame is
+//! estimated from its length with one stuff bit in ten stuffed bits, between none and the worst
+//! case of one in four. The data phase of CAN FD frames with bitrate switch is timed with the data
+//! bitrate.
+
+use crate::error::PcanError;
+use crate::format::{Frame, Record};
+use crate::info::{DataBusSpeed, NominalBusSpeed};
+use crate::socket::Timestamp;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+/// Window of a new [BusStatistics].
+const DEFAULT_WINDOW: Duration = Duration::from_secs(1);
+/// Stuffed bits including the estimated stuff bits.
+const STUFFING: f64 = 1.1;
+/// Error flag, error delimiter and intermission.
+const ERROR_FRAME_BITS: f64 = 17.0;
+
+/* BusTiming */
+
+/// Bitrates of a channel in bits per second.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct BusTiming {
+    pub nominal: u32,
+    /// Bitrate of the data phase of CAN FD frames with bitrate switch.
+    pub data: u32,
+}
+
+impl BusTiming {
+    /// Timing of a classic channel, the data bitrate equals the nominal bitrate.
+    pub fn new(nominal: u32) -> BusTiming {
+        BusTiming {
+            nominal,
+            data: nominal,
+        }
+    }
+
+    pub fn with_data_bitrate(mut self, data: u32) -> BusTiming {
+        self.data = data;
+        self
+    }
+
+    /// Reads both bitrates of a channel initialized for CAN FD.
+    pub fn read<T: NominalBusSpeed + DataBusSpeed>(channel: &T) -> Result<BusTiming, PcanError> {
+        Ok(BusTiming {
+            nominal: channel.nominal_bus_speed()?,
+            data: channel.data_bus_speed()?,
+        })
+    }
+
+    /// Estimated time of `frame` on the bus in seconds, zero for status records.
+    pub fn frame_time(&self, frame: &Frame) -> f64 {
+        let nominal = self.nominal as f64;
+        let (length, extended, fd, brs) = match frame {
+            Frame::Can(frame) if frame.is_error_frame() => return ERROR_FRAME_BITS / nominal,
+            Frame::CanFd(frame) if frame.is_error_frame() => return ERROR_FRAME_BITS / nominal,
+            Frame::Can(frame) => {
+                let length = match frame.is_remote_frame() {
+                    true => 0,
+                    false => frame.data().len(),
+                };
+                (length, frame.is_extended_frame(), false, false)
+            }
+            Frame::CanFd(frame) => (
+                frame.data().len(),
+                frame.is_extended_frame(),
+                frame.is_fd_frame(),
+                frame.is_bitrate_switch(),
+            ),
+            Frame::Error { .. } => return ERROR_FRAME_BITS / nominal,
+            Frame::Status(_) => return 0.0,
+        };
+        let length = length as f64;
+        if !fd {
+            // Start of frame up to the CRC, then delimiters, acknowledge, end of frame and
+            // intermission.
+            let stuffed = if extended { 54.0 } else { 34.0 } + 8.0 * length;
+            return (stuffed * STUFFING + 13.0) / nominal;
+        }
+
+        // Arbitration up to BRS and from the CRC delimiter on, the rest with the data bitrate.
+        let arbitration = if extended { 33.0 } else { 14.0 } * STUFFING + 13.0;
+        let crc = if length > 16.0 { 26.0 } else { 22.0 };
+        let data_phase = (5.0 + 8.0 * length) * STUFFING + crc;
+        match brs {
+            true => arbitration / nominal + data_phase / self.data as f64,
+            false => (arbitration + data_phase) / nominal,
+        }
+    }
+}
+
+/* Snapshot */
+
+/// Intervals between the frames of one CAN-ID.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Cycle {
+    pub mean: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    /// Standard deviation of the intervals.
+    pub jitter: Duration,
+}
+
+/// Frames of one CAN-ID.
+#[derive(Debug, PartialEq, Clone)]
+pub struct IdStatistics {
+    pub can_id: u32,
+    pub extended: bool,
+    /// Number of frames in the window.
+    pub count: u64,
+    /// Number of frames since the start.
+    pub total: u64,
+    /// Data length code of the latest frame.
+    pub dlc: u8,
+    /// Intervals ending in the window, `None` without any.
+    pub cycle: Option<Cycle>,
+}
+
+/// Summary of the window ending with the latest frame.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Snapshot {
+    /// Summarized time, shorter than the window until a whole window has passed.
+    pub window: Duration,
+    /// Estimated share of the time the bus was busy, 1.0 for a fully loaded bus.
+    pub load: f64,
+    /// Data and remote frames per second.
+    pub frame_rate: f64,
+    /// Error frames per second.
+    pub error_rate: f64,
+    /// Number of data and remote frames since the start.
+    pub frames: u64,
+    /// Number of error frames since the start.
+    pub error_frames: u64,
+    /// Statistics of every CAN-ID seen since the start, ordered by CAN-ID.
+    pub ids: Vec<IdStatistics>,
+}
+
+/* BusStatistics */
+
+/// Frame of the window with its bus time in seconds.
+#[derive(Debug)]
+struct Sample {
+    micros: u64,
+    time: f64,
+    error: bool,
+}
+
+#[derive(Debug, Default)]
+struct Track {
+    total: u64,
+    dlc: u8,
+    last: Option<u64>,
+    /// Frames of the window with the interval to the previous frame.
+    frames: VecDeque<(u64, Option<u64>)>,
+}
+
+/// Statistics of one channel, use one instance per channel or bus of a recording.
+#[derive(Debug)]
+pub struct BusStatistics {
+    timing: BusTiming,
+    window: u64,
+    /// First and latest timestamp of the current window in microseconds.
+    span: Option<(u64, u64)>,
+    samples: VecDeque<Sample>,
+    tracks: BTreeMap<(u32, bool), Track>,
+    frames: u64,
+    error_frames: u64,
+}
+
+impl BusStatistics {
+    pub fn new(timing: BusTiming) -> BusStatistics {
+        BusStatistics {
+            timing,
+            window: DEFAULT_WINDOW.as_micros() as u64,
+            span: None,
+            samples: VecDeque::new(),
+            tracks: BTreeMap::new(),
+            frames: 0,
+            error_frames: 0,
+        }
+    }
+
+    /// Sets the window the rates and intervals are measured over, one second by default.
+    pub fn with_window(mut self, window: Duration) -> BusStatistics {
+        self.window = (window.as_micros() as u64).max(1);
+        self
+    }
+
+    pub fn timing(&self) -> BusTiming {
+        self.timing
+    }
+
+    /// Forgets all frames.
+    pub fn clear(&mut self) {
+        self.span = None;
+        self.samples.clear();
+        self.tracks.clear();
+        self.frames = 0;
+        self.error_frames = 0;
+    }
+
+    /// Adds a frame received at `timestamp`. Status records are ignored and a timestamp before
+    /// the latest one starts a new window, e.g. when a recording is replayed in a loop.
+    pub fn push(&mut self, timestamp: Timestamp, frame: &Frame) {
+        let (can_id, extended, dlc, error) = match frame {
+            Frame::Can(frame) => (
+                frame.can_id(),
+                frame.is_extended_frame(),
+                frame.dlc(),
+                frame.is_error_frame(),
+            ),
+            Frame::CanFd(frame) => (
+                frame.can_id(),
+                frame.is_extended_frame(),
+                frame.dlc(),
+                frame.is_error_frame(),
+            ),
+            Frame::Error { .. } => (0, false, 0, true),
+            Frame::Status(_) => return,
+        };
+
+        let micros = timestamp.as_micros();
+        let start = match self.span {
+            Some((start, end)) if micros >= end => start,
+            _ => {
+                self.samples.clear();
+                for track in self.tracks.values_mut() {
+                    track.last = None;
+                    track.frames.clear();
+                }
+                micros
+            }
+        };
+        self.span = Some((start, micros));
+        self.samples.push_back(Sample {
+            micros,
+            time: self.timing.frame_time(frame),
+            error,
+        });
+        while self
+            .samples
+            .front()
+            .is_some_and(|sample| sample.micros + self.window <= micros)
+        {
+            self.samples.pop_front();
+        }
+
+        if error {
+            self.error_frames += 1;
+            return;
+        }
+        self.frames += 1;
+        let track = self.tracks.entry((can_id, extended)).or_default();
+        let interval = track.last.map(|last| micros - last);
+        track.frames.push_back((micros, interval));
+        while track
+            .frames
+            .front()
+            .is_some_and(|(time, _)| time + self.window <= micros)
+        {
+            track.frames.pop_front();
+        }
+        track.total += 1;
+        track.dlc = dlc;
+        track.last = Some(micros);
+    }
+
+    /// Adds the frame of a record, regardless of its bus and direction.
+    pub fn push_record(&mut self, record: &Record) {
+        self.push(record.timestamp, &record.frame);
+    }
+
+    /// Summarizes the window ending with the latest frame.
+    pub fn snapshot(&self) -> Snapshot {
+        let Some((start, end)) = self.span else {
+            return Snapshot::default();
+        };
+        let micros = (end - start).min(self.window);
+        let seconds = micros as f64 / 1_000_000.0;
+        let rate = |count: usize| match micros {
+            0 => 0.0,
+            _ => count as f64 / seconds,
+        };
+
+        let errors = self.samples.iter().filter(|sample| sample.error).count();
+        let busy = self.samples.iter().map(|sample| sample.time).sum::<f64>();
+        let ids = self
+            .tracks
+            .iter()
+            .map(|(&(can_id, extended), track)| {
+                let frames = track
+                    .frames
+                    .iter()
+                    .filter(|(time, _)| time + self.window > end)
+                    .collect::<Vec<_>>();
+                let intervals = frames
+                    .iter()
+                    .filter_map(|(_, interval)| *interval)
+                    .collect::<Vec<_>>();
+                IdStatistics {
+                    can_id,
+                    extended,
+                    count: frames.len() as u64,
+                    total: track.total,
+                    dlc: track.dlc,
+                    cycle: cycle(&intervals),
+                }
+            })
+            .collect();
+
+        Snapshot {
+            window: Duration::from_micros(micros),
+            load: if micros == 0 { 0.0 } else { busy / seconds },
+            frame_rate: rate(self.samples.len() - errors),
+            error_rate: rate(errors),
+            frames: self.frames,
+            error_frames: self.error_frames,
+            ids,
+        }
+    }
+}
+
+/// Cycle of intervals in microseconds.
+fn cycle(intervals: &[u64]) -> Option<Cycle> {
+    let min = *intervals.iter().min()?;
+    let max = *intervals.iter().max()?;
+    let count = intervals.len() as f64;
+    let mean = intervals.iter().sum::<u64>() as f64 / count;
+    let variance = intervals
+        .iter()
+        .map(|interval| (*interval as f64 - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    Some(Cycle {
+        mean: Duration::from_nanos((mean * 1000.0).round() as u64),
+        min: Duration::from_micros(min),
+        max: Duration::from_micros(max),
+        jitter: Duration::from_nanos((variance.sqrt() * 1000.0).round() as u64),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::socket::{CanFdFrame, CanFrame, MessageType};
+
+    fn frame(id: u32, data: &[u8]) -> Frame {
+        Frame::Can(CanFrame::new(id, MessageType::Standard, data).unwrap())
+    }
+
+    #[test]
+    fn frame_time_001() {
+        let timing = BusTiming::new(500_000);
+        // 111 bits without stuffing, about 121 with the estimate.
+        let time = timing.frame_time(&frame(0x123, &[0; 8]));
+        assert!((time * 500_000.0 - 120.8).abs() < 0.01);
+        let remote = CanFrame::new_remote(0x123, MessageType::Standard, 8).unwrap();
+        let time = timing.frame_time(&Frame::Can(remote));
+        assert!((time * 500_000.0 - 50.4).abs() < 0.01);
+
+        let timing = timing.with_data_bitrate(2_000_000);
+        let mut frame = CanFdFrame::new(0x123, MessageType::Standard, &[0; 64]).unwrap();
+        let slow = timing.frame_time(&Frame::CanFd(frame));
+        frame.set_bitrate_switch(true);
+        let fast = timing.frame_time(&Frame::CanFd(frame));
+        assert!(fast < slow / 2.0);
+        assert_eq!(
+            timing.frame_time(&Frame::Status(String::from("BUSOFF"))),
+            0.0
+        );
+    }
+
+    #[test]
+    fn bus_statistics_001() {
+        let mut statistics =
+            BusStatistics::new(BusTiming::new(500_000)).with_window(Duration::from_millis(100));
+        for (index, micros) in [0, 10_000, 20_500, 29_500, 40_000].into_iter().enumerate() {
+            statistics.push(
+                Timestamp::from_micros(micros),
+                &frame(0x100, &[index as u8]),
+            );
+        }
+        statistics.push(Timestamp::from_micros(45_000), &frame(0x7E0, &[1, 2]));
+        statistics.push(
+            Timestamp::from_micros(50_000),
+            &Frame::Error {
+                class: 0,
+                data: vec![0x01],
+            },
+        );
+
+        let snapshot = statistics.snapshot();
+        assert_eq!(snapshot.window, Duration::from_millis(50));
+        assert!((snapshot.frame_rate - 120.0).abs() < 1e-9);
+        assert!((snapshot.error_rate - 20.0).abs() < 1e-9);
+        assert_eq!((snapshot.frames, snapshot.error_frames), (6, 1));
+        assert!(snapshot.load > 0.0 && snapshot.load < 0.1);
+
+        let ids = &snapshot.ids;
+        assert_eq!((ids[0].can_id, ids[0].count, ids[0].dlc), (0x100, 5, 1));
+        let cycle = ids[0].cycle.unwrap();
+        assert_eq!(cycle.mean, Duration::from_millis(10));
+        assert_eq!(cycle.min, Duration::from_micros(9_000));
+        assert_eq!(cycle.max, Duration::from_micros(10_500));
+        assert_eq!(ids[1].cycle, None);
+    }
+
+    #[test]
+    fn bus_statistics_002() {
+        let mut statistics =
+            BusStatistics::new(BusTiming::new(500_000)).with_window(Duration::from_millis(100));
+        for micros in (0..=300_000).step_by(20_000) {
+            statistics.push(Timestamp::from_micros(micros), &frame(0x100, &[]));
+        }
+        statistics.push(Timestamp::from_micros(300_000), &frame(0x200, &[]));
+
+        // Frames older than the window are dropped.
+        let snapshot = statistics.snapshot();
+        assert_eq!(snapshot.window, Duration::from_millis(100));
+        assert_eq!(snapshot.ids[0].count, 5);
+        assert_eq!(snapshot.ids[0].total, 16);
+        assert!((snapshot.frame_rate - 60.0).abs() < 1e-9);
+        assert_eq!(snapshot.ids[0].cycle.unwrap().jitter, Duration::ZERO);
+
+        // An earlier timestamp starts a new window.
+        statistics.push(Timestamp::from_micros(0), &frame(0x100, &[]));
+        let snapshot = statistics.snapshot();
+        assert_eq!(snapshot.window, Duration::ZERO);
+        assert_eq!((snapshot.ids[0].count, snapshot.ids[0].cycle), (1, None));
+        assert_eq!(snapshot.ids[1].count, 0);
+    }
+}
@@ -199,7 +199,7 @@ pub fn set_default_trace_size<T: SetTraceSize>(value: &T) -> Result<(), PcanErro
 
 /* TRACE CONFIGURE traits */
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Copy, Clone)]
 pub enum TraceFile {
     Single,
     Segmented,
@@ -0,0 +1,296 @@
+//! Socket sending and receiving the frames of a channel exposed by a
+//! [TunnelServer](super::server::TunnelServer) or cannelloni.
+//!
+//! A lost TCP connection is reestablished in the reconnect interval while the socket is used.
+//! Over UDP, an empty datagram is sent in the same interval, so a restarted server learns the
+//! address of the socket again. Datagrams from other addresses than the server are ignored.
+
+use super::{open_stream, Connection, Link, Transport, TunnelError, TunnelReport, IO_TIMEOUT};
+use crate::error::PcanError;
+use crate::format::Frame;
+use crate::socket::{CanFdFrame, CanFrame, RecvCan, RecvCanFd, SendCan, SendCanFd, Timestamp};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct RemoteCanSocket {
+    transport: Transport,
+    address: SocketAddr,
+    link: RefCell<Link>,
+    /// Received frames with the microseconds since the socket was connected.
+    pending: RefCell<VecDeque<(Frame, u64)>>,
+    start: Instant,
+    reconnect_interval: Duration,
+    last_attempt: Cell<Instant>,
+}
+
+impl RemoteCanSocket {
+    pub fn connect<A: ToSocketAddrs>(
+        transport: Transport,
+        address: A,
+    ) -> Result<RemoteCanSocket, TunnelError> {
+        let address = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or(io::Error::from(io::ErrorKind::InvalidInput))?;
+        let mut link = Link::new();
+        link.connect(Self::open(transport, address)?);
+        link.announce()?;
+
+        let start = Instant::now();
+        Ok(RemoteCanSocket {
+            transport,
+            address,
+            link: RefCell::new(link),
+            pending: RefCell::new(VecDeque::new()),
+            start,
+            reconnect_interval: RECONNECT_INTERVAL,
+            last_attempt: Cell::new(start),
+        })
+    }
+
+    fn open(transport: Transport, address: SocketAddr) -> Result<Connection, TunnelError> {
+        match transport {
+            Transport::Udp => {
+                let local: SocketAddr = match address {
+                    SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+                    SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+                };
+                let socket = UdpSocket::bind(local)?;
+                socket.set_nonblocking(true)?;
+                Ok(Connection::Udp(socket, Some(address), None))
+            }
+            Transport::Tcp => {
+                let stream = TcpStream::connect_timeout(&address, IO_TIMEOUT)?;
+                Ok(open_stream(stream)?)
+            }
+        }
+    }
+
+    /// Interval in which a lost connection is reestablished, one second by default.
+    pub fn with_reconnect_interval(mut self, interval: Duration) -> RemoteCanSocket {
+        self.reconnect_interval = interval;
+        self
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.link.borrow().is_connected()
+    }
+
+    pub fn report(&self) -> TunnelReport {
+        self.link.borrow().report
+    }
+
+    /// Reconnects a lost stream or announces the address of a UDP socket once per interval.
+    fn maintain(&self) {
+        if self.last_attempt.get().elapsed() < self.reconnect_interval {
+            return;
+        }
+        self.last_attempt.set(Instant::now());
+
+        let mut link = self.link.borrow_mut();
+        match self.transport {
+            Transport::Udp => {
+                let _ = link.announce();
+            }
+            Transport::Tcp if !link.is_connected() => {
+                if let Ok(connection) = Self::open(self.transport, self.address) {
+                    link.connect(connection);
+                }
+            }
+            Transport::Tcp => {}
+        }
+    }
+
+    /// Sends `frame`, failing with [PcanError::QxmtFull] while the peer is not reachable.
+    fn send_frame(&self, frame: Frame) -> Result<(), PcanError> {
+        self.maintain();
+        self.link
+            .borrow_mut()
+            .send(&[frame])
+            .map_err(|_| PcanError::QxmtFull)
+    }
+
+    /// Next received frame, failing with [PcanError::QrcvEmpty] if no frame is pending.
+    fn next_frame(&self) -> Result<(Frame, u64), PcanError> {
+        self.maintain();
+        let mut pending = self.pending.borrow_mut();
+        if pending.is_empty() {
+            let mut frames = Vec::new();
+            // A failing stream disconnects and is reestablished later on.
+            let _ = self.link.borrow_mut().recv(&mut frames);
+            let micros = self.start.elapsed().as_micros() as u64;
+            pending.extend(frames.into_iter().map(|frame| (frame, micros)));
+        }
+        pending.pop_front().ok_or(PcanError::QrcvEmpty)
+    }
+}
+
+impl SendCan for RemoteCanSocket {
+    fn send(&self, frame: CanFrame) -> Result<(), PcanError> {
+        self.send_frame(Frame::Can(frame))
+    }
+}
+
+impl SendCanFd for RemoteCanSocket {
+    fn send_fd(&self, frame: CanFdFrame) -> Result<(), PcanError> {
+        self.send_frame(Frame::CanFd(frame))
+    }
+}
+
+/// CAN FD and error frames are skipped, the timestamp counts from connecting.
+impl RecvCan for RemoteCanSocket {
+    fn recv(&self) -> Result<(CanFrame, Timestamp), PcanError> {
+        loop {
+            if let (Frame::Can(frame), micros) = self.next_frame()? {
+                return Ok((frame, Timestamp::from_micros(micros)));
+            }
+        }
+    }
+
+    fn recv_frame(&self) -> Result<CanFrame, PcanError> {
+        self.recv().map(|(frame, _)| frame)
+    }
+}
+
+/// Error frames are skipped, the timestamp counts the microseconds from connecting.
+impl RecvCanFd for RemoteCanSocket {
+    fn recv_fd(&self) -> Result<(CanFdFrame, u64), PcanError> {
+        loop {
+            match self.next_frame()? {
+                (Frame::Can(frame), micros) => return Ok((CanFdFrame::from(frame), micros)),
+                (Frame::CanFd(frame), micros) => return Ok((frame, micros)),
+                _ => {}
+            }
+        }
+    }
+
+    fn recv_fd_frame(&self) -> Result<CanFdFrame, PcanError> {
+        self.recv_fd().map(|(frame, _)| frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::MemorySink;
+    use crate::socket::MessageType;
+    use crate::tunnel::server::TunnelServer;
+
+    /// Polls the server until `done` holds for the server and its channel.
+    fn poll_until<F: Fn(&TunnelServer, &MemorySink) -> bool>(
+        server: &mut TunnelServer,
+        channel: &mut MemorySink,
+        done: F,
+    ) {
+        let start = Instant::now();
+        loop {
+            server.poll(channel).unwrap();
+            if done(server, channel) {
+                return;
+            }
+            assert!(start.elapsed() < Duration::from_secs(2));
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Receives the next frame from the socket while polling the server.
+    fn recv(
+        socket: &RemoteCanSocket,
+        server: &mut TunnelServer,
+        channel: &mut MemorySink,
+    ) -> CanFdFrame {
+        let start = Instant::now();
+        loop {
+            assert!(start.elapsed() < Duration::from_secs(2));
+            server.poll(channel).unwrap();
+            match socket.recv_fd_frame() {
+                Ok(frame) => return frame,
+                Err(err) => assert_eq!(err, PcanError::QrcvEmpty),
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn loopback(transport: Transport) {
+        let mut server = TunnelServer::bind(transport, "127.0.0.1:0").unwrap();
+        let mut channel = MemorySink::new();
+        let socket = RemoteCanSocket::connect(transport, server.local_addr()).unwrap();
+        poll_until(&mut server, &mut channel, |server, _| server.is_connected());
+
+        let frame = CanFrame::new(0x123, MessageType::Standard, &[1, 2, 3]).unwrap();
+        socket.send(frame).unwrap();
+        let mut fd_frame = CanFdFrame::new(0x1F0, MessageType::Extended, &[7; 20]).unwrap();
+        fd_frame.set_bitrate_switch(true);
+        socket.send_fd(fd_frame).unwrap();
+        poll_until(&mut server, &mut channel, |_, channel| {
+            channel.sent.len() == 2
+        });
+        assert_eq!(
+            channel.sent,
+            vec![Frame::Can(frame), Frame::CanFd(fd_frame)]
+        );
+
+        let remote = CanFrame::new_remote(0x7DF, MessageType::Standard, 8).unwrap();
+        channel.pending.push_back(Frame::Can(remote));
+        channel.pending.push_back(Frame::CanFd(fd_frame));
+        assert_eq!(
+            recv(&socket, &mut server, &mut channel),
+            CanFdFrame::from(remote)
+        );
+        assert_eq!(recv(&socket, &mut server, &mut channel), fd_frame);
+
+        assert_eq!(server.report().received, 2);
+        assert_eq!(server.report().sent, 2);
+        assert_eq!(socket.report().lost, 0);
+        assert_eq!(server.report().connections, 1);
+    }
+
+    #[test]
+    fn loopback_udp_001() {
+        loopback(Transport::Udp);
+    }
+
+    #[test]
+    fn loopback_tcp_001() {
+        loopback(Transport::Tcp);
+    }
+
+    #[test]
+    fn reconnect_tcp_001() {
+        let mut server = TunnelServer::bind(Transport::Tcp, "127.0.0.1:0").unwrap();
+        let address = server.local_addr();
+        let mut channel = MemorySink::new();
+        let socket = RemoteCanSocket::connect(Transport::Tcp, address)
+            .unwrap()
+            .with_reconnect_interval(Duration::from_millis(10));
+        poll_until(&mut server, &mut channel, |server, _| server.is_connected());
+
+        // The socket notices the closed stream while receiving.
+        drop(server);
+        let start = Instant::now();
+        while socket.is_connected() {
+            assert!(start.elapsed() < Duration::from_secs(2));
+            assert_eq!(socket.recv_frame(), Err(PcanError::QrcvEmpty));
+        }
+        let frame = CanFrame::new(0x100, MessageType::Standard, &[1]).unwrap();
+        assert_eq!(socket.send(frame), Err(PcanError::QxmtFull));
+
+        let mut server = TunnelServer::bind(Transport::Tcp, address).unwrap();
+        let start = Instant::now();
+        while socket.send(frame).is_err() {
+            assert!(start.elapsed() < Duration::from_secs(2));
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        poll_until(&mut server, &mut channel, |_, channel| {
+            !channel.sent.is_empty()
+        });
+        assert_eq!(channel.sent, vec![Frame::Can(frame)]);
+        assert_eq!(socket.report().connections, 2);
+    }
+}
@@ -0,0 +1,650 @@
+//! Tunneling of CAN traffic over UDP or TCP, compatible with cannelloni.
+//!
+//! A [TunnelServer](server::TunnelServer) exposes a local channel, a
+//! [RemoteCanSocket](client::RemoteCanSocket) sends and receives its frames from another machine
+//! through the same traits as a local socket.
+//!
+//! Frames are encoded as in cannelloni: the CAN-ID in big-endian byte order with the SocketCAN
+//! flags for extended (`0x80000000`), remote (`0x40000000`) and error frames (`0x20000000`),
+//! followed by the length, where CAN FD frames set `0x80` and add a byte with the flags for bit
+//! rate switch (`0x01`) and error state indicator (`0x02`), followed by the data bytes, which are
+//! omitted for remote frames. Error frames carry the SocketCAN error class in the CAN-ID, PCAN
+//! error frames are sent without class and with the data `[type, direction, capture, rx counter,
+//! tx counter]` as in trace files.
+//!
+//! Over UDP, each datagram starts with a header of the version `2`, the operation `0` for data,
+//! a sequence number counting the datagrams of each side and the number of frames as big-endian
+//! `u16`. Gaps in the sequence numbers are counted as lost datagrams, lost frames are not
+//! retransmitted. Over TCP, both sides exchange the handshake `CANNELLONIv1` after connecting
+//! and then send the encoded frames without header.
+
+pub mod client;
+pub mod server;
+
+use crate::error::PcanError;
+use crate::format::Frame;
+use crate::socket::{CanFdFrame, CanFrame, MessageType};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Interval in which sockets are polled while no frames are pending.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Time a full send buffer and a new connection are waited for.
+const IO_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Silence after which the learned UDP peer may be replaced by another address.
+const PEER_TIMEOUT: Duration = Duration::from_secs(5);
+
+const HANDSHAKE: &[u8] = b"CANNELLONIv1";
+
+const VERSION: u8 = 2;
+const OP_DATA: u8 = 0;
+const HEADER_SIZE: usize = 5;
+
+/// Largest datagram sent, staying below the MTU of common links.
+const MAX_DATAGRAM: usize = 1200;
+
+const CAN_EFF_FLAG: u32 = 0x8000_0000;
+const CAN_RTR_FLAG: u32 = 0x4000_0000;
+const CAN_ERR_FLAG: u32 = 0x2000_0000;
+const CAN_EFF_MASK: u32 = 0x1FFF_FFFF;
+const CAN_SFF_MASK: u32 = 0x0000_07FF;
+
+const CANFD_FRAME: u8 = 0x80;
+const CANFD_BRS: u8 = 0x01;
+const CANFD_ESI: u8 = 0x02;
+
+/// Transport protocol of a tunnel.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Transport {
+    Udp,
+    Tcp,
+}
+
+/* TunnelError */
+
+#[derive(Debug)]
+pub enum TunnelError {
+    Io(io::Error),
+    Pcan(PcanError),
+    /// The peer sent malformed data or an unexpected handshake.
+    Protocol,
+}
+
+impl From<io::Error> for TunnelError {
+    fn from(value: io::Error) -> Self {
+        TunnelError::Io(value)
+    }
+}
+
+impl From<PcanError> for TunnelError {
+    fn from(value: PcanError) -> Self {
+        TunnelError::Pcan(value)
+    }
+}
+
+/// Counters of a tunnel endpoint.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct TunnelReport {
+    /// Frames sent to the peer.
+    pub sent: u64,
+    /// Frames received from the peer.
+    pub received: u64,
+    /// Datagrams of the peer missing in the sequence numbers.
+    pub lost: u64,
+    /// Frames not forwarded, e.g. while no peer is connected or rejected while sending.
+    pub dropped: u64,
+    /// Connections established, more than one after reconnecting.
+    pub connections: u64,
+}
+
+/* Encoding */
+
+/// Appends `frame` to `buffer`, returns false for status records.
+fn encode_frame(frame: &Frame, buffer: &mut Vec<u8>) -> bool {
+    let id = |can_id: u32, extended: bool| match extended {
+        true => can_id | CAN_EFF_FLAG,
+        false => can_id,
+    };
+    match frame {
+        Frame::Can(frame) if frame.is_error_frame() => {
+            encode_error(0, &[&[frame.can_id() as u8], frame.data()].concat(), buffer)
+        }
+        Frame::CanFd(frame) if frame.is_error_frame() => {
+            encode_error(0, &[&[frame.can_id() as u8], frame.data()].concat(), buffer)
+        }
+        Frame::Error { class, data } => encode_error(*class, data, buffer),
+        Frame::Can(frame) => {
+            let mut can_id = id(frame.can_id(), frame.is_extended_frame());
+            if frame.is_remote_frame() {
+                can_id |= CAN_RTR_FLAG;
+            }
+            buffer.extend_from_slice(&can_id.to_be_bytes());
+            buffer.push(frame.dlc());
+            if !frame.is_remote_frame() {
+                buffer.extend_from_slice(frame.data());
+            }
+            true
+        }
+        Frame::CanFd(frame) => {
+            let can_id = id(frame.can_id(), frame.is_extended_frame());
+            buffer.extend_from_slice(&can_id.to_be_bytes());
+            if frame.is_fd_frame() {
+                let mut flags = 0;
+                if frame.is_bitrate_switch() {
+                    flags |= CANFD_BRS;
+                }
+                if frame.is_error_state_indicator() {
+                    flags |= CANFD_ESI;
+                }
+                buffer.extend_from_slice(&[frame.data().len() as u8 | CANFD_FRAME, flags]);
+            } else {
+                buffer.push(frame.data().len() as u8);
+            }
+            buffer.extend_from_slice(frame.data());
+            true
+        }
+        Frame::Status(_) => false,
+    }
+}
+
+/// Appends an error frame with the SocketCAN error class `class`, returns true.
+fn encode_error(class: u32, data: &[u8], buffer: &mut Vec<u8>) -> bool {
+    let len = data.len().min(8);
+    buffer.extend_from_slice(&(CAN_ERR_FLAG | (class & CAN_EFF_MASK)).to_be_bytes());
+    buffer.push(len as u8);
+    buffer.extend_from_slice(&data[..len]);
+    true
+}
+
+/// Frame at the start of `data` and its encoded size, `None` if `data` is incomplete.
+fn decode_frame(data: &[u8]) -> Result<Option<(Frame, usize)>, TunnelError> {
+    let (Some(can_id), Some(&len)) = (data.get(..4), data.get(4)) else {
+        return Ok(None);
+    };
+    let can_id = u32::from_be_bytes([can_id[0], can_id[1], can_id[2], can_id[3]]);
+    let msg_type = match can_id & CAN_EFF_FLAG != 0 {
+        true => MessageType::Extended,
+        false => MessageType::Standard,
+    };
+    let masked = match msg_type {
+        MessageType::Extended => can_id & CAN_EFF_MASK,
+        MessageType::Standard => can_id & CAN_SFF_MASK,
+    };
+
+    if len & CANFD_FRAME != 0 {
+        let len = (len & !CANFD_FRAME) as usize;
+        let Some(&flags) = data.get(5) else {
+            return Ok(None);
+        };
+        if len > 64 {
+            return Err(TunnelError::Protocol);
+        }
+        let Some(payload) = data.get(6..6 + len) else {
+            return Ok(None);
+        };
+        let mut frame =
+            CanFdFrame::new(masked, msg_type, payload).map_err(|_| TunnelError::Protocol)?;
+        frame.set_bitrate_switch(flags & CANFD_BRS != 0);
+        frame.set_error_state_indicator(flags & CANFD_ESI != 0);
+        return Ok(Some((Frame::CanFd(frame), 6 + len)));
+    }
+
+    let len = len as usize;
+    if len > 8 {
+        return Err(TunnelError::Protocol);
+    }
+    if can_id & CAN_RTR_FLAG != 0 {
+        let frame =
+            CanFrame::new_remote(masked, msg_type, len as u8).map_err(|_| TunnelError::Protocol)?;
+        return Ok(Some((Frame::Can(frame), 5)));
+    }
+    let Some(payload) = data.get(5..5 + len) else {
+        return Ok(None);
+    };
+    let frame = match can_id & CAN_ERR_FLAG != 0 {
+        true => Frame::Error {
+            class: can_id & CAN_EFF_MASK,
+            data: payload.to_vec(),
+        },
+        false => {
+            Frame::Can(CanFrame::new(masked, msg_type, payload).map_err(|_| TunnelError::Protocol)?)
+        }
+    };
+    Ok(Some((frame, 5 + len)))
+}
+
+/// Datagram with the sequence number `sequence` and the encoded `frames`.
+fn encode_datagram(sequence: u8, frames: &[Vec<u8>]) -> Vec<u8> {
+    let mut datagram = vec![VERSION, OP_DATA, sequence];
+    datagram.extend_from_slice(&(frames.len() as u16).to_be_bytes());
+    for frame in frames {
+        datagram.extend_from_slice(frame);
+    }
+    datagram
+}
+
+/// Sequence number and frames of a datagram.
+fn decode_datagram(datagram: &[u8]) -> Result<(u8, Vec<Frame>), TunnelError> {
+    let [VERSION, OP_DATA, sequence, count_high, count_low, ..] = *datagram else {
+        return Err(TunnelError::Protocol);
+    };
+    let count = u16::from_be_bytes([count_high, count_low]);
+    let mut frames = Vec::with_capacity(count as usize);
+    let mut offset = HEADER_SIZE;
+    for _ in 0..count {
+        match decode_frame(&datagram[offset..])? {
+            Some((frame, size)) => {
+                frames.push(frame);
+                offset += size;
+            }
+            None => return Err(TunnelError::Protocol),
+        }
+    }
+    Ok((sequence, frames))
+}
+
+/* Link */
+
+#[derive(Debug)]
+enum Connection {
+    /// Socket with the address of the peer and, if the address was learned from received
+    /// datagrams, the time the peer was last heard from. Datagrams of other addresses are ignored
+    /// while the peer is fixed or was heard from within [PEER_TIMEOUT].
+    Udp(UdpSocket, Option<SocketAddr>, Option<Instant>),
+    /// Stream with the received bytes not yet decoded and whether the handshake of the peer was
+    /// received.
+    Tcp(TcpStream, Vec<u8>, bool),
+}
+
+/// Connection to the peer of an endpoint, `None` while disconnected.
+#[derive(Debug)]
+struct Link {
+    connection: Option<Connection>,
+    sequence: u8,
+    expected: Option<u8>,
+    report: TunnelReport,
+}
+
+impl Link {
+    fn new() -> Link {
+        Link {
+            connection: None,
+            sequence: 0,
+            expected: None,
+            report: TunnelReport::default(),
+        }
+    }
+
+    /// Uses `connection`, a UDP socket counts as connection once the address of the peer is known.
+    fn connect(&mut self, connection: Connection) {
+        if let Connection::Udp(_, Some(_), _) | Connection::Tcp(..) = connection {
+            self.report.connections += 1;
+        }
+        self.connection = Some(connection);
+        self.expected = None;
+    }
+
+    fn is_connected(&self) -> bool {
+        match &self.connection {
+            Some(Connection::Udp(_, peer, _)) => peer.is_some(),
+            Some(Connection::Tcp(..)) => true,
+            None => false,
+        }
+    }
+
+    /// Sends `frames`, dropping them while no peer is connected. A failing stream disconnects.
+    fn send(&mut self, frames: &[Frame]) -> Result<(), TunnelError> {
+        let encoded = frames
+            .iter()
+            .filter_map(|frame| {
+                let mut buffer = Vec::new();
+                encode_frame(frame, &mut buffer).then_some(buffer)
+            })
+            .collect::<Vec<_>>();
+        self.report.dropped += (frames.len() - encoded.len()) as u64;
+        if encoded.is_empty() {
+            return Ok(());
+        }
+        if !self.is_connected() {
+            self.report.dropped += encoded.len() as u64;
+            return Err(TunnelError::Io(io::ErrorKind::NotConnected.into()));
+        }
+
+        let result = match self.connection.as_mut() {
+            Some(Connection::Udp(socket, Some(peer), _)) => {
+                let mut result = Ok(());
+                let mut start = 0;
+                while start < encoded.len() {
+                    let mut size = HEADER_SIZE;
+                    let mut end = start;
+                    while end < encoded.len()
+                        && (end == start || size + encoded[end].len() <= MAX_DATAGRAM)
+                    {
+                        size += encoded[end].len();
+                        end += 1;
+                    }
+                    let datagram = encode_datagram(self.sequence, &encoded[start..end]);
+                    self.sequence = self.sequence.wrapping_add(1);
+                    result = socket.send_to(&datagram, *peer).map(|_| ());
+                    if result.is_err() {
+                        break;
+                    }
+                    start = end;
+                }
+                result
+            }
+            Some(Connection::Tcp(stream, ..)) => write_all(stream, &encoded.concat()),
+            _ => Ok(()),
+        };
+        match result {
+            Ok(()) => {
+                self.report.sent += encoded.len() as u64;
+                Ok(())
+            }
+            Err(err) => {
+                self.report.dropped += encoded.len() as u64;
+                if matches!(self.connection, Some(Connection::Tcp(..))) {
+                    self.connection = None;
+                }
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Sends an empty datagram, so the peer learns the address of this endpoint.
+    fn announce(&mut self) -> Result<(), TunnelError> {
+        if let Some(Connection::Udp(socket, Some(peer), _)) = &self.connection {
+            let datagram = encode_datagram(self.sequence, &[]);
+            self.sequence = self.sequence.wrapping_add(1);
+            socket.send_to(&datagram, *peer)?;
+        }
+        Ok(())
+    }
+
+    /// Appends the frames pending on the connection to `frames`, returns whether any data was
+    /// pending. A closed or failing stream disconnects.
+    fn recv(&mut self, frames: &mut Vec<Frame>) -> Result<bool, TunnelError> {
+        let known = frames.len();
+        let result = match self.connection.as_mut() {
+            Some(Connection::Udp(socket, peer, heard)) => Self::recv_udp(
+                socket,
+                (peer, heard),
+                &mut self.expected,
+                &mut self.report,
+                frames,
+            ),
+            Some(Connection::Tcp(stream, buffer, handshake)) => {
+                Self::recv_tcp(stream, buffer, handshake, frames)
+            }
+            None => Ok(false),
+        };
+        if result.is_err() && matches!(self.connection, Some(Connection::Tcp(..))) {
+            self.connection = None;
+        }
+        self.report.received += (frames.len() - known) as u64;
+        result
+    }
+
+    fn recv_udp(
+        socket: &UdpSocket,
+        (peer, heard): (&mut Option<SocketAddr>, &mut Option<Instant>),
+        expected: &mut Option<u8>,
+        report: &mut TunnelReport,
+        frames: &mut Vec<Frame>,
+    ) -> Result<bool, TunnelError> {
+        let mut datagram = [0u8; 65536];
+        let mut pending = false;
+        loop {
+            let (size, address) = match socket.recv_from(&mut datagram) {
+                Ok(received) => received,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(pending),
+                // Reported for an earlier datagram to a peer that was not listening.
+                Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => continue,
+                Err(err) => return Err(err.into()),
+            };
+            pending = true;
+            let Ok((sequence, received)) = decode_datagram(&datagram[..size]) else {
+                continue;
+            };
+            if *peer != Some(address) {
+                // A fixed peer is never replaced, a learned one once it fell silent.
+                let replaceable = match (*peer, *heard) {
+                    (None, _) => true,
+                    (Some(_), Some(heard)) => heard.elapsed() >= PEER_TIMEOUT,
+                    (Some(_), None) => false,
+                };
+                if !replaceable {
+                    report.dropped += received.len() as u64;
+                    continue;
+                }
+                *peer = Some(address);
+                *heard = Some(Instant::now());
+                *expected = None;
+                report.connections += 1;
+            } else if heard.is_some() {
+                *heard = Some(Instant::now());
+            }
+            if let Some(expected) = *expected {
+                // Large gaps are reordered datagrams or a restarted peer.
+                let gap = sequence.wrapping_sub(expected);
+                if gap < 128 {
+                    report.lost += gap as u64;
+                }
+            }
+            *expected = Some(sequence.wrapping_add(1));
+            frames.extend(received);
+        }
+    }
+
+    fn recv_tcp(
+        stream: &mut TcpStream,
+        buffer: &mut Vec<u8>,
+        handshake: &mut bool,
+        frames: &mut Vec<Frame>,
+    ) -> Result<bool, TunnelError> {
+        let mut chunk = [0u8; 4096];
+        let mut pending = false;
+        loop {
+            match stream.read(&mut chunk) {
+                Ok(0) => return Err(TunnelError::Io(io::ErrorKind::UnexpectedEof.into())),
+                Ok(size) => {
+                    buffer.extend_from_slice(&chunk[..size]);
+                    pending = true;
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        if !*handshake {
+            let known = buffer.len().min(HANDSHAKE.len());
+            if buffer[..known] != HANDSHAKE[..known] {
+                return Err(TunnelError::Protocol);
+            }
+            if known < HANDSHAKE.len() {
+                return Ok(pending);
+            }
+            buffer.drain(..known);
+            *handshake = true;
+        }
+
+        let mut offset = 0;
+        while let Some((frame, size)) = decode_frame(&buffer[offset..])? {
+            frames.push(frame);
+            offset += size;
+        }
+        buffer.drain(..offset);
+        Ok(pending)
+    }
+}
+
+/// Writes `data` to a non-blocking stream, waiting up to [IO_TIMEOUT] for a full send buffer.
+fn write_all(stream: &mut TcpStream, mut data: &[u8]) -> io::Result<()> {
+    let start = Instant::now();
+    while !data.is_empty() {
+        match stream.write(data) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(size) => data = &data[size..],
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                if start.elapsed() > IO_TIMEOUT {
+                    return Err(io::ErrorKind::TimedOut.into());
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Switches a new stream to non-blocking mode and sends the handshake, the handshake of the peer
+/// is checked when receiving.
+fn open_stream(mut stream: TcpStream) -> io::Result<Connection> {
+    stream.set_nodelay(true)?;
+    stream.set_nonblocking(true)?;
+    write_all(&mut stream, HANDSHAKE)?;
+    Ok(Connection::Tcp(stream, Vec::new(), false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(frame: Frame) -> Frame {
+        let mut buffer = Vec::new();
+        assert!(encode_frame(&frame, &mut buffer));
+        let (decoded, size) = decode_frame(&buffer).unwrap().unwrap();
+        assert_eq!(size, buffer.len());
+        decoded
+    }
+
+    #[test]
+    fn encode_frame_001() {
+        let frame = Frame::Can(CanFrame::new(0x123, MessageType::Standard, &[1, 2]).unwrap());
+        let mut buffer = Vec::new();
+        assert!(encode_frame(&frame, &mut buffer));
+        assert_eq!(buffer, [0x00, 0x00, 0x01, 0x23, 2, 1, 2]);
+        assert_eq!(round_trip(frame.clone()), frame);
+
+        let frame =
+            Frame::Can(CanFrame::new_remote(0x18DA_F110, MessageType::Extended, 8).unwrap());
+        let mut buffer = Vec::new();
+        assert!(encode_frame(&frame, &mut buffer));
+        assert_eq!(buffer, [0xD8, 0xDA, 0xF1, 0x10, 8]);
+        assert_eq!(round_trip(frame.clone()), frame);
+
+        assert!(!encode_frame(
+            &Frame::Status(String::from("BUSOFF")),
+            &mut buffer
+        ));
+    }
+
+    #[test]
+    fn encode_frame_002() {
+        let mut frame = CanFdFrame::new(0x1F0, MessageType::Standard, &[7; 12]).unwrap();
+        frame.set_bitrate_switch(true);
+        let mut buffer = Vec::new();
+        assert!(encode_frame(&Frame::CanFd(frame), &mut buffer));
+        assert_eq!(buffer[4..6], [12 | CANFD_FRAME, CANFD_BRS]);
+        assert_eq!(round_trip(Frame::CanFd(frame)), Frame::CanFd(frame));
+
+        // Classic frames read with recv_fd are sent as classic frames.
+        let classic = CanFrame::new(0x100, MessageType::Standard, &[1, 2]).unwrap();
+        let decoded = round_trip(Frame::CanFd(CanFdFrame::from(classic)));
+        assert_eq!(decoded, Frame::Can(classic));
+    }
+
+    #[test]
+    fn decode_frame_001() {
+        assert!(decode_frame(&[0, 0, 1, 0x23, 2, 1]).unwrap().is_none());
+        assert!(decode_frame(&[0, 0, 1, 0x23, 9]).is_err());
+        assert!(decode_frame(&[0, 0, 1, 0x23, 65 | CANFD_FRAME, 0]).is_err());
+    }
+
+    #[test]
+    fn error_frame_001() {
+        let frame = Frame::Error {
+            class: 0x0004,
+            data: vec![0, 0x10, 0, 0, 0, 0, 0, 0],
+        };
+        let mut buffer = Vec::new();
+        assert!(encode_frame(&frame, &mut buffer));
+        assert_eq!(buffer[..5], [0x20, 0x00, 0x00, 0x04, 8]);
+        assert_eq!(round_trip(frame.clone()), frame);
+
+        // Error frames of trace files have no class.
+        let frame = Frame::Error {
+            class: 0,
+            data: vec![4, 1, 0, 2, 96],
+        };
+        assert_eq!(round_trip(frame.clone()), frame);
+    }
+
+    #[test]
+    fn link_udp_001() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_nonblocking(true).unwrap();
+        let address = socket.local_addr().unwrap();
+        let mut link = Link::new();
+        link.connect(Connection::Udp(socket, None, None));
+
+        let frame = Frame::Can(CanFrame::new(0x123, MessageType::Standard, &[1]).unwrap());
+        let mut buffer = Vec::new();
+        encode_frame(&frame, &mut buffer);
+        let datagram = encode_datagram(0, &[buffer]);
+        let first = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let second = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut frames = Vec::new();
+        let mut recv = |link: &mut Link, count: u64| {
+            let start = Instant::now();
+            while link.report.received + link.report.dropped < count {
+                assert!(start.elapsed() < Duration::from_secs(2));
+                link.recv(&mut frames).unwrap();
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        };
+
+        // The first peer is kept while it is heard from.
+        first.send_to(&datagram, address).unwrap();
+        recv(&mut link, 1);
+        second.send_to(&datagram, address).unwrap();
+        recv(&mut link, 2);
+        assert_eq!((link.report.received, link.report.dropped), (1, 1));
+
+        let Some(Connection::Udp(_, peer, heard)) = &mut link.connection else {
+            unreachable!();
+        };
+        assert_eq!(*peer, Some(first.local_addr().unwrap()));
+        *heard = Instant::now().checked_sub(PEER_TIMEOUT);
+        second.send_to(&datagram, address).unwrap();
+        recv(&mut link, 3);
+        assert_eq!((link.report.received, link.report.connections), (2, 2));
+        let Some(Connection::Udp(_, peer, _)) = &link.connection else {
+            unreachable!();
+        };
+        assert_eq!(*peer, Some(second.local_addr().unwrap()));
+    }
+
+    #[test]
+    fn datagram_001() {
+        let frames = [
+            Frame::Can(CanFrame::new(0x123, MessageType::Standard, &[1, 2]).unwrap()),
+            Frame::Can(CanFrame::new(0x456, MessageType::Standard, &[]).unwrap()),
+        ];
+        let encoded = frames
+            .iter()
+            .map(|frame| {
+                let mut buffer = Vec::new();
+                encode_frame(frame, &mut buffer);
+                buffer
+            })
+            .collect::<Vec<_>>();
+        let datagram = encode_datagram(7, &encoded);
+        assert_eq!(datagram[..HEADER_SIZE], [VERSION, OP_DATA, 7, 0, 2]);
+        assert_eq!(decode_datagram(&datagram).unwrap(), (7, frames.to_vec()));
+        assert!(decode_datagram(&datagram[..datagram.len() - 1]).is_err());
+    }
+}
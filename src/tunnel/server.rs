@@ -0,0 +1,136 @@
+//! Server exposing a local channel to one remote peer at a time.
+//!
+//! Over UDP, frames are sent to the address the first datagram was received from, so the peer has
+//! to send first, e.g. the empty datagram a [RemoteCanSocket](super::client::RemoteCanSocket)
+//! announces itself with every second. Datagrams from other addresses are ignored until the peer
+//! was silent for five seconds, then the next sender becomes the peer. Over TCP, the next
+//! connection is accepted after the peer disconnected.
+
+use super::{open_stream, Connection, Link, Transport, TunnelError, TunnelReport, POLL_INTERVAL};
+use crate::error::PcanError;
+use crate::format::Frame;
+use crate::replay::ReplaySink;
+use std::io;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug)]
+pub struct TunnelServer {
+    listener: Option<TcpListener>,
+    local_addr: SocketAddr,
+    link: Link,
+}
+
+impl TunnelServer {
+    pub fn bind<A: ToSocketAddrs>(
+        transport: Transport,
+        address: A,
+    ) -> Result<TunnelServer, TunnelError> {
+        let mut link = Link::new();
+        let (listener, local_addr) = match transport {
+            Transport::Udp => {
+                let socket = UdpSocket::bind(address)?;
+                socket.set_nonblocking(true)?;
+                let local_addr = socket.local_addr()?;
+                link.connect(Connection::Udp(socket, None, None));
+                (None, local_addr)
+            }
+            Transport::Tcp => {
+                let listener = TcpListener::bind(address)?;
+                listener.set_nonblocking(true)?;
+                let local_addr = listener.local_addr()?;
+                (Some(listener), local_addr)
+            }
+        };
+        Ok(TunnelServer {
+            listener,
+            local_addr,
+            link,
+        })
+    }
+
+    /// Address the server is bound to, e.g. to learn the port chosen for port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.link.is_connected()
+    }
+
+    pub fn report(&self) -> &TunnelReport {
+        &self.link.report
+    }
+
+    /// Forwards the frames pending on both sides, returns whether any frame was pending.
+    pub fn poll(&mut self, channel: &mut dyn ReplaySink) -> Result<bool, TunnelError> {
+        self.accept()?;
+
+        let mut frames = Vec::new();
+        loop {
+            match channel.recv_frame() {
+                Ok(Some(frame)) => frames.push(frame),
+                Ok(None) => break,
+                Err(
+                    PcanError::BusLight
+                    | PcanError::BusHeavy
+                    | PcanError::BusPassive
+                    | PcanError::BusOff
+                    | PcanError::Overrun
+                    | PcanError::QOverrun,
+                ) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let mut pending = !frames.is_empty();
+        // Frames which can not be sent are counted as dropped, a failing stream disconnects.
+        let _ = self.link.send(&frames);
+
+        frames.clear();
+        pending |= self.link.recv(&mut frames).unwrap_or(true);
+        for frame in &frames {
+            let sent = match frame {
+                Frame::Can(_) | Frame::CanFd(_) => channel.send_frame(frame).is_ok(),
+                Frame::Error { .. } | Frame::Status(_) => false,
+            };
+            if !sent {
+                self.link.report.dropped += 1;
+            }
+        }
+        Ok(pending)
+    }
+
+    /// Forwards frames between the channel and the peer until `running` is cleared.
+    pub fn run(
+        &mut self,
+        channel: &mut dyn ReplaySink,
+        running: &AtomicBool,
+    ) -> Result<(), TunnelError> {
+        while running.load(Ordering::Relaxed) {
+            if !self.poll(channel)? {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+        Ok(())
+    }
+
+    fn accept(&mut self) -> Result<(), TunnelError> {
+        let Some(listener) = &self.listener else {
+            return Ok(());
+        };
+        if self.link.is_connected() {
+            return Ok(());
+        }
+        match listener.accept() {
+            Ok((stream, _)) => {
+                // A peer disconnecting right away is not an error of the server.
+                if let Ok(connection) = open_stream(stream) {
+                    self.link.connect(connection);
+                }
+                Ok(())
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Configuration of DAQ lists and decoding of the data transfer objects (DTOs) they produce.
+
+use crate::socket::Timestamp;
+
+/// Memory location sampled into an ODT.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct OdtEntry {
+    pub address: u32,
+    pub extension: u8,
+    /// Number of bytes sampled.
+    pub size: u8,
+}
+
+impl OdtEntry {
+    pub fn new(address: u32, extension: u8, size: u8) -> OdtEntry {
+        OdtEntry {
+            address,
+            extension,
+            size,
+        }
+    }
+}
+
+/// DAQ list sampled whenever `event` occurs, each ODT is transferred in its own DTO.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DaqList {
+    pub event: u16,
+    /// Sample only every n-th occurrence of the event.
+    pub prescaler: u8,
+    pub priority: u8,
+    /// Request the slave to add its timestamp to the first ODT of every sample.
+    pub timestamp: bool,
+    pub odts: Vec<Vec<OdtEntry>>,
+}
+
+impl DaqList {
+    pub fn new(event: u16) -> DaqList {
+        DaqList {
+            event,
+            prescaler: 1,
+            priority: 0,
+            timestamp: false,
+            odts: Vec::new(),
+        }
+    }
+
+    pub fn with_timestamp(mut self) -> DaqList {
+        self.timestamp = true;
+        self
+    }
+
+    pub fn with_odt(mut self, entries: Vec<OdtEntry>) -> DaqList {
+        self.odts.push(entries);
+        self
+    }
+}
+
+/// DAQ timestamp format of the slave, reported by GET_DAQ_RESOLUTION_INFO.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct DaqResolution {
+    /// Largest size of a single ODT entry.
+    pub max_odt_entry_size: u8,
+    /// Size of the timestamp in bytes, zero if the slave does not support timestamps.
+    pub timestamp_size: u8,
+    /// Duration of a single timestamp tick in picoseconds.
+    pub tick_picos: u64,
+}
+
+impl DaqResolution {
+    pub(crate) fn decode(mode: u8, ticks: u16, max_odt_entry_size: u8) -> DaqResolution {
+        let unit: u64 = match mode >> 4 {
+            0x0 => 1_000,
+            0x1 => 10_000,
+            0x2 => 100_000,
+            0x3 => 1_000_000,
+            0x4 => 10_000_000,
+            0x5 => 100_000_000,
+            0x6 => 1_000_000_000,
+            0x7 => 10_000_000_000,
+            0x8 => 100_000_000_000,
+            0x9 => 1_000_000_000_000,
+            0xA => 1,
+            0xB => 10,
+            _ => 100,
+        };
+        DaqResolution {
+            max_odt_entry_size,
+            timestamp_size: mode & 0x07,
+            tick_picos: unit * ticks as u64,
+        }
+    }
+
+    /// Converts a number of timestamp ticks into microseconds.
+    pub fn ticks_to_micros(&self, ticks: u64) -> u64 {
+        (ticks as u128 * self.tick_picos as u128 / 1_000_000) as u64
+    }
+}
+
+/// Extends the slave's wrapping timestamp counter to 64 bits.
+#[derive(Debug, Default)]
+pub(crate) struct TimestampCounter {
+    last: Option<u64>,
+    offset: u64,
+}
+
+impl TimestampCounter {
+    pub fn extend(&mut self, raw: u64, size: u8) -> u64 {
+        if let Some(last) = self.last {
+            if raw < last {
+                self.offset += 1 << (8 * size as u32);
+            }
+        }
+        self.last = Some(raw);
+        self.offset + raw
+    }
+}
+
+/// Layout of a configured DAQ list, used to map DTO identifiers back to the list.
+#[derive(Debug, Clone)]
+pub(crate) struct DaqLayout {
+    pub first_pid: u8,
+    /// Number of sampled bytes of each ODT.
+    pub odt_sizes: Vec<usize>,
+    pub timestamp: bool,
+    /// Timestamp of the latest sample, later ODTs of a sample carry no timestamp.
+    pub last_timestamp: Option<Timestamp>,
+}
+
+/// ODT received from the slave.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DaqSample {
+    pub daq: u16,
+    pub odt: u8,
+    /// Slave timestamp if the DAQ list carries timestamps, otherwise the time the DTO was
+    /// received.
+    pub timestamp: Timestamp,
+    /// Sampled bytes of all ODT entries in configuration order.
+    pub data: Vec<u8>,
+}
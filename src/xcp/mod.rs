@@ -0,0 +1,695 @@
+//! XCP (ASAM MCD-1 XCP) master for measurement and calibration of a single slave.
+//!
+//! Commands (CTOs) are sent one at a time and answered by the slave before the next one is
+//! sent. DAQ lists are identified by absolute ODT numbers, so every DTO starts with a PID.
+
+pub mod daq;
+pub mod transport;
+
+pub use daq::{DaqList, DaqResolution, DaqSample, OdtEntry};
+pub use transport::{CanFdTransport, CanTransport, Transport};
+
+use crate::error::PcanError;
+use crate::socket::Timestamp;
+use daq::{DaqLayout, TimestampCounter};
+use std::collections::VecDeque;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const CMD_CONNECT: u8 = 0xFF;
+const CMD_DISCONNECT: u8 = 0xFE;
+const CMD_GET_STATUS: u8 = 0xFD;
+const CMD_SET_MTA: u8 = 0xF6;
+const CMD_UPLOAD: u8 = 0xF5;
+const CMD_SHORT_UPLOAD: u8 = 0xF4;
+const CMD_DOWNLOAD: u8 = 0xF0;
+const CMD_SET_DAQ_PTR: u8 = 0xE2;
+const CMD_WRITE_DAQ: u8 = 0xE1;
+const CMD_SET_DAQ_LIST_MODE: u8 = 0xE0;
+const CMD_START_STOP_DAQ_LIST: u8 = 0xDE;
+const CMD_START_STOP_SYNCH: u8 = 0xDD;
+const CMD_GET_DAQ_RESOLUTION_INFO: u8 = 0xD9;
+const CMD_FREE_DAQ: u8 = 0xD6;
+const CMD_ALLOC_DAQ: u8 = 0xD5;
+const CMD_ALLOC_ODT: u8 = 0xD4;
+const CMD_ALLOC_ODT_ENTRY: u8 = 0xD3;
+
+const PID_RESPONSE: u8 = 0xFF;
+const PID_ERROR: u8 = 0xFE;
+const PID_EVENT: u8 = 0xFD;
+const PID_SERVICE: u8 = 0xFC;
+
+const DAQ_LIST_MODE_TIMESTAMP: u8 = 0x10;
+const SESSION_STATUS_DAQ_RUNNING: u8 = 0x40;
+
+/// Size of the CTOs before MAX_CTO is known, CONNECT fits into every transport.
+const DEFAULT_MAX_CTO: usize = 8;
+
+/* XcpError */
+
+#[derive(Debug, PartialEq)]
+pub enum XcpError {
+    Pcan(PcanError),
+    /// The slave did not answer within the timeout.
+    Timeout,
+    /// The slave rejected the command with the given error code, e.g. `0x22` ERR_OUT_OF_RANGE.
+    Command(u8),
+    /// A response was too short or malformed.
+    Protocol,
+    /// The command requires a connection.
+    NotConnected,
+    /// The slave uses a feature this master does not implement.
+    Unsupported,
+    /// A parameter exceeds the limits negotiated with the slave, e.g. an ODT larger than MAX_DTO.
+    InvalidArgument,
+}
+
+impl From<PcanError> for XcpError {
+    fn from(value: PcanError) -> Self {
+        XcpError::Pcan(value)
+    }
+}
+
+/* Connection */
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ByteOrder {
+    Intel,
+    Motorola,
+}
+
+/// Properties of the slave reported by CONNECT.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ConnectInfo {
+    /// Available resources, e.g. `0x04` for DAQ.
+    pub resource: u8,
+    pub byte_order: ByteOrder,
+    /// Largest command and response packet.
+    pub max_cto: u8,
+    /// Largest data transfer packet.
+    pub max_dto: u16,
+    pub protocol_version: u8,
+    pub transport_version: u8,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Status {
+    pub session_status: u8,
+    pub resource_protection: u8,
+    pub session_configuration_id: u16,
+}
+
+impl Status {
+    pub fn is_daq_running(&self) -> bool {
+        self.session_status & SESSION_STATUS_DAQ_RUNNING != 0
+    }
+}
+
+/* XcpMaster */
+
+#[derive(Debug)]
+pub struct XcpMaster<T> {
+    transport: T,
+    timeout: Duration,
+    connection: Option<ConnectInfo>,
+    resolution: Option<DaqResolution>,
+    layouts: Vec<DaqLayout>,
+    counter: TimestampCounter,
+    /// DTOs received while waiting for a response.
+    dtos: VecDeque<(Vec<u8>, Timestamp)>,
+}
+
+impl<T: Transport> XcpMaster<T> {
+    /// Timeout T1 of the standard commands.
+    const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+    pub fn new(transport: T) -> XcpMaster<T> {
+        XcpMaster {
+            transport,
+            timeout: Self::DEFAULT_TIMEOUT,
+            connection: None,
+            resolution: None,
+            layouts: Vec::new(),
+            counter: TimestampCounter::default(),
+            dtos: VecDeque::new(),
+        }
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Properties negotiated by the last successful [XcpMaster::connect].
+    pub fn connection(&self) -> Option<&ConnectInfo> {
+        self.connection.as_ref()
+    }
+
+    fn max_cto(&self) -> usize {
+        match &self.connection {
+            Some(connection) => connection.max_cto as usize,
+            None => DEFAULT_MAX_CTO,
+        }
+    }
+
+    fn max_dto(&self) -> Result<usize, XcpError> {
+        match &self.connection {
+            Some(connection) => Ok(connection.max_dto as usize),
+            None => Err(XcpError::NotConnected),
+        }
+    }
+
+    fn byte_order(&self) -> ByteOrder {
+        match &self.connection {
+            Some(connection) => connection.byte_order,
+            None => ByteOrder::Intel,
+        }
+    }
+
+    fn word(&self, value: u16) -> [u8; 2] {
+        match self.byte_order() {
+            ByteOrder::Intel => value.to_le_bytes(),
+            ByteOrder::Motorola => value.to_be_bytes(),
+        }
+    }
+
+    fn dword(&self, value: u32) -> [u8; 4] {
+        match self.byte_order() {
+            ByteOrder::Intel => value.to_le_bytes(),
+            ByteOrder::Motorola => value.to_be_bytes(),
+        }
+    }
+
+    fn read_word(&self, bytes: &[u8]) -> u16 {
+        match self.byte_order() {
+            ByteOrder::Intel => u16::from_le_bytes([bytes[0], bytes[1]]),
+            ByteOrder::Motorola => u16::from_be_bytes([bytes[0], bytes[1]]),
+        }
+    }
+
+    fn read_integer(&self, bytes: &[u8]) -> u64 {
+        let fold = |value: u64, byte: &u8| (value << 8) | *byte as u64;
+        match self.byte_order() {
+            ByteOrder::Intel => bytes.iter().rev().fold(0, fold),
+            ByteOrder::Motorola => bytes.iter().fold(0, fold),
+        }
+    }
+
+    /// Sends a command and waits for its positive response, returned including the PID.
+    fn command(&mut self, command: &[u8]) -> Result<Vec<u8>, XcpError> {
+        if command.len() > self.max_cto() {
+            return Err(XcpError::InvalidArgument);
+        }
+        self.transport.send_packet(command)?;
+
+        let deadline = Instant::now() + self.timeout;
+        while Instant::now() < deadline {
+            let (packet, timestamp) = match self.transport.recv_packet()? {
+                Some(received) => received,
+                None => {
+                    sleep(Duration::from_millis(1));
+                    continue;
+                }
+            };
+
+            match packet[0] {
+                PID_RESPONSE => return Ok(packet),
+                PID_ERROR => {
+                    return match packet.get(1) {
+                        Some(code) => Err(XcpError::Command(*code)),
+                        None => Err(XcpError::Protocol),
+                    }
+                }
+                PID_EVENT | PID_SERVICE => continue,
+                _ => self.dtos.push_back((packet, timestamp)),
+            }
+        }
+
+        Err(XcpError::Timeout)
+    }
+
+    fn command_connected(&mut self, command: &[u8]) -> Result<Vec<u8>, XcpError> {
+        if self.connection.is_none() {
+            return Err(XcpError::NotConnected);
+        }
+        self.command(command)
+    }
+
+    /// Connects in normal mode and negotiates MAX_CTO and MAX_DTO. Only slaves with byte
+    /// address granularity are supported.
+    pub fn connect(&mut self) -> Result<ConnectInfo, XcpError> {
+        self.connection = None;
+        let response = self.command(&[CMD_CONNECT, 0x00])?;
+        if response.len() < 8 {
+            return Err(XcpError::Protocol);
+        }
+
+        let comm_mode_basic = response[2];
+        let byte_order = match comm_mode_basic & 0x01 {
+            0 => ByteOrder::Intel,
+            _ => ByteOrder::Motorola,
+        };
+        let max_dto = match byte_order {
+            ByteOrder::Intel => u16::from_le_bytes([response[4], response[5]]),
+            ByteOrder::Motorola => u16::from_be_bytes([response[4], response[5]]),
+        };
+        let max_packet = self.transport.max_packet_size();
+        let info = ConnectInfo {
+            resource: response[1],
+            byte_order,
+            max_cto: response[3].min(max_packet as u8),
+            max_dto: max_dto.min(max_packet as u16),
+            protocol_version: response[6],
+            transport_version: response[7],
+        };
+
+        if comm_mode_basic & 0x06 != 0 {
+            let _ = self.command(&[CMD_DISCONNECT]);
+            return Err(XcpError::Unsupported);
+        }
+        if info.max_cto < 8 || info.max_dto < 8 {
+            return Err(XcpError::Protocol);
+        }
+
+        self.connection = Some(info);
+        self.layouts.clear();
+        self.counter = TimestampCounter::default();
+        Ok(info)
+    }
+
+    pub fn disconnect(&mut self) -> Result<(), XcpError> {
+        self.command_connected(&[CMD_DISCONNECT])?;
+        self.connection = None;
+        Ok(())
+    }
+
+    pub fn get_status(&mut self) -> Result<Status, XcpError> {
+        let response = self.command_connected(&[CMD_GET_STATUS])?;
+        if response.len() < 6 {
+            return Err(XcpError::Protocol);
+        }
+        Ok(Status {
+            session_status: response[1],
+            resource_protection: response[2],
+            session_configuration_id: self.read_word(&response[4..6]),
+        })
+    }
+
+    /// Reads `size` bytes at `address` with a single command.
+    pub fn short_upload(
+        &mut self,
+        address: u32,
+        extension: u8,
+        size: u8,
+    ) -> Result<Vec<u8>, XcpError> {
+        if size as usize > self.max_cto() - 1 {
+            return Err(XcpError::InvalidArgument);
+        }
+        let mut command = vec![CMD_SHORT_UPLOAD, size, 0x00, extension];
+        command.extend_from_slice(&self.dword(address));
+        let response = self.command_connected(&command)?;
+        match response.get(1..1 + size as usize) {
+            Some(data) => Ok(data.to_vec()),
+            None => Err(XcpError::Protocol),
+        }
+    }
+
+    /// Sets the memory transfer address used by [XcpMaster::upload] and [XcpMaster::download].
+    pub fn set_mta(&mut self, address: u32, extension: u8) -> Result<(), XcpError> {
+        let mut command = vec![CMD_SET_MTA, 0x00, 0x00, extension];
+        command.extend_from_slice(&self.dword(address));
+        self.command_connected(&command)?;
+        Ok(())
+    }
+
+    /// Reads `size` bytes at `address`, split into as many UPLOAD commands as necessary.
+    pub fn upload(
+        &mut self,
+        address: u32,
+        extension: u8,
+        size: usize,
+    ) -> Result<Vec<u8>, XcpError> {
+        self.set_mta(address, extension)?;
+
+        let chunk = self.max_cto() - 1;
+        let mut data = Vec::with_capacity(size);
+        while data.len() < size {
+            let n = chunk.min(size - data.len());
+            let response = self.command(&[CMD_UPLOAD, n as u8])?;
+            match response.get(1..1 + n) {
+                Some(bytes) => data.extend_from_slice(bytes),
+                None => return Err(XcpError::Protocol),
+            }
+        }
+        Ok(data)
+    }
+
+    /// Writes `data` to `address`, split into as many DOWNLOAD commands as necessary.
+    pub fn download(&mut self, address: u32, extension: u8, data: &[u8]) -> Result<(), XcpError> {
+        self.set_mta(address, extension)?;
+
+        for chunk in data.chunks(self.max_cto() - 2) {
+            let mut command = vec![CMD_DOWNLOAD, chunk.len() as u8];
+            command.extend_from_slice(chunk);
+            self.command(&command)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_daq_resolution_info(&mut self) -> Result<DaqResolution, XcpError> {
+        let response = self.command_connected(&[CMD_GET_DAQ_RESOLUTION_INFO])?;
+        if response.len() < 8 {
+            return Err(XcpError::Protocol);
+        }
+        let ticks = self.read_word(&response[6..8]);
+        let resolution = DaqResolution::decode(response[5], ticks, response[2]);
+        self.resolution = Some(resolution);
+        Ok(resolution)
+    }
+
+    /// Replaces the dynamic DAQ configuration of the slave with `lists` and selects them to be
+    /// started by [XcpMaster::start_daq]. The n-th list gets DAQ list number n.
+    pub fn configure_daq(&mut self, lists: &[DaqList]) -> Result<(), XcpError> {
+        let max_dto = self.max_dto()?;
+        let timestamp_size = match lists.iter().any(|list| list.timestamp) {
+            true => match self.get_daq_resolution_info()?.timestamp_size {
+                0 => return Err(XcpError::Unsupported),
+                size => size as usize,
+            },
+            false => 0,
+        };
+
+        for list in lists {
+            if list.odts.is_empty() || list.odts.len() > u8::MAX as usize {
+                return Err(XcpError::InvalidArgument);
+            }
+            for (i, odt) in list.odts.iter().enumerate() {
+                let mut size = 1 + odt.iter().map(|entry| entry.size as usize).sum::<usize>();
+                if i == 0 && list.timestamp {
+                    size += timestamp_size;
+                }
+                if odt.is_empty() || odt.len() > u8::MAX as usize || size > max_dto {
+                    return Err(XcpError::InvalidArgument);
+                }
+            }
+        }
+
+        self.layouts.clear();
+        self.command(&[CMD_FREE_DAQ])?;
+
+        let mut command = vec![CMD_ALLOC_DAQ, 0x00];
+        command.extend_from_slice(&self.word(lists.len() as u16));
+        self.command(&command)?;
+
+        for (daq, list) in lists.iter().enumerate() {
+            let mut command = vec![CMD_ALLOC_ODT, 0x00];
+            command.extend_from_slice(&self.word(daq as u16));
+            command.push(list.odts.len() as u8);
+            self.command(&command)?;
+        }
+
+        for (daq, list) in lists.iter().enumerate() {
+            for (odt, entries) in list.odts.iter().enumerate() {
+                let mut command = vec![CMD_ALLOC_ODT_ENTRY, 0x00];
+                command.extend_from_slice(&self.word(daq as u16));
+                command.extend_from_slice(&[odt as u8, entries.len() as u8]);
+                self.command(&command)?;
+            }
+        }
+
+        for (daq, list) in lists.iter().enumerate() {
+            for (odt, entries) in list.odts.iter().enumerate() {
+                let mut command = vec![CMD_SET_DAQ_PTR, 0x00];
+                command.extend_from_slice(&self.word(daq as u16));
+                command.extend_from_slice(&[odt as u8, 0x00]);
+                self.command(&command)?;
+
+                for entry in entries {
+                    // Bit offset 0xFF, the entry is not a single bit.
+                    let mut command = vec![CMD_WRITE_DAQ, 0xFF, entry.size, entry.extension];
+                    command.extend_from_slice(&self.dword(entry.address));
+                    self.command(&command)?;
+                }
+            }
+
+            let mode = match list.timestamp {
+                true => DAQ_LIST_MODE_TIMESTAMP,
+                false => 0x00,
+            };
+            let mut command = vec![CMD_SET_DAQ_LIST_MODE, mode];
+            command.extend_from_slice(&self.word(daq as u16));
+            command.extend_from_slice(&self.word(list.event));
+            command.extend_from_slice(&[list.prescaler, list.priority]);
+            self.command(&command)?;
+
+            // Select the list, the slave answers with the PID of its first ODT.
+            let mut command = vec![CMD_START_STOP_DAQ_LIST, 0x02];
+            command.extend_from_slice(&self.word(daq as u16));
+            let response = self.command(&command)?;
+            let first_pid = *response.get(1).ok_or(XcpError::Protocol)?;
+
+            self.layouts.push(daq::DaqLayout {
+                first_pid,
+                odt_sizes: list
+                    .odts
+                    .iter()
+                    .map(|odt| odt.iter().map(|entry| entry.size as usize).sum())
+                    .collect(),
+                timestamp: list.timestamp,
+                last_timestamp: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Starts all DAQ lists selected by [XcpMaster::configure_daq].
+    pub fn start_daq(&mut self) -> Result<(), XcpError> {
+        self.counter = TimestampCounter::default();
+        self.command_connected(&[CMD_START_STOP_SYNCH, 0x01])?;
+        Ok(())
+    }
+
+    /// Stops all DAQ lists.
+    pub fn stop_daq(&mut self) -> Result<(), XcpError> {
+        self.command_connected(&[CMD_START_STOP_SYNCH, 0x00])?;
+        Ok(())
+    }
+
+    /// Returns the next ODT sent by the slave or `None` if no DTO is pending. DTOs of unknown
+    /// DAQ lists are dropped.
+    pub fn recv_daq(&mut self) -> Result<Option<DaqSample>, XcpError> {
+        loop {
+            let (packet, received) = match self.dtos.pop_front() {
+                Some(dto) => dto,
+                None => match self.transport.recv_packet()? {
+                    Some(packet) => packet,
+                    None => return Ok(None),
+                },
+            };
+            if packet[0] >= PID_SERVICE {
+                continue;
+            }
+            if let Some(sample) = self.decode_dto(&packet, received)? {
+                return Ok(Some(sample));
+            }
+        }
+    }
+
+    fn decode_dto(
+        &mut self,
+        packet: &[u8],
+        received: Timestamp,
+    ) -> Result<Option<DaqSample>, XcpError> {
+        let pid = packet[0];
+        let daq = self.layouts.iter().position(|layout| {
+            pid >= layout.first_pid && ((pid - layout.first_pid) as usize) < layout.odt_sizes.len()
+        });
+        let daq = match daq {
+            Some(daq) => daq,
+            None => return Ok(None),
+        };
+        let odt = pid - self.layouts[daq].first_pid;
+
+        let mut data = &packet[1..];
+        let mut timestamp = received;
+        if self.layouts[daq].timestamp {
+            let resolution = self.resolution.ok_or(XcpError::Protocol)?;
+            if odt == 0 {
+                let size = resolution.timestamp_size;
+                if data.len() < size as usize {
+                    return Err(XcpError::Protocol);
+                }
+                let raw = self.read_integer(&data[..size as usize]);
+                let ticks = self.counter.extend(raw, size);
+                timestamp = Timestamp::from_micros(resolution.ticks_to_micros(ticks));
+                data = &data[size as usize..];
+                self.layouts[daq].last_timestamp = Some(timestamp);
+            } else if let Some(last) = self.layouts[daq].last_timestamp {
+                timestamp = last;
+            }
+        }
+
+        // Frames may be padded beyond the configured ODT entries.
+        let size = self.layouts[daq].odt_sizes[odt as usize];
+        let data = data.get(..size).ok_or(XcpError::Protocol)?;
+
+        Ok(Some(DaqSample {
+            daq: daq as u16,
+            odt,
+            timestamp,
+            data: data.to_vec(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::socket::mock::MockSocket;
+    use crate::socket::{CanFrame, MessageType};
+
+    const MASTER_ID: u32 = 0x7F0;
+    const SLAVE_ID: u32 = 0x7F1;
+
+    fn frame(data: &[u8]) -> CanFrame {
+        CanFrame::new(SLAVE_ID, MessageType::Standard, data).unwrap()
+    }
+
+    fn connect_response(comm_mode_basic: u8) -> CanFrame {
+        frame(&[0xFF, 0x15, comm_mode_basic, 0x08, 0x08, 0x00, 0x01, 0x01])
+    }
+
+    #[test]
+    fn connect_001() {
+        let socket = MockSocket::with_responses(vec![vec![frame(&[
+            0xFF, 0x15, 0x01, 0x08, 0x00, 0x08, 0x01, 0x01,
+        ])]]);
+        let mut master = XcpMaster::new(CanTransport::new(
+            &socket,
+            MASTER_ID,
+            SLAVE_ID,
+            MessageType::Standard,
+        ));
+
+        let info = master.connect().unwrap();
+        assert_eq!(info.byte_order, ByteOrder::Motorola);
+        assert_eq!(info.max_cto, 8);
+        assert_eq!(info.max_dto, 8);
+
+        let sent = socket.sent.borrow();
+        assert_eq!(sent[0].can_id(), MASTER_ID);
+        assert_eq!(sent[0].data(), &[0xFF, 0x00, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn short_upload_001() {
+        let socket = MockSocket::with_responses(vec![
+            vec![connect_response(0x00)],
+            vec![frame(&[0xFE, 0x22])],
+            vec![frame(&[0xFF, 0x12, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00])],
+        ]);
+        let mut master = XcpMaster::new(CanTransport::new(
+            &socket,
+            MASTER_ID,
+            SLAVE_ID,
+            MessageType::Standard,
+        ));
+
+        assert_eq!(
+            master.short_upload(0x1000, 0, 2),
+            Err(XcpError::NotConnected)
+        );
+        master.connect().unwrap();
+        assert_eq!(
+            master.short_upload(0x1000, 0, 2),
+            Err(XcpError::Command(0x22))
+        );
+        assert_eq!(master.short_upload(0x1000, 0, 2), Ok(vec![0x12, 0x34]));
+        assert_eq!(
+            socket.sent.borrow()[2].data(),
+            &[0xF4, 0x02, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn download_001() {
+        let socket = MockSocket::new(|frame| match frame.data()[0] {
+            0xFF => vec![connect_response(0x00)],
+            _ => vec![self::frame(&[0xFF])],
+        });
+        let mut master = XcpMaster::new(CanTransport::new(
+            &socket,
+            MASTER_ID,
+            SLAVE_ID,
+            MessageType::Standard,
+        ));
+
+        master.connect().unwrap();
+        master
+            .download(0x2000, 0, &[1, 2, 3, 4, 5, 6, 7, 8])
+            .unwrap();
+
+        let sent = socket.sent.borrow();
+        assert_eq!(sent.len(), 4);
+        assert_eq!(sent[1].data()[..8], [0xF6, 0, 0, 0, 0x00, 0x20, 0x00, 0x00]);
+        assert_eq!(sent[2].data(), &[0xF0, 6, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(sent[3].data()[..4], [0xF0, 2, 7, 8]);
+    }
+
+    #[test]
+    fn daq_001() {
+        let socket = MockSocket::new(|frame| match frame.data()[0] {
+            CMD_CONNECT => vec![connect_response(0x00)],
+            // Two byte timestamp, unit 1 ms, one tick.
+            CMD_GET_DAQ_RESOLUTION_INFO => {
+                vec![self::frame(&[
+                    0xFF, 0x01, 0x04, 0x01, 0x04, 0x62, 0x01, 0x00,
+                ])]
+            }
+            CMD_START_STOP_DAQ_LIST => vec![self::frame(&[0xFF, 0x00])],
+            CMD_START_STOP_SYNCH => vec![
+                self::frame(&[0xFF]),
+                self::frame(&[0x00, 0xFE, 0xFF, 0xAA, 0xBB, 0, 0, 0]),
+                self::frame(&[0x01, 0xCC, 0, 0, 0, 0, 0, 0]),
+                self::frame(&[0x00, 0x02, 0x00, 0xAA, 0xBC, 0, 0, 0]),
+            ],
+            _ => vec![self::frame(&[0xFF])],
+        });
+        let mut master = XcpMaster::new(CanTransport::new(
+            &socket,
+            MASTER_ID,
+            SLAVE_ID,
+            MessageType::Standard,
+        ));
+        master.connect().unwrap();
+
+        let list = DaqList::new(0)
+            .with_timestamp()
+            .with_odt(vec![OdtEntry::new(0x1000, 0, 2)])
+            .with_odt(vec![OdtEntry::new(0x1002, 0, 1)]);
+        master.configure_daq(&[list]).unwrap();
+        master.start_daq().unwrap();
+
+        let first = master.recv_daq().unwrap().unwrap();
+        assert_eq!((first.daq, first.odt), (0, 0));
+        assert_eq!(first.data, vec![0xAA, 0xBB]);
+        assert_eq!(first.timestamp.as_micros(), 0xFFFE * 1000);
+
+        let second = master.recv_daq().unwrap().unwrap();
+        assert_eq!(second.odt, 1);
+        assert_eq!(second.data, vec![0xCC]);
+        assert_eq!(second.timestamp, first.timestamp);
+
+        let third = master.recv_daq().unwrap().unwrap();
+        assert_eq!(third.timestamp.as_micros(), 0x1_0002 * 1000);
+        assert_eq!(master.recv_daq(), Ok(None));
+    }
+}
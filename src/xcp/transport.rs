@@ -0,0 +1,141 @@
+//! Transport layers carrying XCP packets in the data field of CAN and CAN FD frames.
+
+use crate::error::PcanError;
+use crate::socket::{
+    CanFdFrame, CanFrame, MessageType, RecvCan, RecvCanFd, SendCan, SendCanFd, Timestamp,
+};
+
+/// Packet oriented transport between an XCP master and a single slave.
+pub trait Transport {
+    /// Largest packet the transport is able to carry.
+    fn max_packet_size(&self) -> usize;
+    fn send_packet(&self, packet: &[u8]) -> Result<(), PcanError>;
+    /// Returns the next packet of the slave and the time it was received or `None` if no packet
+    /// is pending.
+    fn recv_packet(&self) -> Result<Option<(Vec<u8>, Timestamp)>, PcanError>;
+}
+
+fn is_type(msg_type: MessageType, extended: bool) -> bool {
+    match msg_type {
+        MessageType::Standard => !extended,
+        MessageType::Extended => extended,
+    }
+}
+
+/* CanTransport */
+
+/// XCP on CAN. Packets of the master are sent with `master_id`, packets of the slave are
+/// received with `slave_id`. Frames are padded to eight data bytes.
+#[derive(Debug)]
+pub struct CanTransport<'a, S> {
+    socket: &'a S,
+    master_id: u32,
+    slave_id: u32,
+    msg_type: MessageType,
+}
+
+impl<'a, S: SendCan + RecvCan> CanTransport<'a, S> {
+    const PADDING: u8 = 0x00;
+
+    pub fn new(
+        socket: &'a S,
+        master_id: u32,
+        slave_id: u32,
+        msg_type: MessageType,
+    ) -> CanTransport<'a, S> {
+        CanTransport {
+            socket,
+            master_id,
+            slave_id,
+            msg_type,
+        }
+    }
+}
+
+impl<'a, S: SendCan + RecvCan> Transport for CanTransport<'a, S> {
+    fn max_packet_size(&self) -> usize {
+        8
+    }
+
+    fn send_packet(&self, packet: &[u8]) -> Result<(), PcanError> {
+        let mut data = [Self::PADDING; 8];
+        data[..packet.len()].copy_from_slice(packet);
+        let frame =
+            CanFrame::new(self.master_id, self.msg_type, &data).map_err(|_| PcanError::IllData)?;
+        self.socket.send(frame)
+    }
+
+    fn recv_packet(&self) -> Result<Option<(Vec<u8>, Timestamp)>, PcanError> {
+        loop {
+            let (frame, timestamp) = match self.socket.recv() {
+                Ok(received) => received,
+                Err(PcanError::QrcvEmpty) => return Ok(None),
+                Err(err) => return Err(err),
+            };
+            if frame.can_id() == self.slave_id
+                && is_type(self.msg_type, frame.is_extended_frame())
+                && frame.dlc() > 0
+            {
+                return Ok(Some((frame.data().to_vec(), timestamp)));
+            }
+        }
+    }
+}
+
+/* CanFdTransport */
+
+/// XCP on CAN FD. Packets are padded to the next valid CAN FD data length.
+#[derive(Debug)]
+pub struct CanFdTransport<'a, S> {
+    socket: &'a S,
+    master_id: u32,
+    slave_id: u32,
+    msg_type: MessageType,
+}
+
+impl<'a, S: SendCanFd + RecvCanFd> CanFdTransport<'a, S> {
+    pub fn new(
+        socket: &'a S,
+        master_id: u32,
+        slave_id: u32,
+        msg_type: MessageType,
+    ) -> CanFdTransport<'a, S> {
+        CanFdTransport {
+            socket,
+            master_id,
+            slave_id,
+            msg_type,
+        }
+    }
+}
+
+impl<'a, S: SendCanFd + RecvCanFd> Transport for CanFdTransport<'a, S> {
+    fn max_packet_size(&self) -> usize {
+        64
+    }
+
+    fn send_packet(&self, packet: &[u8]) -> Result<(), PcanError> {
+        let frame = CanFdFrame::new(self.master_id, self.msg_type, packet)
+            .map_err(|_| PcanError::IllData)?;
+        self.socket.send_fd(frame)
+    }
+
+    fn recv_packet(&self) -> Result<Option<(Vec<u8>, Timestamp)>, PcanError> {
+        loop {
+            let (frame, micros) = match self.socket.recv_fd() {
+                Ok(received) => received,
+                Err(PcanError::QrcvEmpty) => return Ok(None),
+                Err(err) => return Err(err),
+            };
+            if frame.can_id() == self.slave_id
+                && is_type(self.msg_type, frame.is_extended_frame())
+                && frame.dlc() > 0
+            {
+                return Ok(Some((
+                    frame.data().to_vec(),
+                    Timestamp::from_micros(micros),
+                )));
+            }
+        }
+    }
+}